@@ -6,6 +6,30 @@ use snafu::Snafu;
 pub enum CliError {
     #[snafu(display("Failed to load config: {message}"))]
     ConfigLoad { message: String },
+    #[snafu(display("Failed to read {path}: {source}"))]
+    ReadInput {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Failed to write {path}: {source}"))]
+    WriteOutput {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Failed to parse store archive {path}: {source}"))]
+    ParseArchive {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Failed to parse access rules {path}: {source}"))]
+    ParseAccessRules {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Invalid htpasswd line {line}: expected `username:hash`"))]
+    InvalidHtpasswdLine { line: usize },
+    #[snafu(display("Audit log chain broken at record {seq}: {message}"))]
+    AuditChainBroken { seq: u64, message: String },
     #[snafu(transparent)]
     CredsManage { source: CredsManageError },
     #[snafu(transparent)]
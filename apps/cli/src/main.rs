@@ -1,16 +1,39 @@
 mod config;
 mod error;
 
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand};
-use securitydept_core::creds_manage::{
-    migrations::{Migrator, models::MigratorTrait},
-    models::{AuthEntry, AuthEntryKind, Group},
-    store::CredsManageStore,
+use securitydept_core::{
+    creds::MultiFormatBasicAuthCred,
+    creds_manage::{
+        AuditActor, AuditActorSource, CredsManageError,
+        archive::{ImportConflictPolicy, ImportMode, ImportOptions, ImportReport, StoreArchive},
+        audit::{AuditOperation, AuditQuery, AuditRecord},
+        migrations::{
+            Migrator,
+            models::{MigrationDirection, MigrationPlan, MigrationState, MigratorTrait},
+        },
+        models::{
+            AccessRule, AuthEntry, AuthEntryKind, Availability, ClaimRequirements, Group,
+            IdentityAccess, TokenEntryConstraints, Unavailable, UpdateEntryRequest,
+            UpdateGroupRequest,
+        },
+        store::{CredsManageStore, FileBackend},
+    },
 };
+use snafu::ResultExt;
 use tabled::{Table, Tabled};
 use tracing_subscriber::EnvFilter;
 
-use crate::{config::CliConfig, error::CliResult};
+use crate::{
+    config::CliConfig,
+    error::{
+        CliError, CliResult, ParseAccessRulesSnafu, ParseArchiveSnafu, ReadInputSnafu,
+        WriteOutputSnafu,
+    },
+};
 
 #[derive(Parser)]
 #[command(name = "securitydept-cli", about = "SecurityDept management CLI")]
@@ -35,6 +58,16 @@ enum Commands {
         #[command(subcommand)]
         action: GroupAction,
     },
+    /// Inspect the audit log of entry and group changes
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+    /// Back up and restore the credential store
+    Store {
+        #[command(subcommand)]
+        action: StoreAction,
+    },
     /// Manage data file migrations
     Migrate {
         #[command(subcommand)]
@@ -63,6 +96,18 @@ enum EntryAction {
         #[arg(long, value_delimiter = ',')]
         group_ids: Vec<String>,
     },
+    /// Import basic auth entries from an htpasswd file.
+    ///
+    /// Each `username:hash` line becomes an entry named after its username.
+    /// bcrypt, apr1, `{SHA}`, PBKDF2 and argon2 hashes are accepted; legacy
+    /// hashes are re-hashed to argon2 on first successful login.
+    ImportHtpasswd {
+        #[arg(long)]
+        file: String,
+        /// Comma-separated group IDs
+        #[arg(long, value_delimiter = ',')]
+        group_ids: Vec<String>,
+    },
     /// Create a new token auth entry
     CreateToken {
         #[arg(long)]
@@ -70,11 +115,23 @@ enum EntryAction {
         /// Comma-separated group IDs
         #[arg(long, value_delimiter = ',')]
         group_ids: Vec<String>,
+        /// RFC 3339 time after which the token is rejected
+        #[arg(long)]
+        expires_at: Option<DateTime<Utc>>,
+        /// RFC 3339 time before which the token is rejected
+        #[arg(long)]
+        not_before: Option<DateTime<Utc>>,
+        /// Comma-separated scopes granted to the token
+        #[arg(long, value_delimiter = ',')]
+        scopes: Vec<String>,
     },
     /// Delete an auth entry
     Delete {
         #[arg(long)]
         id: String,
+        /// Refuse to delete unless the entry is still at this revision
+        #[arg(long)]
+        expect_revision: Option<u64>,
     },
     /// Update an auth entry
     Update {
@@ -89,6 +146,29 @@ enum EntryAction {
         /// Comma-separated group IDs
         #[arg(long, value_delimiter = ',')]
         group_ids: Option<Vec<String>>,
+        /// Refuse to update unless the entry is still at this revision
+        #[arg(long)]
+        expect_revision: Option<u64>,
+    },
+    /// Disable an auth entry without deleting it
+    Disable {
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Re-enable a disabled auth entry
+    Enable {
+        #[arg(long)]
+        id: String,
+    },
+    /// Set or clear the expiry of an auth entry
+    SetExpiry {
+        #[arg(long)]
+        id: String,
+        /// RFC 3339 time after which the entry is rejected; omit to clear
+        #[arg(long)]
+        expires_at: Option<DateTime<Utc>>,
     },
 }
 
@@ -108,6 +188,9 @@ enum GroupAction {
         /// Comma-separated auth entry IDs to bind with this group
         #[arg(long, value_delimiter = ',')]
         entry_ids: Option<Vec<String>>,
+        /// Comma-separated IDs of the groups this group is nested below
+        #[arg(long, value_delimiter = ',')]
+        parent_ids: Option<Vec<String>>,
     },
     /// Update a group
     Update {
@@ -118,22 +201,174 @@ enum GroupAction {
         /// Comma-separated auth entry IDs to bind with this group
         #[arg(long, value_delimiter = ',')]
         entry_ids: Option<Vec<String>>,
+        /// Comma-separated IDs of the groups this group is nested below
+        #[arg(long, value_delimiter = ',')]
+        parent_ids: Option<Vec<String>>,
+        /// Refuse to update unless the group is still at this revision
+        #[arg(long)]
+        expect_revision: Option<u64>,
     },
     /// Delete a group
     Delete {
         #[arg(long)]
         id: String,
+        /// Refuse to delete unless the group is still at this revision
+        #[arg(long)]
+        expect_revision: Option<u64>,
+    },
+    /// Disable a group without deleting it
+    Disable {
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Re-enable a disabled group
+    Enable {
+        #[arg(long)]
+        id: String,
+    },
+    /// Set or clear the expiry of a group
+    SetExpiry {
+        #[arg(long)]
+        id: String,
+        /// RFC 3339 time after which the group is rejected; omit to clear
+        #[arg(long)]
+        expires_at: Option<DateTime<Utc>>,
+    },
+    /// Set the OIDC sessions and access tokens forward auth accepts for a
+    /// group; omitted options turn them off
+    SetIdentityAccess {
+        #[arg(long)]
+        id: String,
+        /// Accept a signed-in OIDC session
+        #[arg(long)]
+        session: bool,
+        /// Accept bearer access tokens verified by the resource server
+        #[arg(long)]
+        bearer: bool,
+        /// Issuer the session or access token must come from
+        #[arg(long)]
+        issuer: Option<String>,
+        /// Claim listing groups or roles, dots select nested claims
+        #[arg(long)]
+        groups_claim: Option<String>,
+        /// Comma-separated groups, at least one of which must be in the
+        /// groups claim
+        #[arg(long, value_delimiter = ',')]
+        any_of: Option<Vec<String>>,
+        /// Refuse to update unless the group is still at this revision
+        #[arg(long)]
+        expect_revision: Option<u64>,
+    },
+    /// Replace the host, path and method rules forward auth applies to a
+    /// group; an empty array lifts every restriction
+    SetAccessRules {
+        #[arg(long)]
+        id: String,
+        /// JSON file holding an array of rules, the first match applies
+        #[arg(long)]
+        file: String,
+        /// Refuse to replace the rules unless the group is still at this
+        /// revision
+        #[arg(long)]
+        expect_revision: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditAction {
+    /// List audit records, newest first
+    List {
+        #[arg(long)]
+        entity_id: Option<String>,
+        /// e.g. entry_create, entry_update, group_delete
+        #[arg(long, value_parser = parse_audit_operation)]
+        operation: Option<AuditOperation>,
+        /// Actor subject
+        #[arg(long)]
+        actor: Option<String>,
+        /// RFC 3339 time of the oldest record to include
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+        /// RFC 3339 time after which records are excluded
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+    },
+    /// Verify the hash chain of the audit log
+    Verify,
+}
+
+#[derive(Subcommand)]
+enum StoreAction {
+    /// Export all groups and entries, hashes included, as a JSON archive
+    Export {
+        /// Output file; the archive is written to stdout when omitted
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Import a JSON archive written by `store export`.
+    ///
+    /// The import is all or nothing: conflicting ids, duplicate names and
+    /// memberships of missing groups abort it before anything is written.
+    Import {
+        #[arg(long)]
+        file: String,
+        /// `merge` adds the archive to the store, `replace` makes the store
+        /// hold exactly the archive
+        #[arg(long, default_value = "merge", value_parser = parse_json_name::<ImportMode>)]
+        mode: ImportMode,
+        /// What a merge does with existing ids: `fail`, `skip` or `overwrite`
+        #[arg(
+            long,
+            default_value = "fail",
+            value_parser = parse_json_name::<ImportConflictPolicy>
+        )]
+        on_conflict: ImportConflictPolicy,
+        /// Report what the import would do without changing the store
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
 #[derive(Subcommand)]
 enum MigrateAction {
-    /// Apply forward migrations.
+    /// Apply pending migrations.
+    ///
+    /// The data file is copied to `<data file>.<timestamp>.bak` before it is
+    /// rewritten.
     Up {
         /// Optional number of migration steps to apply.
         #[arg(long)]
         steps: Option<u32>,
     },
+    /// Revert applied migrations, newest first.
+    Down {
+        /// Number of migration steps to revert.
+        #[arg(long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// List migrations and whether they have been applied.
+    Status,
+    /// Show what `up` or `down` would change in the data file without
+    /// applying it.
+    Plan {
+        /// Plan reverting instead of applying migrations.
+        #[arg(long)]
+        down: bool,
+        /// Number of migration steps; all pending for `up`, one for `down`
+        /// when omitted.
+        #[arg(long)]
+        steps: Option<u32>,
+    },
+    /// Re-seal the data file with the current `data_encryption.key`.
+    ///
+    /// To rotate the key, move the old key to `previous_keys`, configure the
+    /// new one as `key`, run this command and then drop the old key. A
+    /// plaintext data file is sealed for the first time.
+    RotateDataKey,
 }
 
 // Display structs for tabled output
@@ -148,10 +383,30 @@ struct EntryRow {
     kind: String,
     #[tabled(rename = "Username")]
     username: String,
+    #[tabled(rename = "Token Prefix")]
+    token_prefix: String,
+    #[tabled(rename = "Scopes")]
+    scopes: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Expires")]
+    expires_at: String,
     #[tabled(rename = "Group IDs")]
     group_ids: String,
     #[tabled(rename = "Created")]
     created_at: String,
+    #[tabled(rename = "Revision")]
+    revision: u64,
+    #[tabled(rename = "Last Used")]
+    last_used_at: String,
+    #[tabled(rename = "Last Failure")]
+    last_failure_at: String,
+    #[tabled(rename = "Last IP")]
+    last_client_ip: String,
+    #[tabled(rename = "OK / Failed (7d)")]
+    recent_use_counts: String,
+    #[tabled(rename = "OK / Failed (total)")]
+    use_counts: String,
 }
 
 impl From<AuthEntry> for EntryRow {
@@ -164,8 +419,44 @@ impl From<AuthEntry> for EntryRow {
                 AuthEntryKind::Token => "token".to_string(),
             },
             username: e.username.unwrap_or_default(),
+            token_prefix: e.token_prefix.unwrap_or_default(),
+            scopes: e.constraints.scopes.join(", "),
+            status: availability_status(&e.meta.availability),
+            expires_at: e
+                .meta
+                .availability
+                .expires_at
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
             group_ids: e.meta.group_ids.join(", "),
             created_at: e.meta.created_at.format("%Y-%m-%d %H:%M").to_string(),
+            revision: e.meta.revision,
+            last_used_at: e
+                .meta
+                .usage
+                .last_success_at
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            last_failure_at: e
+                .meta
+                .usage
+                .last_failure_at
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            last_client_ip: e
+                .meta
+                .usage
+                .last_client_ip
+                .map(|ip| ip.to_string())
+                .unwrap_or_default(),
+            recent_use_counts: {
+                let (success, failure) = e.meta.usage.counts_within(Utc::now(), 7);
+                format!("{success} / {failure}")
+            },
+            use_counts: format!(
+                "{} / {}",
+                e.meta.usage.success_count, e.meta.usage.failure_count
+            ),
         }
     }
 }
@@ -176,17 +467,195 @@ struct GroupRow {
     id: String,
     #[tabled(rename = "Name")]
     name: String,
+    #[tabled(rename = "Parents")]
+    parent_ids: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Expires")]
+    expires_at: String,
+    #[tabled(rename = "Revision")]
+    revision: u64,
 }
 
 impl From<Group> for GroupRow {
     fn from(g: Group) -> Self {
         Self {
+            parent_ids: g.parent_ids.join(", "),
+            revision: g.revision,
+            status: availability_status(&g.availability),
+            expires_at: g
+                .availability
+                .expires_at
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
             id: g.id,
             name: g.name,
         }
     }
 }
 
+/// "active", "expired" or "disabled" with its reason.
+fn availability_status(availability: &Availability) -> String {
+    match availability.check(Utc::now()) {
+        Ok(()) => "active".to_string(),
+        Err(Unavailable::Expired) => "expired".to_string(),
+        Err(Unavailable::Disabled) => match &availability.disabled_reason {
+            Some(reason) => format!("disabled ({reason})"),
+            None => "disabled".to_string(),
+        },
+    }
+}
+
+#[derive(Tabled)]
+struct MigrationRow {
+    #[tabled(rename = "Migration")]
+    name: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+#[derive(Tabled)]
+struct AuditRow {
+    #[tabled(rename = "Seq")]
+    seq: u64,
+    #[tabled(rename = "Time")]
+    timestamp: String,
+    #[tabled(rename = "Actor")]
+    actor: String,
+    #[tabled(rename = "Operation")]
+    operation: String,
+    #[tabled(rename = "Entity ID")]
+    entity_id: String,
+}
+
+impl From<AuditRecord> for AuditRow {
+    fn from(r: AuditRecord) -> Self {
+        Self {
+            seq: r.seq,
+            timestamp: r.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+            actor: format!("{} ({})", r.actor.subject, json_name(&r.actor.source)),
+            operation: json_name(&r.operation),
+            entity_id: r.entity_id,
+        }
+    }
+}
+
+/// The serialized name of a unit enum variant.
+fn json_name(value: &impl serde::Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn parse_audit_operation(value: &str) -> Result<AuditOperation, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("unknown audit operation `{value}`"))
+}
+
+/// Parse a unit enum variant from its serialized name.
+fn parse_json_name<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("unknown value `{value}`"))
+}
+
+fn print_migration_plan(plan: &MigrationPlan, dry_run: bool) {
+    if plan.migrations.is_empty() {
+        println!("No migrations to run.");
+        return;
+    }
+    let action = match (plan.direction, dry_run) {
+        (MigrationDirection::Up, true) => "Would apply",
+        (MigrationDirection::Up, false) => "Applied",
+        (MigrationDirection::Down, true) => "Would revert",
+        (MigrationDirection::Down, false) => "Reverted",
+    };
+    for name in &plan.migrations {
+        println!("{action} {name}");
+    }
+    if let Some(backup_path) = &plan.backup_path {
+        println!("Data file backup: {}", backup_path.display());
+    }
+    if dry_run {
+        if plan.data_file_diff.is_empty() {
+            println!("Data file unchanged.");
+        } else {
+            print!("{}", plan.data_file_diff);
+        }
+    }
+}
+
+fn print_import_report(report: &ImportReport) {
+    let verb = if report.dry_run {
+        "Would import"
+    } else {
+        "Imported"
+    };
+    for (kind, counts) in [("groups", &report.groups), ("entries", &report.entries)] {
+        println!(
+            "{verb} {kind} ({}): {} created, {} updated, {} skipped, {} deleted",
+            json_name(&report.mode),
+            counts.created,
+            counts.updated,
+            counts.skipped,
+            counts.deleted
+        );
+    }
+}
+
+/// Mutations made through the CLI are attributed to the OS user.
+fn cli_audit_actor() -> AuditActor {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    AuditActor::new(AuditActorSource::Cli, user)
+}
+
+/// Parse `username:hash` lines, skipping blanks and `#` comments.
+fn parse_htpasswd(content: &str) -> CliResult<Vec<(String, String)>> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| match line.split_once(':') {
+            Some((username, hash)) if !username.is_empty() && !hash.is_empty() => {
+                Ok((username.to_string(), hash.to_string()))
+            }
+            _ => Err(CliError::InvalidHtpasswdLine { line: line_number }),
+        })
+        .collect()
+}
+
+/// Check every htpasswd line against the store before anything is created,
+/// so a bad hash or a name clash aborts the import without a partial result.
+async fn ensure_htpasswd_importable(
+    store: &CredsManageStore,
+    lines: &[(String, String)],
+    group_ids: &[String],
+) -> CliResult<()> {
+    for group_id in group_ids {
+        store.get_group(group_id).await?;
+    }
+
+    let mut names: HashSet<String> = store
+        .list_entries()
+        .await?
+        .into_iter()
+        .map(|entry| entry.meta.name)
+        .collect();
+    for (username, password_hash) in lines {
+        MultiFormatBasicAuthCred::from_password_hash(username.clone(), password_hash.clone())?;
+        if !names.insert(username.clone()) {
+            return Err(CredsManageError::DuplicateEntryName {
+                name: username.clone(),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> CliResult<()> {
     tracing_subscriber::fmt()
@@ -197,27 +666,62 @@ async fn main() -> CliResult<()> {
     let config = CliConfig::load(&cli.config)?;
 
     let migrator = Migrator::default();
-    migrator.try_auto_migrate(&config.creds_manage)?;
-
     if let Commands::Migrate { action } = &cli.command {
         match action {
             MigrateAction::Up { steps } => {
-                migrator.up(&config.creds_manage, *steps)?;
+                let plan = migrator.up(&config.creds_manage, *steps)?;
+                print_migration_plan(&plan, false);
+            }
+            MigrateAction::Down { steps } => {
+                let plan = migrator.down(&config.creds_manage, Some(*steps))?;
+                print_migration_plan(&plan, false);
+            }
+            MigrateAction::Status => {
+                let rows: Vec<MigrationRow> = migrator
+                    .status(&config.creds_manage)?
+                    .into_iter()
+                    .map(|status| MigrationRow {
+                        name: status.name.to_string(),
+                        status: match status.state {
+                            MigrationState::Applied { applied_at } => {
+                                format!("applied {}", applied_at.format("%Y-%m-%d %H:%M"))
+                            }
+                            MigrationState::Pending => "pending".to_string(),
+                            MigrationState::NotApplicable => "not applicable".to_string(),
+                        },
+                    })
+                    .collect();
+                println!("{}", Table::new(rows));
+            }
+            MigrateAction::Plan { down, steps } => {
+                let direction = if *down {
+                    MigrationDirection::Down
+                } else {
+                    MigrationDirection::Up
+                };
+                let plan = migrator.plan(&config.creds_manage, direction, *steps)?;
+                print_migration_plan(&plan, true);
+            }
+            MigrateAction::RotateDataKey => {
+                migrator.try_auto_migrate(&config.creds_manage)?;
+                let key_id = FileBackend::reseal(&config.creds_manage).await?;
                 println!(
-                    "Migration completed: data file {}",
+                    "Data file {} sealed with key {key_id}",
                     config.creds_manage.data_path
                 );
             }
         }
         return Ok(());
     }
+    migrator.try_auto_migrate(&config.creds_manage)?;
 
-    let store = CredsManageStore::load(&config.creds_manage.data_path).await?;
+    let store = CredsManageStore::load_from_config(&config.creds_manage).await?;
+    let actor = cli_audit_actor();
 
     match cli.command {
         Commands::Entry { action } => match action {
             EntryAction::List => {
-                let entries = store.list_entries().await;
+                let entries = store.list_entries().await?;
                 if entries.is_empty() {
                     println!("No entries found.");
                 } else {
@@ -237,23 +741,63 @@ async fn main() -> CliResult<()> {
                 group_ids,
             } => {
                 let created = store
-                    .create_basic_entry(name, username, password, group_ids)
+                    .create_basic_entry(&actor, name, username, password, group_ids)
                     .await?;
                 println!(
                     "Created basic auth entry: {} ({})",
                     created.meta.name, created.meta.id
                 );
             }
-            EntryAction::CreateToken { name, group_ids } => {
-                let (created, token) = store.create_token_entry(name, group_ids).await?;
+            EntryAction::ImportHtpasswd { file, group_ids } => {
+                let content = std::fs::read_to_string(&file)
+                    .context(ReadInputSnafu { path: file.clone() })?;
+                let lines = parse_htpasswd(&content)?;
+                ensure_htpasswd_importable(&store, &lines, &group_ids).await?;
+                for (username, password_hash) in lines {
+                    let created = store
+                        .create_basic_entry_with_password_hash(
+                            &actor,
+                            username.clone(),
+                            username,
+                            password_hash,
+                            group_ids.clone(),
+                        )
+                        .await?;
+                    println!(
+                        "Imported basic auth entry: {} ({})",
+                        created.meta.name, created.meta.id
+                    );
+                }
+            }
+            EntryAction::CreateToken {
+                name,
+                group_ids,
+                expires_at,
+                not_before,
+                scopes,
+            } => {
+                let (created, token) = store
+                    .create_token_entry_with_constraints(
+                        &actor,
+                        name,
+                        group_ids,
+                        expires_at,
+                        TokenEntryConstraints { not_before, scopes },
+                    )
+                    .await?;
                 println!(
                     "Created token auth entry: {} ({})",
                     created.meta.name, created.meta.id
                 );
                 println!("Token (save this, it won't be shown again): {token}");
             }
-            EntryAction::Delete { id } => {
-                store.delete_entry(&id).await?;
+            EntryAction::Delete {
+                id,
+                expect_revision,
+            } => {
+                store
+                    .delete_entry_at_revision(&actor, &id, expect_revision)
+                    .await?;
                 println!("Deleted entry: {id}");
             }
             EntryAction::Update {
@@ -262,16 +806,78 @@ async fn main() -> CliResult<()> {
                 username,
                 password,
                 group_ids,
+                expect_revision,
             } => {
                 let updated = store
-                    .update_entry(&id, name, username, password, group_ids)
+                    .update_entry_at_revision(
+                        &actor,
+                        &id,
+                        expect_revision,
+                        UpdateEntryRequest {
+                            name,
+                            username,
+                            password,
+                            group_ids,
+                        },
+                    )
                     .await?;
                 println!("Updated entry: {} ({})", updated.meta.name, updated.meta.id);
             }
+            EntryAction::Disable { id, reason } => {
+                let current = store.get_entry(&id).await?.meta;
+                let updated = store
+                    .set_entry_availability_at_revision(
+                        &actor,
+                        &id,
+                        Some(current.revision),
+                        Availability {
+                            expires_at: current.availability.expires_at,
+                            ..Availability::disabled(reason)
+                        },
+                    )
+                    .await?;
+                println!(
+                    "Disabled entry: {} ({})",
+                    updated.meta.name, updated.meta.id
+                );
+            }
+            EntryAction::Enable { id } => {
+                let current = store.get_entry(&id).await?.meta;
+                let updated = store
+                    .set_entry_availability_at_revision(
+                        &actor,
+                        &id,
+                        Some(current.revision),
+                        Availability {
+                            expires_at: current.availability.expires_at,
+                            ..Availability::default()
+                        },
+                    )
+                    .await?;
+                println!("Enabled entry: {} ({})", updated.meta.name, updated.meta.id);
+            }
+            EntryAction::SetExpiry { id, expires_at } => {
+                let current = store.get_entry(&id).await?.meta;
+                let updated = store
+                    .set_entry_availability_at_revision(
+                        &actor,
+                        &id,
+                        Some(current.revision),
+                        Availability {
+                            expires_at,
+                            ..current.availability
+                        },
+                    )
+                    .await?;
+                println!(
+                    "Updated entry expiry: {} ({})",
+                    updated.meta.name, updated.meta.id
+                );
+            }
         },
         Commands::Group { action } => match action {
             GroupAction::List => {
-                let groups = store.list_groups().await;
+                let groups = store.list_groups().await?;
                 if groups.is_empty() {
                     println!("No groups found.");
                 } else {
@@ -284,23 +890,227 @@ async fn main() -> CliResult<()> {
                 let rows = vec![GroupRow::from(group)];
                 println!("{}", Table::new(rows));
             }
-            GroupAction::Create { name, entry_ids } => {
-                let group = Group::new(name);
-                let created = store.create_group(group, entry_ids).await?;
+            GroupAction::Create {
+                name,
+                entry_ids,
+                parent_ids,
+            } => {
+                let mut group = Group::new(name);
+                group.parent_ids = parent_ids.unwrap_or_default();
+                let created = store.create_group(&actor, group, entry_ids).await?;
                 println!("Created group: {} ({})", created.name, created.id);
             }
             GroupAction::Update {
                 id,
                 name,
                 entry_ids,
+                parent_ids,
+                expect_revision,
             } => {
-                let updated = store.update_group(&id, name, entry_ids).await?;
+                let updated = store
+                    .update_group_at_revision(
+                        &actor,
+                        &id,
+                        expect_revision,
+                        UpdateGroupRequest {
+                            name,
+                            parent_ids,
+                            entry_ids,
+                        },
+                    )
+                    .await?;
                 println!("Updated group: {} ({})", updated.name, updated.id);
             }
-            GroupAction::Delete { id } => {
-                store.delete_group(&id).await?;
+            GroupAction::Delete {
+                id,
+                expect_revision,
+            } => {
+                store
+                    .delete_group_at_revision(&actor, &id, expect_revision)
+                    .await?;
                 println!("Deleted group: {id}");
             }
+            GroupAction::Disable { id, reason } => {
+                let current = store.get_group(&id).await?;
+                let updated = store
+                    .set_group_availability_at_revision(
+                        &actor,
+                        &id,
+                        Some(current.revision),
+                        Availability {
+                            expires_at: current.availability.expires_at,
+                            ..Availability::disabled(reason)
+                        },
+                    )
+                    .await?;
+                println!("Disabled group: {} ({})", updated.name, updated.id);
+            }
+            GroupAction::Enable { id } => {
+                let current = store.get_group(&id).await?;
+                let updated = store
+                    .set_group_availability_at_revision(
+                        &actor,
+                        &id,
+                        Some(current.revision),
+                        Availability {
+                            expires_at: current.availability.expires_at,
+                            ..Availability::default()
+                        },
+                    )
+                    .await?;
+                println!("Enabled group: {} ({})", updated.name, updated.id);
+            }
+            GroupAction::SetExpiry { id, expires_at } => {
+                let current = store.get_group(&id).await?;
+                let updated = store
+                    .set_group_availability_at_revision(
+                        &actor,
+                        &id,
+                        Some(current.revision),
+                        Availability {
+                            expires_at,
+                            ..current.availability
+                        },
+                    )
+                    .await?;
+                println!("Updated group expiry: {} ({})", updated.name, updated.id);
+            }
+            GroupAction::SetIdentityAccess {
+                id,
+                session,
+                bearer,
+                issuer,
+                groups_claim,
+                any_of,
+                expect_revision,
+            } => {
+                let updated = store
+                    .set_group_identity_access_at_revision(
+                        &actor,
+                        &id,
+                        expect_revision,
+                        IdentityAccess {
+                            session,
+                            bearer,
+                            claims: ClaimRequirements {
+                                issuer,
+                                groups_claim,
+                                any_of: any_of.unwrap_or_default(),
+                            },
+                        },
+                    )
+                    .await?;
+                println!(
+                    "Updated group identity access: {} ({})",
+                    updated.name, updated.id
+                );
+            }
+            GroupAction::SetAccessRules {
+                id,
+                file,
+                expect_revision,
+            } => {
+                let content = std::fs::read_to_string(&file)
+                    .context(ReadInputSnafu { path: file.clone() })?;
+                let rules: Vec<AccessRule> =
+                    serde_json::from_str(&content).context(ParseAccessRulesSnafu { path: file })?;
+                let updated = store
+                    .set_group_access_rules_at_revision(&actor, &id, expect_revision, rules)
+                    .await?;
+                println!(
+                    "Updated group access rules: {} ({}), {} rule(s)",
+                    updated.name,
+                    updated.id,
+                    updated.access_rules.len()
+                );
+            }
+        },
+        Commands::Audit { action } => {
+            let audit_log = store
+                .audit_log()
+                .ok_or(CredsManageError::AuditLogDisabled)?;
+            match action {
+                AuditAction::List {
+                    entity_id,
+                    operation,
+                    actor,
+                    since,
+                    until,
+                    limit,
+                } => {
+                    let records = audit_log
+                        .query(AuditQuery {
+                            entity_id,
+                            operation,
+                            actor,
+                            since,
+                            until,
+                            limit: Some(limit),
+                        })
+                        .await?;
+                    if records.is_empty() {
+                        println!("No audit records found.");
+                    } else {
+                        let rows: Vec<AuditRow> = records.into_iter().map(Into::into).collect();
+                        println!("{}", Table::new(rows));
+                    }
+                }
+                AuditAction::Verify => {
+                    let verification = audit_log.verify().await?;
+                    if !verification.valid {
+                        return Err(CliError::AuditChainBroken {
+                            seq: verification.broken_at_seq.unwrap_or_default(),
+                            message: verification.message.unwrap_or_default(),
+                        });
+                    }
+                    println!(
+                        "Audit log intact: {} records, last hash {}",
+                        verification.records, verification.last_hash
+                    );
+                }
+            }
+        }
+        Commands::Store { action } => match action {
+            StoreAction::Export { output } => {
+                let archive = store.export_archive().await?;
+                let json =
+                    serde_json::to_string_pretty(&archive).expect("store archive should serialize");
+                match output {
+                    Some(path) => {
+                        std::fs::write(&path, json)
+                            .context(WriteOutputSnafu { path: path.clone() })?;
+                        println!(
+                            "Exported {} groups and {} entries to {path}",
+                            archive.groups.len(),
+                            archive.basic_creds.len() + archive.token_creds.len()
+                        );
+                    }
+                    None => println!("{json}"),
+                }
+            }
+            StoreAction::Import {
+                file,
+                mode,
+                on_conflict,
+                dry_run,
+            } => {
+                let content = std::fs::read_to_string(&file)
+                    .context(ReadInputSnafu { path: file.clone() })?;
+                let archive: StoreArchive =
+                    serde_json::from_str(&content).context(ParseArchiveSnafu { path: file })?;
+                let report = store
+                    .import_archive(
+                        &actor,
+                        archive,
+                        ImportOptions {
+                            mode,
+                            on_conflict,
+                            dry_run,
+                        },
+                    )
+                    .await?;
+                print_import_report(&report);
+            }
         },
         Commands::Migrate { .. } => unreachable!("handled above"),
     }
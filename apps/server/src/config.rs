@@ -12,7 +12,7 @@ use securitydept_core::{
         BasicAuthContextRejectZonePostAuthRedirectOverrideValidator, BasicAuthZoneConfig,
         ResolvedBasicAuthContextConfig,
     },
-    creds::Argon2BasicAuthCred,
+    creds::{Argon2BasicAuthCred, SigningKeyringConfig},
     creds_manage::CredsManageConfig,
     oidc::MokaPendingOauthStoreConfig,
     realip::RealIpResolveConfig,
@@ -47,6 +47,7 @@ use securitydept_core::{
     },
 };
 use serde::Deserialize;
+use url::Url;
 
 use crate::error::{ServerError, ServerResult};
 
@@ -284,10 +285,16 @@ pub struct ServerConfig {
     pub basic_auth_context: BasicAuthContextConfig<Argon2BasicAuthCred>,
     #[serde(default)]
     pub real_ip_resolve: Option<RealIpResolveConfig>,
+    #[serde(default)]
+    pub forward_auth: ForwardAuthConfig,
 
     // -- Infra --
     #[serde(default)]
     pub creds_manage: CredsManageConfig,
+    /// Managed signing keyring. When set, its public keys are served at
+    /// `/.well-known/jwks.json` and rotated in the background.
+    #[serde(default)]
+    pub signing_keyring: Option<SigningKeyringConfig>,
 }
 
 impl ServerConfig {
@@ -324,13 +331,19 @@ impl ServerConfig {
             config.post_auth_redirect = fixed_post_auth_redirect.clone();
         }
 
-        config
+        let mut resolved = config
             .resolve_all_with_validator(&SessionContextFixedPostAuthRedirectValidator::new(
                 fixed_post_auth_redirect,
             ))
             .map_err(|e| ServerError::InvalidConfig {
                 message: e.to_string(),
-            })
+            })?;
+        // Forward-auth login redirects return to the protected app.
+        if let Some(login_redirect) = &self.forward_auth.login_redirect {
+            resolved.absolute_post_auth_redirect_targets =
+                login_redirect.allowed_redirect_targets.clone();
+        }
+        Ok(resolved)
     }
 
     pub fn resolved_basic_auth_context_config(
@@ -459,6 +472,21 @@ impl ServerConfig {
                 message: e.to_string(),
             })?;
         }
+        if let Some(login_redirect) = &self.forward_auth.login_redirect {
+            login_redirect
+                .post_auth_redirect()
+                .validate_as_uri()
+                .map_err(|e| ServerError::InvalidConfig {
+                    message: format!("forward_auth.login_redirect: {e}"),
+                })?;
+        }
+        if let Some(signing_keyring) = &self.signing_keyring {
+            signing_keyring
+                .validate()
+                .map_err(|e| ServerError::InvalidConfig {
+                    message: format!("signing_keyring: {e}"),
+                })?;
+        }
         Ok(())
     }
 }
@@ -481,6 +509,35 @@ pub struct ServerCoreConfig {
     pub external_base_url: ExternalBaseUrl,
 }
 
+/// Behaviour shared by the forward-auth adapters.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ForwardAuthConfig {
+    /// When set, browser navigations without credentials to a group that
+    /// accepts OIDC sessions are redirected to the session login instead of
+    /// getting a `401`.
+    #[serde(default)]
+    pub login_redirect: Option<ForwardAuthLoginRedirectConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForwardAuthLoginRedirectConfig {
+    /// External URL of the session login route, e.g.
+    /// `https://auth.example.com/auth/session/login`. It cannot be inferred
+    /// from forward-auth requests, which carry the protected app's host.
+    pub login_url: Url,
+    /// Original URLs, rebuilt from `X-Forwarded-Proto`, `X-Forwarded-Host`
+    /// and `X-Forwarded-Uri`, that users may return to after login. Requests
+    /// to any other URL keep getting a `401`.
+    pub allowed_redirect_targets: Vec<RedirectTargetRule>,
+}
+
+impl ForwardAuthLoginRedirectConfig {
+    /// The post-auth redirect policy for original URLs.
+    pub fn post_auth_redirect(&self) -> RedirectTargetConfig {
+        RedirectTargetConfig::dynamic_targets(self.allowed_redirect_targets.iter().cloned())
+    }
+}
+
 fn default_host() -> String {
     "0.0.0.0".to_string()
 }
@@ -6,12 +6,15 @@ mod middleware;
 mod routes;
 mod state;
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use clap::{Parser, Subcommand};
 use securitydept_core::{
     basic_auth_context::BasicAuthContext,
-    creds::hash_password_argon2,
+    creds::{JwtSigningKeyTrait, LocalJwtSigningKeyring, hash_password_argon2},
     creds_manage::{migrations::Migrator, store::CredsManageStore},
     realip::RealIpResolver,
     token_set_context::{
@@ -23,8 +26,9 @@ use securitydept_core::{
     },
 };
 use snafu::ResultExt;
+use tokio::sync::RwLock;
 use tower_sessions_memory_store::MemoryStore;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
 
 use crate::{
@@ -125,7 +129,15 @@ async fn run_serve(config_arg: String) -> ServerResult<()> {
 
     Migrator::default().try_auto_migrate(&config.creds_manage)?;
 
-    let store = CredsManageStore::load(&config.creds_manage.data_path).await?;
+    let store = Arc::new(CredsManageStore::load_from_config(&config.creds_manage).await?);
+    spawn_entry_usage_flush(
+        store.clone(),
+        Duration::from_secs(config.creds_manage.usage_flush_interval_secs.max(1)),
+    );
+    spawn_expiry_sweep(
+        store.clone(),
+        Duration::from_secs(config.creds_manage.expiry_sweep_interval_secs.max(1)),
+    );
 
     info!(external_base_url = ?config.server.external_base_url, "Resolved external base URL config");
 
@@ -148,6 +160,15 @@ async fn run_serve(config_arg: String) -> ServerResult<()> {
 
     let session_context_config = config.resolved_session_context_config()?;
     let basic_auth_context_config = config.resolved_basic_auth_context_config()?;
+    for user in &basic_auth_context_config.creds.users {
+        if basic_auth_context_config.creds.needs_rehash(user) {
+            warn!(
+                username = %user.username,
+                "Dashboard user password hash is weaker than basic_auth_context.argon2; \
+                 replace it in the config with a hash generated at the current policy"
+            );
+        }
+    }
     let session_context_store = MemoryStore::default();
     let real_ip_resolver = if let Some(real_ip_config) = config.real_ip_resolve.clone() {
         Some(Arc::new(
@@ -189,11 +210,25 @@ async fn run_serve(config_arg: String) -> ServerResult<()> {
         info!("Propagation forwarder enabled");
     }
 
+    let signing_keyring = if let Some(keyring_config) = config.signing_keyring.clone() {
+        let keyring = LocalJwtSigningKeyring::open(keyring_config)?;
+        info!(
+            path = %keyring.config().path.display(),
+            keys = keyring.to_public_jwk_set().keys.len(),
+            "Signing keyring loaded"
+        );
+        let keyring = Arc::new(RwLock::new(keyring));
+        spawn_signing_keyring_rotation(keyring.clone());
+        Some(keyring)
+    } else {
+        None
+    };
+
     let bind_addr = format!("{}:{}", config.server.host, config.server.port);
     info!(addr = %bind_addr, "Starting server");
 
     let state = ServerState {
-        creds_manage_store: Arc::new(store),
+        creds_manage_store: store.clone(),
         backend_oidc_runtime: Arc::new(backend_oidc_runtime),
         frontend_oidc_runtime,
         substrate_runtime,
@@ -205,10 +240,12 @@ async fn run_serve(config_arg: String) -> ServerResult<()> {
             })?,
         ),
         session_context_config: Arc::new(session_context_config.clone()),
+        session_store: session_context_store.clone(),
         real_ip_resolver,
         oidc_client,
         oauth_resource_server_verifier,
         propagation_forwarder,
+        signing_keyring,
         config: Arc::new(config),
     };
 
@@ -226,9 +263,113 @@ async fn run_serve(config_arg: String) -> ServerResult<()> {
         listener,
         app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
     )
+    .with_graceful_shutdown(shutdown_signal())
     .await
     .boxed()
     .context(ServerBootSnafu)?;
 
+    if let Err(e) = store.flush_entry_usage().await {
+        warn!(error = %e, "Failed to write entry usage statistics on shutdown");
+    }
+
     Ok(())
 }
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+    info!("Shutting down");
+}
+
+/// Periodically write the entry usage statistics recorded by forward auth,
+/// so the store is not rewritten on every request.
+fn spawn_entry_usage_flush(store: Arc<CredsManageStore>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = store.flush_entry_usage().await {
+                warn!(error = %e, "Failed to write entry usage statistics; retrying later");
+            }
+        }
+    });
+}
+
+/// Periodically look for expired entries and groups so they are flagged in
+/// the health output.
+fn spawn_expiry_sweep(store: Arc<CredsManageStore>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = store.sweep_expired().await {
+                warn!(error = %e, "Failed to sweep expired creds-manage entries");
+            }
+        }
+    });
+}
+
+/// Upper bound between keyring checks, so clock jumps and edits to the
+/// keyring file are picked up without waiting for a full rotation interval.
+const SIGNING_KEYRING_MAX_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Rotate the signing keyring when due and prune keys past their overlap
+/// window.
+fn spawn_signing_keyring_rotation(keyring: Arc<RwLock<LocalJwtSigningKeyring>>) {
+    tokio::spawn(async move {
+        loop {
+            let next_transition_at = keyring.read().await.next_transition_at();
+            let wait = next_transition_at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+                .min(SIGNING_KEYRING_MAX_CHECK_INTERVAL);
+            tokio::time::sleep(wait).await;
+
+            // Generate under the read guard so verifications keep running;
+            // RSA key generation is CPU-bound, so keep it off the async
+            // workers too. The write guard is only held for the swap.
+            let rotation = {
+                let keyring = keyring.read().await;
+                tokio::task::block_in_place(|| keyring.prepare_rotation())
+            };
+            let rotated = match rotation {
+                Ok(Some(rotation)) => {
+                    let mut keyring = keyring.write().await;
+                    keyring
+                        .apply_rotation(rotation)
+                        .map(|applied| applied.then(|| keyring.active_key().kid().to_string()))
+                }
+                Ok(None) => Ok(None),
+                Err(e) => Err(e),
+            };
+            match rotated {
+                Ok(Some(kid)) => info!(kid = %kid, "Signing keyring rotated"),
+                Ok(None) => {}
+                Err(e) => {
+                    warn!(error = %e, "Signing keyring rotation failed; retrying later");
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                }
+            }
+        }
+    });
+}
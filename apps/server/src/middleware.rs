@@ -8,6 +8,7 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use securitydept_core::{
+    creds_manage::{AuditActor, AuditActorSource},
     session_context::{SessionContextError, SessionContextSession},
     token_set_context::access_token_substrate::AccessTokenSubstrateResourceService,
     utils::{
@@ -42,7 +43,7 @@ const CREDENTIAL_SOURCE_NONE: &str = "none";
 pub async fn require_basic_auth(
     Extension(state): Extension<ServerState>,
     ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> ServerResult<Response> {
     let propagation =
@@ -87,6 +88,9 @@ pub async fn require_basic_auth(
     }
 
     if authorization_result.map_err(crate::error::ServerError::from)? {
+        if let Some(actor) = authorization.as_deref().and_then(basic_audit_actor) {
+            request.extensions_mut().insert(actor);
+        }
         Ok(next.run(request).await)
     } else if let Some(zone) = state
         .basic_auth_context
@@ -104,7 +108,7 @@ pub async fn require_dashboard_auth(
     Extension(state): Extension<ServerState>,
     ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
     session: Session,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> ServerResult<Response> {
     let has_cookie_header = request.headers().contains_key(header::COOKIE);
@@ -238,6 +242,15 @@ pub async fn require_dashboard_auth(
             "Dashboard bearer authentication succeeded",
         );
 
+        // Client-credentials tokens may carry no subject; fall back to the
+        // client they were issued to.
+        let actor_subject = resource_token_principal
+            .subject
+            .or(resource_token_principal.authorized_party)
+            .unwrap_or_else(|| "unknown".to_string());
+        request
+            .extensions_mut()
+            .insert(AuditActor::new(AuditActorSource::Bearer, actor_subject));
         return Ok(next.run(request).await);
     }
 
@@ -246,7 +259,7 @@ pub async fn require_dashboard_auth(
             SessionContextSession::from_resolved_config(session, &state.session_context_config);
 
         match handle.get::<HashMap<String, Value>>().await {
-            Ok(Some(_)) => {
+            Ok(Some(context)) => {
                 if has_propagation_directive {
                     let diagnosis = propagation_auth_mismatch_diagnosis(
                         CREDENTIAL_SOURCE_SESSION,
@@ -284,6 +297,10 @@ pub async fn require_dashboard_auth(
                     "Dashboard session-cookie authentication succeeded",
                 );
 
+                request.extensions_mut().insert(AuditActor::new(
+                    AuditActorSource::Session,
+                    context.principal.subject,
+                ));
                 return Ok(next.run(request).await);
             }
             Ok(None) => {
@@ -381,6 +398,9 @@ pub async fn require_dashboard_auth(
                     &diagnosis,
                     "Dashboard basic-auth authentication succeeded",
                 );
+                if let Some(actor) = basic_audit_actor(authorization) {
+                    request.extensions_mut().insert(actor);
+                }
                 return Ok(next.run(request).await);
             }
             Ok(false) => {
@@ -449,6 +469,12 @@ pub async fn require_dashboard_auth(
     })
 }
 
+/// Audit actor for accepted Basic credentials, identified by username.
+fn basic_audit_actor(authorization: &str) -> Option<AuditActor> {
+    securitydept_core::creds::parse_basic_auth_header_opt(authorization)
+        .map(|(username, _)| AuditActor::new(AuditActorSource::Basic, username))
+}
+
 fn dashboard_credential_source(
     authorization: Option<&str>,
     has_cookie_header: bool,
@@ -0,0 +1,172 @@
+use axum::{Extension, Json, extract::Query};
+use securitydept_core::{
+    creds_manage::{
+        AuditLog, CredsManageError,
+        audit::{AuditQuery, AuditRecord, AuditVerification},
+    },
+    utils::observability::{
+        AuthFlowDiagnosis, AuthFlowDiagnosisField, AuthFlowDiagnosisOutcome, AuthFlowOperation,
+    },
+};
+
+use crate::{
+    diagnosis::{RouteDiagnosisContext, log_route_diagnosis, log_route_diagnosis_error},
+    error::ServerError,
+    state::ServerState,
+};
+
+const AUDIT_ENTITY_KIND: &str = "audit";
+
+fn audit_route_base_diagnosis(
+    operation: &'static str,
+    route: &'static str,
+    operation_kind: &'static str,
+) -> AuthFlowDiagnosis {
+    AuthFlowDiagnosis::started(operation)
+        .field(AuthFlowDiagnosisField::ROUTE, route)
+        .field(AuthFlowDiagnosisField::METHOD, "GET")
+        .field(AuthFlowDiagnosisField::ENTITY_KIND, AUDIT_ENTITY_KIND)
+        .field(AuthFlowDiagnosisField::OPERATION_KIND, operation_kind)
+}
+
+fn audit_log(state: &ServerState) -> Result<&AuditLog, CredsManageError> {
+    state
+        .creds_manage_store
+        .audit_log()
+        .ok_or(CredsManageError::AuditLogDisabled)
+}
+
+/// GET /api/audit
+///
+/// Query: entity_id, operation, actor, since, until, limit (default 100).
+/// Records are returned newest first.
+pub async fn list(
+    Extension(state): Extension<ServerState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditRecord>>, ServerError> {
+    let result = match audit_log(&state) {
+        Ok(audit_log) => audit_log.query(query).await,
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok(records) => {
+            let diagnosis = audit_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_AUDIT_LIST,
+                "/api/audit",
+                "list",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(AuthFlowDiagnosisField::RESULT_COUNT, records.len());
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/audit",
+                    method: "GET",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Audit list completed",
+            );
+            Ok(Json(records))
+        }
+        Err(error) => {
+            let diagnosis = audit_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_AUDIT_LIST,
+                "/api/audit",
+                "list",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Failed);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/audit",
+                    method: "GET",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Audit list failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
+/// GET /api/audit/verify
+///
+/// Walk the hash chain of the audit log. A broken chain is reported in the
+/// response body, not as an error status.
+pub async fn verify(
+    Extension(state): Extension<ServerState>,
+) -> Result<Json<AuditVerification>, ServerError> {
+    let result = match audit_log(&state) {
+        Ok(audit_log) => audit_log.verify().await,
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok(verification) => {
+            let diagnosis = audit_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_AUDIT_VERIFY,
+                "/api/audit/verify",
+                "verify",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(AuthFlowDiagnosisField::RESULT_COUNT, verification.records)
+            .field("chain_valid", verification.valid);
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/audit/verify",
+                    method: "GET",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Audit verify completed",
+            );
+            Ok(Json(verification))
+        }
+        Err(error) => {
+            let diagnosis = audit_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_AUDIT_VERIFY,
+                "/api/audit/verify",
+                "verify",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Failed);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/audit/verify",
+                    method: "GET",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Audit verify failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    use super::*;
+    use crate::routes::test_support::{assert_server_error_envelope, test_server_state};
+
+    #[tokio::test]
+    async fn list_without_audit_log_reports_disabled() {
+        let state = test_server_state("audit-disabled").await;
+
+        let response = list(Extension(state), Query(AuditQuery::default()))
+            .await
+            .expect_err("audit list should fail without an audit log")
+            .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::NOT_FOUND,
+            "invalid_request",
+            "audit_log_disabled",
+            "none",
+        )
+        .await;
+    }
+}
@@ -1,8 +1,11 @@
-use axum::{Extension, Json, extract::Path};
+use axum::{Extension, Json, extract::Path, http::HeaderMap};
 use securitydept_core::{
-    creds_manage::models::{
-        AuthEntry, CreateBasicEntryRequest, CreateBasicEntryResponse, CreateTokenEntryRequest,
-        CreateTokenEntryResponse, UpdateEntryRequest,
+    creds_manage::{
+        AuditActor,
+        models::{
+            AuthEntry, Availability, CreateBasicEntryRequest, CreateBasicEntryResponse,
+            CreateTokenEntryRequest, CreateTokenEntryResponse, UpdateEntryRequest,
+        },
     },
     utils::observability::{
         AuthFlowDiagnosis, AuthFlowDiagnosisField, AuthFlowDiagnosisOutcome, AuthFlowOperation,
@@ -12,6 +15,7 @@ use securitydept_core::{
 use crate::{
     diagnosis::{RouteDiagnosisContext, log_route_diagnosis, log_route_diagnosis_error},
     error::ServerError,
+    routes::revision::{WithETag, if_match_revision, with_etag},
     state::ServerState,
 };
 
@@ -66,8 +70,32 @@ fn entry_route_failure_diagnosis(
 }
 
 /// GET /api/entries
-pub async fn list(Extension(state): Extension<ServerState>) -> Json<Vec<AuthEntry>> {
-    let entries = state.creds_manage_store.list_entries().await;
+pub async fn list(
+    Extension(state): Extension<ServerState>,
+) -> Result<Json<Vec<AuthEntry>>, ServerError> {
+    let entries = match state.creds_manage_store.list_entries().await {
+        Ok(entries) => entries,
+        Err(error) => {
+            let diagnosis = entry_route_failure_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_ENTRY_LIST,
+                "/api/entries",
+                "GET",
+                "list",
+                None,
+            );
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/entries",
+                    method: "GET",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Entries list failed",
+            );
+            return Err(error.into());
+        }
+    };
     let diagnosis = entry_route_base_diagnosis(
         AuthFlowOperation::CREDS_MANAGE_ENTRY_LIST,
         "/api/entries",
@@ -87,14 +115,16 @@ pub async fn list(Extension(state): Extension<ServerState>) -> Json<Vec<AuthEntr
         "Entries list completed",
     );
 
-    Json(entries)
+    Ok(Json(entries))
 }
 
 /// GET /api/entries/:id
+///
+/// The `ETag` is the entry's revision.
 pub async fn get(
     Extension(state): Extension<ServerState>,
     Path(id): Path<String>,
-) -> Result<Json<AuthEntry>, ServerError> {
+) -> Result<WithETag<AuthEntry>, ServerError> {
     match state.creds_manage_store.get_entry(&id).await {
         Ok(entry) => {
             let diagnosis = entry_route_base_diagnosis(
@@ -115,7 +145,7 @@ pub async fn get(
                 &diagnosis,
                 "Entry get completed",
             );
-            Ok(Json(entry))
+            Ok(with_etag(entry.meta.revision, entry))
         }
         Err(error) => {
             let diagnosis = entry_route_failure_diagnosis(
@@ -143,12 +173,13 @@ pub async fn get(
 /// POST /api/entries/basic
 pub async fn create_basic(
     Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
     Json(req): Json<CreateBasicEntryRequest>,
 ) -> Result<Json<CreateBasicEntryResponse>, ServerError> {
     let group_ids_count = req.group_ids.len();
     match state
         .creds_manage_store
-        .create_basic_entry(req.name, req.username, req.password, req.group_ids)
+        .create_basic_entry(&actor, req.name, req.username, req.password, req.group_ids)
         .await
     {
         Ok(created) => {
@@ -200,12 +231,19 @@ pub async fn create_basic(
 /// POST /api/entries/token
 pub async fn create_token(
     Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
     Json(req): Json<CreateTokenEntryRequest>,
 ) -> Result<Json<CreateTokenEntryResponse>, ServerError> {
     let group_ids_count = req.group_ids.len();
     match state
         .creds_manage_store
-        .create_token_entry(req.name, req.group_ids)
+        .create_token_entry_with_constraints(
+            &actor,
+            req.name,
+            req.group_ids,
+            req.expires_at,
+            req.constraints,
+        )
         .await
     {
         Ok((created, token)) => {
@@ -250,15 +288,21 @@ pub async fn create_token(
 }
 
 /// PUT /api/entries/:id
+///
+/// With `If-Match`, the update only applies while the entry's `ETag` still
+/// matches and fails with 412 otherwise.
 pub async fn update(
     Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(req): Json<UpdateEntryRequest>,
-) -> Result<Json<AuthEntry>, ServerError> {
+) -> Result<WithETag<AuthEntry>, ServerError> {
+    let expected_revision = if_match_revision(&headers)?;
     let group_ids_count = req.group_ids.as_ref().map_or(0, Vec::len);
     match state
         .creds_manage_store
-        .update_entry(&id, req.name, req.username, req.password, req.group_ids)
+        .update_entry_at_revision(&actor, &id, expected_revision, req)
         .await
     {
         Ok(updated) => {
@@ -271,7 +315,8 @@ pub async fn update(
             .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
             .field(AuthFlowDiagnosisField::HAS_TARGET_ID, true)
             .field(AuthFlowDiagnosisField::TARGET_ID, id.clone())
-            .field(AuthFlowDiagnosisField::GROUP_IDS_COUNT, group_ids_count);
+            .field(AuthFlowDiagnosisField::GROUP_IDS_COUNT, group_ids_count)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
             log_route_diagnosis(
                 RouteDiagnosisContext {
                     route: "/api/entries/:id",
@@ -281,7 +326,7 @@ pub async fn update(
                 &diagnosis,
                 "Entry update completed",
             );
-            Ok(Json(updated))
+            Ok(with_etag(updated.meta.revision, updated))
         }
         Err(error) => {
             let diagnosis = entry_route_failure_diagnosis(
@@ -291,7 +336,8 @@ pub async fn update(
                 "update",
                 Some(&id),
             )
-            .field(AuthFlowDiagnosisField::GROUP_IDS_COUNT, group_ids_count);
+            .field(AuthFlowDiagnosisField::GROUP_IDS_COUNT, group_ids_count)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
             log_route_diagnosis_error(
                 RouteDiagnosisContext {
                     route: "/api/entries/:id",
@@ -307,12 +353,92 @@ pub async fn update(
     }
 }
 
+/// PUT /api/entries/:id/availability
+///
+/// Replace the disabled flag, disabled reason and expiry of an entry. The
+/// credential material is kept. With `If-Match`, the change only applies
+/// while the entry's `ETag` still matches.
+pub async fn availability(
+    Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<Availability>,
+) -> Result<WithETag<AuthEntry>, ServerError> {
+    let expected_revision = if_match_revision(&headers)?;
+    let disabled = req.disabled;
+    let has_expiry = req.expires_at.is_some();
+    match state
+        .creds_manage_store
+        .set_entry_availability_at_revision(&actor, &id, expected_revision, req)
+        .await
+    {
+        Ok(updated) => {
+            let diagnosis = entry_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_ENTRY_AVAILABILITY,
+                "/api/entries/:id/availability",
+                "PUT",
+                "availability",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(AuthFlowDiagnosisField::HAS_TARGET_ID, true)
+            .field(AuthFlowDiagnosisField::TARGET_ID, id.clone())
+            .field(AuthFlowDiagnosisField::DISABLED, disabled)
+            .field(AuthFlowDiagnosisField::HAS_EXPIRY, has_expiry)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/entries/:id/availability",
+                    method: "PUT",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Entry availability update completed",
+            );
+            Ok(with_etag(updated.meta.revision, updated))
+        }
+        Err(error) => {
+            let diagnosis = entry_route_failure_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_ENTRY_AVAILABILITY,
+                "/api/entries/:id/availability",
+                "PUT",
+                "availability",
+                Some(&id),
+            )
+            .field(AuthFlowDiagnosisField::DISABLED, disabled)
+            .field(AuthFlowDiagnosisField::HAS_EXPIRY, has_expiry)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/entries/:id/availability",
+                    method: "PUT",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Entry availability update failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
 /// DELETE /api/entries/:id
+///
+/// With `If-Match`, the entry is only deleted while its `ETag` still
+/// matches.
 pub async fn delete(
     Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
     Path(id): Path<String>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, ServerError> {
-    match state.creds_manage_store.delete_entry(&id).await {
+    let expected_revision = if_match_revision(&headers)?;
+    match state
+        .creds_manage_store
+        .delete_entry_at_revision(&actor, &id, expected_revision)
+        .await
+    {
         Ok(()) => {
             let diagnosis = entry_route_base_diagnosis(
                 AuthFlowOperation::CREDS_MANAGE_ENTRY_DELETE,
@@ -359,8 +485,16 @@ pub async fn delete(
 
 #[cfg(test)]
 mod tests {
-    use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
-    use securitydept_core::creds_manage::models::{AuthEntryKind, AuthEntryMeta};
+    use axum::{
+        Extension, Json,
+        extract::Path,
+        http::{HeaderValue, StatusCode, header},
+        response::IntoResponse,
+    };
+    use securitydept_core::creds_manage::{
+        AuditActorSource,
+        models::{AuthEntryKind, AuthEntryMeta},
+    };
 
     use super::*;
     use crate::routes::test_support::{assert_server_error_envelope, test_server_state};
@@ -370,6 +504,8 @@ mod tests {
             meta: AuthEntryMeta::new("api-token".to_string(), vec!["group-1".to_string()]),
             kind: AuthEntryKind::Token,
             username: None,
+            token_prefix: None,
+            constraints: Default::default(),
         }
     }
 
@@ -415,6 +551,7 @@ mod tests {
 
         let response = create_basic(
             Extension(state),
+            Extension(AuditActor::new(AuditActorSource::Session, "tester")),
             Json(CreateBasicEntryRequest {
                 name: "ops-user".to_string(),
                 username: "   ".to_string(),
@@ -435,4 +572,57 @@ mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn availability_with_stale_if_match_returns_precondition_failed() {
+        let state = test_server_state("entry-availability-revision").await;
+        let actor = AuditActor::new(AuditActorSource::Session, "tester");
+        let entry = state
+            .creds_manage_store
+            .create_basic_entry(
+                &actor,
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                Vec::new(),
+            )
+            .await
+            .expect("entry should be created");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MATCH,
+            HeaderValue::from_str(&format!("\"{}\"", entry.meta.revision)).unwrap(),
+        );
+
+        let (etag, Json(updated)) = availability(
+            Extension(state.clone()),
+            Extension(actor.clone()),
+            Path(entry.meta.id.clone()),
+            headers.clone(),
+            Json(Availability::disabled(None)),
+        )
+        .await
+        .expect("availability at the current revision should be replaced");
+        assert_eq!(etag[0].1, format!("\"{}\"", updated.meta.revision));
+
+        let response = availability(
+            Extension(state),
+            Extension(actor),
+            Path(entry.meta.id.clone()),
+            headers,
+            Json(Availability::default()),
+        )
+        .await
+        .expect_err("availability at a stale revision should be refused")
+        .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::PRECONDITION_FAILED,
+            "conflict",
+            "revision_mismatch",
+            "none",
+        )
+        .await;
+    }
 }
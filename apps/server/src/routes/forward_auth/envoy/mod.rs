@@ -0,0 +1,572 @@
+//! Envoy `ext_authz` adapters, for both the HTTP and the gRPC authorization
+//! service. Istio's `CUSTOM` authorization policies use the same protocol.
+//!
+//! The HTTP service receives the original method and the original path
+//! appended to `path_prefix`, so the route takes any method and path below
+//! the group. Envoy only copies `X-Auth-User` upstream when it is listed in
+//! `allowed_upstream_headers`; the required scopes can be sent in the
+//! `X-Forward-Auth-Scope` header through `headers_to_add`.
+//!
+//! The gRPC service reads the group and scopes from the `group` and `scope`
+//! context extensions. It is served on the main listener, which accepts
+//! HTTP/2 without TLS for Envoy's gRPC client.
+
+use std::{future::Future, net::SocketAddr, pin::Pin};
+
+use axum::{
+    Extension,
+    extract::{ConnectInfo, Path, Request},
+    http::{HeaderMap, HeaderName, Method, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use securitydept_core::utils::observability::{AuthFlowDiagnosis, AuthFlowDiagnosisField};
+use serde::Deserialize;
+use tonic::{Code, Status, server::UnaryService};
+use tonic_prost::ProstCodec;
+
+use self::proto::{
+    CheckRequest, CheckResponse, DeniedHttpResponse, HeaderValueOption, HttpRequest, HttpStatus,
+    OkHttpResponse, RpcStatus,
+};
+use super::{
+    OriginalRequest, WWW_AUTHENTICATE_CHALLENGE, check_forward_auth, login_redirect_location,
+    rejection_response, resolved_client_ip, split_scopes,
+};
+use crate::{
+    diagnosis::{RouteDiagnosisContext, log_route_diagnosis},
+    state::ServerState,
+};
+
+pub mod proto;
+
+/// Path of the `envoy.service.auth.v3.Authorization/Check` method.
+pub const GRPC_CHECK_PATH: &str = "/envoy.service.auth.v3.Authorization/Check";
+
+/// Request header carrying the space-separated scopes required by the HTTP
+/// service.
+pub const SCOPE_HEADER: &str = "x-forward-auth-scope";
+
+/// Path parameters of the HTTP service routes.
+#[derive(Debug, Deserialize)]
+pub struct EnvoyHttpPath {
+    pub group: String,
+    /// The original request path without its leading `/`.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// ANY /api/forwardauth/envoy/:group/*path
+///
+/// Envoy `ext_authz` HTTP service: returns 200 with `X-Auth-User` if
+/// authenticated, 401 with a challenge that Envoy passes to the client
+/// otherwise.
+pub async fn http(
+    Extension(state): Extension<ServerState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    Path(path): Path<EnvoyHttpPath>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    let client_ip = resolved_client_ip(&state, &headers, peer_addr).await;
+    let original = OriginalRequest {
+        proto: headers
+            .get("x-forwarded-proto")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        method: Some(method.to_string()),
+        host: headers
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        uri: Some(match uri.query() {
+            Some(query) => format!("/{}?{query}", path.path.unwrap_or_default()),
+            None => format!("/{}", path.path.unwrap_or_default()),
+        }),
+    };
+    let required_scopes = split_scopes(
+        headers
+            .get(SCOPE_HEADER)
+            .and_then(|value| value.to_str().ok()),
+    );
+    match check_forward_auth(
+        &state,
+        &path.group,
+        &required_scopes,
+        &headers,
+        &original,
+        client_ip,
+        "envoy_http",
+    )
+    .await
+    {
+        Ok((entry_name, diagnosis)) => {
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/forwardauth/envoy/:group/*path",
+                    method: method.as_str(),
+                    status: Some(StatusCode::OK.as_u16()),
+                },
+                &diagnosis,
+                "Envoy forward auth passed",
+            );
+            let mut resp_headers = HeaderMap::new();
+            if let Ok(val) = entry_name.parse() {
+                resp_headers.insert("X-Auth-User", val);
+            }
+            (StatusCode::OK, resp_headers).into_response()
+        }
+        Err((status, diagnosis)) => {
+            let login_location = login_redirect_location(&state, &headers, &original, &diagnosis);
+            let response = rejection_response(status, login_location.as_deref());
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/forwardauth/envoy/:group/*path",
+                    method: method.as_str(),
+                    status: Some(response.status().as_u16()),
+                },
+                &diagnosis.field(
+                    AuthFlowDiagnosisField::LOGIN_REDIRECT,
+                    login_location.is_some(),
+                ),
+                "Envoy forward auth rejected",
+            );
+            response
+        }
+    }
+}
+
+/// POST /envoy.service.auth.v3.Authorization/Check
+///
+/// Envoy `ext_authz` gRPC service. Rejections are answered with a
+/// `denied_response` rather than a gRPC error, so Envoy sends the client
+/// the same status and challenge as the HTTP adapters.
+pub async fn grpc_check(
+    Extension(state): Extension<ServerState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    request: Request,
+) -> Response {
+    tonic::server::Grpc::new(ProstCodec::default())
+        .unary(GrpcCheck { state, peer_addr }, request)
+        .await
+        .into_response()
+}
+
+struct GrpcCheck {
+    state: ServerState,
+    peer_addr: SocketAddr,
+}
+
+impl UnaryService<CheckRequest> for GrpcCheck {
+    type Response = CheckResponse;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<tonic::Response<CheckResponse>, Status>> + Send>>;
+
+    fn call(&mut self, request: tonic::Request<CheckRequest>) -> Self::Future {
+        let state = self.state.clone();
+        let peer_addr = self.peer_addr;
+        Box::pin(async move {
+            check(&state, peer_addr, request.into_inner())
+                .await
+                .map(tonic::Response::new)
+        })
+    }
+}
+
+async fn check(
+    state: &ServerState,
+    peer_addr: SocketAddr,
+    request: CheckRequest,
+) -> Result<CheckResponse, Status> {
+    let attributes = request.attributes.unwrap_or_default();
+    let Some(group) = attributes.context_extensions.get("group") else {
+        return Err(Status::invalid_argument(
+            "the `group` context extension must name the forward-auth group",
+        ));
+    };
+    let required_scopes = split_scopes(
+        attributes
+            .context_extensions
+            .get("scope")
+            .map(String::as_str),
+    );
+    let http = attributes
+        .request
+        .and_then(|request| request.http)
+        .unwrap_or_default();
+    let headers = request_headers(&http);
+    let original = OriginalRequest {
+        proto: Some(http.scheme.clone()).filter(|scheme| !scheme.is_empty()),
+        method: Some(http.method.clone()).filter(|method| !method.is_empty()),
+        host: Some(http.host.clone()).filter(|host| !host.is_empty()),
+        uri: Some(http.path.clone()).filter(|path| !path.is_empty()),
+    };
+    let source_addr = attributes
+        .source
+        .and_then(|peer| peer.address)
+        .and_then(|address| address.socket_address)
+        .and_then(|address| {
+            Some(SocketAddr::new(
+                address.address.parse().ok()?,
+                u16::try_from(address.port_value).ok()?,
+            ))
+        });
+    // The source is the peer Envoy saw. It takes the place of ours only when
+    // the check comes from a trusted proxy, since anyone can send a request.
+    let peer_addr = match source_addr {
+        Some(source_addr) if state.is_trusted_proxy(peer_addr).await => source_addr,
+        _ => peer_addr,
+    };
+    let client_ip = resolved_client_ip(state, &headers, peer_addr).await;
+
+    let result = check_forward_auth(
+        state,
+        group,
+        &required_scopes,
+        &headers,
+        &original,
+        client_ip,
+        "envoy_grpc",
+    )
+    .await;
+    let (status, diagnosis, response) = match result {
+        Ok((entry_name, diagnosis)) => (StatusCode::OK, diagnosis, ok_response(entry_name)),
+        Err((status, diagnosis)) => {
+            match login_redirect_location(state, &headers, &original, &diagnosis) {
+                Some(location) => (
+                    StatusCode::FOUND,
+                    diagnosis.field(AuthFlowDiagnosisField::LOGIN_REDIRECT, true),
+                    login_redirect_response(location),
+                ),
+                None => (status, diagnosis, denied_response(status)),
+            }
+        }
+    };
+    log_check_diagnosis(&http, status, &diagnosis);
+    Ok(response)
+}
+
+fn log_check_diagnosis(http: &HttpRequest, status: StatusCode, diagnosis: &AuthFlowDiagnosis) {
+    log_route_diagnosis(
+        RouteDiagnosisContext {
+            route: GRPC_CHECK_PATH,
+            method: &http.method,
+            status: Some(status.as_u16()),
+        },
+        diagnosis,
+        if status.is_success() {
+            "Envoy gRPC forward auth passed"
+        } else {
+            "Envoy gRPC forward auth rejected"
+        },
+    );
+}
+
+/// Headers of the checked request. Pseudo-headers such as `:path` are
+/// skipped.
+fn request_headers(http: &HttpRequest) -> HeaderMap {
+    let pairs: Vec<(&str, &[u8])> = match &http.header_map {
+        Some(header_map) => header_map
+            .headers
+            .iter()
+            .map(|header| {
+                let value = if header.raw_value.is_empty() {
+                    header.value.as_bytes()
+                } else {
+                    &header.raw_value
+                };
+                (header.key.as_str(), value)
+            })
+            .collect(),
+        None => http
+            .headers
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_bytes()))
+            .collect(),
+    };
+
+    let mut headers = HeaderMap::new();
+    for (key, value) in pairs {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(key.as_bytes()), value.try_into()) {
+            headers.append(name, value);
+        }
+    }
+    headers
+}
+
+fn ok_response(entry_name: String) -> CheckResponse {
+    CheckResponse {
+        status: Some(RpcStatus {
+            code: Code::Ok.into(),
+            message: String::new(),
+        }),
+        denied_response: None,
+        ok_response: Some(OkHttpResponse {
+            headers: vec![HeaderValueOption::overwrite("x-auth-user", entry_name)],
+            headers_to_remove: Vec::new(),
+        }),
+    }
+}
+
+/// A denial that Envoy answers with a `302` to the login page.
+fn login_redirect_response(location: String) -> CheckResponse {
+    CheckResponse {
+        status: Some(RpcStatus {
+            code: Code::Unauthenticated.into(),
+            message: StatusCode::FOUND
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        denied_response: Some(DeniedHttpResponse {
+            status: Some(HttpStatus {
+                code: StatusCode::FOUND.as_u16().into(),
+            }),
+            headers: vec![HeaderValueOption::overwrite("location", location)],
+            body: String::new(),
+        }),
+        ok_response: None,
+    }
+}
+
+fn denied_response(status: StatusCode) -> CheckResponse {
+    let code = match status {
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    let headers = if status == StatusCode::UNAUTHORIZED {
+        vec![HeaderValueOption::overwrite(
+            "www-authenticate",
+            WWW_AUTHENTICATE_CHALLENGE,
+        )]
+    } else {
+        Vec::new()
+    };
+    CheckResponse {
+        status: Some(RpcStatus {
+            code: code.into(),
+            message: status.canonical_reason().unwrap_or_default().to_string(),
+        }),
+        denied_response: Some(DeniedHttpResponse {
+            status: Some(HttpStatus {
+                code: status.as_u16().into(),
+            }),
+            headers,
+            body: String::new(),
+        }),
+        ok_response: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::{Body, to_bytes},
+        http::{HeaderValue, header},
+    };
+    use prost::Message;
+    use securitydept_core::{
+        creds_manage::{AuditActor, models::Group},
+        realip::{RealIpResolveConfig, RealIpResolver},
+    };
+    use tower::util::ServiceExt;
+
+    use super::{
+        proto::{Address, AttributeContext, HeaderAppendAction, Peer, SocketAddress},
+        *,
+    };
+    use crate::routes::{build_router, test_support::test_server_state};
+
+    async fn token_in_group(state: &ServerState, group: &str) -> String {
+        let store = &state.creds_manage_store;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new(group.to_string()), None)
+            .await
+            .unwrap();
+        let (_, token) = store
+            .create_token_entry(&actor, "deploy".to_string(), vec![group.id])
+            .await
+            .unwrap();
+        token
+    }
+
+    fn check_request(group: &str, authorization: Option<&str>) -> CheckRequest {
+        CheckRequest {
+            attributes: Some(AttributeContext {
+                source: None,
+                request: Some(proto::Request {
+                    http: Some(HttpRequest {
+                        method: "GET".to_string(),
+                        headers: authorization
+                            .map(|value| ("authorization".to_string(), value.to_string()))
+                            .into_iter()
+                            .chain([(":path".to_string(), "/app".to_string())])
+                            .collect(),
+                        path: "/app".to_string(),
+                        ..Default::default()
+                    }),
+                }),
+                context_extensions: [("group".to_string(), group.to_string())].into(),
+            }),
+        }
+    }
+
+    /// A unary gRPC call through the router; returns the decoded response.
+    async fn grpc_call(state: ServerState, request: CheckRequest) -> CheckResponse {
+        let message = request.encode_to_vec();
+        let mut frame = vec![0];
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&message);
+        let mut request = axum::http::Request::post(GRPC_CHECK_PATH)
+            .header(header::CONTENT_TYPE, "application/grpc")
+            .header("te", "trailers")
+            .body(Body::from(frame))
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 65000))));
+        let response = build_router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        CheckResponse::decode(&body[5..]).expect("response should be a CheckResponse frame")
+    }
+
+    #[tokio::test]
+    async fn grpc_check_returns_header_mutation_or_denied_response() {
+        let state = test_server_state("forward-auth-envoy-grpc").await;
+        let token = token_in_group(&state, "ops").await;
+
+        let allowed = grpc_call(
+            state.clone(),
+            check_request("ops", Some(&format!("Bearer {token}"))),
+        )
+        .await;
+        assert_eq!(allowed.status.unwrap().code, i32::from(Code::Ok));
+        let header = &allowed.ok_response.unwrap().headers[0];
+        assert_eq!(
+            header.append_action,
+            i32::from(HeaderAppendAction::OverwriteIfExistsOrAdd)
+        );
+        let header = header.header.as_ref().unwrap();
+        assert_eq!(
+            (header.key.as_str(), header.value.as_str()),
+            ("x-auth-user", "deploy")
+        );
+
+        let denied = grpc_call(state, check_request("ops", None)).await;
+        assert_eq!(
+            denied.status.unwrap().code,
+            i32::from(Code::Unauthenticated)
+        );
+        let denied = denied.denied_response.unwrap();
+        assert_eq!(denied.status.unwrap().code, 401);
+        let header = denied.headers[0].header.as_ref().unwrap();
+        assert_eq!(
+            (header.key.as_str(), header.value.as_str()),
+            ("www-authenticate", WWW_AUTHENTICATE_CHALLENGE)
+        );
+    }
+
+    #[tokio::test]
+    async fn grpc_check_requires_group_context_extension() {
+        let state = test_server_state("forward-auth-envoy-grpc-group").await;
+        let mut request = check_request("ops", None);
+        request
+            .attributes
+            .as_mut()
+            .unwrap()
+            .context_extensions
+            .clear();
+
+        let status = check(&state, SocketAddr::from(([127, 0, 0, 1], 65000)), request)
+            .await
+            .expect_err("a check without a group should fail");
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn grpc_check_trusts_source_only_from_trusted_proxies() {
+        let mut state = test_server_state("forward-auth-envoy-grpc-source").await;
+        let config: RealIpResolveConfig = serde_json::from_value(serde_json::json!({
+            "providers": [{ "name": "envoy", "kind": "inline", "cidrs": ["10.0.0.0/8"] }],
+            "sources": [{ "name": "envoy", "peers_from": ["envoy"] }],
+        }))
+        .unwrap();
+        state.real_ip_resolver = Some(Arc::new(RealIpResolver::from_config(config).await.unwrap()));
+        let token = token_in_group(&state, "ops").await;
+        let mut request = check_request("ops", Some(&format!("Bearer {token}")));
+        request.attributes.as_mut().unwrap().source = Some(Peer {
+            address: Some(Address {
+                socket_address: Some(SocketAddress {
+                    address: "198.51.100.9".to_string(),
+                    port_value: 443,
+                }),
+            }),
+        });
+        let store = &state.creds_manage_store;
+
+        for (peer_addr, expected) in [
+            (SocketAddr::from(([192, 0, 2, 1], 65000)), "192.0.2.1"),
+            (SocketAddr::from(([10, 0, 0, 2], 65000)), "198.51.100.9"),
+        ] {
+            check(&state, peer_addr, request.clone()).await.unwrap();
+            store.flush_entry_usage().await.unwrap();
+            let entries = store.list_entries().await.unwrap();
+            let entry = entries
+                .iter()
+                .find(|entry| entry.meta.name == "deploy")
+                .unwrap();
+            assert_eq!(
+                entry.meta.usage.last_client_ip,
+                Some(expected.parse().unwrap()),
+                "peer {peer_addr}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn http_service_accepts_any_method_and_original_path() {
+        let state = test_server_state("forward-auth-envoy-http").await;
+        let token = token_in_group(&state, "ops").await;
+        let app = build_router(state);
+
+        for (method, uri, scope, expected) in [
+            (
+                "POST",
+                "/api/forwardauth/envoy/ops/app/items?page=2",
+                None,
+                StatusCode::OK,
+            ),
+            ("GET", "/api/forwardauth/envoy/ops/", None, StatusCode::OK),
+            ("DELETE", "/api/forwardauth/envoy/ops", None, StatusCode::OK),
+            (
+                "GET",
+                "/api/forwardauth/envoy/ops/app",
+                Some("admin"),
+                StatusCode::UNAUTHORIZED,
+            ),
+        ] {
+            let mut request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {token}"));
+            if let Some(scope) = scope {
+                request = request.header(SCOPE_HEADER, HeaderValue::from_static(scope));
+            }
+            let mut request = request.body(Body::empty()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 65000))));
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), expected, "{method} {uri}");
+            if expected == StatusCode::OK {
+                assert_eq!(response.headers()["x-auth-user"], "deploy");
+            }
+        }
+    }
+}
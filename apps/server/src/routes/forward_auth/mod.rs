@@ -0,0 +1,1323 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    Extension,
+    extract::{ConnectInfo, Path, Query},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use securitydept_core::{
+    creds::{BasicAuthCred, parse_basic_auth_header_opt, parse_bearer_auth_header_opt},
+    creds_manage::{
+        CredsManageError,
+        auth::{
+            AccessDecision, TokenAuthDecision, basic_auth_target_entry_id, decide_access,
+            decide_token_auth, find_basic_auth_entry, token_auth_target_entry_id,
+        },
+        models::{AccessRequest, Group, IdentityAccess, TokenConstraintViolation, Unavailable},
+    },
+    utils::{
+        http::ToHttpStatus,
+        observability::{
+            AuthFlowDiagnosis, AuthFlowDiagnosisField, AuthFlowDiagnosisOutcome, AuthFlowOperation,
+        },
+        redirect::UriRedirectTargetResolver,
+    },
+};
+use serde::Deserialize;
+
+use crate::{
+    diagnosis::{RouteDiagnosisContext, log_route_diagnosis},
+    state::ServerState,
+};
+
+pub mod envoy;
+
+/// Challenge sent with a forward-auth `401`.
+const WWW_AUTHENTICATE_CHALLENGE: &str =
+    r#"Basic realm="securitydept", Bearer realm="securitydept""#;
+
+/// Rejection reason when a group accepts sessions but the request has none.
+const SESSION_MISSING: &str = "session_missing";
+
+/// Optional query of the forward-auth routes.
+#[derive(Debug, Default, Deserialize)]
+pub struct ForwardAuthQuery {
+    /// Space-separated scopes a bearer token must carry. Basic credentials
+    /// carry no scopes, so they are rejected with
+    /// `basic_auth_scopes_unsupported` when any scope is required.
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+impl ForwardAuthQuery {
+    fn required_scopes(&self) -> Vec<String> {
+        split_scopes(self.scope.as_deref())
+    }
+}
+
+/// Scopes of a space-separated scope requirement.
+fn split_scopes(scope: Option<&str>) -> Vec<String> {
+    scope
+        .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// The query is left out of the original request, since it may carry
+/// secrets.
+fn forward_auth_base_diagnosis(
+    group: &str,
+    headers: &HeaderMap,
+    original: &OriginalRequest,
+    adapter: &str,
+) -> AuthFlowDiagnosis {
+    AuthFlowDiagnosis::started(AuthFlowOperation::FORWARD_AUTH_CHECK)
+        .field(AuthFlowDiagnosisField::GROUP, group)
+        .field(AuthFlowDiagnosisField::ADAPTER, adapter)
+        .field(
+            AuthFlowDiagnosisField::ORIGINAL_METHOD,
+            original.method.as_deref(),
+        )
+        .field(
+            AuthFlowDiagnosisField::ORIGINAL_HOST,
+            original.host.as_deref(),
+        )
+        .field(AuthFlowDiagnosisField::ORIGINAL_PATH, original.path())
+        .field(
+            AuthFlowDiagnosisField::HAS_AUTHORIZATION_HEADER,
+            headers.contains_key("authorization"),
+        )
+}
+
+fn forward_auth_terminal_rejection_diagnosis(
+    diagnosis: AuthFlowDiagnosis,
+    group_id: String,
+    reason: &'static str,
+    failure_stage: Option<&'static str>,
+) -> AuthFlowDiagnosis {
+    let diagnosis = diagnosis
+        .with_outcome(AuthFlowDiagnosisOutcome::Rejected)
+        .field(AuthFlowDiagnosisField::GROUP_ID, group_id)
+        .field(AuthFlowDiagnosisField::REASON, reason);
+
+    if let Some(failure_stage) = failure_stage {
+        diagnosis.field(AuthFlowDiagnosisField::FAILURE_STAGE, failure_stage)
+    } else {
+        diagnosis
+    }
+}
+
+/// Reverse proxies that ask forward auth with a plain `GET`, named by the
+/// `{adapter}` path segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardAuthAdapter {
+    /// Traefik `ForwardAuth`.
+    Traefik,
+    /// Nginx `auth_request`.
+    Nginx,
+    /// Caddy `forward_auth`.
+    Caddy,
+    /// HAProxy with an auth-request action.
+    Haproxy,
+}
+
+impl ForwardAuthAdapter {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Traefik => "traefik",
+            Self::Nginx => "nginx",
+            Self::Caddy => "caddy",
+            Self::Haproxy => "haproxy",
+        }
+    }
+}
+
+/// GET /api/forwardauth/:adapter/:group
+///
+/// Returns 200 with `X-Auth-User` if authenticated, 401 with a challenge
+/// otherwise. Checks the `Authorization` header forwarded by the proxy, or
+/// the session cookie for groups that accept OIDC sessions, and reads the
+/// original request from its `X-Forwarded-*` headers.
+pub async fn check(
+    Extension(state): Extension<ServerState>,
+    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    Path((adapter, group)): Path<(ForwardAuthAdapter, String)>,
+    Query(query): Query<ForwardAuthQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let client_ip = resolved_client_ip(&state, &headers, peer_addr).await;
+    let original = OriginalRequest::from_forwarded_headers(&headers);
+    match check_forward_auth(
+        &state,
+        &group,
+        &query.required_scopes(),
+        &headers,
+        &original,
+        client_ip,
+        adapter.as_str(),
+    )
+    .await
+    {
+        Ok((entry_name, diagnosis)) => {
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/forwardauth/:adapter/:group",
+                    method: "GET",
+                    status: Some(StatusCode::OK.as_u16()),
+                },
+                &diagnosis,
+                "Forward auth passed",
+            );
+            let mut resp_headers = HeaderMap::new();
+            // Pass the authenticated entry name downstream
+            if let Ok(val) = entry_name.parse() {
+                resp_headers.insert("X-Auth-User", val);
+            }
+            (StatusCode::OK, resp_headers).into_response()
+        }
+        Err((status, diagnosis)) => {
+            let login_location = login_redirect_location(&state, &headers, &original, &diagnosis);
+            let response = match login_location.as_deref() {
+                // Nginx `auth_request` treats any status but 401 and 403 as
+                // an error, so the location is handed to its `error_page`.
+                Some(location) if adapter == ForwardAuthAdapter::Nginx => {
+                    (StatusCode::UNAUTHORIZED, [(header::LOCATION, location)]).into_response()
+                }
+                login_location => rejection_response(status, login_location),
+            };
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/forwardauth/:adapter/:group",
+                    method: "GET",
+                    status: Some(response.status().as_u16()),
+                },
+                &diagnosis.field(
+                    AuthFlowDiagnosisField::LOGIN_REDIRECT,
+                    login_location.is_some(),
+                ),
+                "Forward auth rejected",
+            );
+            response
+        }
+    }
+}
+
+/// The proxied request a forward-auth check is about, as far as the proxy
+/// tells.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OriginalRequest {
+    /// `http` or `https`.
+    pub proto: Option<String>,
+    pub method: Option<String>,
+    pub host: Option<String>,
+    /// Path and query, e.g. `/app/items?page=2`.
+    pub uri: Option<String>,
+}
+
+impl OriginalRequest {
+    /// Read `X-Forwarded-Proto`, `X-Forwarded-Method`, `X-Forwarded-Host`
+    /// and `X-Forwarded-Uri`, as sent by Traefik, Caddy and HAProxy. The
+    /// `X-Original-Method` and `X-Original-URI` headers usually configured
+    /// for Nginx are accepted as well.
+    pub fn from_forwarded_headers(headers: &HeaderMap) -> Self {
+        let first = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| headers.get(*name)?.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            proto: first(&["x-forwarded-proto"]),
+            method: first(&["x-forwarded-method", "x-original-method"]),
+            host: first(&["x-forwarded-host"]),
+            uri: first(&["x-forwarded-uri", "x-original-uri"]),
+        }
+    }
+
+    /// The path of [`Self::uri`], without the query.
+    pub fn path(&self) -> Option<&str> {
+        let uri = self.uri.as_deref()?;
+        Some(uri.split_once('?').map_or(uri, |(path, _)| path))
+    }
+
+    /// The absolute URL of the original request, assuming `https` when the
+    /// proxy does not tell.
+    pub fn url(&self) -> Option<String> {
+        let proto = self.proto.as_deref().unwrap_or("https");
+        Some(format!(
+            "{proto}://{}{}",
+            self.host.as_deref()?,
+            self.uri.as_deref()?
+        ))
+    }
+
+    /// Whether this is a top-level browser navigation, which can follow a
+    /// redirect to the login page, rather than e.g. an API call.
+    fn is_browser_navigation(&self, headers: &HeaderMap) -> bool {
+        if !matches!(self.method.as_deref(), None | Some("GET" | "HEAD")) {
+            return false;
+        }
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        match header("sec-fetch-mode") {
+            Some(mode) => mode == "navigate",
+            None => {
+                header(header::ACCEPT.as_str()).is_some_and(|accept| accept.contains("text/html"))
+            }
+        }
+    }
+}
+
+/// Where to send a browser rejected with `diagnosis` to sign in: the
+/// configured session login, carrying the original URL as its post-auth
+/// redirect. Only offered when the group accepts sessions and the request
+/// has none, since signing in fixes nothing else.
+fn login_redirect_location(
+    state: &ServerState,
+    headers: &HeaderMap,
+    original: &OriginalRequest,
+    diagnosis: &AuthFlowDiagnosis,
+) -> Option<String> {
+    let login_redirect = state.config.forward_auth.login_redirect.as_ref()?;
+    let reason = diagnosis
+        .fields
+        .get(AuthFlowDiagnosisField::REASON)
+        .and_then(|reason| reason.as_str());
+    if reason != Some(SESSION_MISSING) || !original.is_browser_navigation(headers) {
+        return None;
+    }
+
+    let original_url = original.url()?;
+    let post_auth_redirect =
+        UriRedirectTargetResolver::from_config(login_redirect.post_auth_redirect())
+            .and_then(|resolver| resolver.resolve_redirect_target(Some(&original_url)))
+            .inspect_err(|error| {
+                tracing::debug!(
+                    error = %error,
+                    "forward auth does not redirect to login for a disallowed original URL"
+                );
+            })
+            .ok()?;
+    let mut login_url = login_redirect.login_url.clone();
+    login_url
+        .query_pairs_mut()
+        .append_pair("post_auth_redirect_uri", post_auth_redirect.as_str());
+    Some(login_url.into())
+}
+
+/// A `302` to `login_location` when set, otherwise `status` with a
+/// challenge.
+fn rejection_response(status: StatusCode, login_location: Option<&str>) -> Response {
+    match login_location {
+        Some(location) => (StatusCode::FOUND, [(header::LOCATION, location)]).into_response(),
+        None => unauthorized_with_challenge(status),
+    }
+}
+
+fn unauthorized_with_challenge(status: StatusCode) -> Response {
+    if status != StatusCode::UNAUTHORIZED {
+        return status.into_response();
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "WWW-Authenticate",
+        HeaderValue::from_static(WWW_AUTHENTICATE_CHALLENGE),
+    );
+    (StatusCode::UNAUTHORIZED, headers).into_response()
+}
+
+/// Fail a forward-auth check because the credential store could not be read.
+fn store_unavailable(
+    diagnosis: &AuthFlowDiagnosis,
+    error: CredsManageError,
+) -> (StatusCode, AuthFlowDiagnosis) {
+    tracing::warn!(error = %error, "forward auth could not read the credential store");
+    (
+        error.to_http_status(),
+        diagnosis
+            .clone()
+            .with_outcome(AuthFlowDiagnosisOutcome::Failed)
+            .field(AuthFlowDiagnosisField::REASON, "creds_manage_unavailable"),
+    )
+}
+
+/// Client IP recorded in entry usage statistics. Only set when a real-ip
+/// resolver is configured, since the peer is usually the proxy itself.
+async fn resolved_client_ip(
+    state: &ServerState,
+    headers: &HeaderMap,
+    peer_addr: SocketAddr,
+) -> Option<IpAddr> {
+    state
+        .resolve_client_ip(headers, Some(peer_addr))
+        .await
+        .map(|resolved| resolved.client_ip)
+}
+
+/// Why a session or access token identity was not accepted for a group.
+struct IdentityRejection {
+    reason: &'static str,
+    failure_stage: Option<&'static str>,
+}
+
+impl IdentityRejection {
+    fn rejected(reason: &'static str) -> Self {
+        Self {
+            reason,
+            failure_stage: None,
+        }
+    }
+}
+
+/// Subject of the signed-in OIDC session of the request, if it meets the
+/// group's claim requirements. Sessions carry no scopes, so they never
+/// satisfy a non-empty scope requirement.
+async fn check_session_identity(
+    state: &ServerState,
+    identity_access: &IdentityAccess,
+    required_scopes: &[String],
+    headers: &HeaderMap,
+) -> Result<String, IdentityRejection> {
+    let context = match state.session_context_from_headers(headers).await {
+        Ok(Some(context)) => context,
+        Ok(None) => return Err(IdentityRejection::rejected(SESSION_MISSING)),
+        Err(error) => {
+            tracing::warn!(error = %error, "forward auth could not read the session");
+            return Err(IdentityRejection {
+                reason: "session_lookup_failed",
+                failure_stage: Some("session_lookup"),
+            });
+        }
+    };
+    if !required_scopes.is_empty() {
+        return Err(IdentityRejection::rejected(
+            TokenConstraintViolation::MissingScopes.as_reason(),
+        ));
+    }
+    let principal = context.principal;
+    identity_access
+        .claims
+        .check(principal.issuer.as_deref(), &principal.claims)
+        .map_err(|violation| IdentityRejection::rejected(violation.as_reason()))?;
+    Ok(principal.subject)
+}
+
+/// Subject of a bearer access token verified by the OAuth resource server,
+/// if it carries the required scopes and meets the group's claim
+/// requirements.
+async fn check_bearer_identity(
+    state: &ServerState,
+    identity_access: &IdentityAccess,
+    required_scopes: &[String],
+    auth_header: &str,
+) -> Result<String, IdentityRejection> {
+    let Some(resource_service) = state.resource_service() else {
+        return Err(IdentityRejection::rejected(
+            "bearer_verifier_not_configured",
+        ));
+    };
+    let principal = match resource_service
+        .authenticate_authorization_header(Some(auth_header))
+        .await
+    {
+        Ok(Some(principal)) => principal,
+        Ok(None) => return Err(IdentityRejection::rejected("no_valid_credentials")),
+        Err(error) => {
+            tracing::debug!(error = %error, "forward auth rejected a bearer access token");
+            return Err(IdentityRejection {
+                reason: "access_token_rejected",
+                failure_stage: Some("access_token_verification"),
+            });
+        }
+    };
+    if !required_scopes
+        .iter()
+        .all(|scope| principal.scopes.contains(scope))
+    {
+        return Err(IdentityRejection::rejected(
+            TokenConstraintViolation::MissingScopes.as_reason(),
+        ));
+    }
+    identity_access
+        .claims
+        .check(principal.issuer.as_deref(), &principal.claims)
+        .map_err(|violation| IdentityRejection::rejected(violation.as_reason()))?;
+    Ok(principal
+        .subject
+        .or(principal.authorized_party)
+        .unwrap_or_else(|| "unknown".to_string()))
+}
+
+/// Shared logic: authenticate the request against the group, then apply the
+/// group's access rules.
+///
+/// Every attempt that can be attributed to an entry counts towards that
+/// entry's usage statistics.
+async fn check_forward_auth(
+    state: &ServerState,
+    group: &str,
+    required_scopes: &[String],
+    headers: &HeaderMap,
+    original: &OriginalRequest,
+    client_ip: Option<IpAddr>,
+    adapter: &str,
+) -> Result<(String, AuthFlowDiagnosis), (StatusCode, AuthFlowDiagnosis)> {
+    let diagnosis = forward_auth_base_diagnosis(group, headers, original, adapter).field(
+        AuthFlowDiagnosisField::REQUIRED_SCOPES_COUNT,
+        required_scopes.len(),
+    );
+    let now = Utc::now();
+
+    let group_obj = match state.creds_manage_store.find_group_by_name(group).await {
+        Ok(Some(group_obj)) => group_obj,
+        Ok(None) => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                diagnosis
+                    .with_outcome(AuthFlowDiagnosisOutcome::Rejected)
+                    .field(AuthFlowDiagnosisField::REASON, "group_not_found"),
+            ));
+        }
+        Err(error) => return Err(store_unavailable(&diagnosis, error)),
+    };
+    if let Err(unavailable) = group_obj.availability.check(now) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            diagnosis
+                .with_outcome(AuthFlowDiagnosisOutcome::Rejected)
+                .field(AuthFlowDiagnosisField::GROUP_ID, group_obj.id.to_string())
+                .field(
+                    AuthFlowDiagnosisField::REASON,
+                    match unavailable {
+                        Unavailable::Disabled => "group_disabled",
+                        Unavailable::Expired => "group_expired",
+                    },
+                ),
+        ));
+    }
+
+    let (user, diagnosis) = authenticate_for_group(
+        state,
+        &group_obj,
+        required_scopes,
+        headers,
+        client_ip,
+        diagnosis,
+        now,
+    )
+    .await?;
+    check_access_rules(&group_obj, original, user, diagnosis)
+}
+
+/// Refuse an authenticated request that the group's access rules do not
+/// allow with `403`.
+fn check_access_rules(
+    group_obj: &Group,
+    original: &OriginalRequest,
+    user: String,
+    diagnosis: AuthFlowDiagnosis,
+) -> Result<(String, AuthFlowDiagnosis), (StatusCode, AuthFlowDiagnosis)> {
+    let request = AccessRequest {
+        method: original.method.as_deref(),
+        host: original.host.as_deref(),
+        path: original.path(),
+    };
+    let (rule, reason) = match decide_access(&group_obj.access_rules, &request) {
+        AccessDecision::Unrestricted => return Ok((user, diagnosis)),
+        AccessDecision::Allowed(rule) => {
+            return Ok((
+                user,
+                diagnosis.field(AuthFlowDiagnosisField::ACCESS_RULE_ID, rule.id.as_str()),
+            ));
+        }
+        AccessDecision::Denied(rule) => (Some(rule), "access_rule_denied"),
+        AccessDecision::NoMatch => (None, "no_access_rule_matched"),
+    };
+    Err((
+        StatusCode::FORBIDDEN,
+        diagnosis
+            .with_outcome(AuthFlowDiagnosisOutcome::Rejected)
+            .field(AuthFlowDiagnosisField::REASON, reason)
+            .field(
+                AuthFlowDiagnosisField::ACCESS_RULE_ID,
+                rule.map(|rule| rule.id.as_str()),
+            ),
+    ))
+}
+
+/// Authenticate the request with the entries and identities `group_obj`
+/// accepts.
+async fn authenticate_for_group(
+    state: &ServerState,
+    group_obj: &Group,
+    required_scopes: &[String],
+    headers: &HeaderMap,
+    client_ip: Option<IpAddr>,
+    diagnosis: AuthFlowDiagnosis,
+    now: DateTime<Utc>,
+) -> Result<(String, AuthFlowDiagnosis), (StatusCode, AuthFlowDiagnosis)> {
+    let mut credential_validation_failure_stage = None;
+    let mut rejection_reason = None;
+
+    let basic_entries = state
+        .creds_manage_store
+        .basic_entries_by_group_id(&group_obj.id)
+        .await
+        .map_err(|error| store_unavailable(&diagnosis, error))?;
+    let token_entries = state
+        .creds_manage_store
+        .token_entries_by_group_id(&group_obj.id)
+        .await
+        .map_err(|error| store_unavailable(&diagnosis, error))?;
+
+    if basic_entries.is_empty()
+        && token_entries.is_empty()
+        && !group_obj.identity_access.is_enabled()
+    {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            diagnosis
+                .clone()
+                .with_outcome(AuthFlowDiagnosisOutcome::Rejected)
+                .field(AuthFlowDiagnosisField::GROUP_ID, group_obj.id.to_string())
+                .field(AuthFlowDiagnosisField::REASON, "group_has_no_entries"),
+        ));
+    }
+
+    let auth_header = headers.get("authorization").and_then(|v| v.to_str().ok());
+    let Some(auth_header) = auth_header else {
+        if group_obj.identity_access.session {
+            return match check_session_identity(
+                state,
+                &group_obj.identity_access,
+                required_scopes,
+                headers,
+            )
+            .await
+            {
+                Ok(subject) => Ok((
+                    subject.clone(),
+                    diagnosis
+                        .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+                        .field(AuthFlowDiagnosisField::GROUP_ID, group_obj.id.to_string())
+                        .field(AuthFlowDiagnosisField::AUTH_SCHEME, "session")
+                        .field(AuthFlowDiagnosisField::SUBJECT, subject),
+                )),
+                Err(rejection) => Err((
+                    StatusCode::UNAUTHORIZED,
+                    forward_auth_terminal_rejection_diagnosis(
+                        diagnosis,
+                        group_obj.id.to_string(),
+                        rejection.reason,
+                        rejection.failure_stage,
+                    ),
+                )),
+            };
+        }
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            diagnosis
+                .clone()
+                .with_outcome(AuthFlowDiagnosisOutcome::Rejected)
+                .field(AuthFlowDiagnosisField::GROUP_ID, group_obj.id.to_string())
+                .field(
+                    AuthFlowDiagnosisField::REASON,
+                    "missing_authorization_header",
+                ),
+        ));
+    };
+
+    // Try basic auth first
+    if let Some((username, password)) = parse_basic_auth_header_opt(auth_header) {
+        let target_entry_id = basic_auth_target_entry_id(&basic_entries, &username);
+        match find_basic_auth_entry(&basic_entries, &username, &password) {
+            Ok(Some(entry)) if let Err(unavailable) = entry.meta.availability.check(now) => {
+                rejection_reason = Some(match unavailable {
+                    Unavailable::Disabled => "entry_disabled",
+                    Unavailable::Expired => "entry_expired",
+                });
+            }
+            Ok(Some(_)) if !required_scopes.is_empty() => {
+                rejection_reason = Some("basic_auth_scopes_unsupported");
+            }
+            Ok(Some(entry)) => {
+                state
+                    .creds_manage_store
+                    .record_entry_use(&entry.meta.id, true, client_ip);
+                let password_rehashed = entry
+                    .needs_rehash(state.creds_manage_store.argon2_config())
+                    && match state
+                        .creds_manage_store
+                        .upgrade_basic_entry_password_hash(&entry.meta.id, &password)
+                        .await
+                    {
+                        Ok(upgraded) => upgraded,
+                        Err(error) => {
+                            tracing::warn!(
+                                entry_id = %entry.meta.id,
+                                error = %error,
+                                "failed to re-hash legacy basic auth password"
+                            );
+                            false
+                        }
+                    };
+                let name = entry.meta.name;
+                return Ok((
+                    name.clone(),
+                    diagnosis
+                        .clone()
+                        .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+                        .field(AuthFlowDiagnosisField::GROUP_ID, group_obj.id.to_string())
+                        .field(AuthFlowDiagnosisField::AUTH_SCHEME, "basic")
+                        .field(AuthFlowDiagnosisField::ENTRY_NAME, name)
+                        .field(AuthFlowDiagnosisField::PASSWORD_REHASHED, password_rehashed),
+                ));
+            }
+            Ok(None) => {}
+            Err(error) => {
+                let _ = error;
+                credential_validation_failure_stage = Some("basic_credential_validation");
+            }
+        }
+        if let Some(entry_id) = target_entry_id {
+            state
+                .creds_manage_store
+                .record_entry_use(&entry_id, false, client_ip);
+        }
+    }
+
+    // Try bearer token
+    if let Some(token) = parse_bearer_auth_header_opt(auth_header) {
+        let target_entry_id = token_auth_target_entry_id(&token_entries, &token);
+        match decide_token_auth(&token_entries, &token, required_scopes, now) {
+            Ok(TokenAuthDecision::Accepted(name)) => {
+                if let Some(entry_id) = &target_entry_id {
+                    state
+                        .creds_manage_store
+                        .record_entry_use(entry_id, true, client_ip);
+                }
+                return Ok((
+                    name.clone(),
+                    diagnosis
+                        .clone()
+                        .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+                        .field(AuthFlowDiagnosisField::GROUP_ID, group_obj.id.to_string())
+                        .field(AuthFlowDiagnosisField::AUTH_SCHEME, "bearer")
+                        .field(AuthFlowDiagnosisField::ENTRY_NAME, name),
+                ));
+            }
+            Ok(TokenAuthDecision::Rejected(violation)) => {
+                rejection_reason = Some(violation.as_reason());
+            }
+            Ok(TokenAuthDecision::NoMatch) if group_obj.identity_access.bearer => {
+                match check_bearer_identity(
+                    state,
+                    &group_obj.identity_access,
+                    required_scopes,
+                    auth_header,
+                )
+                .await
+                {
+                    Ok(subject) => {
+                        return Ok((
+                            subject.clone(),
+                            diagnosis
+                                .clone()
+                                .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+                                .field(AuthFlowDiagnosisField::GROUP_ID, group_obj.id.to_string())
+                                .field(AuthFlowDiagnosisField::AUTH_SCHEME, "bearer_jwt")
+                                .field(AuthFlowDiagnosisField::SUBJECT, subject),
+                        ));
+                    }
+                    Err(rejection) => {
+                        rejection_reason = Some(rejection.reason);
+                        if rejection.failure_stage.is_some() {
+                            credential_validation_failure_stage = rejection.failure_stage;
+                        }
+                    }
+                }
+            }
+            Ok(TokenAuthDecision::NoMatch) => {}
+            Err(error) => {
+                let _ = error;
+                if credential_validation_failure_stage.is_none() {
+                    credential_validation_failure_stage = Some("token_credential_validation");
+                }
+            }
+        }
+        if let Some(entry_id) = target_entry_id {
+            state
+                .creds_manage_store
+                .record_entry_use(&entry_id, false, client_ip);
+        }
+    }
+
+    Err((
+        StatusCode::UNAUTHORIZED,
+        forward_auth_terminal_rejection_diagnosis(
+            diagnosis,
+            group_obj.id.to_string(),
+            if let Some(reason) = rejection_reason {
+                reason
+            } else if credential_validation_failure_stage.is_some() {
+                "credential_validation_failed"
+            } else {
+                "no_valid_credentials"
+            },
+            credential_validation_failure_stage,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header;
+    use securitydept_core::creds_manage::{
+        AuditActor,
+        models::{Availability, Group},
+    };
+
+    use super::*;
+    use crate::routes::test_support::test_server_state;
+
+    #[test]
+    fn forward_auth_base_diagnosis_uses_shared_operation_vocabulary() {
+        let headers = HeaderMap::new();
+        let diagnosis =
+            forward_auth_base_diagnosis("ops", &headers, &OriginalRequest::default(), "traefik");
+
+        assert_eq!(diagnosis.operation, AuthFlowOperation::FORWARD_AUTH_CHECK);
+        assert_eq!(diagnosis.fields[AuthFlowDiagnosisField::GROUP], "ops");
+        assert_eq!(diagnosis.fields[AuthFlowDiagnosisField::ADAPTER], "traefik");
+    }
+
+    #[test]
+    fn forward_auth_terminal_rejection_diagnosis_stays_secret_safe() {
+        let diagnosis = forward_auth_terminal_rejection_diagnosis(
+            forward_auth_base_diagnosis(
+                "ops",
+                &HeaderMap::new(),
+                &OriginalRequest::default(),
+                "traefik",
+            ),
+            "group-1".to_string(),
+            "credential_validation_failed",
+            Some("basic_credential_validation"),
+        );
+
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::GROUP_ID],
+            "group-1"
+        );
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::FAILURE_STAGE],
+            "basic_credential_validation"
+        );
+        assert!(!diagnosis.fields.contains_key("username"));
+        assert!(!diagnosis.fields.contains_key("password"));
+        assert!(!diagnosis.fields.contains_key("token_value"));
+        assert!(!diagnosis.fields.contains_key("cookie"));
+    }
+
+    #[tokio::test]
+    async fn disabled_group_and_entry_are_rejected_with_reason() {
+        let state = test_server_state("forward-auth-availability").await;
+        let store = &state.creds_manage_store;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let (entry, token) = store
+            .create_token_entry(&actor, "deploy".to_string(), vec![group.id.clone()])
+            .await
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        let original = OriginalRequest::default();
+        let reason = |result: Result<_, (StatusCode, AuthFlowDiagnosis)>| {
+            let (status, diagnosis) = result.expect_err("forward auth should reject");
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            diagnosis.fields[AuthFlowDiagnosisField::REASON].clone()
+        };
+
+        assert!(
+            check_forward_auth(&state, "ops", &[], &headers, &original, None, "traefik")
+                .await
+                .is_ok()
+        );
+
+        store
+            .set_entry_availability(&actor, &entry.meta.id, Availability::disabled(None))
+            .await
+            .unwrap();
+        assert_eq!(
+            reason(
+                check_forward_auth(&state, "ops", &[], &headers, &original, None, "traefik").await
+            ),
+            "token_disabled"
+        );
+
+        store
+            .set_group_availability(&actor, &group.id, Availability::disabled(None))
+            .await
+            .unwrap();
+        assert_eq!(
+            reason(
+                check_forward_auth(&state, "ops", &[], &headers, &original, None, "traefik").await
+            ),
+            "group_disabled"
+        );
+    }
+
+    #[tokio::test]
+    async fn group_accepting_sessions_checks_session_claims() {
+        use std::{collections::HashMap, sync::Arc};
+
+        use securitydept_core::{
+            creds_manage::models::{ClaimRequirements, IdentityAccess},
+            session_context::{SessionContext, SessionContextSession},
+            utils::principal::AuthenticatedPrincipal,
+        };
+        use tower_sessions::Session;
+
+        let state = test_server_state("forward-auth-session").await;
+        let store = &state.creds_manage_store;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new("apps".to_string()), None)
+            .await
+            .unwrap();
+        let original = OriginalRequest::default();
+        let reason = |result: Result<_, (StatusCode, AuthFlowDiagnosis)>| {
+            let (_, diagnosis) = result.expect_err("forward auth should reject");
+            diagnosis.fields[AuthFlowDiagnosisField::REASON].clone()
+        };
+
+        let session = Session::new(None, Arc::new(state.session_store.clone()), None);
+        SessionContextSession::from_resolved_config(session.clone(), &state.session_context_config)
+            .insert(
+                &SessionContext::<HashMap<String, serde_json::Value>>::builder()
+                    .principal(
+                        AuthenticatedPrincipal::builder()
+                            .subject("user-1")
+                            .display_name("Alice")
+                            .issuer("https://issuer.example.com")
+                            .claims(HashMap::from([(
+                                "groups".to_string(),
+                                serde_json::json!(["staff"]),
+                            )]))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .unwrap();
+        session.save().await.unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&format!(
+                "{}={}",
+                state.session_context_config.cookie_name,
+                session.id().unwrap()
+            ))
+            .unwrap(),
+        );
+
+        assert_eq!(
+            reason(
+                check_forward_auth(&state, "apps", &[], &headers, &original, None, "caddy").await
+            ),
+            "group_has_no_entries"
+        );
+
+        let mut identity_access = IdentityAccess {
+            session: true,
+            claims: ClaimRequirements {
+                groups_claim: Some("groups".to_string()),
+                any_of: vec!["staff".to_string()],
+                ..ClaimRequirements::default()
+            },
+            ..IdentityAccess::default()
+        };
+        store
+            .set_group_identity_access(&actor, &group.id, identity_access.clone())
+            .await
+            .unwrap();
+        let (user, diagnosis) =
+            check_forward_auth(&state, "apps", &[], &headers, &original, None, "caddy")
+                .await
+                .unwrap();
+        assert_eq!(user, "user-1");
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::AUTH_SCHEME],
+            "session"
+        );
+        assert_eq!(
+            reason(
+                check_forward_auth(
+                    &state,
+                    "apps",
+                    &[],
+                    &HeaderMap::new(),
+                    &original,
+                    None,
+                    "caddy"
+                )
+                .await
+            ),
+            "session_missing"
+        );
+
+        identity_access.claims.any_of = vec!["admins".to_string()];
+        store
+            .set_group_identity_access(&actor, &group.id, identity_access)
+            .await
+            .unwrap();
+        assert_eq!(
+            reason(
+                check_forward_auth(&state, "apps", &[], &headers, &original, None, "caddy").await
+            ),
+            "groups_claim_mismatch"
+        );
+    }
+
+    #[tokio::test]
+    async fn access_rules_restrict_authenticated_requests() {
+        use securitydept_core::creds_manage::models::{AccessRule, AccessRuleEffect, PathPattern};
+
+        let state = test_server_state("forward-auth-access-rules").await;
+        let store = &state.creds_manage_store;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let (_, token) = store
+            .create_token_entry(&actor, "deploy".to_string(), vec![group.id.clone()])
+            .await
+            .unwrap();
+        store
+            .set_group_access_rules(
+                &actor,
+                &group.id,
+                vec![AccessRule {
+                    id: "read-api".to_string(),
+                    effect: AccessRuleEffect::Allow,
+                    hosts: Vec::new(),
+                    methods: vec!["GET".to_string()],
+                    path: Some(PathPattern::Glob("/api/*".to_string())),
+                }],
+            )
+            .await
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+        );
+        let request = |method: &str| OriginalRequest {
+            method: Some(method.to_string()),
+            uri: Some("/api/items?page=2".to_string()),
+            ..OriginalRequest::default()
+        };
+
+        let (_, diagnosis) = check_forward_auth(
+            &state,
+            "ops",
+            &[],
+            &headers,
+            &request("GET"),
+            None,
+            "traefik",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::ACCESS_RULE_ID],
+            "read-api"
+        );
+
+        let (status, diagnosis) = check_forward_auth(
+            &state,
+            "ops",
+            &[],
+            &headers,
+            &request("DELETE"),
+            None,
+            "traefik",
+        )
+        .await
+        .expect_err("DELETE should not match the rule");
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::REASON],
+            "no_access_rule_matched"
+        );
+
+        let (status, _) = check_forward_auth(
+            &state,
+            "ops",
+            &[],
+            &HeaderMap::new(),
+            &request("GET"),
+            None,
+            "traefik",
+        )
+        .await
+        .expect_err("rules only apply once the request is authenticated");
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn original_request_is_read_from_forwarded_headers_and_diagnosed_without_query() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-method", HeaderValue::from_static("POST"));
+        headers.insert("x-forwarded-host", HeaderValue::from_static("app.example"));
+        headers.insert(
+            "x-forwarded-uri",
+            HeaderValue::from_static("/items?token=secret"),
+        );
+        let original = OriginalRequest::from_forwarded_headers(&headers);
+        assert_eq!(original.method.as_deref(), Some("POST"));
+        assert_eq!(original.path(), Some("/items"));
+
+        let diagnosis = forward_auth_base_diagnosis("ops", &headers, &original, "caddy");
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::ORIGINAL_METHOD],
+            "POST"
+        );
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::ORIGINAL_HOST],
+            "app.example"
+        );
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::ORIGINAL_PATH],
+            "/items"
+        );
+
+        let mut nginx_headers = HeaderMap::new();
+        nginx_headers.insert("x-original-uri", HeaderValue::from_static("/admin"));
+        assert_eq!(
+            OriginalRequest::from_forwarded_headers(&nginx_headers).path(),
+            Some("/admin")
+        );
+    }
+
+    #[tokio::test]
+    async fn adapter_route_serves_each_proxy_and_rejects_unknown_ones() {
+        use axum::{body::Body, extract::Request};
+        use tower::util::ServiceExt;
+
+        let state = test_server_state("forward-auth-adapters").await;
+        let store = &state.creds_manage_store;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let (_, token) = store
+            .create_token_entry(&actor, "deploy".to_string(), vec![group.id])
+            .await
+            .unwrap();
+        let app = crate::routes::build_router(state);
+
+        for (adapter, expected) in [
+            ("traefik", StatusCode::OK),
+            ("nginx", StatusCode::OK),
+            ("caddy", StatusCode::OK),
+            ("haproxy", StatusCode::OK),
+            ("squid", StatusCode::BAD_REQUEST),
+        ] {
+            let mut request = Request::get(format!("/api/forwardauth/{adapter}/ops"))
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .header("x-forwarded-method", "DELETE")
+                .header("x-forwarded-uri", "/items/1")
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 65000))));
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), expected, "{adapter}");
+        }
+    }
+
+    #[tokio::test]
+    async fn browser_navigation_without_session_is_redirected_to_login() {
+        use std::sync::Arc;
+
+        use axum::{body::Body, extract::Request};
+        use securitydept_core::{
+            creds_manage::models::IdentityAccess, utils::redirect::RedirectTargetRule,
+        };
+        use tower::util::ServiceExt;
+
+        use crate::config::{ForwardAuthConfig, ForwardAuthLoginRedirectConfig, ServerConfig};
+
+        let mut state = test_server_state("forward-auth-login-redirect").await;
+        state.config = Arc::new(ServerConfig {
+            forward_auth: ForwardAuthConfig {
+                login_redirect: Some(ForwardAuthLoginRedirectConfig {
+                    login_url: "https://auth.example.com/auth/session/login"
+                        .parse()
+                        .unwrap(),
+                    allowed_redirect_targets: vec![RedirectTargetRule::Strict {
+                        value: "https://wiki.example.com/wiki?page=1".to_string(),
+                    }],
+                }),
+            },
+            ..(*state.config).clone()
+        });
+        // The session login returns to the allowed original URLs only.
+        let session_config = state.config.resolved_session_context_config().unwrap();
+        assert_eq!(
+            session_config
+                .resolve_post_auth_redirect(Some("https://wiki.example.com/wiki?page=1"))
+                .unwrap(),
+            "https://wiki.example.com/wiki?page=1"
+        );
+        assert!(
+            session_config
+                .resolve_post_auth_redirect(Some("https://evil.example.net/"))
+                .is_err()
+        );
+        let store = &state.creds_manage_store;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new("apps".to_string()), None)
+            .await
+            .unwrap();
+        store
+            .set_group_identity_access(
+                &actor,
+                &group.id,
+                IdentityAccess {
+                    session: true,
+                    ..IdentityAccess::default()
+                },
+            )
+            .await
+            .unwrap();
+        let app = crate::routes::build_router(state);
+        let check = |adapter: &str, host: &str, accept: &str| {
+            let mut request = Request::get(format!("/api/forwardauth/{adapter}/apps"))
+                .header(header::ACCEPT, accept)
+                .header("x-forwarded-proto", "https")
+                .header("x-forwarded-host", host)
+                .header("x-forwarded-uri", "/wiki?page=1")
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 65000))));
+            app.clone().oneshot(request)
+        };
+        let location = |response: &Response| {
+            response
+                .headers()
+                .get(header::LOCATION)
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+        let expected_location = "https://auth.example.com/auth/session/login?\
+                                 post_auth_redirect_uri=https%3A%2F%2Fwiki.example.com%2Fwiki%3Fpage%3D1";
+
+        let response = check("traefik", "wiki.example.com", "text/html")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(location(&response).as_deref(), Some(expected_location));
+
+        let response = check("nginx", "wiki.example.com", "text/html")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(location(&response).as_deref(), Some(expected_location));
+
+        for (host, accept) in [
+            ("wiki.example.com", "application/json"),
+            ("wiki.example.net", "text/html"),
+        ] {
+            let response = check("caddy", host, accept).await.unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::UNAUTHORIZED,
+                "{host} {accept}"
+            );
+            assert_eq!(location(&response), None, "{host} {accept}");
+        }
+    }
+
+    #[test]
+    fn forward_auth_query_splits_space_separated_scopes() {
+        let query = ForwardAuthQuery {
+            scope: Some("deploy  read".to_string()),
+        };
+        assert_eq!(query.required_scopes(), vec!["deploy", "read"]);
+        assert!(ForwardAuthQuery::default().required_scopes().is_empty());
+    }
+
+    #[tokio::test]
+    async fn basic_credentials_with_required_scopes_get_a_basic_specific_reason() {
+        let state = test_server_state("forward-auth-basic-scopes").await;
+        let group = state
+            .creds_manage_store
+            .create_group(&AuditActor::system(), Group::new("ops".to_string()), None)
+            .await
+            .expect("group should be created");
+        state
+            .creds_manage_store
+            .create_basic_entry(
+                &AuditActor::system(),
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                vec![group.id],
+            )
+            .await
+            .expect("basic entry should be created");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Basic b3BzOnNlY3JldDEyMw=="),
+        );
+
+        let (status, diagnosis) = check_forward_auth(
+            &state,
+            "ops",
+            &["deploy".to_string()],
+            &headers,
+            &OriginalRequest::default(),
+            None,
+            "traefik",
+        )
+        .await
+        .expect_err("basic credentials cannot satisfy a scope requirement");
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            diagnosis.fields[AuthFlowDiagnosisField::REASON],
+            "basic_auth_scopes_unsupported"
+        );
+    }
+
+    #[test]
+    fn unauthorized_with_challenge_preserves_protocol_header() {
+        let response = unauthorized_with_challenge(StatusCode::UNAUTHORIZED);
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::WWW_AUTHENTICATE)
+                .expect("challenge response should preserve header"),
+            "Basic realm=\"securitydept\", Bearer realm=\"securitydept\""
+        );
+    }
+}
@@ -1,6 +1,11 @@
-use axum::{Extension, Json, extract::Path};
+use axum::{Extension, Json, extract::Path, http::HeaderMap};
 use securitydept_core::{
-    creds_manage::models::{CreateGroupRequest, Group, UpdateGroupRequest},
+    creds_manage::{
+        AuditActor,
+        models::{
+            AccessRule, Availability, CreateGroupRequest, Group, IdentityAccess, UpdateGroupRequest,
+        },
+    },
     utils::observability::{
         AuthFlowDiagnosis, AuthFlowDiagnosisField, AuthFlowDiagnosisOutcome, AuthFlowOperation,
     },
@@ -9,6 +14,7 @@ use securitydept_core::{
 use crate::{
     diagnosis::{RouteDiagnosisContext, log_route_diagnosis, log_route_diagnosis_error},
     error::ServerError,
+    routes::revision::{WithETag, if_match_revision, with_etag},
     state::ServerState,
 };
 
@@ -59,8 +65,32 @@ fn group_route_failure_diagnosis(
 }
 
 /// GET /api/groups
-pub async fn list(Extension(state): Extension<ServerState>) -> Json<Vec<Group>> {
-    let groups = state.creds_manage_store.list_groups().await;
+pub async fn list(
+    Extension(state): Extension<ServerState>,
+) -> Result<Json<Vec<Group>>, ServerError> {
+    let groups = match state.creds_manage_store.list_groups().await {
+        Ok(groups) => groups,
+        Err(error) => {
+            let diagnosis = group_route_failure_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_GROUP_LIST,
+                "/api/groups",
+                "GET",
+                "list",
+                None,
+            );
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/groups",
+                    method: "GET",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Groups list failed",
+            );
+            return Err(error.into());
+        }
+    };
     let diagnosis = group_route_base_diagnosis(
         AuthFlowOperation::CREDS_MANAGE_GROUP_LIST,
         "/api/groups",
@@ -80,14 +110,16 @@ pub async fn list(Extension(state): Extension<ServerState>) -> Json<Vec<Group>>
         "Groups list completed",
     );
 
-    Json(groups)
+    Ok(Json(groups))
 }
 
 /// GET /api/groups/:id
+///
+/// The `ETag` is the group's revision.
 pub async fn get(
     Extension(state): Extension<ServerState>,
     Path(id): Path<String>,
-) -> Result<Json<Group>, ServerError> {
+) -> Result<WithETag<Group>, ServerError> {
     match state.creds_manage_store.get_group(&id).await {
         Ok(group) => {
             let diagnosis = group_route_base_diagnosis(
@@ -108,7 +140,7 @@ pub async fn get(
                 &diagnosis,
                 "Group get completed",
             );
-            Ok(Json(group))
+            Ok(with_etag(group.revision, group))
         }
         Err(error) => {
             let diagnosis = group_route_failure_diagnosis(
@@ -136,14 +168,16 @@ pub async fn get(
 /// POST /api/groups
 pub async fn create(
     Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
     Json(req): Json<CreateGroupRequest>,
 ) -> Result<Json<Group>, ServerError> {
     let entry_ids_count = req.entry_ids.as_ref().map_or(0, Vec::len);
-    let group = Group::new(req.name);
+    let mut group = Group::new(req.name);
+    group.parent_ids = req.parent_ids;
     let attempted_group_id = group.id.clone();
     match state
         .creds_manage_store
-        .create_group(group, req.entry_ids)
+        .create_group(&actor, group, req.entry_ids)
         .await
     {
         Ok(created) => {
@@ -184,15 +218,21 @@ pub async fn create(
 }
 
 /// PUT /api/groups/:id
+///
+/// With `If-Match`, the update only applies while the group's `ETag` still
+/// matches and fails with 412 otherwise.
 pub async fn update(
     Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(req): Json<UpdateGroupRequest>,
-) -> Result<Json<Group>, ServerError> {
+) -> Result<WithETag<Group>, ServerError> {
+    let expected_revision = if_match_revision(&headers)?;
     let entry_ids_count = req.entry_ids.as_ref().map_or(0, Vec::len);
     match state
         .creds_manage_store
-        .update_group(&id, req.name, req.entry_ids)
+        .update_group_at_revision(&actor, &id, expected_revision, req)
         .await
     {
         Ok(updated) => {
@@ -205,7 +245,8 @@ pub async fn update(
             .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
             .field(AuthFlowDiagnosisField::HAS_TARGET_ID, true)
             .field(AuthFlowDiagnosisField::TARGET_ID, id.clone())
-            .field(AuthFlowDiagnosisField::ENTRY_IDS_COUNT, entry_ids_count);
+            .field(AuthFlowDiagnosisField::ENTRY_IDS_COUNT, entry_ids_count)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
             log_route_diagnosis(
                 RouteDiagnosisContext {
                     route: "/api/groups/:id",
@@ -215,7 +256,7 @@ pub async fn update(
                 &diagnosis,
                 "Group update completed",
             );
-            Ok(Json(updated))
+            Ok(with_etag(updated.revision, updated))
         }
         Err(error) => {
             let diagnosis = group_route_failure_diagnosis(
@@ -225,7 +266,8 @@ pub async fn update(
                 "update",
                 Some(&id),
             )
-            .field(AuthFlowDiagnosisField::ENTRY_IDS_COUNT, entry_ids_count);
+            .field(AuthFlowDiagnosisField::ENTRY_IDS_COUNT, entry_ids_count)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
             log_route_diagnosis_error(
                 RouteDiagnosisContext {
                     route: "/api/groups/:id",
@@ -241,12 +283,229 @@ pub async fn update(
     }
 }
 
+/// PUT /api/groups/:id/availability
+///
+/// Replace the disabled flag, disabled reason and expiry of a group. Forward
+/// auth refuses an unavailable group as a whole. With `If-Match`, the change
+/// only applies while the group's `ETag` still matches.
+pub async fn availability(
+    Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<Availability>,
+) -> Result<WithETag<Group>, ServerError> {
+    let expected_revision = if_match_revision(&headers)?;
+    let disabled = req.disabled;
+    let has_expiry = req.expires_at.is_some();
+    match state
+        .creds_manage_store
+        .set_group_availability_at_revision(&actor, &id, expected_revision, req)
+        .await
+    {
+        Ok(updated) => {
+            let diagnosis = group_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_GROUP_AVAILABILITY,
+                "/api/groups/:id/availability",
+                "PUT",
+                "availability",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(AuthFlowDiagnosisField::HAS_TARGET_ID, true)
+            .field(AuthFlowDiagnosisField::TARGET_ID, id.clone())
+            .field(AuthFlowDiagnosisField::DISABLED, disabled)
+            .field(AuthFlowDiagnosisField::HAS_EXPIRY, has_expiry)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/groups/:id/availability",
+                    method: "PUT",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Group availability update completed",
+            );
+            Ok(with_etag(updated.revision, updated))
+        }
+        Err(error) => {
+            let diagnosis = group_route_failure_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_GROUP_AVAILABILITY,
+                "/api/groups/:id/availability",
+                "PUT",
+                "availability",
+                Some(&id),
+            )
+            .field(AuthFlowDiagnosisField::DISABLED, disabled)
+            .field(AuthFlowDiagnosisField::HAS_EXPIRY, has_expiry)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/groups/:id/availability",
+                    method: "PUT",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Group availability update failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
+/// PUT /api/groups/:id/identity-access
+///
+/// Replace the OIDC sessions and bearer access tokens forward auth accepts
+/// for a group besides its member entries. With `If-Match`, they are only
+/// replaced while the group's `ETag` still matches.
+pub async fn identity_access(
+    Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<IdentityAccess>,
+) -> Result<WithETag<Group>, ServerError> {
+    let expected_revision = if_match_revision(&headers)?;
+    let (accepts_session, accepts_bearer) = (req.session, req.bearer);
+    match state
+        .creds_manage_store
+        .set_group_identity_access_at_revision(&actor, &id, expected_revision, req)
+        .await
+    {
+        Ok(updated) => {
+            let diagnosis = group_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_GROUP_IDENTITY_ACCESS,
+                "/api/groups/:id/identity-access",
+                "PUT",
+                "identity_access",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(AuthFlowDiagnosisField::HAS_TARGET_ID, true)
+            .field(AuthFlowDiagnosisField::TARGET_ID, id.clone())
+            .field(AuthFlowDiagnosisField::ACCEPTS_SESSION, accepts_session)
+            .field(AuthFlowDiagnosisField::ACCEPTS_BEARER, accepts_bearer)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/groups/:id/identity-access",
+                    method: "PUT",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Group identity access update completed",
+            );
+            Ok(with_etag(updated.revision, updated))
+        }
+        Err(error) => {
+            let diagnosis = group_route_failure_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_GROUP_IDENTITY_ACCESS,
+                "/api/groups/:id/identity-access",
+                "PUT",
+                "identity_access",
+                Some(&id),
+            )
+            .field(AuthFlowDiagnosisField::ACCEPTS_SESSION, accepts_session)
+            .field(AuthFlowDiagnosisField::ACCEPTS_BEARER, accepts_bearer)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/groups/:id/identity-access",
+                    method: "PUT",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Group identity access update failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
+/// PUT /api/groups/:id/access-rules
+///
+/// Replace the rules restricting which requests forward auth grants for a
+/// group. The first matching rule applies; with rules set, requests no rule
+/// matches are refused. With `If-Match`, the rules are only replaced while
+/// the group's `ETag` still matches.
+pub async fn access_rules(
+    Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<Vec<AccessRule>>,
+) -> Result<WithETag<Group>, ServerError> {
+    let expected_revision = if_match_revision(&headers)?;
+    let rules_count = req.len();
+    match state
+        .creds_manage_store
+        .set_group_access_rules_at_revision(&actor, &id, expected_revision, req)
+        .await
+    {
+        Ok(updated) => {
+            let diagnosis = group_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_GROUP_ACCESS_RULES,
+                "/api/groups/:id/access-rules",
+                "PUT",
+                "access_rules",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(AuthFlowDiagnosisField::HAS_TARGET_ID, true)
+            .field(AuthFlowDiagnosisField::TARGET_ID, id.clone())
+            .field(AuthFlowDiagnosisField::ACCESS_RULES_COUNT, rules_count)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/groups/:id/access-rules",
+                    method: "PUT",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Group access rules update completed",
+            );
+            Ok(with_etag(updated.revision, updated))
+        }
+        Err(error) => {
+            let diagnosis = group_route_failure_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_GROUP_ACCESS_RULES,
+                "/api/groups/:id/access-rules",
+                "PUT",
+                "access_rules",
+                Some(&id),
+            )
+            .field(AuthFlowDiagnosisField::ACCESS_RULES_COUNT, rules_count)
+            .field(AuthFlowDiagnosisField::EXPECTED_REVISION, expected_revision);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/groups/:id/access-rules",
+                    method: "PUT",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Group access rules update failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
 /// DELETE /api/groups/:id
+///
+/// With `If-Match`, the group is only deleted while its `ETag` still
+/// matches.
 pub async fn delete(
     Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
     Path(id): Path<String>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>, ServerError> {
-    match state.creds_manage_store.delete_group(&id).await {
+    let expected_revision = if_match_revision(&headers)?;
+    match state
+        .creds_manage_store
+        .delete_group_at_revision(&actor, &id, expected_revision)
+        .await
+    {
         Ok(()) => {
             let diagnosis = group_route_base_diagnosis(
                 AuthFlowOperation::CREDS_MANAGE_GROUP_DELETE,
@@ -293,14 +552,31 @@ pub async fn delete(
 
 #[cfg(test)]
 mod tests {
-    use axum::{Extension, Json, extract::Path, http::StatusCode, response::IntoResponse};
-    use securitydept_core::creds_manage::models::{DataFile, Group};
+    use axum::{
+        Extension, Json,
+        extract::Path,
+        http::{HeaderValue, StatusCode, header},
+        response::IntoResponse,
+    };
+    use securitydept_core::creds_manage::{
+        AuditActorSource,
+        models::{DataFile, Group},
+    };
 
     use super::*;
     use crate::routes::test_support::{
         assert_server_error_envelope, test_server_state, test_server_state_with_data,
     };
 
+    fn if_match(revision: u64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MATCH,
+            HeaderValue::from_str(&format!("\"{revision}\"")).unwrap(),
+        );
+        headers
+    }
+
     #[test]
     fn group_create_success_diagnosis_reports_created_group_without_secret_fields() {
         let group = Group::new("Operators".to_string());
@@ -367,8 +643,10 @@ mod tests {
 
         let response = create(
             Extension(state),
+            Extension(AuditActor::new(AuditActorSource::Session, "tester")),
             Json(CreateGroupRequest {
                 name: "Operators".to_string(),
+                parent_ids: Vec::new(),
                 entry_ids: None,
             }),
         )
@@ -385,4 +663,243 @@ mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn update_with_stale_if_match_returns_precondition_failed() {
+        let group = Group::new("Operators".to_string());
+        let state = test_server_state_with_data(
+            "group-stale-revision",
+            Some(DataFile {
+                groups: vec![group.clone()],
+                ..Default::default()
+            }),
+        )
+        .await;
+        let actor = AuditActor::new(AuditActorSource::Session, "tester");
+        let rename = |name: &str| UpdateGroupRequest {
+            name: name.to_string(),
+            parent_ids: None,
+            entry_ids: None,
+        };
+
+        let (etag, Json(updated)) = update(
+            Extension(state.clone()),
+            Extension(actor.clone()),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(rename("Admins")),
+        )
+        .await
+        .expect("update at the current revision should succeed");
+        assert_eq!(updated.revision, group.revision + 1);
+        assert_eq!(etag[0].1, format!("\"{}\"", updated.revision));
+
+        let response = update(
+            Extension(state),
+            Extension(actor),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(rename("Auditors")),
+        )
+        .await
+        .expect_err("update at a stale revision should fail")
+        .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::PRECONDITION_FAILED,
+            "conflict",
+            "revision_mismatch",
+            "none",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn update_after_an_entry_membership_change_returns_precondition_failed() {
+        let group = Group::new("Operators".to_string());
+        let state = test_server_state_with_data(
+            "group-membership-revision",
+            Some(DataFile {
+                groups: vec![group.clone()],
+                ..Default::default()
+            }),
+        )
+        .await;
+        let actor = AuditActor::new(AuditActorSource::Session, "tester");
+        let entry = state
+            .creds_manage_store
+            .create_basic_entry(
+                &actor,
+                "operator".to_string(),
+                "operator".to_string(),
+                "secret123".to_string(),
+                vec![group.id.clone()],
+            )
+            .await
+            .expect("entry should join the group");
+
+        let response = update(
+            Extension(state),
+            Extension(actor),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(UpdateGroupRequest {
+                name: group.name.clone(),
+                parent_ids: None,
+                entry_ids: Some(vec![entry.meta.id]),
+            }),
+        )
+        .await
+        .expect_err("the entry joining must invalidate the group ETag")
+        .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::PRECONDITION_FAILED,
+            "conflict",
+            "revision_mismatch",
+            "none",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn availability_with_stale_if_match_returns_precondition_failed() {
+        let group = Group::new("Operators".to_string());
+        let state = test_server_state_with_data(
+            "group-availability-revision",
+            Some(DataFile {
+                groups: vec![group.clone()],
+                ..Default::default()
+            }),
+        )
+        .await;
+        let actor = AuditActor::new(AuditActorSource::Session, "tester");
+
+        let (etag, Json(updated)) = availability(
+            Extension(state.clone()),
+            Extension(actor.clone()),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(Availability::disabled(None)),
+        )
+        .await
+        .expect("availability at the current revision should be replaced");
+        assert_eq!(etag[0].1, format!("\"{}\"", updated.revision));
+
+        let response = availability(
+            Extension(state),
+            Extension(actor),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(Availability::default()),
+        )
+        .await
+        .expect_err("availability at a stale revision should be refused")
+        .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::PRECONDITION_FAILED,
+            "conflict",
+            "revision_mismatch",
+            "none",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn access_rules_with_stale_if_match_returns_precondition_failed() {
+        let group = Group::new("Operators".to_string());
+        let state = test_server_state_with_data(
+            "group-access-rules-revision",
+            Some(DataFile {
+                groups: vec![group.clone()],
+                ..Default::default()
+            }),
+        )
+        .await;
+        let actor = AuditActor::new(AuditActorSource::Session, "tester");
+
+        let (etag, Json(updated)) = access_rules(
+            Extension(state.clone()),
+            Extension(actor.clone()),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(Vec::new()),
+        )
+        .await
+        .expect("access rules at the current revision should be replaced");
+        assert_eq!(etag[0].1, format!("\"{}\"", updated.revision));
+
+        let response = access_rules(
+            Extension(state),
+            Extension(actor),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(Vec::new()),
+        )
+        .await
+        .expect_err("access rules at a stale revision should be refused")
+        .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::PRECONDITION_FAILED,
+            "conflict",
+            "revision_mismatch",
+            "none",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn identity_access_with_stale_if_match_returns_precondition_failed() {
+        let group = Group::new("Operators".to_string());
+        let state = test_server_state_with_data(
+            "group-identity-access-revision",
+            Some(DataFile {
+                groups: vec![group.clone()],
+                ..Default::default()
+            }),
+        )
+        .await;
+        let actor = AuditActor::new(AuditActorSource::Session, "tester");
+        let session_only = IdentityAccess {
+            session: true,
+            ..IdentityAccess::default()
+        };
+
+        let (etag, Json(updated)) = identity_access(
+            Extension(state.clone()),
+            Extension(actor.clone()),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(session_only.clone()),
+        )
+        .await
+        .expect("identity access at the current revision should be replaced");
+        assert_eq!(etag[0].1, format!("\"{}\"", updated.revision));
+
+        let response = identity_access(
+            Extension(state),
+            Extension(actor),
+            Path(group.id.clone()),
+            if_match(group.revision),
+            Json(session_only),
+        )
+        .await
+        .expect_err("identity access at a stale revision should be refused")
+        .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::PRECONDITION_FAILED,
+            "conflict",
+            "revision_mismatch",
+            "none",
+        )
+        .await;
+    }
 }
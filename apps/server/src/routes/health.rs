@@ -1,4 +1,6 @@
 use axum::{Extension, Json, extract::Query};
+use chrono::{DateTime, Utc};
+use securitydept_core::creds_manage::models::ExpirySweep;
 use serde::{Deserialize, Serialize};
 
 use crate::state::ServerState;
@@ -55,10 +57,32 @@ impl ApiCatalogCapabilities {
 pub struct HealthResponse {
     pub status: &'static str,
     pub service: &'static str,
+    /// Outcome of the latest expiry sweep; absent until the first sweep ran.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<ExpiryHealth>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub apis: Option<Vec<ApiRouteInfo>>,
 }
 
+/// Counts of expired entries and groups. Ids are left to the authenticated
+/// entry and group listings, since this endpoint is public.
+#[derive(Debug, Serialize)]
+pub struct ExpiryHealth {
+    pub swept_at: DateTime<Utc>,
+    pub expired_entries: usize,
+    pub expired_groups: usize,
+}
+
+impl From<ExpirySweep> for ExpiryHealth {
+    fn from(sweep: ExpirySweep) -> Self {
+        Self {
+            swept_at: sweep.swept_at,
+            expired_entries: sweep.expired_entry_ids.len(),
+            expired_groups: sweep.expired_group_ids.len(),
+        }
+    }
+}
+
 pub(crate) fn api_route_catalog(capabilities: ApiCatalogCapabilities) -> Vec<ApiRouteInfo> {
     vec![
         ApiRouteInfo {
@@ -77,6 +101,14 @@ pub(crate) fn api_route_catalog(capabilities: ApiCatalogCapabilities) -> Vec<Api
             availability: ApiRouteAvailability::Always,
             description: "Compatibility alias for service health and API metadata",
         },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/.well-known/jwks.json",
+            auth_required: false,
+            auth_boundary: ApiRouteAuthBoundary::Public,
+            availability: ApiRouteAvailability::Always,
+            description: "Public keys of the managed signing keyring (empty when not configured)",
+        },
         ApiRouteInfo {
             method: "GET",
             path: "/auth/session/login",
@@ -229,6 +261,15 @@ pub(crate) fn api_route_catalog(capabilities: ApiCatalogCapabilities) -> Vec<Api
             availability: ApiRouteAvailability::Always,
             description: "Delete an auth entry by id through the Basic Auth protected mirror",
         },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/basic/api/entries/{id}/availability",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Disable, enable or set the expiry of an auth entry through the Basic \
+                          Auth protected mirror",
+        },
         ApiRouteInfo {
             method: "GET",
             path: "/basic/api/groups",
@@ -269,6 +310,75 @@ pub(crate) fn api_route_catalog(capabilities: ApiCatalogCapabilities) -> Vec<Api
             availability: ApiRouteAvailability::Always,
             description: "Delete a group by id through the Basic Auth protected mirror",
         },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/basic/api/groups/{id}/availability",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Disable, enable or set the expiry of a group through the Basic Auth \
+                          protected mirror",
+        },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/basic/api/groups/{id}/identity-access",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Set the OIDC sessions and access tokens forward auth accepts for a \
+                          group through the Basic Auth protected mirror",
+        },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/basic/api/groups/{id}/access-rules",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Replace the forward-auth host, path and method rules of a group through \
+                          the Basic Auth protected mirror",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/basic/api/audit",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Query the creds-manage audit log through the Basic Auth protected mirror",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/basic/api/audit/verify",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Verify the audit log hash chain through the Basic Auth protected mirror",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/basic/api/store/export",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Export all groups and entries as a store archive through the Basic Auth \
+                          protected mirror",
+        },
+        ApiRouteInfo {
+            method: "POST",
+            path: "/basic/api/store/import",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Import a store archive through the Basic Auth protected mirror",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/basic/api/store/events",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::BasicAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "Stream store change events as server-sent events through the Basic Auth \
+                          protected mirror",
+        },
         ApiRouteInfo {
             method: "GET",
             path: "/api/entries",
@@ -317,6 +427,15 @@ pub(crate) fn api_route_catalog(capabilities: ApiCatalogCapabilities) -> Vec<Api
             availability: ApiRouteAvailability::Always,
             description: "Delete an auth entry by id through the dashboard auth boundary",
         },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/api/entries/{id}/availability",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Disable, enable or set the expiry of an auth entry through the \
+                          dashboard auth boundary",
+        },
         ApiRouteInfo {
             method: "GET",
             path: "/api/groups",
@@ -357,21 +476,117 @@ pub(crate) fn api_route_catalog(capabilities: ApiCatalogCapabilities) -> Vec<Api
             availability: ApiRouteAvailability::Always,
             description: "Delete a group by id through the dashboard auth boundary",
         },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/api/groups/{id}/availability",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Disable, enable or set the expiry of a group through the dashboard auth \
+                          boundary",
+        },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/api/groups/{id}/identity-access",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Set the OIDC sessions and access tokens forward auth accepts for a \
+                          group through the dashboard auth boundary",
+        },
+        ApiRouteInfo {
+            method: "PUT",
+            path: "/api/groups/{id}/access-rules",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Replace the forward-auth host, path and method rules of a group through \
+                          the dashboard auth boundary",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/api/audit",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Query the creds-manage audit log through the dashboard auth boundary",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/api/audit/verify",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Verify the audit log hash chain through the dashboard auth boundary",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/api/store/export",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Export all groups and entries as a store archive through the dashboard \
+                          auth boundary",
+        },
+        ApiRouteInfo {
+            method: "POST",
+            path: "/api/store/import",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Import a store archive through the dashboard auth boundary",
+        },
         ApiRouteInfo {
             method: "GET",
-            path: "/api/forwardauth/traefik/{group}",
+            path: "/api/store/events",
+            auth_required: true,
+            auth_boundary: ApiRouteAuthBoundary::Dashboard,
+            availability: ApiRouteAvailability::Always,
+            description: "Stream store change events as server-sent events through the dashboard \
+                          auth boundary",
+        },
+        ApiRouteInfo {
+            method: "GET",
+            path: "/api/forwardauth/{adapter}/{group}",
             auth_required: false,
             auth_boundary: ApiRouteAuthBoundary::ForwardAuth,
             availability: ApiRouteAvailability::Always,
-            description: "ForwardAuth endpoint for Traefik",
+            description: "ForwardAuth endpoint for Traefik, Nginx, Caddy and HAProxy (adapter \
+                          traefik, nginx, caddy or haproxy)",
         },
         ApiRouteInfo {
-            method: "GET",
-            path: "/api/forwardauth/nginx/{group}",
+            method: "ANY",
+            path: "/api/forwardauth/envoy/{group}",
+            auth_required: false,
+            auth_boundary: ApiRouteAuthBoundary::ForwardAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "ForwardAuth endpoint for the Envoy ext_authz HTTP service",
+        },
+        ApiRouteInfo {
+            method: "ANY",
+            path: "/api/forwardauth/envoy/{group}/",
+            auth_required: false,
+            auth_boundary: ApiRouteAuthBoundary::ForwardAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "ForwardAuth endpoint for the Envoy ext_authz HTTP service, checking the \
+                          original path /",
+        },
+        ApiRouteInfo {
+            method: "ANY",
+            path: "/api/forwardauth/envoy/{group}/{*path}",
+            auth_required: false,
+            auth_boundary: ApiRouteAuthBoundary::ForwardAuth,
+            availability: ApiRouteAvailability::Always,
+            description: "ForwardAuth endpoint for the Envoy ext_authz HTTP service, followed by \
+                          the original request path",
+        },
+        ApiRouteInfo {
+            method: "POST",
+            path: "/envoy.service.auth.v3.Authorization/Check",
             auth_required: false,
             auth_boundary: ApiRouteAuthBoundary::ForwardAuth,
             availability: ApiRouteAvailability::Always,
-            description: "ForwardAuth endpoint for Nginx",
+            description: "ForwardAuth endpoint for the Envoy ext_authz gRPC service",
         },
         ApiRouteInfo {
             method: "ANY",
@@ -407,6 +622,10 @@ pub async fn health(
     Json(HealthResponse {
         status: "ok",
         service: "securitydept-server",
+        expiry: state
+            .creds_manage_store
+            .last_expiry_sweep()
+            .map(ExpiryHealth::from),
         apis,
     })
 }
@@ -453,7 +672,7 @@ mod tests {
             ApiRouteAuthBoundary::Public
         );
         assert_eq!(
-            find_route(&catalog, "GET", "/api/forwardauth/nginx/{group}").auth_boundary,
+            find_route(&catalog, "GET", "/api/forwardauth/{adapter}/{group}").auth_boundary,
             ApiRouteAuthBoundary::ForwardAuth
         );
     }
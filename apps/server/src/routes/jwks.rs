@@ -0,0 +1,89 @@
+use axum::{Extension, Json};
+use securitydept_core::jsonwebtoken::jwk::JwkSet;
+
+use crate::state::ServerState;
+
+/// GET /.well-known/jwks.json
+///
+/// Public keys of the managed signing keyring, including retired keys still
+/// inside their overlap window. Returns an empty key set when no
+/// `[signing_keyring]` is configured.
+pub async fn jwks(Extension(state): Extension<ServerState>) -> Json<JwkSet> {
+    let Some(keyring) = state.signing_keyring.as_deref() else {
+        return Json(JwkSet { keys: Vec::new() });
+    };
+
+    Json(keyring.read().await.to_public_jwk_set())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use axum::{
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+    };
+    use securitydept_core::creds::{
+        JwtSigningKeyTrait, LocalJwtSigningKeyring, SigningKeyringConfig,
+    };
+    use tokio::sync::RwLock;
+    use tower::util::ServiceExt;
+
+    use crate::routes::{build_router, test_support::test_server_state};
+
+    async fn get_jwks(state: crate::state::ServerState) -> serde_json::Value {
+        let response = build_router(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/.well-known/jwks.json")
+                    .body(Body::empty())
+                    .expect("request should build"),
+            )
+            .await
+            .expect("jwks request should succeed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        serde_json::from_slice(
+            &to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("jwks body should be readable"),
+        )
+        .expect("jwks body should be valid json")
+    }
+
+    #[tokio::test]
+    async fn jwks_is_empty_without_a_signing_keyring() {
+        let body = get_jwks(test_server_state("jwks-empty").await).await;
+
+        assert_eq!(body, serde_json::json!({ "keys": [] }));
+    }
+
+    #[tokio::test]
+    async fn jwks_publishes_the_active_signing_key() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after unix epoch")
+            .as_nanos();
+        let keyring_path =
+            std::env::temp_dir().join(format!("securitydept-server-jwks-test-{nanos}.json"));
+        let keyring = LocalJwtSigningKeyring::open(SigningKeyringConfig::new(&keyring_path))
+            .expect("signing keyring should open");
+        let kid = keyring.active_key().kid().to_string();
+
+        let mut state = test_server_state("jwks-keyring").await;
+        state.signing_keyring = Some(Arc::new(RwLock::new(keyring)));
+        let body = get_jwks(state).await;
+
+        let keys = body["keys"].as_array().expect("keys should be an array");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0]["kid"], kid);
+        assert_eq!(keys[0]["use"], "sig");
+        assert!(keys[0].get("d").is_none(), "private key must not leak");
+
+        std::fs::remove_file(&keyring_path).ok();
+    }
+}
@@ -1,10 +1,14 @@
+pub mod audit;
 pub mod auth;
 pub mod entries;
 pub mod forward_auth;
 pub mod groups;
 pub mod health;
+pub mod jwks;
 pub mod policy;
 pub mod propagation;
+pub mod revision;
+pub mod store;
 #[cfg(test)]
 pub mod test_support;
 
@@ -56,11 +60,20 @@ pub fn build_router(state: ServerState) -> Router {
         .route("/entries/{id}", get(entries::get))
         .route("/entries/{id}", put(entries::update))
         .route("/entries/{id}", delete(entries::delete))
+        .route("/entries/{id}/availability", put(entries::availability))
         .route("/groups", get(groups::list))
         .route("/groups", post(groups::create))
         .route("/groups/{id}", get(groups::get))
         .route("/groups/{id}", put(groups::update))
-        .route("/groups/{id}", delete(groups::delete));
+        .route("/groups/{id}", delete(groups::delete))
+        .route("/groups/{id}/availability", put(groups::availability))
+        .route("/groups/{id}/identity-access", put(groups::identity_access))
+        .route("/groups/{id}/access-rules", put(groups::access_rules))
+        .route("/audit", get(audit::list))
+        .route("/audit/verify", get(audit::verify))
+        .route("/store/export", get(store::export))
+        .route("/store/import", post(store::import))
+        .route("/store/events", get(store::events));
 
     let api_routes = Router::new()
         .nest("/api", creds_manage_api_routes.clone())
@@ -71,10 +84,25 @@ pub fn build_router(state: ServerState) -> Router {
 
     let forward_auth_routes = Router::new()
         .route(
-            "/api/forwardauth/traefik/{group}",
-            get(forward_auth::traefik),
+            "/api/forwardauth/{adapter}/{group}",
+            get(forward_auth::check),
         )
-        .route("/api/forwardauth/nginx/{group}", get(forward_auth::nginx));
+        .route(
+            "/api/forwardauth/envoy/{group}",
+            any(forward_auth::envoy::http),
+        )
+        .route(
+            "/api/forwardauth/envoy/{group}/",
+            any(forward_auth::envoy::http),
+        )
+        .route(
+            "/api/forwardauth/envoy/{group}/{*path}",
+            any(forward_auth::envoy::http),
+        )
+        .route(
+            forward_auth::envoy::GRPC_CHECK_PATH,
+            post(forward_auth::envoy::grpc_check),
+        );
 
     // Propagation forwarding: forward bearer-authenticated requests with
     // propagation context to downstream services via reverse proxy.
@@ -92,6 +120,7 @@ pub fn build_router(state: ServerState) -> Router {
     let app = Router::new()
         .route("/api/health", get(health::health))
         .route("/health", get(health::health))
+        .route("/.well-known/jwks.json", get(jwks::jwks))
         .nest("/basic", auth::basic::router())
         .merge(auth_routes)
         .nest(
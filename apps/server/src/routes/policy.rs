@@ -57,8 +57,9 @@ pub enum RouteDiagnosisPolicy {
     /// ordinary failure. The route may still emit a diagnosis, but its
     /// observable response shape is owned by the protocol contract.
     ProtocolException,
-    /// Capability metadata endpoint (route catalog, health) that is not an
-    /// auth-flow operation and intentionally does not pretend to be one.
+    /// Capability metadata endpoint (route catalog, health, signing JWKS)
+    /// that is not an auth-flow operation and intentionally does not pretend
+    /// to be one.
     CapabilityCatalog,
     /// Static webui asset serving via fallback `ServeDir`. Never enters
     /// the auth-flow diagnosis baseline.
@@ -91,7 +92,7 @@ pub enum RouteErrorPolicy {
     /// semantics distinct from a server failure.
     BusinessNotFound,
     /// Protocol-specific `401` challenge that must keep `WWW-Authenticate`
-    /// (Basic-Auth login, ForwardAuth Traefik / Nginx). Wrapping these
+    /// (Basic-Auth login, ForwardAuth adapters). Wrapping these
     /// in the shared envelope would break the browser-native or proxy
     /// contract.
     ProtocolChallengeException,
@@ -113,8 +114,9 @@ pub enum RouteErrorPolicy {
     /// unauthorized response shape from being retrofitted back into
     /// `SharedEnvelope`.
     BasicAuthMirrorUnauthorized,
-    /// Capability metadata endpoint (`/api/health`, `/health`); never
-    /// returns an envelope failure under normal operation.
+    /// Capability metadata endpoint (`/api/health`, `/health`,
+    /// `/.well-known/jwks.json`); never returns an envelope failure under
+    /// normal operation.
     CapabilityCatalog,
     /// Static webui asset serving; failures come from the underlying
     /// `ServeDir` / `ServeFile` services, not from the application error
@@ -132,7 +134,7 @@ pub struct RouteErrorEntry {
 
 fn classify_diagnosis(method: &str, path: &str) -> (RouteDiagnosisPolicy, Option<&'static str>) {
     match (method, path) {
-        ("GET", "/api/health") | ("GET", "/health") => {
+        ("GET", "/api/health") | ("GET", "/health") | ("GET", "/.well-known/jwks.json") => {
             (RouteDiagnosisPolicy::CapabilityCatalog, None)
         }
 
@@ -189,12 +191,14 @@ fn classify_diagnosis(method: &str, path: &str) -> (RouteDiagnosisPolicy, Option
             Some(AuthFlowOperation::BASIC_AUTH_LOGOUT),
         ),
 
-        ("GET", "/api/forwardauth/traefik/{group}") | ("GET", "/api/forwardauth/nginx/{group}") => {
-            (
-                RouteDiagnosisPolicy::ProtocolException,
-                Some(AuthFlowOperation::FORWARD_AUTH_CHECK),
-            )
-        }
+        ("GET", "/api/forwardauth/{adapter}/{group}")
+        | ("ANY", "/api/forwardauth/envoy/{group}")
+        | ("ANY", "/api/forwardauth/envoy/{group}/")
+        | ("ANY", "/api/forwardauth/envoy/{group}/{*path}")
+        | ("POST", "/envoy.service.auth.v3.Authorization/Check") => (
+            RouteDiagnosisPolicy::ProtocolException,
+            Some(AuthFlowOperation::FORWARD_AUTH_CHECK),
+        ),
 
         ("ANY", "/api/propagation/{*rest}") => (
             RouteDiagnosisPolicy::Diagnosed,
@@ -217,6 +221,26 @@ fn classify_diagnosis(method: &str, path: &str) -> (RouteDiagnosisPolicy, Option
         (_, p) if p.starts_with("/api/entries/") => classify_creds_entry(method, path),
         (_, "/api/groups") => classify_creds_group(method, path),
         (_, p) if p.starts_with("/api/groups/") => classify_creds_group(method, path),
+        ("GET", "/api/audit") => (
+            RouteDiagnosisPolicy::Diagnosed,
+            Some(AuthFlowOperation::CREDS_MANAGE_AUDIT_LIST),
+        ),
+        ("GET", "/api/audit/verify") => (
+            RouteDiagnosisPolicy::Diagnosed,
+            Some(AuthFlowOperation::CREDS_MANAGE_AUDIT_VERIFY),
+        ),
+        ("GET", "/api/store/export") => (
+            RouteDiagnosisPolicy::Diagnosed,
+            Some(AuthFlowOperation::CREDS_MANAGE_STORE_EXPORT),
+        ),
+        ("POST", "/api/store/import") => (
+            RouteDiagnosisPolicy::Diagnosed,
+            Some(AuthFlowOperation::CREDS_MANAGE_STORE_IMPORT),
+        ),
+        ("GET", "/api/store/events") => (
+            RouteDiagnosisPolicy::Diagnosed,
+            Some(AuthFlowOperation::CREDS_MANAGE_STORE_EVENTS),
+        ),
 
         _ => unreachable!("unclassified mounted route family: {method} {path}"),
     }
@@ -228,6 +252,9 @@ fn classify_creds_entry(method: &str, path: &str) -> (RouteDiagnosisPolicy, Opti
         ("GET", _) => AuthFlowOperation::CREDS_MANAGE_ENTRY_GET,
         ("POST", "/api/entries/basic") => AuthFlowOperation::CREDS_MANAGE_ENTRY_CREATE_BASIC,
         ("POST", "/api/entries/token") => AuthFlowOperation::CREDS_MANAGE_ENTRY_CREATE_TOKEN,
+        ("PUT", p) if p.ends_with("/availability") => {
+            AuthFlowOperation::CREDS_MANAGE_ENTRY_AVAILABILITY
+        }
         ("PUT", _) => AuthFlowOperation::CREDS_MANAGE_ENTRY_UPDATE,
         ("DELETE", _) => AuthFlowOperation::CREDS_MANAGE_ENTRY_DELETE,
         _ => unreachable!("unclassified creds-manage entry route: {method} {path}"),
@@ -240,6 +267,15 @@ fn classify_creds_group(method: &str, path: &str) -> (RouteDiagnosisPolicy, Opti
         ("GET", "/api/groups") => AuthFlowOperation::CREDS_MANAGE_GROUP_LIST,
         ("GET", _) => AuthFlowOperation::CREDS_MANAGE_GROUP_GET,
         ("POST", "/api/groups") => AuthFlowOperation::CREDS_MANAGE_GROUP_CREATE,
+        ("PUT", p) if p.ends_with("/availability") => {
+            AuthFlowOperation::CREDS_MANAGE_GROUP_AVAILABILITY
+        }
+        ("PUT", p) if p.ends_with("/identity-access") => {
+            AuthFlowOperation::CREDS_MANAGE_GROUP_IDENTITY_ACCESS
+        }
+        ("PUT", p) if p.ends_with("/access-rules") => {
+            AuthFlowOperation::CREDS_MANAGE_GROUP_ACCESS_RULES
+        }
         ("PUT", _) => AuthFlowOperation::CREDS_MANAGE_GROUP_UPDATE,
         ("DELETE", _) => AuthFlowOperation::CREDS_MANAGE_GROUP_DELETE,
         _ => unreachable!("unclassified creds-manage group route: {method} {path}"),
@@ -249,10 +285,16 @@ fn classify_creds_group(method: &str, path: &str) -> (RouteDiagnosisPolicy, Opti
 
 fn classify_error(method: &str, path: &str) -> RouteErrorPolicy {
     match (method, path) {
-        ("GET", "/api/health") | ("GET", "/health") => RouteErrorPolicy::CapabilityCatalog,
+        ("GET", "/api/health") | ("GET", "/health") | ("GET", "/.well-known/jwks.json") => {
+            RouteErrorPolicy::CapabilityCatalog
+        }
         ("GET", "/basic/login") => RouteErrorPolicy::ProtocolChallengeException,
         ("POST", "/basic/logout") => RouteErrorPolicy::ProtocolPoisonException,
-        ("GET", "/api/forwardauth/traefik/{group}") | ("GET", "/api/forwardauth/nginx/{group}") => {
+        ("GET", "/api/forwardauth/{adapter}/{group}")
+        | ("ANY", "/api/forwardauth/envoy/{group}")
+        | ("ANY", "/api/forwardauth/envoy/{group}/")
+        | ("ANY", "/api/forwardauth/envoy/{group}/{*path}")
+        | ("POST", "/envoy.service.auth.v3.Authorization/Check") => {
             RouteErrorPolicy::ProtocolChallengeException
         }
         ("POST", "/auth/token-set/backend-mode/metadata/redeem") => {
@@ -347,12 +389,21 @@ fn known_auth_flow_operations() -> &'static [&'static str] {
         AuthFlowOperation::CREDS_MANAGE_GROUP_CREATE,
         AuthFlowOperation::CREDS_MANAGE_GROUP_UPDATE,
         AuthFlowOperation::CREDS_MANAGE_GROUP_DELETE,
+        AuthFlowOperation::CREDS_MANAGE_GROUP_AVAILABILITY,
+        AuthFlowOperation::CREDS_MANAGE_GROUP_IDENTITY_ACCESS,
+        AuthFlowOperation::CREDS_MANAGE_GROUP_ACCESS_RULES,
         AuthFlowOperation::CREDS_MANAGE_ENTRY_LIST,
         AuthFlowOperation::CREDS_MANAGE_ENTRY_GET,
         AuthFlowOperation::CREDS_MANAGE_ENTRY_CREATE_BASIC,
         AuthFlowOperation::CREDS_MANAGE_ENTRY_CREATE_TOKEN,
         AuthFlowOperation::CREDS_MANAGE_ENTRY_UPDATE,
         AuthFlowOperation::CREDS_MANAGE_ENTRY_DELETE,
+        AuthFlowOperation::CREDS_MANAGE_ENTRY_AVAILABILITY,
+        AuthFlowOperation::CREDS_MANAGE_AUDIT_LIST,
+        AuthFlowOperation::CREDS_MANAGE_AUDIT_VERIFY,
+        AuthFlowOperation::CREDS_MANAGE_STORE_EXPORT,
+        AuthFlowOperation::CREDS_MANAGE_STORE_IMPORT,
+        AuthFlowOperation::CREDS_MANAGE_STORE_EVENTS,
     ]
 }
 
@@ -461,7 +512,7 @@ mod tests {
             propagation_route_enabled: false,
         });
 
-        for path in ["/api/health", "/health"] {
+        for path in ["/api/health", "/health", "/.well-known/jwks.json"] {
             let entry = find_diag(&entries, "GET", path);
             assert_eq!(
                 entry.policy,
@@ -492,11 +543,12 @@ mod tests {
             Some(AuthFlowOperation::BASIC_AUTH_LOGOUT)
         );
 
-        for path in [
-            "/api/forwardauth/traefik/{group}",
-            "/api/forwardauth/nginx/{group}",
+        for (method, path) in [
+            ("GET", "/api/forwardauth/{adapter}/{group}"),
+            ("ANY", "/api/forwardauth/envoy/{group}/{*path}"),
+            ("POST", "/envoy.service.auth.v3.Authorization/Check"),
         ] {
-            let fa = find_diag(&entries, "GET", path);
+            let fa = find_diag(&entries, method, path);
             assert_eq!(fa.policy, RouteDiagnosisPolicy::ProtocolException);
             assert_eq!(fa.operation, Some(AuthFlowOperation::FORWARD_AUTH_CHECK));
         }
@@ -591,7 +643,8 @@ mod tests {
                     assert_eq!(entry.policy, RouteDiagnosisPolicy::Diagnosed);
                 }
                 ApiRouteAuthBoundary::Public => {
-                    // /api/health and /health: capability catalog.
+                    // /api/health, /health and the signing JWKS: capability
+                    // catalog.
                     assert_eq!(entry.policy, RouteDiagnosisPolicy::CapabilityCatalog);
                 }
             }
@@ -631,11 +684,16 @@ mod tests {
             RouteErrorPolicy::ProtocolPoisonException,
         );
         assert_eq!(
-            find_err(&entries, "GET", "/api/forwardauth/traefik/{group}").policy,
+            find_err(&entries, "GET", "/api/forwardauth/{adapter}/{group}").policy,
             RouteErrorPolicy::ProtocolChallengeException,
         );
         assert_eq!(
-            find_err(&entries, "GET", "/api/forwardauth/nginx/{group}").policy,
+            find_err(
+                &entries,
+                "POST",
+                "/envoy.service.auth.v3.Authorization/Check"
+            )
+            .policy,
             RouteErrorPolicy::ProtocolChallengeException,
         );
         assert_eq!(
@@ -698,7 +756,7 @@ mod tests {
         let entries = route_error_policy(ApiCatalogCapabilities {
             propagation_route_enabled: false,
         });
-        for path in ["/api/health", "/health"] {
+        for path in ["/api/health", "/health", "/.well-known/jwks.json"] {
             assert_eq!(
                 find_err(&entries, "GET", path).policy,
                 RouteErrorPolicy::CapabilityCatalog,
@@ -784,14 +842,21 @@ mod tests {
 
     #[tokio::test]
     async fn forward_auth_unauthorized_keeps_protocol_challenge_shape() {
+        use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+        use axum::extract::ConnectInfo;
+
         let app = build_router(test_server_state("policy-forward-auth").await);
+        let mut request = Request::builder()
+            .uri("/api/forwardauth/traefik/unknown-group")
+            .body(Body::empty())
+            .expect("request should build");
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            65000,
+        )));
         let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/api/forwardauth/traefik/unknown-group")
-                    .body(Body::empty())
-                    .expect("request should build"),
-            )
+            .oneshot(request)
             .await
             .expect("forward-auth request should be served");
 
@@ -0,0 +1,72 @@
+//! `ETag` / `If-Match` for creds-manage entries and groups. The entity tag
+//! of an entry or group is its quoted revision, e.g. `"3"`.
+
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+};
+use securitydept_core::utils::error::{ErrorPresentation, UserRecovery};
+
+use crate::error::ServerError;
+
+/// A response body sent with its `ETag`.
+pub type WithETag<T> = ([(HeaderName, HeaderValue); 1], Json<T>);
+
+pub fn with_etag<T>(revision: u64, body: T) -> WithETag<T> {
+    let etag = HeaderValue::from_str(&format!("\"{revision}\""))
+        .expect("a quoted revision should be a valid header value");
+    ([(header::ETAG, etag)], Json(body))
+}
+
+/// The revision required by the `If-Match` header, `None` when the header is
+/// absent or `*`.
+pub fn if_match_revision(headers: &HeaderMap) -> Result<Option<u64>, ServerError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .and_then(|revision| revision.parse().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            ServerError::route_presentation(
+                StatusCode::BAD_REQUEST,
+                ErrorPresentation::new(
+                    "invalid_if_match",
+                    "If-Match must be a single entity tag returned as ETag, such as \"3\".",
+                    UserRecovery::None,
+                ),
+                format!("Invalid If-Match header: {value}"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(if_match: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, HeaderValue::from_str(if_match).unwrap());
+        headers
+    }
+
+    #[test]
+    fn if_match_accepts_strong_revision_tags_and_wildcard() {
+        assert_eq!(if_match_revision(&HeaderMap::new()).unwrap(), None);
+        assert_eq!(if_match_revision(&headers("*")).unwrap(), None);
+        assert_eq!(if_match_revision(&headers("\"7\"")).unwrap(), Some(7));
+
+        for invalid in ["7", "W/\"7\"", "\"7\", \"8\"", "\"seven\""] {
+            assert!(
+                if_match_revision(&headers(invalid)).is_err(),
+                "{invalid} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use std::convert::Infallible;
+
+use axum::{
+    Extension, Json,
+    extract::Query,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use securitydept_core::{
+    creds_manage::{
+        AuditActor, StoreChangeEvent,
+        archive::{ImportOptions, ImportReport, StoreArchive},
+    },
+    utils::observability::{
+        AuthFlowDiagnosis, AuthFlowDiagnosisField, AuthFlowDiagnosisOutcome, AuthFlowOperation,
+    },
+};
+use tokio_stream::{
+    Stream, StreamExt,
+    wrappers::{BroadcastStream, errors::BroadcastStreamRecvError},
+};
+
+use crate::{
+    diagnosis::{RouteDiagnosisContext, log_route_diagnosis, log_route_diagnosis_error},
+    error::ServerError,
+    state::ServerState,
+};
+
+const STORE_ENTITY_KIND: &str = "store";
+
+fn store_route_base_diagnosis(
+    operation: &'static str,
+    route: &'static str,
+    method: &'static str,
+    operation_kind: &'static str,
+) -> AuthFlowDiagnosis {
+    AuthFlowDiagnosis::started(operation)
+        .field(AuthFlowDiagnosisField::ROUTE, route)
+        .field(AuthFlowDiagnosisField::METHOD, method)
+        .field(AuthFlowDiagnosisField::ENTITY_KIND, STORE_ENTITY_KIND)
+        .field(AuthFlowDiagnosisField::OPERATION_KIND, operation_kind)
+}
+
+/// GET /api/store/export
+///
+/// Every group and entry as a versioned archive, password and token hashes
+/// included.
+pub async fn export(
+    Extension(state): Extension<ServerState>,
+) -> Result<Json<StoreArchive>, ServerError> {
+    match state.creds_manage_store.export_archive().await {
+        Ok(archive) => {
+            let diagnosis = store_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_STORE_EXPORT,
+                "/api/store/export",
+                "GET",
+                "export",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(
+                AuthFlowDiagnosisField::RESULT_COUNT,
+                archive.basic_creds.len() + archive.token_creds.len(),
+            );
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/store/export",
+                    method: "GET",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Store export completed",
+            );
+            Ok(Json(archive))
+        }
+        Err(error) => {
+            let diagnosis = store_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_STORE_EXPORT,
+                "/api/store/export",
+                "GET",
+                "export",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Failed);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/store/export",
+                    method: "GET",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Store export failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
+/// POST /api/store/import
+///
+/// Query: mode (`merge` or `replace`), on_conflict (`fail`, `skip` or
+/// `overwrite`), dry_run. The body is an archive from [`export`]; the import
+/// is all or nothing.
+pub async fn import(
+    Extension(state): Extension<ServerState>,
+    Extension(actor): Extension<AuditActor>,
+    Query(options): Query<ImportOptions>,
+    Json(archive): Json<StoreArchive>,
+) -> Result<Json<ImportReport>, ServerError> {
+    match state
+        .creds_manage_store
+        .import_archive(&actor, archive, options)
+        .await
+    {
+        Ok(report) => {
+            let diagnosis = store_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_STORE_IMPORT,
+                "/api/store/import",
+                "POST",
+                "import",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Succeeded)
+            .field(AuthFlowDiagnosisField::MODE, json_name(&options.mode))
+            .field(AuthFlowDiagnosisField::DRY_RUN, options.dry_run)
+            .field(
+                AuthFlowDiagnosisField::RESULT_COUNT,
+                report.entries.created + report.entries.updated,
+            );
+            log_route_diagnosis(
+                RouteDiagnosisContext {
+                    route: "/api/store/import",
+                    method: "POST",
+                    status: Some(200),
+                },
+                &diagnosis,
+                "Store import completed",
+            );
+            Ok(Json(report))
+        }
+        Err(error) => {
+            let diagnosis = store_route_base_diagnosis(
+                AuthFlowOperation::CREDS_MANAGE_STORE_IMPORT,
+                "/api/store/import",
+                "POST",
+                "import",
+            )
+            .with_outcome(AuthFlowDiagnosisOutcome::Failed)
+            .field(AuthFlowDiagnosisField::MODE, json_name(&options.mode))
+            .field(AuthFlowDiagnosisField::DRY_RUN, options.dry_run);
+            log_route_diagnosis_error(
+                RouteDiagnosisContext {
+                    route: "/api/store/import",
+                    method: "POST",
+                    status: None,
+                },
+                &diagnosis,
+                &error,
+                "Store import failed",
+            );
+            Err(error.into())
+        }
+    }
+}
+
+/// GET /api/store/events
+///
+/// Server-sent events, one `change` event per [`StoreChangeEvent`] as JSON.
+/// A `lagged` event with the number of `skipped` changes means the client
+/// fell behind and should reload everything.
+pub async fn events(
+    Extension(state): Extension<ServerState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.creds_manage_store.subscribe();
+    let diagnosis = store_route_base_diagnosis(
+        AuthFlowOperation::CREDS_MANAGE_STORE_EVENTS,
+        "/api/store/events",
+        "GET",
+        "subscribe",
+    )
+    .with_outcome(AuthFlowDiagnosisOutcome::Succeeded);
+    log_route_diagnosis(
+        RouteDiagnosisContext {
+            route: "/api/store/events",
+            method: "GET",
+            status: Some(200),
+        },
+        &diagnosis,
+        "Store event stream opened",
+    );
+
+    let stream = BroadcastStream::new(receiver).map(|change| Ok(change_sse_event(change)));
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn change_sse_event(change: Result<StoreChangeEvent, BroadcastStreamRecvError>) -> Event {
+    match change {
+        Ok(change) => Event::default()
+            .event("change")
+            .json_data(&change)
+            .unwrap_or_else(|_| Event::default().event("change")),
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => Event::default()
+            .event("lagged")
+            .data(serde_json::json!({ "skipped": skipped }).to_string()),
+    }
+}
+
+/// The serialized name of a unit enum variant.
+fn json_name(value: &impl serde::Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{StatusCode, header},
+        response::IntoResponse,
+    };
+    use securitydept_core::creds_manage::{AuditActorSource, archive::ImportMode};
+
+    use super::*;
+    use crate::routes::test_support::{assert_server_error_envelope, test_server_state};
+
+    fn tester() -> Extension<AuditActor> {
+        Extension(AuditActor::new(AuditActorSource::Session, "tester"))
+    }
+
+    #[tokio::test]
+    async fn exported_archive_imports_as_dry_run() {
+        let state = test_server_state("store-export").await;
+        state
+            .creds_manage_store
+            .create_token_entry(
+                &AuditActor::new(AuditActorSource::Session, "tester"),
+                "deploy".to_string(),
+                Vec::new(),
+            )
+            .await
+            .expect("token entry should be created");
+
+        let Json(archive) = export(Extension(state.clone()))
+            .await
+            .expect("export should succeed");
+        assert_eq!(archive.token_creds.len(), 1);
+
+        let Json(report) = import(
+            Extension(state),
+            tester(),
+            Query(ImportOptions {
+                mode: ImportMode::Replace,
+                dry_run: true,
+                ..ImportOptions::default()
+            }),
+            Json(archive),
+        )
+        .await
+        .expect("dry-run import should succeed");
+        assert!(report.dry_run);
+        assert_eq!(report.entries.updated, 1);
+    }
+
+    #[tokio::test]
+    async fn event_stream_sends_store_changes() {
+        let state = test_server_state("store-events").await;
+        let response = events(Extension(state.clone())).await.into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        let (created, _) = state
+            .creds_manage_store
+            .create_token_entry(
+                &AuditActor::new(AuditActorSource::Session, "tester"),
+                "deploy".to_string(),
+                Vec::new(),
+            )
+            .await
+            .expect("token entry should be created");
+
+        let mut body = response.into_body().into_data_stream();
+        let frame = body
+            .next()
+            .await
+            .expect("stream should yield an event")
+            .expect("event frame should be readable");
+        let frame = String::from_utf8(frame.to_vec()).expect("event should be utf-8");
+        assert!(frame.starts_with("event: change\n"), "{frame}");
+        assert!(frame.contains("\"type\":\"entry_created\""), "{frame}");
+        assert!(frame.contains(&created.meta.id), "{frame}");
+    }
+
+    #[tokio::test]
+    async fn import_of_unsupported_archive_returns_shared_error_envelope() {
+        let state = test_server_state("store-import-invalid").await;
+        let Json(mut archive) = export(Extension(state.clone()))
+            .await
+            .expect("export should succeed");
+        archive.format = "something-else".to_string();
+
+        let response = import(
+            Extension(state),
+            tester(),
+            Query(ImportOptions::default()),
+            Json(archive),
+        )
+        .await
+        .expect_err("unknown archive format should be rejected")
+        .into_response();
+
+        assert_server_error_envelope(
+            response,
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "invalid_archive",
+            "none",
+        )
+        .await;
+    }
+}
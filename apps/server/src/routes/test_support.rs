@@ -76,6 +76,7 @@ pub async fn test_server_state_with_data(
     ServerState {
         config,
         session_context_config,
+        session_store: Default::default(),
         creds_manage_store,
         backend_oidc_runtime,
         frontend_oidc_runtime: None,
@@ -85,6 +86,7 @@ pub async fn test_server_state_with_data(
         oidc_client: None,
         oauth_resource_server_verifier: None,
         propagation_forwarder: None,
+        signing_keyring: None,
     }
 }
 
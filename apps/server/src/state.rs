@@ -1,15 +1,16 @@
 use std::{net::SocketAddr, sync::Arc};
 
-use axum::http::HeaderMap;
+use axum::http::{HeaderMap, header};
 pub use securitydept_core::oidc::MokaPendingOauthStore;
 use securitydept_core::{
     basic_auth_context::{BasicAuthContext, BasicAuthContextService},
-    creds::Argon2BasicAuthCred,
+    creds::{Argon2BasicAuthCred, LocalJwtSigningKeyring},
     creds_manage::store::CredsManageStore,
     oidc::{OidcClient, OidcError},
     realip::{RealIpResolver, ResolvedClientIp, TransportContext},
     session_context::{
         OidcSessionAuthService, OidcSessionAuthServiceConfig, ResolvedSessionContextConfig,
+        SessionContext, SessionContextResult, SessionContextSession,
     },
     token_set_context::{
         access_token_substrate::{
@@ -23,6 +24,9 @@ use securitydept_core::{
         frontend_oidc_mode::{FrontendOidcModeRuntime, FrontendOidcModeService},
     },
 };
+use tokio::sync::RwLock;
+use tower_sessions::{Session, cookie::Cookie, session::Id};
+use tower_sessions_memory_store::MemoryStore;
 use url::Url;
 
 use crate::{
@@ -35,6 +39,9 @@ use crate::{
 pub struct ServerState {
     pub config: Arc<ServerConfig>,
     pub session_context_config: Arc<ResolvedSessionContextConfig>,
+    /// The store behind the session layer, to read sessions from requests
+    /// that do not pass through it, such as forward-auth checks.
+    pub session_store: MemoryStore,
     pub creds_manage_store: Arc<CredsManageStore>,
     pub backend_oidc_runtime:
         Arc<BackendOidcModeRuntime<MokaPendingAuthStateMetadataRedemptionStore>>,
@@ -49,6 +56,8 @@ pub struct ServerState {
     pub oauth_resource_server_verifier: Option<Arc<OAuthResourceServerVerifier>>,
     /// None when [oauth_resource_server.token_propagation] is not enabled.
     pub propagation_forwarder: Option<Arc<AxumReverseProxyPropagationForwarder>>,
+    /// None when [signing_keyring] is not configured.
+    pub signing_keyring: Option<Arc<RwLock<LocalJwtSigningKeyring>>>,
 }
 
 impl ServerState {
@@ -114,6 +123,39 @@ impl ServerState {
             .map_err(|e| OidcError::RedirectUrl { source: e }.into())
     }
 
+    /// The session context of the session cookie in `headers`, if any.
+    pub async fn session_context_from_headers(
+        &self,
+        headers: &HeaderMap,
+    ) -> SessionContextResult<Option<SessionContext>> {
+        let cookie_name = &self.session_context_config.cookie_name;
+        let session_id = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse)
+            .filter_map(Result::ok)
+            .find(|cookie| cookie.name() == cookie_name)
+            .and_then(|cookie| cookie.value().parse::<Id>().ok());
+        let Some(session_id) = session_id else {
+            return Ok(None);
+        };
+
+        let session = Session::new(Some(session_id), Arc::new(self.session_store.clone()), None);
+        SessionContextSession::from_resolved_config(session, &self.session_context_config)
+            .get()
+            .await
+    }
+
+    /// Whether `peer_addr` is a trusted proxy of the real-ip resolver. Always
+    /// false when no resolver is configured.
+    pub async fn is_trusted_proxy(&self, peer_addr: SocketAddr) -> bool {
+        match self.real_ip_resolver.as_deref() {
+            Some(resolver) => resolver.is_trusted_peer(peer_addr.ip()).await,
+            None => false,
+        }
+    }
+
     pub async fn resolve_client_ip(
         &self,
         headers: &HeaderMap,
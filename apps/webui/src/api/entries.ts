@@ -1,5 +1,6 @@
 import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
 import { api } from "./client";
+import type { Availability } from "./groups";
 
 export const AuthEntryKind = {
 	Basic: "basic",
@@ -8,7 +9,46 @@ export const AuthEntryKind = {
 
 export type AuthEntryKind = (typeof AuthEntryKind)[keyof typeof AuthEntryKind];
 
-export interface AuthEntry {
+export interface DailyUsage {
+	/** UTC day, `YYYY-MM-DD`. */
+	date: string;
+	success_count: number;
+	failure_count: number;
+}
+
+export interface AuthEntryUsage {
+	last_success_at?: string;
+	last_failure_at?: string;
+	last_client_ip?: string;
+	/** Lifetime totals. */
+	success_count: number;
+	failure_count: number;
+	/** Per-day counts for the last 30 days, oldest first. */
+	daily?: DailyUsage[];
+}
+
+/** Successful and failed uses over the last `days` UTC days. */
+export function recentUsageCounts(
+	usage: AuthEntryUsage,
+	days: number,
+	now: Date = new Date(),
+): { success: number; failure: number } {
+	const first = new Date(now);
+	first.setUTCDate(first.getUTCDate() - (days - 1));
+	const firstDay = first.toISOString().slice(0, 10);
+	const today = now.toISOString().slice(0, 10);
+	return (usage.daily ?? [])
+		.filter((day) => day.date >= firstDay && day.date <= today)
+		.reduce(
+			(counts, day) => ({
+				success: counts.success + day.success_count,
+				failure: counts.failure + day.failure_count,
+			}),
+			{ success: 0, failure: 0 },
+		);
+}
+
+export interface AuthEntry extends Availability {
 	id: string;
 	name: string;
 	kind: AuthEntryKind;
@@ -16,6 +56,8 @@ export interface AuthEntry {
 	group_ids: string[];
 	created_at: string;
 	updated_at: string;
+	/** Absent until the entry was first used through forward auth. */
+	usage?: AuthEntryUsage;
 }
 
 export type CreateBasicEntryResponse = {
@@ -1,9 +1,42 @@
 import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
 import { api } from "./client";
 
-export interface Group {
+export interface Availability {
+	disabled: boolean;
+	disabled_reason?: string;
+	expires_at?: string;
+}
+
+export interface ClaimRequirements {
+	issuer?: string;
+	groups_claim?: string;
+	any_of?: string[];
+}
+
+export interface IdentityAccess {
+	session: boolean;
+	bearer: boolean;
+	claims: ClaimRequirements;
+}
+
+export type PathPattern =
+	| { type: "glob"; value: string }
+	| { type: "regex"; value: string };
+
+export interface AccessRule {
+	id?: string;
+	effect?: "allow" | "deny";
+	hosts?: string[];
+	methods?: string[];
+	path?: PathPattern;
+}
+
+export interface Group extends Availability {
 	id: string;
 	name: string;
+	parent_ids: string[];
+	identity_access: IdentityAccess;
+	access_rules: AccessRule[];
 }
 
 interface GroupQueryOptions {
@@ -29,7 +62,11 @@ export function useGroup(id: string, options: GroupQueryOptions = {}) {
 export function useCreateGroup() {
 	const qc = useQueryClient();
 	return useMutation({
-		mutationFn: (data: { name: string; entry_ids?: string[] }) =>
+		mutationFn: (data: {
+			name: string;
+			entry_ids?: string[];
+			parent_ids?: string[];
+		}) =>
 			api.post<Group>("/api/groups", data),
 		onSuccess: () => {
 			qc.invalidateQueries({ queryKey: ["groups"] });
@@ -45,11 +82,14 @@ export function useUpdateGroup() {
 			id,
 			name,
 			entry_ids,
+			parent_ids,
 		}: {
 			id: string;
 			name: string;
 			entry_ids?: string[];
-		}) => api.put<Group>(`/api/groups/${id}`, { name, entry_ids }),
+			parent_ids?: string[];
+		}) =>
+			api.put<Group>(`/api/groups/${id}`, { name, entry_ids, parent_ids }),
 		onSuccess: () => {
 			qc.invalidateQueries({ queryKey: ["groups"] });
 			qc.invalidateQueries({ queryKey: ["entries"] });
@@ -58,6 +98,40 @@ export function useUpdateGroup() {
 	});
 }
 
+export function useSetGroupIdentityAccess() {
+	const qc = useQueryClient();
+	return useMutation({
+		mutationFn: ({
+			id,
+			identity_access,
+		}: {
+			id: string;
+			identity_access: IdentityAccess;
+		}) => api.put<Group>(`/api/groups/${id}/identity-access`, identity_access),
+		onSuccess: () => {
+			qc.invalidateQueries({ queryKey: ["groups"] });
+			qc.invalidateQueries({ queryKey: ["group"] });
+		},
+	});
+}
+
+export function useSetGroupAccessRules() {
+	const qc = useQueryClient();
+	return useMutation({
+		mutationFn: ({
+			id,
+			access_rules,
+		}: {
+			id: string;
+			access_rules: AccessRule[];
+		}) => api.put<Group>(`/api/groups/${id}/access-rules`, access_rules),
+		onSuccess: () => {
+			qc.invalidateQueries({ queryKey: ["groups"] });
+			qc.invalidateQueries({ queryKey: ["group"] });
+		},
+	});
+}
+
 export function useDeleteGroup() {
 	const qc = useQueryClient();
 	return useMutation({
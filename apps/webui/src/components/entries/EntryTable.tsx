@@ -7,7 +7,11 @@ import {
 } from "@tanstack/react-table";
 import { Pencil, Trash2 } from "lucide-react";
 import { useMemo } from "react";
-import { type AuthEntry, AuthEntryKind } from "@/api/entries";
+import {
+	type AuthEntry,
+	AuthEntryKind,
+	recentUsageCounts,
+} from "@/api/entries";
 import type { Group } from "@/api/groups";
 import { useDashboardDeleteEntryMutation } from "@/hooks/useDashboardApi";
 
@@ -82,6 +86,59 @@ export function EntryTable({
 					</span>
 				),
 			},
+			{
+				header: "Status",
+				id: "status",
+				cell: ({ row }) => {
+					const { disabled, disabled_reason, expires_at } = row.original;
+					const expired =
+						expires_at !== undefined && new Date(expires_at) <= new Date();
+					if (!disabled && !expired) {
+						return (
+							<span
+								className="text-zinc-500 dark:text-zinc-400"
+								title={
+									expires_at
+										? `Expires ${new Date(expires_at).toLocaleString()}`
+										: undefined
+								}
+							>
+								Active
+							</span>
+						);
+					}
+					return (
+						<span
+							className="inline-flex items-center rounded-full bg-red-50 px-2 py-0.5 text-xs font-medium text-red-700 dark:bg-red-900/30 dark:text-red-400"
+							title={disabled ? disabled_reason : undefined}
+						>
+							{disabled ? "Disabled" : "Expired"}
+						</span>
+					);
+				},
+			},
+			{
+				header: "Last Used",
+				id: "last_used",
+				cell: ({ row }) => {
+					const usage = row.original.usage;
+					const recent = usage ? recentUsageCounts(usage, 7) : undefined;
+					return (
+						<span
+							className="text-zinc-500 dark:text-zinc-400"
+							title={
+								usage
+									? `Last 7 days: ${recent?.success} succeeded, ${recent?.failure} failed. Total: ${usage.success_count} succeeded, ${usage.failure_count} failed${usage.last_client_ip ? `, last from ${usage.last_client_ip}` : ""}`
+									: undefined
+							}
+						>
+							{usage?.last_success_at
+								? new Date(usage.last_success_at).toLocaleString()
+								: "Never"}
+						</span>
+					);
+				},
+			},
 			{
 				header: "",
 				id: "actions",
@@ -397,6 +397,7 @@ mod tests {
                     username: "admin".to_string(),
                     password: "secret".to_string(),
                 }],
+                ..Default::default()
             })
             .zones(vec![BasicAuthZoneConfig::default()])
             .build();
@@ -415,6 +416,7 @@ mod tests {
                     username: "admin".to_string(),
                     password: "secret".to_string(),
                 }],
+                ..Default::default()
             })
             .zones(vec![BasicAuthZoneConfig::builder()
                 .post_auth_redirect(
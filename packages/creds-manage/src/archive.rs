@@ -0,0 +1,323 @@
+//! Versioned export and import of everything a
+//! [`CredsManageStore`](crate::store::CredsManageStore) holds, for backups
+//! and for promoting credentials between environments.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use securitydept_creds::StaticTokenAuthCred;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{CredsManageError, CredsManageResult},
+    models::{BasicAuthEntry, DataFile, Group, TokenAuthEntry, validate_access_rules},
+    store::backend::{carry_revisions, ensure_group_parents_valid},
+};
+
+/// `format` marker of a store archive.
+pub const STORE_ARCHIVE_FORMAT: &str = "securitydept-creds-archive";
+/// Current store archive version.
+pub const STORE_ARCHIVE_VERSION: u32 = 1;
+
+/// Groups and entries of a store, password and token hashes included.
+///
+/// Archives contain credential hashes and must be handled like the data file
+/// itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreArchive {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub groups: Vec<Group>,
+    pub basic_creds: Vec<BasicAuthEntry>,
+    pub token_creds: Vec<TokenAuthEntry>,
+}
+
+impl StoreArchive {
+    pub fn from_data(data: DataFile) -> Self {
+        Self {
+            format: STORE_ARCHIVE_FORMAT.to_string(),
+            version: STORE_ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            groups: data.groups,
+            basic_creds: data.basic_creds,
+            token_creds: data.token_creds,
+        }
+    }
+
+    /// Check the archive header and that ids are unique within the archive.
+    pub fn validate(&self) -> CredsManageResult<()> {
+        if self.format != STORE_ARCHIVE_FORMAT {
+            return Err(invalid_archive(format!(
+                "unknown archive format `{}`",
+                self.format
+            )));
+        }
+        if self.version != STORE_ARCHIVE_VERSION {
+            return Err(invalid_archive(format!(
+                "unsupported archive version {} (expected {STORE_ARCHIVE_VERSION})",
+                self.version
+            )));
+        }
+
+        let mut group_ids = HashSet::new();
+        for group in &self.groups {
+            if !group_ids.insert(group.id.as_str()) {
+                return Err(invalid_archive(format!("group {} appears twice", group.id)));
+            }
+        }
+        let mut entry_ids = HashSet::new();
+        for id in self.entry_ids() {
+            if !entry_ids.insert(id) {
+                return Err(invalid_archive(format!("entry {id} appears twice")));
+            }
+        }
+        Ok(())
+    }
+
+    fn entry_ids(&self) -> impl Iterator<Item = &str> {
+        self.basic_creds
+            .iter()
+            .map(|e| e.meta.id.as_str())
+            .chain(self.token_creds.iter().map(|e| e.meta.id.as_str()))
+    }
+}
+
+/// How an archive is combined with the current store content.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Add the archive to the current content.
+    #[default]
+    Merge,
+    /// Make the store hold exactly the archive.
+    Replace,
+}
+
+/// What a merge does with a group or entry whose id already exists.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflictPolicy {
+    /// Abort the whole import.
+    #[default]
+    Fail,
+    /// Keep the current item.
+    Skip,
+    /// Replace the current item with the archived one.
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub mode: ImportMode,
+    #[serde(default)]
+    pub on_conflict: ImportConflictPolicy,
+    /// Validate and report without changing the store.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportCounts {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub deleted: usize,
+}
+
+/// Outcome of an import, or of what it would do for a dry run.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub groups: ImportCounts,
+    pub entries: ImportCounts,
+}
+
+/// Apply `archive` to `data` and check that the result is consistent:
+/// unique names and tokens, existing groups for every membership and an
+/// acyclic group hierarchy. Items the import changes get a new revision. `data`
+/// is only meaningful when this succeeds.
+pub(crate) fn apply_archive(
+    data: &mut DataFile,
+    archive: &StoreArchive,
+    options: ImportOptions,
+) -> CredsManageResult<ImportReport> {
+    archive.validate()?;
+    let previous = data.clone();
+    let mut report = ImportReport {
+        mode: options.mode,
+        dry_run: options.dry_run,
+        groups: ImportCounts::default(),
+        entries: ImportCounts::default(),
+    };
+
+    match options.mode {
+        ImportMode::Replace => {
+            let archived_groups: HashSet<&str> =
+                archive.groups.iter().map(|g| g.id.as_str()).collect();
+            let archived_entries: HashSet<&str> = archive.entry_ids().collect();
+
+            for group in &data.groups {
+                if archived_groups.contains(group.id.as_str()) {
+                    report.groups.updated += 1;
+                } else {
+                    report.groups.deleted += 1;
+                }
+            }
+            report.groups.created = archive.groups.len() - report.groups.updated;
+            for id in data_entry_ids(data) {
+                if archived_entries.contains(id) {
+                    report.entries.updated += 1;
+                } else {
+                    report.entries.deleted += 1;
+                }
+            }
+            report.entries.created = archived_entries.len() - report.entries.updated;
+
+            data.groups = archive.groups.clone();
+            data.basic_creds = archive.basic_creds.clone();
+            data.token_creds = archive.token_creds.clone();
+        }
+        ImportMode::Merge => {
+            for group in &archive.groups {
+                let existing = data.groups.iter().position(|g| g.id == group.id);
+                match resolve_conflict(existing.is_some(), &group.id, options.on_conflict)? {
+                    Resolution::Create => {
+                        data.groups.push(group.clone());
+                        report.groups.created += 1;
+                    }
+                    Resolution::Skip => report.groups.skipped += 1,
+                    Resolution::Overwrite => {
+                        if let Some(index) = existing {
+                            data.groups[index] = group.clone();
+                        }
+                        report.groups.updated += 1;
+                    }
+                }
+            }
+
+            let archived_entries = archive
+                .basic_creds
+                .iter()
+                .map(|e| (&e.meta.id, ArchivedEntry::Basic(e)))
+                .chain(
+                    archive
+                        .token_creds
+                        .iter()
+                        .map(|e| (&e.meta.id, ArchivedEntry::Token(e))),
+                );
+            for (id, entry) in archived_entries {
+                let exists = data_entry_ids(data).any(|existing| existing == id);
+                match resolve_conflict(exists, id, options.on_conflict)? {
+                    Resolution::Skip => {
+                        report.entries.skipped += 1;
+                        continue;
+                    }
+                    Resolution::Create => report.entries.created += 1,
+                    Resolution::Overwrite => {
+                        // The archived entry may be of the other kind.
+                        data.basic_creds.retain(|e| &e.meta.id != id);
+                        data.token_creds.retain(|e| &e.meta.id != id);
+                        report.entries.updated += 1;
+                    }
+                }
+                match entry {
+                    ArchivedEntry::Basic(entry) => data.basic_creds.push(entry.clone()),
+                    ArchivedEntry::Token(entry) => data.token_creds.push(entry.clone()),
+                }
+            }
+        }
+    }
+
+    carry_revisions(&previous, data);
+    validate_references(data)?;
+    Ok(report)
+}
+
+enum ArchivedEntry<'a> {
+    Basic(&'a BasicAuthEntry),
+    Token(&'a TokenAuthEntry),
+}
+
+enum Resolution {
+    Create,
+    Skip,
+    Overwrite,
+}
+
+fn resolve_conflict(
+    exists: bool,
+    id: &str,
+    policy: ImportConflictPolicy,
+) -> CredsManageResult<Resolution> {
+    match (exists, policy) {
+        (false, _) => Ok(Resolution::Create),
+        (true, ImportConflictPolicy::Fail) => {
+            Err(CredsManageError::ImportConflict { id: id.to_string() })
+        }
+        (true, ImportConflictPolicy::Skip) => Ok(Resolution::Skip),
+        (true, ImportConflictPolicy::Overwrite) => Ok(Resolution::Overwrite),
+    }
+}
+
+fn data_entry_ids(data: &DataFile) -> impl Iterator<Item = &str> {
+    data.basic_creds
+        .iter()
+        .map(|e| e.meta.id.as_str())
+        .chain(data.token_creds.iter().map(|e| e.meta.id.as_str()))
+}
+
+fn validate_references(data: &DataFile) -> CredsManageResult<()> {
+    let mut group_names = HashSet::new();
+    for group in &data.groups {
+        if !group_names.insert(group.name.as_str()) {
+            return Err(CredsManageError::DuplicateGroupName {
+                name: group.name.clone(),
+            });
+        }
+        ensure_group_parents_valid(&data.groups, &group.id, &group.parent_ids)?;
+        group.identity_access.validate()?;
+        validate_access_rules(&group.access_rules)?;
+    }
+
+    let mut entry_names = HashSet::new();
+    let metas = data
+        .basic_creds
+        .iter()
+        .map(|e| &e.meta)
+        .chain(data.token_creds.iter().map(|e| &e.meta));
+    for meta in metas {
+        if !entry_names.insert(meta.name.as_str()) {
+            return Err(CredsManageError::DuplicateEntryName {
+                name: meta.name.clone(),
+            });
+        }
+        if let Some(group_id) = meta
+            .group_ids
+            .iter()
+            .find(|group_id| !data.groups.iter().any(|g| &g.id == *group_id))
+        {
+            return Err(CredsManageError::GroupNotFound {
+                id: group_id.clone(),
+            });
+        }
+    }
+
+    // Token lookup finds the first entry with a matching hash, so a second
+    // entry with the same token could never be told apart.
+    let mut token_hashes = HashSet::new();
+    for entry in &data.token_creds {
+        if !token_hashes.insert(entry.token_hash()) {
+            return Err(CredsManageError::DuplicateToken {
+                id: entry.meta.id.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn invalid_archive(message: String) -> CredsManageError {
+    CredsManageError::InvalidArchive { message }
+}
@@ -0,0 +1,657 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+
+use crate::{
+    error::{self, CredsManageResult},
+    models::{AuthEntry, Group},
+};
+
+/// `prev_hash` of the first record in a log.
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const TAIL_CHUNK_SIZE: u64 = 4096;
+
+/// How the actor of an audited mutation authenticated.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditActorSource {
+    Session,
+    Bearer,
+    Basic,
+    Cli,
+    /// Mutations the store performs on its own, e.g. password re-hashing.
+    System,
+}
+
+/// The principal that performed an audited mutation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditActor {
+    pub source: AuditActorSource,
+    pub subject: String,
+}
+
+impl AuditActor {
+    pub fn new(source: AuditActorSource, subject: impl Into<String>) -> Self {
+        Self {
+            source,
+            subject: subject.into(),
+        }
+    }
+
+    pub fn system() -> Self {
+        Self::new(AuditActorSource::System, "securitydept")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntityKind {
+    Entry,
+    Group,
+    /// The store as a whole.
+    Store,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOperation {
+    EntryCreate,
+    EntryUpdate,
+    EntryDelete,
+    EntryPasswordRehash,
+    GroupCreate,
+    GroupUpdate,
+    GroupDelete,
+    StoreImport,
+}
+
+impl AuditOperation {
+    pub fn entity_kind(self) -> AuditEntityKind {
+        match self {
+            Self::EntryCreate
+            | Self::EntryUpdate
+            | Self::EntryDelete
+            | Self::EntryPasswordRehash => AuditEntityKind::Entry,
+            Self::GroupCreate | Self::GroupUpdate | Self::GroupDelete => AuditEntityKind::Group,
+            Self::StoreImport => AuditEntityKind::Store,
+        }
+    }
+}
+
+/// One line of the audit log.
+///
+/// `hash` is the SHA-256 of the record serialized with an empty `hash`, and
+/// `prev_hash` links it to the previous record, so editing, removing or
+/// reordering lines breaks the chain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub actor: AuditActor,
+    pub operation: AuditOperation,
+    pub entity_kind: AuditEntityKind,
+    pub entity_id: String,
+    /// Entity state before the mutation. Never contains secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    /// Entity state after the mutation. Never contains secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    pub fn compute_hash(&self) -> CredsManageResult<String> {
+        let unsealed = Self {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let bytes = serde_json::to_vec(&unsealed).context(error::DataSerializeSnafu)?;
+        Ok(hex::encode(Sha256::digest(bytes)))
+    }
+}
+
+/// Filters for [`AuditLog::query`]. Results are returned newest first.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default)]
+    pub entity_id: Option<String>,
+    #[serde(default)]
+    pub operation: Option<AuditOperation>,
+    /// Matches the actor subject.
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    /// Defaults to 100.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.entity_id
+            .as_ref()
+            .is_none_or(|id| &record.entity_id == id)
+            && self.operation.is_none_or(|op| record.operation == op)
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|subject| &record.actor.subject == subject)
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+    }
+}
+
+/// Result of walking the hash chain of an audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditVerification {
+    /// Number of records checked before the walk stopped.
+    pub records: u64,
+    pub valid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broken_at_seq: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Hash of the last valid record, to be compared with an externally kept
+    /// copy when truncation of the log tail must be detected.
+    pub last_hash: String,
+}
+
+/// Append-only, hash-chained JSON Lines log of credential and group
+/// mutations.
+///
+/// Appends take an exclusive lock on the log file so the server and the CLI
+/// can write to the same log without forking the chain.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record chained to the current last record.
+    pub async fn append(
+        &self,
+        actor: AuditActor,
+        operation: AuditOperation,
+        entity_id: String,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> CredsManageResult<AuditRecord> {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || -> CredsManageResult<AuditRecord> {
+            if let Some(parent) = path.parent()
+                && !parent.as_os_str().is_empty()
+            {
+                std::fs::create_dir_all(parent).context(error::AuditWriteSnafu)?;
+            }
+
+            let mut file = std::fs::OpenOptions::new()
+                .read(true)
+                .append(true)
+                .create(true)
+                .open(&path)
+                .context(error::AuditWriteSnafu)?;
+            file.lock_exclusive().context(error::AuditWriteSnafu)?;
+
+            let result = (|| -> CredsManageResult<AuditRecord> {
+                let last = read_last_record(&mut file)?;
+                let mut record = AuditRecord {
+                    seq: last.as_ref().map_or(1, |last| last.seq + 1),
+                    timestamp: Utc::now(),
+                    entity_kind: operation.entity_kind(),
+                    actor,
+                    operation,
+                    entity_id,
+                    before,
+                    after,
+                    prev_hash: last
+                        .map_or_else(|| AUDIT_GENESIS_HASH.to_string(), |last| last.hash),
+                    hash: String::new(),
+                };
+                record.hash = record.compute_hash()?;
+
+                let mut line = serde_json::to_vec(&record).context(error::DataSerializeSnafu)?;
+                line.push(b'\n');
+                file.write_all(&line).context(error::AuditWriteSnafu)?;
+                file.sync_data().context(error::AuditWriteSnafu)?;
+                Ok(record)
+            })();
+
+            let _ = file.unlock();
+            result
+        })
+        .await
+        .expect("audit append task panicked")
+    }
+
+    /// Return records matching `query`, newest first.
+    pub async fn query(&self, query: AuditQuery) -> CredsManageResult<Vec<AuditRecord>> {
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        let mut records = self.read_records().await?;
+        records.retain(|record| query.matches(record));
+        records.reverse();
+        records.truncate(limit);
+        Ok(records)
+    }
+
+    /// Walk the whole log and check every record's hash and link.
+    pub async fn verify(&self) -> CredsManageResult<AuditVerification> {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || -> CredsManageResult<AuditVerification> {
+            let mut verification = AuditVerification {
+                records: 0,
+                valid: true,
+                broken_at_seq: None,
+                message: None,
+                last_hash: AUDIT_GENESIS_HASH.to_string(),
+            };
+            let Some(file) = open_for_read(&path)? else {
+                return Ok(verification);
+            };
+
+            let mut expected_seq = 1;
+            let result = (|| -> CredsManageResult<()> {
+                for (index, line) in BufReader::new(&file).lines().enumerate() {
+                    let line = line.context(error::AuditReadSnafu)?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let failure = match serde_json::from_str::<AuditRecord>(&line) {
+                        Err(_) => Some(format!("line {} is not a valid audit record", index + 1)),
+                        Ok(record) if record.seq != expected_seq => {
+                            Some(format!("expected seq {expected_seq}, found {}", record.seq))
+                        }
+                        Ok(record) if record.prev_hash != verification.last_hash => {
+                            Some("prev_hash does not match the previous record".to_string())
+                        }
+                        Ok(record) if record.compute_hash()? != record.hash => {
+                            Some("record content does not match its hash".to_string())
+                        }
+                        Ok(record) => {
+                            verification.records += 1;
+                            verification.last_hash = record.hash;
+                            expected_seq += 1;
+                            None
+                        }
+                    };
+                    if let Some(message) = failure {
+                        verification.valid = false;
+                        verification.broken_at_seq = Some(expected_seq);
+                        verification.message = Some(message);
+                        break;
+                    }
+                }
+                Ok(())
+            })();
+
+            let _ = file.unlock();
+            result.map(|()| verification)
+        })
+        .await
+        .expect("audit verify task panicked")
+    }
+
+    async fn read_records(&self) -> CredsManageResult<Vec<AuditRecord>> {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || -> CredsManageResult<Vec<AuditRecord>> {
+            let Some(file) = open_for_read(&path)? else {
+                return Ok(Vec::new());
+            };
+
+            let result = BufReader::new(&file)
+                .lines()
+                .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+                .map(|line| {
+                    let line = line.context(error::AuditReadSnafu)?;
+                    serde_json::from_str(&line).context(error::AuditParseSnafu)
+                })
+                .collect();
+
+            let _ = file.unlock();
+            result
+        })
+        .await
+        .expect("audit read task panicked")
+    }
+}
+
+/// Audit snapshot of an entry. [`AuthEntry`] carries no password or token
+/// hashes.
+pub(crate) fn entry_snapshot(entry: &AuthEntry) -> Option<Value> {
+    serde_json::to_value(entry).ok()
+}
+
+/// Audit snapshot of a group together with its members.
+pub(crate) fn group_snapshot(group: &Group, entry_ids: &[String]) -> Option<Value> {
+    let mut value = serde_json::to_value(group).ok()?;
+    value
+        .as_object_mut()?
+        .insert("entry_ids".to_string(), Value::from(entry_ids.to_vec()));
+    Some(value)
+}
+
+/// Open the log under a shared lock, or `None` when nothing was logged yet.
+fn open_for_read(path: &Path) -> CredsManageResult<Option<File>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(source) if source.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => return Err(error::CredsManageError::AuditRead { source }),
+    };
+    file.lock_shared().context(error::AuditReadSnafu)?;
+    Ok(Some(file))
+}
+
+/// Read the last non-empty line by scanning backwards from the end of the
+/// file, so appends stay cheap as the log grows.
+fn read_last_record(file: &mut File) -> CredsManageResult<Option<AuditRecord>> {
+    let len = file.metadata().context(error::AuditReadSnafu)?.len();
+    let mut position = len;
+    let mut tail = Vec::new();
+
+    while position > 0 {
+        let chunk = TAIL_CHUNK_SIZE.min(position);
+        position -= chunk;
+        let mut buf = vec![0; chunk as usize];
+        file.seek(SeekFrom::Start(position))
+            .context(error::AuditReadSnafu)?;
+        file.read_exact(&mut buf).context(error::AuditReadSnafu)?;
+        buf.extend_from_slice(&tail);
+        tail = buf;
+
+        let content = tail.trim_ascii_end();
+        if content.contains(&b'\n') {
+            break;
+        }
+    }
+
+    let content = tail.trim_ascii_end();
+    if content.is_empty() {
+        return Ok(None);
+    }
+    let line_start = content
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map_or(0, |index| index + 1);
+    serde_json::from_slice(&content[line_start..])
+        .map(Some)
+        .context(error::AuditParseSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::store::CredsManageStore;
+
+    fn unique_path(label: &str, extension: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "securitydept-creds-manage-audit-{label}-{nanos}.{extension}"
+        ))
+    }
+
+    fn operator() -> AuditActor {
+        AuditActor::new(AuditActorSource::Session, "alice")
+    }
+
+    #[tokio::test]
+    async fn store_mutations_are_chained_without_secrets() {
+        let audit_path = unique_path("store", "jsonl");
+        let store = CredsManageStore::load(unique_path("store", "json"))
+            .await
+            .expect("test store should load")
+            .with_audit_log(AuditLog::new(&audit_path));
+
+        let group = store
+            .create_group(&operator(), Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let entry = store
+            .create_basic_entry(
+                &operator(),
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                vec![group.id.clone()],
+            )
+            .await
+            .unwrap();
+        store
+            .update_entry(
+                &operator(),
+                &entry.meta.id,
+                Some("ops-admin".to_string()),
+                None,
+                Some("rotated123".to_string()),
+                None,
+            )
+            .await
+            .unwrap();
+        store.delete_group(&operator(), &group.id).await.unwrap();
+
+        let audit_log = store.audit_log().expect("audit log should be attached");
+        let records = audit_log.query(AuditQuery::default()).await.unwrap();
+        let operations: Vec<_> = records.iter().map(|record| record.operation).collect();
+        assert_eq!(
+            operations,
+            vec![
+                AuditOperation::GroupDelete,
+                AuditOperation::EntryUpdate,
+                AuditOperation::EntryCreate,
+                AuditOperation::GroupCreate,
+            ]
+        );
+        assert_eq!(records[3].prev_hash, AUDIT_GENESIS_HASH);
+        assert_eq!(records[0].prev_hash, records[1].hash);
+        assert_eq!(records[0].actor, operator());
+        assert_eq!(
+            records[0].before.as_ref().unwrap()["entry_ids"],
+            serde_json::json!([entry.meta.id])
+        );
+        assert_eq!(records[1].before.as_ref().unwrap()["name"], "ops-user");
+        assert_eq!(records[1].after.as_ref().unwrap()["name"], "ops-admin");
+
+        let raw = std::fs::read_to_string(&audit_path).unwrap();
+        assert!(!raw.contains("password"));
+        assert!(!raw.contains("secret123"));
+        assert!(!raw.contains("rotated123"));
+
+        let verification = audit_log.verify().await.unwrap();
+        assert!(verification.valid);
+        assert_eq!(verification.records, 4);
+        assert_eq!(verification.last_hash, records[0].hash);
+    }
+
+    #[tokio::test]
+    async fn failed_append_is_reported_as_an_unaudited_mutation() {
+        // A directory cannot be opened for appending.
+        let audit_path = unique_path("unwritable", "d");
+        std::fs::create_dir_all(&audit_path).unwrap();
+        let store = CredsManageStore::load(unique_path("unwritable", "json"))
+            .await
+            .expect("test store should load")
+            .with_audit_log(AuditLog::new(&audit_path));
+
+        let error = store
+            .create_group(&operator(), Group::new("ops".to_string()), None)
+            .await
+            .expect_err("a mutation without its audit record should be reported");
+        assert!(matches!(
+            error,
+            error::CredsManageError::UnauditedMutation {
+                operation: AuditOperation::GroupCreate,
+                ..
+            }
+        ));
+        // The change itself was applied.
+        assert!(store.find_group_by_name("ops").await.unwrap().is_some());
+
+        std::fs::remove_dir(&audit_path).ok();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_mutations_record_consecutive_snapshots() {
+        let store = std::sync::Arc::new(
+            CredsManageStore::load(unique_path("concurrent", "json"))
+                .await
+                .expect("test store should load")
+                .with_audit_log(AuditLog::new(unique_path("concurrent", "jsonl"))),
+        );
+        let group = store
+            .create_group(&operator(), Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|index| {
+                let (store, group_id) = (store.clone(), group.id.clone());
+                tokio::spawn(async move {
+                    store
+                        .update_group(&operator(), &group_id, format!("ops-{index}"), None, None)
+                        .await
+                        .unwrap();
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let mut records = store
+            .audit_log()
+            .unwrap()
+            .query(AuditQuery::default())
+            .await
+            .unwrap();
+        records.reverse();
+        assert_eq!(records.len(), 9);
+        for pair in records.windows(2) {
+            assert_eq!(pair[1].before, pair[0].after);
+        }
+    }
+
+    #[tokio::test]
+    async fn tampering_breaks_the_chain() {
+        let path = unique_path("tamper", "jsonl");
+        let audit_log = AuditLog::new(&path);
+        for id in ["a", "b", "c"] {
+            audit_log
+                .append(
+                    operator(),
+                    AuditOperation::EntryDelete,
+                    id.to_string(),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        let original = std::fs::read_to_string(&path).unwrap();
+
+        std::fs::write(
+            &path,
+            original.replace("\"entity_id\":\"b\"", "\"entity_id\":\"x\""),
+        )
+        .unwrap();
+        let verification = audit_log.verify().await.unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at_seq, Some(2));
+        assert_eq!(verification.records, 1);
+
+        let lines: Vec<&str> = original.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let verification = audit_log.verify().await.unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at_seq, Some(2));
+
+        std::fs::write(&path, &original).unwrap();
+        assert!(audit_log.verify().await.unwrap().valid);
+    }
+
+    #[tokio::test]
+    async fn query_filters_records_newest_first() {
+        let audit_log = AuditLog::new(unique_path("query", "jsonl"));
+        assert!(
+            audit_log
+                .query(AuditQuery::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        for (actor, operation, id) in [
+            (operator(), AuditOperation::EntryCreate, "a"),
+            (
+                AuditActor::system(),
+                AuditOperation::EntryPasswordRehash,
+                "a",
+            ),
+            (operator(), AuditOperation::GroupCreate, "g"),
+            (operator(), AuditOperation::EntryDelete, "a"),
+        ] {
+            audit_log
+                .append(actor, operation, id.to_string(), None, None)
+                .await
+                .unwrap();
+        }
+
+        let entry_a = audit_log
+            .query(AuditQuery {
+                entity_id: Some("a".to_string()),
+                actor: Some("alice".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let seqs: Vec<u64> = entry_a.iter().map(|record| record.seq).collect();
+        assert_eq!(seqs, vec![4, 1]);
+
+        let limited = audit_log
+            .query(AuditQuery {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].seq, 4);
+
+        let groups = audit_log
+            .query(AuditQuery {
+                operation: Some(AuditOperation::GroupCreate),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].entity_kind, AuditEntityKind::Group);
+    }
+}
@@ -1,51 +1,183 @@
+use chrono::{DateTime, Utc};
 use securitydept_creds::{
     BasicAuthCred, BasicAuthCredsConfig, BasicAuthCredsValidator, MapBasicAuthCredsValidator,
-    MapStaticTokenAuthCredsValidator, StaticTokenAuthCredsConfig, token::TokenAuthCred,
+    StaticTokenAuthCred, StructuredStaticToken, token::TokenAuthCred,
 };
 pub use securitydept_creds::{
-    hash_password_argon2, hash_token_sha256, parse_basic_auth_header_opt,
-    parse_bearer_auth_header_opt, verify_password_argon2, verify_token_sha256,
+    generate_structured_static_token, hash_password_argon2, hash_token_sha256,
+    is_structured_static_token, parse_basic_auth_header_opt, parse_bearer_auth_header_opt,
+    verify_password_argon2, verify_password_hash, verify_token_sha256,
 };
 
 use crate::{
     error::CredsManageResult,
-    models::{BasicAuthEntry, TokenAuthEntry},
+    models::{
+        AccessRequest, AccessRule, AccessRuleEffect, BasicAuthEntry, TokenAuthEntry,
+        TokenConstraintViolation, normalize_path,
+    },
 };
 
+/// Outcome of checking a request against the access rules of a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDecision<'a> {
+    /// The group has no rules, so it grants every request.
+    Unrestricted,
+    Allowed(&'a AccessRule),
+    Denied(&'a AccessRule),
+    /// The group has rules but none matched, which refuses the request.
+    NoMatch,
+}
+
+/// Decide `request` under `rules`, where the first matching rule applies.
+/// The request path is normalized first, so `/api/../admin` or `//admin`
+/// are matched as the `/admin` the upstream serves.
+pub fn decide_access<'a>(
+    rules: &'a [AccessRule],
+    request: &AccessRequest<'_>,
+) -> AccessDecision<'a> {
+    if rules.is_empty() {
+        return AccessDecision::Unrestricted;
+    }
+    let path = request.path.map(normalize_path);
+    let request = AccessRequest {
+        path: path.as_deref(),
+        ..*request
+    };
+    match rules.iter().find(|rule| rule.matches(&request)) {
+        Some(rule) if rule.effect == AccessRuleEffect::Allow => AccessDecision::Allowed(rule),
+        Some(rule) => AccessDecision::Denied(rule),
+        None => AccessDecision::NoMatch,
+    }
+}
+
+/// Outcome of checking a bearer token against token entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenAuthDecision {
+    /// The token matched an entry whose constraints hold; carries its name.
+    Accepted(String),
+    /// The token matched an entry that is unavailable or whose constraints
+    /// rejected the request.
+    Rejected(TokenConstraintViolation),
+    /// No entry matched the token.
+    NoMatch,
+}
+
 /// Check basic auth credentials against a list of entries in a group.
+///
+/// Disabled and expired entries never match.
 pub fn check_basic_auth(
     entries: &[BasicAuthEntry],
     username: &str,
     password: &str,
 ) -> CredsManageResult<Option<String>> {
+    Ok(find_basic_auth_entry(entries, username, password)?
+        .filter(|entry| entry.meta.availability.check(Utc::now()).is_ok())
+        .map(|entry| entry.meta.name))
+}
+
+/// Find the entry matching basic auth credentials.
+///
+/// Unlike [`check_basic_auth`], this returns the whole entry so callers can
+/// inspect [`BasicAuthCred::needs_rehash`] and upgrade legacy hashes via
+/// [`CredsManageStore::upgrade_basic_entry_password_hash`](crate::store::CredsManageStore::upgrade_basic_entry_password_hash).
+/// The entry's [`Availability`](crate::models::Availability) is left for
+/// the caller to check.
+pub fn find_basic_auth_entry(
+    entries: &[BasicAuthEntry],
+    username: &str,
+    password: &str,
+) -> CredsManageResult<Option<BasicAuthEntry>> {
     let validator = MapBasicAuthCredsValidator::from_config(&BasicAuthCredsConfig {
         users: entries.to_vec(),
+        ..Default::default()
     })?;
 
     if let Some(entry) = validator.get_cred(username)?
         && entry.verify_password(password)?
     {
-        return Ok(Some(entry.meta.name.clone()));
+        return Ok(Some(entry.clone()));
     }
 
     Ok(None)
 }
 
 /// Check bearer token against a list of entries in a group.
+///
+/// Availability and not-before are enforced against the current time;
+/// scopes are not required. See [`decide_token_auth`] for the full decision.
 pub fn check_token_auth(
     entries: &[TokenAuthEntry],
     token: &str,
 ) -> CredsManageResult<Option<String>> {
-    let validator = MapStaticTokenAuthCredsValidator::from_config(&StaticTokenAuthCredsConfig {
-        tokens: entries.to_vec(),
-    })?;
+    match decide_token_auth(entries, token, &[], Utc::now())? {
+        TokenAuthDecision::Accepted(name) => Ok(Some(name)),
+        TokenAuthDecision::Rejected(_) | TokenAuthDecision::NoMatch => Ok(None),
+    }
+}
 
-    let token_hash = hash_token_sha256(token);
-    if let Some(entry) = validator.creds.get(&token_hash)
-        && entry.verify_token(token)?
-    {
-        return Ok(Some(entry.meta.name.clone()));
+/// Match a bearer token against entries and enforce their availability and
+/// constraints.
+///
+/// Structured tokens (`sdt_<entry-id>_<secret>_<crc>`) are looked up by
+/// their entry id after a checksum check; bare tokens fall back to a lookup
+/// by hash.
+pub fn decide_token_auth(
+    entries: &[TokenAuthEntry],
+    token: &str,
+    required_scopes: &[String],
+    now: DateTime<Utc>,
+) -> CredsManageResult<TokenAuthDecision> {
+    let Some(entry) = find_token_auth_entry(entries, token)? else {
+        return Ok(TokenAuthDecision::NoMatch);
+    };
+
+    if let Err(unavailable) = entry.meta.availability.check(now) {
+        return Ok(TokenAuthDecision::Rejected(unavailable.into()));
     }
+    Ok(match entry.constraints.check(now, required_scopes) {
+        Ok(()) => TokenAuthDecision::Accepted(entry.meta.name.clone()),
+        Err(violation) => TokenAuthDecision::Rejected(violation),
+    })
+}
 
-    Ok(None)
+/// Id of the basic entry `username` belongs to, whether or not the
+/// password matches. Used to attribute failed logins to an entry.
+pub fn basic_auth_target_entry_id(entries: &[BasicAuthEntry], username: &str) -> Option<String> {
+    entries
+        .iter()
+        .find(|entry| entry.username() == username)
+        .map(|entry| entry.meta.id.clone())
+}
+
+/// Id of the token entry a bearer token targets, whether or not it
+/// verifies. Structured tokens name their entry; bare tokens are matched by
+/// hash.
+pub fn token_auth_target_entry_id(entries: &[TokenAuthEntry], token: &str) -> Option<String> {
+    let target = match StructuredStaticToken::parse(token) {
+        Ok(Some(structured)) => entries.iter().find(|e| e.meta.id == structured.id),
+        Ok(None) => {
+            let token_hash = hash_token_sha256(token);
+            entries.iter().find(|e| e.token_hash() == token_hash)
+        }
+        Err(_) => None,
+    };
+    target.map(|entry| entry.meta.id.clone())
+}
+
+fn find_token_auth_entry<'a>(
+    entries: &'a [TokenAuthEntry],
+    token: &str,
+) -> CredsManageResult<Option<&'a TokenAuthEntry>> {
+    if let Some(structured) = StructuredStaticToken::parse(token)? {
+        let Some(entry) = entries.iter().find(|e| e.meta.id == structured.id) else {
+            return Ok(None);
+        };
+        return Ok(entry.verify_token(token)?.then_some(entry));
+    }
+
+    let token_hash = hash_token_sha256(token);
+    match entries.iter().find(|e| e.token_hash() == token_hash) {
+        Some(entry) if entry.verify_token(token)? => Ok(Some(entry)),
+        _ => Ok(None),
+    }
 }
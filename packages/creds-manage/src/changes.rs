@@ -0,0 +1,245 @@
+//! Change notifications for consumers of a
+//! [`CredsManageStore`](crate::store::CredsManageStore), such as caches that
+//! would otherwise poll the store.
+//!
+//! Events carry ids only; subscribers look up the current state themselves.
+//! Delivery is best effort: a subscriber that falls more than
+//! [`CHANGE_CHANNEL_CAPACITY`] events behind is told it lagged and should
+//! reload everything.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::models::{AuthEntryMeta, AuthEntryUsage, DataFile, Group};
+
+/// Events buffered per subscriber before it lags.
+pub const CHANGE_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StoreChangeEvent {
+    EntryCreated {
+        entry_id: String,
+    },
+    /// Any change to an entry except its usage statistics.
+    EntryUpdated {
+        entry_id: String,
+    },
+    EntryDeleted {
+        entry_id: String,
+    },
+    GroupCreated {
+        group_id: String,
+    },
+    GroupUpdated {
+        group_id: String,
+    },
+    GroupDeleted {
+        group_id: String,
+    },
+    /// Entries were added to or removed from a group directly; members of
+    /// its subgroups are not listed.
+    GroupMembershipChanged {
+        group_id: String,
+        added_entry_ids: Vec<String>,
+        removed_entry_ids: Vec<String>,
+    },
+}
+
+/// Sending half of the store's change channel.
+#[derive(Debug, Clone)]
+pub struct ChangeNotifier {
+    sender: broadcast::Sender<StoreChangeEvent>,
+}
+
+impl Default for ChangeNotifier {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::Sender::new(CHANGE_CHANNEL_CAPACITY),
+        }
+    }
+}
+
+impl ChangeNotifier {
+    pub fn subscribe(&self) -> broadcast::Receiver<StoreChangeEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Whether anyone listens; lets publishers skip computing events.
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn send(&self, events: impl IntoIterator<Item = StoreChangeEvent>) {
+        for event in events {
+            // Without subscribers the event is simply dropped.
+            let _ = self.sender.send(event);
+        }
+    }
+}
+
+/// Membership changes of one entry moving from the `before` to the `after`
+/// groups, removals first.
+pub(crate) fn entry_membership_changes(
+    entry_id: &str,
+    before: &[String],
+    after: &[String],
+) -> Vec<StoreChangeEvent> {
+    let removed = before
+        .iter()
+        .filter(|group_id| !after.contains(group_id))
+        .map(|group_id| StoreChangeEvent::GroupMembershipChanged {
+            group_id: group_id.clone(),
+            added_entry_ids: Vec::new(),
+            removed_entry_ids: vec![entry_id.to_string()],
+        });
+    let added = after
+        .iter()
+        .filter(|group_id| !before.contains(group_id))
+        .map(|group_id| StoreChangeEvent::GroupMembershipChanged {
+            group_id: group_id.clone(),
+            added_entry_ids: vec![entry_id.to_string()],
+            removed_entry_ids: Vec::new(),
+        });
+    removed.chain(added).collect()
+}
+
+/// The membership change of a group whose direct members went from
+/// `before` to `after`, if any.
+pub(crate) fn group_membership_change(
+    group_id: &str,
+    before: &BTreeSet<String>,
+    after: &BTreeSet<String>,
+) -> Option<StoreChangeEvent> {
+    let added_entry_ids: Vec<String> = after.difference(before).cloned().collect();
+    let removed_entry_ids: Vec<String> = before.difference(after).cloned().collect();
+    (!added_entry_ids.is_empty() || !removed_entry_ids.is_empty()).then(|| {
+        StoreChangeEvent::GroupMembershipChanged {
+            group_id: group_id.to_string(),
+            added_entry_ids,
+            removed_entry_ids,
+        }
+    })
+}
+
+/// Every change between two snapshots of the data, e.g. after the data file
+/// was replaced by another process.
+pub(crate) fn data_file_changes(old: &DataFile, new: &DataFile) -> Vec<StoreChangeEvent> {
+    let (old_entries, new_entries) = (entry_states(old), entry_states(new));
+    let mut events = Vec::new();
+    for (id, state) in &new_entries {
+        match old_entries.get(id) {
+            None => events.push(StoreChangeEvent::EntryCreated {
+                entry_id: id.clone(),
+            }),
+            Some(old_state) if old_state != state => events.push(StoreChangeEvent::EntryUpdated {
+                entry_id: id.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    events.extend(
+        old_entries
+            .keys()
+            .filter(|id| !new_entries.contains_key(*id))
+            .map(|id| StoreChangeEvent::EntryDeleted {
+                entry_id: id.clone(),
+            }),
+    );
+
+    let (old_groups, new_groups) = (group_states(old), group_states(new));
+    for (id, group) in &new_groups {
+        match old_groups.get(id) {
+            None => events.push(StoreChangeEvent::GroupCreated {
+                group_id: id.clone(),
+            }),
+            Some(old_group) if old_group != group => events.push(StoreChangeEvent::GroupUpdated {
+                group_id: id.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    events.extend(
+        old_groups
+            .keys()
+            .filter(|id| !new_groups.contains_key(*id))
+            .map(|id| StoreChangeEvent::GroupDeleted {
+                group_id: id.clone(),
+            }),
+    );
+
+    let (old_members, new_members) = (group_members(old), group_members(new));
+    let group_ids: BTreeSet<&String> = old_members.keys().chain(new_members.keys()).collect();
+    let empty = BTreeSet::new();
+    events.extend(group_ids.into_iter().filter_map(|group_id| {
+        group_membership_change(
+            group_id,
+            old_members.get(group_id).unwrap_or(&empty),
+            new_members.get(group_id).unwrap_or(&empty),
+        )
+    }));
+    events
+}
+
+/// Serialized entries by id, without their usage statistics and revision.
+pub(crate) fn entry_states(data: &DataFile) -> BTreeMap<String, serde_json::Value> {
+    fn state<T: Serialize + Clone>(
+        entry: &T,
+        meta: impl Fn(&mut T) -> &mut AuthEntryMeta,
+    ) -> (String, serde_json::Value) {
+        let mut entry = entry.clone();
+        let meta = meta(&mut entry);
+        meta.usage = AuthEntryUsage::default();
+        meta.revision = 0;
+        let id = meta.id.clone();
+        (id, serde_json::to_value(&entry).unwrap_or_default())
+    }
+
+    data.basic_creds
+        .iter()
+        .map(|entry| state(entry, |entry| &mut entry.meta))
+        .chain(
+            data.token_creds
+                .iter()
+                .map(|entry| state(entry, |entry| &mut entry.meta)),
+        )
+        .collect()
+}
+
+/// Serialized groups by id, without their revision.
+pub(crate) fn group_states(data: &DataFile) -> BTreeMap<String, serde_json::Value> {
+    data.groups
+        .iter()
+        .map(|group| {
+            let group = Group {
+                revision: 0,
+                ..group.clone()
+            };
+            (
+                group.id.clone(),
+                serde_json::to_value(&group).unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+/// Direct member entry ids by group id.
+pub(crate) fn group_members(data: &DataFile) -> BTreeMap<String, BTreeSet<String>> {
+    let mut members: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let metas = data
+        .basic_creds
+        .iter()
+        .map(|entry| &entry.meta)
+        .chain(data.token_creds.iter().map(|entry| &entry.meta));
+    for meta in metas {
+        for group_id in &meta.group_ids {
+            members
+                .entry(group_id.clone())
+                .or_default()
+                .insert(meta.id.clone());
+        }
+    }
+    members
+}
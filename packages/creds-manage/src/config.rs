@@ -1,18 +1,102 @@
+use securitydept_creds::Argon2HashConfig;
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct CredsManageConfig {
+    /// Storage backend holding entries and groups.
+    #[serde(default)]
+    pub backend: CredsManageBackendKind,
     #[serde(default = "default_data_path")]
     pub data_path: String,
+    /// SQLite database used by the `sqlite` backend. On first use it is
+    /// filled from `data_path` by the data file import migration.
+    #[cfg(feature = "sqlite")]
+    #[serde(default = "default_sqlite_path")]
+    pub sqlite_path: String,
+    /// JSON Lines file receiving a hash-chained record of every entry and
+    /// group mutation. Auditing is off when unset.
+    #[serde(default)]
+    pub audit_log_path: Option<String>,
+    /// Seal the data file at rest. Applies to the `file` backend and to the
+    /// data file read by the SQLite import; the SQLite database itself is
+    /// not encrypted. The data file is plaintext when unset.
+    #[cfg(feature = "encryption")]
+    #[serde(default)]
+    pub data_encryption: Option<DataEncryptionConfig>,
+    /// How often recorded entry usage statistics are written to the store.
+    #[serde(default = "default_usage_flush_interval_secs")]
+    pub usage_flush_interval_secs: u64,
+    /// How often entries and groups are checked for a passed `expires_at`.
+    #[serde(default = "default_expiry_sweep_interval_secs")]
+    pub expiry_sweep_interval_secs: u64,
     #[cfg(feature = "migration")]
     #[serde(default = "default_auto_migrate")]
     pub auto_migrate: bool,
+    /// Argon2 cost parameters for new and re-hashed basic entry passwords.
+    #[serde(default)]
+    pub argon2: Argon2HashConfig,
+}
+
+/// Where [`CredsManageStore`](crate::store::CredsManageStore) keeps its data.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CredsManageBackendKind {
+    /// A single JSON data file, replaced atomically on every write.
+    #[default]
+    File,
+    /// An embedded SQLite database with indexed lookups.
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+/// Keys for sealing the data file, see [`crate::encryption`].
+#[cfg(feature = "encryption")]
+#[derive(Debug, Clone, Deserialize)]
+pub struct DataEncryptionConfig {
+    /// Key that new writes are sealed with.
+    pub key: DataKeySource,
+    /// Keys that may still open the data file while it is re-sealed with
+    /// `key` during a rotation.
+    #[serde(default)]
+    pub previous_keys: Vec<DataKeySource>,
+}
+
+/// Where a base64-encoded 32-byte data encryption key is read from.
+#[cfg(feature = "encryption")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum DataKeySource {
+    Env {
+        var: String,
+    },
+    File {
+        path: String,
+    },
+    /// Standard output of a command, e.g. a secret manager CLI.
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
 }
 
 fn default_data_path() -> String {
     "./data/data.json".to_string()
 }
 
+#[cfg(feature = "sqlite")]
+fn default_sqlite_path() -> String {
+    "./data/creds.sqlite3".to_string()
+}
+
+fn default_usage_flush_interval_secs() -> u64 {
+    30
+}
+
+fn default_expiry_sweep_interval_secs() -> u64 {
+    60
+}
+
 #[cfg(feature = "migration")]
 fn default_auto_migrate() -> bool {
     true
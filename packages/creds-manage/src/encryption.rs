@@ -0,0 +1,323 @@
+//! Envelope encryption of the data file at rest.
+//!
+//! Every write seals the serialized data file with a fresh random data key,
+//! and that data key is in turn sealed with the configured key encryption
+//! key. Both layers use XChaCha20-Poly1305. Rotating the key encryption key
+//! therefore only needs the previous key to open the file once.
+
+use std::{borrow::Cow, fmt, process::Command};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use orion::aead::{self, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+
+use crate::{
+    config::{DataEncryptionConfig, DataKeySource},
+    error::{self, CredsManageError, CredsManageResult},
+};
+
+/// `format` marker of a sealed data file.
+pub const DATA_ENVELOPE_FORMAT: &str = "securitydept-envelope-v1";
+
+const DATA_ENVELOPE_ALG: &str = "xchacha20-poly1305";
+const DATA_KEY_LEN: usize = 32;
+
+/// On-disk form of a sealed data file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataEnvelope {
+    pub format: String,
+    pub alg: String,
+    /// [`DataKey::id`] of the key encryption key that sealed `wrapped_key`.
+    pub key_id: String,
+    /// Base64 of the sealed data key.
+    pub wrapped_key: String,
+    /// Base64 of the sealed data file.
+    pub ciphertext: String,
+}
+
+impl DataEnvelope {
+    /// Parse `raw` as an envelope, or `None` when it is a plaintext data
+    /// file.
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        serde_json::from_slice::<Self>(raw)
+            .ok()
+            .filter(|envelope| envelope.format == DATA_ENVELOPE_FORMAT)
+    }
+}
+
+/// A 256-bit key encryption key.
+pub struct DataKey {
+    id: String,
+    key: SecretKey,
+}
+
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DataKey {
+    /// Parse a base64-encoded 32-byte key, as printed by
+    /// `openssl rand -base64 32`. Surrounding whitespace is ignored.
+    pub fn from_base64(encoded: &str) -> CredsManageResult<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| CredsManageError::DataKey {
+                message: format!("key is not valid base64: {e}"),
+            })?;
+        if bytes.len() != DATA_KEY_LEN {
+            return Err(CredsManageError::DataKey {
+                message: format!(
+                    "key must be {DATA_KEY_LEN} bytes, found {} bytes",
+                    bytes.len()
+                ),
+            });
+        }
+
+        let id = hex::encode(&Sha256::digest(&bytes)[..8]);
+        let key = SecretKey::from_slice(&bytes).map_err(|e| CredsManageError::DataKey {
+            message: e.to_string(),
+        })?;
+        Ok(Self { id, key })
+    }
+
+    /// Read and parse a key from `source`. Commands run synchronously.
+    pub fn load(source: &DataKeySource) -> CredsManageResult<Self> {
+        let encoded = match source {
+            DataKeySource::Env { var } => {
+                std::env::var(var).map_err(|_| CredsManageError::DataKey {
+                    message: format!("environment variable {var} is not set"),
+                })?
+            }
+            DataKeySource::File { path } => {
+                std::fs::read_to_string(path).map_err(|e| CredsManageError::DataKey {
+                    message: format!("failed to read key file {path}: {e}"),
+                })?
+            }
+            DataKeySource::Command { command, args } => {
+                let output = Command::new(command).args(args).output().map_err(|e| {
+                    CredsManageError::DataKey {
+                        message: format!("failed to run key command {command}: {e}"),
+                    }
+                })?;
+                if !output.status.success() {
+                    return Err(CredsManageError::DataKey {
+                        message: format!("key command {command} exited with {}", output.status),
+                    });
+                }
+                String::from_utf8(output.stdout).map_err(|_| CredsManageError::DataKey {
+                    message: format!("key command {command} printed non UTF-8 output"),
+                })?
+            }
+        };
+        Self::from_base64(&encoded)
+    }
+
+    /// Short fingerprint recorded in envelopes sealed with this key.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// The key new envelopes are sealed with, plus keys still accepted for
+/// opening envelopes during a rotation.
+#[derive(Debug)]
+pub struct DataKeyring {
+    current: DataKey,
+    previous: Vec<DataKey>,
+}
+
+impl DataKeyring {
+    pub fn new(current: DataKey) -> Self {
+        Self {
+            current,
+            previous: Vec::new(),
+        }
+    }
+
+    pub fn with_previous(mut self, key: DataKey) -> Self {
+        self.previous.push(key);
+        self
+    }
+
+    /// Load every key named by `config`.
+    pub fn load(config: &DataEncryptionConfig) -> CredsManageResult<Self> {
+        config
+            .previous_keys
+            .iter()
+            .try_fold(Self::new(DataKey::load(&config.key)?), |keyring, source| {
+                Ok(keyring.with_previous(DataKey::load(source)?))
+            })
+    }
+
+    pub fn current_key_id(&self) -> &str {
+        self.current.id()
+    }
+
+    /// Seal `plaintext` into the bytes of a [`DataEnvelope`].
+    pub fn seal(&self, plaintext: &[u8]) -> CredsManageResult<Vec<u8>> {
+        let data_key = SecretKey::default();
+        let ciphertext = aead::seal(&data_key, plaintext).map_err(seal_error)?;
+        let wrapped_key =
+            aead::seal(&self.current.key, data_key.unprotected_as_bytes()).map_err(seal_error)?;
+
+        let envelope = DataEnvelope {
+            format: DATA_ENVELOPE_FORMAT.to_string(),
+            alg: DATA_ENVELOPE_ALG.to_string(),
+            key_id: self.current.id.clone(),
+            wrapped_key: BASE64.encode(wrapped_key),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+        serde_json::to_vec_pretty(&envelope).context(error::DataSerializeSnafu)
+    }
+
+    /// Open an envelope sealed with the current or a previous key.
+    pub fn open(&self, envelope: &DataEnvelope) -> CredsManageResult<Vec<u8>> {
+        let kek = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.id == envelope.key_id)
+            .ok_or_else(|| CredsManageError::DataKeyUnknown {
+                key_id: envelope.key_id.clone(),
+            })?;
+        if envelope.alg != DATA_ENVELOPE_ALG {
+            return Err(CredsManageError::DataCrypto {
+                message: format!("unsupported algorithm {}", envelope.alg),
+            });
+        }
+
+        let wrapped_key = decode_field(&envelope.wrapped_key, "wrapped_key")?;
+        let data_key = aead::open(&kek.key, &wrapped_key)
+            .ok()
+            .and_then(|bytes| SecretKey::from_slice(&bytes).ok())
+            .ok_or_else(|| CredsManageError::DataCrypto {
+                message: "data key does not authenticate".to_string(),
+            })?;
+        let ciphertext = decode_field(&envelope.ciphertext, "ciphertext")?;
+        aead::open(&data_key, &ciphertext).map_err(|_| CredsManageError::DataCrypto {
+            message: "data file does not authenticate".to_string(),
+        })
+    }
+}
+
+/// The plaintext of data file bytes that may or may not be sealed.
+///
+/// With a keyring, a plaintext file is rejected unless `allow_plaintext` is
+/// set, so a file swapped for an unsealed one is not trusted. Only sealing
+/// an existing file for the first time allows it. An empty file is a new
+/// data file and always accepted.
+pub fn open_data_file<'a>(
+    raw: &'a [u8],
+    keyring: Option<&DataKeyring>,
+    allow_plaintext: bool,
+) -> CredsManageResult<Cow<'a, [u8]>> {
+    match (DataEnvelope::parse(raw), keyring) {
+        (None, Some(_)) if !allow_plaintext && !raw.iter().all(|b| b.is_ascii_whitespace()) => {
+            Err(CredsManageError::DataNotEncrypted)
+        }
+        (None, _) => Ok(Cow::Borrowed(raw)),
+        (Some(envelope), Some(keyring)) => keyring.open(&envelope).map(Cow::Owned),
+        (Some(envelope), None) => Err(CredsManageError::DataKeyUnknown {
+            key_id: envelope.key_id,
+        }),
+    }
+}
+
+fn decode_field(value: &str, field: &str) -> CredsManageResult<Vec<u8>> {
+    BASE64
+        .decode(value)
+        .map_err(|e| CredsManageError::DataCrypto {
+            message: format!("{field} is not valid base64: {e}"),
+        })
+}
+
+fn seal_error(error: orion::errors::UnknownCryptoError) -> CredsManageError {
+    CredsManageError::DataCrypto {
+        message: format!("failed to seal data file: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> DataKey {
+        DataKey::from_base64(&BASE64.encode([byte; DATA_KEY_LEN])).unwrap()
+    }
+
+    #[test]
+    fn sealed_data_opens_with_current_or_previous_key() {
+        let plaintext = br#"{"version":2}"#;
+        let sealed = DataKeyring::new(key(1)).seal(plaintext).unwrap();
+        let envelope = DataEnvelope::parse(&sealed).expect("sealed bytes are an envelope");
+        assert_eq!(envelope.key_id, key(1).id());
+        assert!(!String::from_utf8_lossy(&sealed).contains("version"));
+
+        let rotated = DataKeyring::new(key(2)).with_previous(key(1));
+        assert_eq!(
+            open_data_file(&sealed, Some(&rotated), false)
+                .unwrap()
+                .as_ref(),
+            plaintext
+        );
+        assert!(matches!(
+            open_data_file(&sealed, Some(&DataKeyring::new(key(2))), false),
+            Err(CredsManageError::DataKeyUnknown { .. })
+        ));
+        assert!(matches!(
+            open_data_file(&sealed, None, false),
+            Err(CredsManageError::DataKeyUnknown { .. })
+        ));
+    }
+
+    #[test]
+    fn plaintext_is_rejected_when_a_keyring_is_configured() {
+        let plaintext = br#"{"version":2}"#;
+        let keyring = DataKeyring::new(key(1));
+
+        assert!(matches!(
+            open_data_file(plaintext, Some(&keyring), false),
+            Err(CredsManageError::DataNotEncrypted)
+        ));
+        assert_eq!(
+            open_data_file(plaintext, Some(&keyring), true)
+                .unwrap()
+                .as_ref(),
+            plaintext
+        );
+        assert_eq!(
+            open_data_file(plaintext, None, false).unwrap().as_ref(),
+            plaintext
+        );
+        assert!(
+            open_data_file(b"", Some(&keyring), false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn tampered_envelope_is_rejected() {
+        let keyring = DataKeyring::new(key(1));
+        let mut envelope = DataEnvelope::parse(&keyring.seal(b"{}").unwrap()).unwrap();
+        let mut ciphertext = BASE64.decode(&envelope.ciphertext).unwrap();
+        *ciphertext.last_mut().unwrap() ^= 1;
+        envelope.ciphertext = BASE64.encode(ciphertext);
+
+        assert!(matches!(
+            keyring.open(&envelope),
+            Err(CredsManageError::DataCrypto { .. })
+        ));
+    }
+
+    #[test]
+    fn keys_must_be_32_bytes_of_base64() {
+        assert!(DataKey::from_base64("not base64!").is_err());
+        assert!(DataKey::from_base64(&BASE64.encode([0u8; 16])).is_err());
+        assert!(DataKey::from_base64(&format!("{}\n", BASE64.encode([0u8; 32]))).is_ok());
+    }
+}
@@ -23,6 +23,25 @@ pub enum CredsManageError {
     #[snafu(display("Failed to serialize data: {source}"))]
     DataSerialize { source: serde_json::Error },
 
+    #[cfg(feature = "encryption")]
+    #[snafu(display("Failed to load data encryption key: {message}"))]
+    DataKey { message: String },
+
+    #[cfg(feature = "encryption")]
+    #[snafu(display("Data file is sealed with key {key_id}, which is not configured"))]
+    DataKeyUnknown { key_id: String },
+
+    #[cfg(feature = "encryption")]
+    #[snafu(display(
+        "Data file is not encrypted, but data_encryption is configured; seal it with \
+         `securitydept-cli migrate rotate-data-key`"
+    ))]
+    DataNotEncrypted,
+
+    #[cfg(feature = "encryption")]
+    #[snafu(display("Data file encryption failed: {message}"))]
+    DataCrypto { message: String },
+
     #[snafu(display("Entry not found: {id}"))]
     EntryNotFound { id: String },
 
@@ -35,9 +54,54 @@ pub enum CredsManageError {
     #[snafu(display("Duplicate group name: {name}"))]
     DuplicateGroupName { name: String },
 
+    #[snafu(display("Token entry {id} has the same token as another entry"))]
+    DuplicateToken { id: String },
+
+    #[snafu(display("Group {id} cannot be nested below itself"))]
+    GroupCycle { id: String },
+
+    #[snafu(display("{id} is at revision {current}, not the expected {expected}"))]
+    RevisionMismatch {
+        id: String,
+        expected: u64,
+        current: u64,
+    },
+
+    #[snafu(display("Invalid store archive: {message}"))]
+    InvalidArchive { message: String },
+
+    #[snafu(display("Import conflicts with existing id: {id}"))]
+    ImportConflict { id: String },
+
     #[snafu(display("Invalid configuration: {message}"))]
     InvalidConfig { message: String },
 
+    #[snafu(display("Invalid entry: {message}"))]
+    InvalidEntry { message: String },
+
+    #[snafu(display("Failed to read audit log: {source}"))]
+    AuditRead { source: std::io::Error },
+
+    #[snafu(display("Failed to append to audit log: {source}"))]
+    AuditWrite { source: std::io::Error },
+
+    #[snafu(display("Failed to parse audit log: {source}"))]
+    AuditParse { source: serde_json::Error },
+
+    #[snafu(display("Audit log is not configured"))]
+    AuditLogDisabled,
+
+    #[snafu(display("{operation:?} of {entity_id} was applied but not audited: {source}"))]
+    UnauditedMutation {
+        operation: crate::audit::AuditOperation,
+        entity_id: String,
+        source: Box<CredsManageError>,
+    },
+
+    #[cfg(feature = "sqlite")]
+    #[snafu(display("Database error: {source}"))]
+    Database { source: rusqlite::Error },
+
     #[snafu(transparent)]
     Creds {
         source: securitydept_creds::error::CredsError,
@@ -57,7 +121,14 @@ impl ToHttpStatus for CredsManageError {
                 StatusCode::NOT_FOUND
             }
             CredsManageError::DuplicateEntryName { .. }
-            | CredsManageError::DuplicateGroupName { .. } => StatusCode::CONFLICT,
+            | CredsManageError::DuplicateGroupName { .. }
+            | CredsManageError::DuplicateToken { .. }
+            | CredsManageError::ImportConflict { .. } => StatusCode::CONFLICT,
+            CredsManageError::RevisionMismatch { .. } => StatusCode::PRECONDITION_FAILED,
+            CredsManageError::InvalidEntry { .. }
+            | CredsManageError::GroupCycle { .. }
+            | CredsManageError::InvalidArchive { .. } => StatusCode::BAD_REQUEST,
+            CredsManageError::AuditLogDisabled => StatusCode::NOT_FOUND,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -87,6 +158,32 @@ impl ToErrorPresentation for CredsManageError {
                 "A group with the same name already exists.",
                 UserRecovery::None,
             ),
+            CredsManageError::DuplicateToken { .. } => ErrorPresentation::new(
+                "duplicate_token",
+                "A token entry with the same token already exists.",
+                UserRecovery::None,
+            ),
+            CredsManageError::GroupCycle { .. } => ErrorPresentation::new(
+                "group_cycle",
+                "A group cannot be nested below itself or one of its subgroups.",
+                UserRecovery::None,
+            ),
+            CredsManageError::RevisionMismatch { .. } => ErrorPresentation::new(
+                "revision_mismatch",
+                "This item was changed by someone else. Reload it and try again.",
+                UserRecovery::None,
+            ),
+            CredsManageError::InvalidArchive { message } => {
+                ErrorPresentation::new("invalid_archive", message.clone(), UserRecovery::None)
+            }
+            CredsManageError::ImportConflict { .. } => ErrorPresentation::new(
+                "import_conflict",
+                "The archive contains an id that already exists in the store.",
+                UserRecovery::None,
+            ),
+            CredsManageError::InvalidEntry { message } => {
+                ErrorPresentation::new("invalid_entry", message.clone(), UserRecovery::None)
+            }
             CredsManageError::ConfigLoad { .. }
             | CredsManageError::DataRead { .. }
             | CredsManageError::DataWrite { .. }
@@ -97,6 +194,38 @@ impl ToErrorPresentation for CredsManageError {
                 "Credential management is temporarily unavailable.",
                 UserRecovery::ContactSupport,
             ),
+            CredsManageError::AuditRead { .. }
+            | CredsManageError::AuditWrite { .. }
+            | CredsManageError::AuditParse { .. } => ErrorPresentation::new(
+                "audit_log_unavailable",
+                "The audit log is temporarily unavailable.",
+                UserRecovery::ContactSupport,
+            ),
+            CredsManageError::UnauditedMutation { .. } => ErrorPresentation::new(
+                "unaudited_mutation",
+                "The change was saved, but the audit log could not record it.",
+                UserRecovery::ContactSupport,
+            ),
+            CredsManageError::AuditLogDisabled => ErrorPresentation::new(
+                "audit_log_disabled",
+                "The audit log is not enabled on this server.",
+                UserRecovery::None,
+            ),
+            #[cfg(feature = "encryption")]
+            CredsManageError::DataKey { .. }
+            | CredsManageError::DataKeyUnknown { .. }
+            | CredsManageError::DataNotEncrypted
+            | CredsManageError::DataCrypto { .. } => ErrorPresentation::new(
+                "creds_manage_unavailable",
+                "Credential management is temporarily unavailable.",
+                UserRecovery::ContactSupport,
+            ),
+            #[cfg(feature = "sqlite")]
+            CredsManageError::Database { .. } => ErrorPresentation::new(
+                "creds_manage_unavailable",
+                "Credential management is temporarily unavailable.",
+                UserRecovery::ContactSupport,
+            ),
             #[cfg(feature = "migration")]
             CredsManageError::Migration { .. } => ErrorPresentation::new(
                 "creds_manage_unavailable",
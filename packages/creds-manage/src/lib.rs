@@ -1,10 +1,17 @@
+pub mod archive;
+pub mod audit;
 pub mod auth;
+pub mod changes;
 pub mod config;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 pub mod error;
 #[cfg(feature = "migration")]
 pub mod migrations;
 pub mod models;
 pub mod store;
 
-pub use config::CredsManageConfig;
+pub use audit::{AuditActor, AuditActorSource, AuditLog};
+pub use changes::StoreChangeEvent;
+pub use config::{CredsManageBackendKind, CredsManageConfig};
 pub use error::{CredsManageError, CredsManageResult};
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use crate::{CredsManageConfig, CredsManageResult, error};
+
+/// Applied migrations, kept as `<data file>.migrations.json` next to the
+/// data file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MigrationHistory {
+    pub applied: Vec<AppliedMigration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub name: String,
+    pub applied_at: DateTime<Utc>,
+    /// Data file copy taken before the migration was applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+}
+
+impl MigrationHistory {
+    pub fn path(config: &CredsManageConfig) -> PathBuf {
+        sibling_path(Path::new(&config.data_path), "migrations.json")
+    }
+
+    /// The history at `path`, empty when the file does not exist yet.
+    pub fn load(path: &Path) -> CredsManageResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read(path).context(error::DataReadSnafu)?;
+        serde_json::from_slice(&raw).context(error::DataParseSnafu)
+    }
+
+    pub fn save(&self, path: &Path) -> CredsManageResult<()> {
+        let serialized = serde_json::to_vec_pretty(self).context(error::DataSerializeSnafu)?;
+        std::fs::write(path, serialized).context(error::DataWriteSnafu)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AppliedMigration> {
+        self.applied.iter().find(|applied| applied.name == name)
+    }
+}
+
+/// `<path>.<suffix>` in the directory of `path`.
+pub(crate) fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| format!("{}.{suffix}", name.to_string_lossy()))
+        .unwrap_or_else(|| format!("store.{suffix}"));
+
+    match path.parent() {
+        Some(parent) => parent.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// `<path>.<timestamp>.bak`, where a copy of `path` taken now is kept.
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    sibling_path(path, &format!("{stamp}.bak"))
+}
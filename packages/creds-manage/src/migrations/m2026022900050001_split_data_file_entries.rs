@@ -1,12 +1,10 @@
-use std::path::PathBuf;
-
-use securitydept_creds::{Argon2BasicAuthCred, Sha256TokenAuthCred};
+use securitydept_creds::{MultiFormatBasicAuthCred, Sha256TokenAuthCred};
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 
 use crate::{
-    CredsManageConfig, CredsManageResult, error,
-    migrations::models::MigratorTrait,
+    CredsManageBackendKind, CredsManageResult, error,
+    migrations::models::{MigrationManager, MigrationTrait},
     models::{AuthEntryKind, AuthEntryMeta, DATA_FILE_VERSION, DataFile, Group},
 };
 
@@ -35,31 +33,25 @@ pub struct DataFileV1 {
 pub type DataFileV2 = DataFile;
 pub type DataFileCurrent = DataFile;
 
-pub struct Migrator;
-
-impl MigratorTrait for Migrator {
-    fn up(&self, config: &CredsManageConfig, _steps: Option<u32>) -> CredsManageResult<()> {
-        let path = PathBuf::from(&config.data_path);
+/// Split the single `entries` list of the legacy data file into
+/// `basic_creds` and `token_creds`.
+pub struct Migration;
 
-        if let Some(parent) = path.parent()
-            && !parent.as_os_str().is_empty()
-        {
-            std::fs::create_dir_all(parent).context(error::DataWriteSnafu)?;
-        }
-
-        if !path.exists() {
-            write_current_empty(&path)?;
-            return Ok(());
-        }
+impl MigrationTrait for Migration {
+    fn name(&self) -> &'static str {
+        "m2026022900050001_split_data_file_entries"
+    }
 
-        let raw = std::fs::read_to_string(&path).context(error::DataReadSnafu)?;
-        if raw.trim().is_empty() {
-            write_current_empty(&path)?;
+    fn up(&self, manager: &mut MigrationManager<'_>) -> CredsManageResult<()> {
+        let Some(value) = manager.data() else {
+            // Other backends only read the data file to import it.
+            if manager.config().backend == CredsManageBackendKind::File {
+                manager.set_data(to_value(&DataFileCurrent::default())?);
+            }
             return Ok(());
-        }
+        };
 
-        let value: serde_json::Value = serde_json::from_str(&raw).context(error::DataParseSnafu)?;
-        let current_version = detect_version(&value);
+        let current_version = detect_version(value);
         if current_version >= 2 {
             return Ok(());
         }
@@ -72,21 +64,34 @@ impl MigratorTrait for Migrator {
             });
         }
 
-        let old: DataFileV1 = serde_json::from_value(value).context(error::DataParseSnafu)?;
+        let old: DataFileV1 =
+            serde_json::from_value(value.clone()).context(error::DataParseSnafu)?;
         let migrated = migrate_v1_to_v2(old)?;
+        manager.set_data(to_value(&migrated)?);
+        Ok(())
+    }
+
+    /// Token prefixes have no place in the legacy layout and are dropped.
+    /// Token constraints cannot be dropped without widening what a token
+    /// grants, so reverting is refused while any token entry has them.
+    fn down(&self, manager: &mut MigrationManager<'_>) -> CredsManageResult<()> {
+        let Some(value) = manager.data() else {
+            return Ok(());
+        };
+        if detect_version(value) != 2 {
+            return Ok(());
+        }
 
-        let serialized =
-            serde_json::to_string_pretty(&migrated).context(error::DataSerializeSnafu)?;
-        std::fs::write(&path, serialized).context(error::DataWriteSnafu)?;
+        let current: DataFileV2 =
+            serde_json::from_value(value.clone()).context(error::DataParseSnafu)?;
+        let migrated = migrate_v2_to_v1(current)?;
+        manager.set_data(to_value(&migrated)?);
         Ok(())
     }
 }
 
-fn write_current_empty(path: &PathBuf) -> CredsManageResult<()> {
-    let data = DataFileCurrent::default();
-    let serialized = serde_json::to_string_pretty(&data).context(error::DataSerializeSnafu)?;
-    std::fs::write(path, serialized).context(error::DataWriteSnafu)?;
-    Ok(())
+fn to_value<T: Serialize>(data: &T) -> CredsManageResult<serde_json::Value> {
+    serde_json::to_value(data).context(error::DataSerializeSnafu)
 }
 
 fn detect_version(value: &serde_json::Value) -> u32 {
@@ -118,7 +123,7 @@ fn migrate_v1_to_v2(old: DataFileV1) -> CredsManageResult<DataFileV2> {
                             message: format!("basic entry {} missing password_hash", entry.meta.id),
                         })?;
                 basic_creds.push(crate::models::BasicAuthEntry {
-                    cred: Argon2BasicAuthCred {
+                    cred: MultiFormatBasicAuthCred {
                         username,
                         password_hash,
                     },
@@ -134,7 +139,9 @@ fn migrate_v1_to_v2(old: DataFileV1) -> CredsManageResult<DataFileV2> {
                         })?;
                 token_creds.push(crate::models::TokenAuthEntry {
                     cred: Sha256TokenAuthCred { token_hash },
+                    token_prefix: None,
                     meta: entry.meta,
+                    constraints: Default::default(),
                 });
             }
         }
@@ -142,8 +149,45 @@ fn migrate_v1_to_v2(old: DataFileV1) -> CredsManageResult<DataFileV2> {
 
     Ok(DataFileV2 {
         version: DATA_FILE_VERSION,
+        revision: 0,
         groups: old.groups,
         basic_creds,
         token_creds,
     })
 }
+
+fn migrate_v2_to_v1(current: DataFileV2) -> CredsManageResult<DataFileV1> {
+    if let Some(entry) = current
+        .token_creds
+        .iter()
+        .find(|entry| entry.constraints != Default::default())
+    {
+        return Err(crate::CredsManageError::InvalidConfig {
+            message: format!(
+                "token entry {} has a not_before or scopes constraint, which the legacy data file \
+                 cannot keep; remove it before reverting",
+                entry.meta.id
+            ),
+        });
+    }
+
+    let basic = current.basic_creds.into_iter().map(|entry| AuthEntryV1 {
+        kind: AuthEntryKind::Basic,
+        username: Some(entry.cred.username),
+        password_hash: Some(entry.cred.password_hash),
+        token_hash: None,
+        meta: entry.meta,
+    });
+    let token = current.token_creds.into_iter().map(|entry| AuthEntryV1 {
+        kind: AuthEntryKind::Token,
+        username: None,
+        password_hash: None,
+        token_hash: Some(entry.cred.token_hash),
+        meta: entry.meta,
+    });
+
+    Ok(DataFileV1 {
+        entries: basic.chain(token).collect(),
+        groups: current.groups,
+    })
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use snafu::ResultExt;
+
+use crate::{
+    CredsManageBackendKind, CredsManageConfig, CredsManageResult, error,
+    migrations::{
+        history::backup_path,
+        models::{MigrationManager, MigrationTrait},
+    },
+    models::DataFile,
+    store::sqlite::{export_database, import_data_file},
+};
+
+/// Import the JSON data file into a new SQLite database when switching to
+/// the `sqlite` backend. The data file is left in place.
+///
+/// Reverting writes the database content back to the data file and moves the
+/// database aside, so the `file` backend continues where SQLite stopped.
+pub struct Migration;
+
+impl MigrationTrait for Migration {
+    fn name(&self) -> &'static str {
+        "m2026101600000001_import_data_file_into_sqlite"
+    }
+
+    fn is_applicable(&self, config: &CredsManageConfig) -> bool {
+        config.backend == CredsManageBackendKind::Sqlite
+    }
+
+    fn up(&self, manager: &mut MigrationManager<'_>) -> CredsManageResult<()> {
+        let Some(value) = manager.data() else {
+            return Ok(());
+        };
+        let data: DataFile =
+            serde_json::from_value(value.clone()).context(error::DataParseSnafu)?;
+        if manager.is_dry_run() {
+            return Ok(());
+        }
+
+        let config = manager.config();
+        if import_data_file(Path::new(&config.sqlite_path), &data)? {
+            tracing::info!(
+                data_path = %config.data_path,
+                sqlite_path = %config.sqlite_path,
+                entries = data.basic_creds.len() + data.token_creds.len(),
+                groups = data.groups.len(),
+                "imported creds data file into sqlite database"
+            );
+        }
+        Ok(())
+    }
+
+    fn down(&self, manager: &mut MigrationManager<'_>) -> CredsManageResult<()> {
+        let sqlite_path = Path::new(&manager.config().sqlite_path);
+        let Some(data) = export_database(sqlite_path)? else {
+            return Ok(());
+        };
+        manager.set_data(serde_json::to_value(&data).context(error::DataSerializeSnafu)?);
+        if manager.is_dry_run() {
+            return Ok(());
+        }
+
+        let backup = backup_path(sqlite_path);
+        std::fs::rename(sqlite_path, &backup).context(error::DataWriteSnafu)?;
+        tracing::info!(
+            sqlite_path = %sqlite_path.display(),
+            backup_path = %backup.display(),
+            "exported sqlite database into creds data file"
+        );
+        Ok(())
+    }
+}
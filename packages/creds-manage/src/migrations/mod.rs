@@ -1,18 +1,39 @@
-use crate::{CredsManageConfig, CredsManageResult, migrations::models::MigratorTrait};
+use std::path::Path;
 
+use chrono::Utc;
+use serde_json::Value;
+use snafu::ResultExt;
+
+use crate::{
+    CredsManageConfig, CredsManageError, CredsManageResult, error,
+    migrations::{
+        history::{AppliedMigration, MigrationHistory, backup_path},
+        models::{
+            MigrationDirection, MigrationManager, MigrationPlan, MigrationState, MigrationStatus,
+            MigrationTrait, MigratorTrait,
+        },
+    },
+    store::file::DataFileCodec,
+};
+
+pub mod history;
 pub mod m2026022900050001_split_data_file_entries;
+#[cfg(feature = "sqlite")]
+pub mod m2026101600000001_import_data_file_into_sqlite;
 pub mod models;
 
 pub struct Migrator {
-    migrators: Vec<Box<dyn MigratorTrait>>,
+    migrations: Vec<Box<dyn MigrationTrait>>,
 }
 
 impl Default for Migrator {
     fn default() -> Self {
         Self {
-            migrators: vec![Box::new(
-                m2026022900050001_split_data_file_entries::Migrator,
-            )],
+            migrations: vec![
+                Box::new(m2026022900050001_split_data_file_entries::Migration),
+                #[cfg(feature = "sqlite")]
+                Box::new(m2026101600000001_import_data_file_into_sqlite::Migration),
+            ],
         }
     }
 }
@@ -24,14 +45,213 @@ impl Migrator {
         }
         Ok(())
     }
+
+    /// Every known migration and whether it has been applied.
+    pub fn status(&self, config: &CredsManageConfig) -> CredsManageResult<Vec<MigrationStatus>> {
+        let history = MigrationHistory::load(&MigrationHistory::path(config))?;
+        Ok(self
+            .migrations
+            .iter()
+            .map(|m| MigrationStatus {
+                name: m.name(),
+                state: match history.get(m.name()) {
+                    Some(applied) => MigrationState::Applied {
+                        applied_at: applied.applied_at,
+                    },
+                    None if m.is_applicable(config) => MigrationState::Pending,
+                    None => MigrationState::NotApplicable,
+                },
+            })
+            .collect())
+    }
+
+    /// What [`MigratorTrait::up`] or [`MigratorTrait::down`] would do with
+    /// the same `steps`, without writing anything.
+    pub fn plan(
+        &self,
+        config: &CredsManageConfig,
+        direction: MigrationDirection,
+        steps: Option<u32>,
+    ) -> CredsManageResult<MigrationPlan> {
+        self.run(config, direction, steps, true)
+    }
+
+    fn run(
+        &self,
+        config: &CredsManageConfig,
+        direction: MigrationDirection,
+        steps: Option<u32>,
+        dry_run: bool,
+    ) -> CredsManageResult<MigrationPlan> {
+        let history_path = MigrationHistory::path(config);
+        let mut history = MigrationHistory::load(&history_path)?;
+        let selected = self.select(config, &history, direction, steps)?;
+        if selected.is_empty() {
+            return Ok(MigrationPlan {
+                direction,
+                migrations: Vec::new(),
+                data_file_diff: String::new(),
+                backup_path: None,
+            });
+        }
+
+        let data_path = Path::new(&config.data_path);
+        let codec = DataFileCodec::from_config(config)?;
+        let original = read_data_file(data_path, &codec)?;
+
+        let mut manager = MigrationManager::new(config, original.clone(), dry_run);
+        for m in &selected {
+            match direction {
+                MigrationDirection::Up => m.up(&mut manager)?,
+                MigrationDirection::Down => m.down(&mut manager)?,
+            }
+        }
+        let migrated = manager.into_data();
+
+        let mut plan = MigrationPlan {
+            direction,
+            migrations: selected.iter().map(|m| m.name()).collect(),
+            data_file_diff: data_file_diff(original.as_ref(), migrated.as_ref())?,
+            backup_path: None,
+        };
+        if dry_run {
+            return Ok(plan);
+        }
+
+        if let Some(migrated) = migrated.filter(|migrated| original.as_ref() != Some(migrated)) {
+            if data_path.exists() {
+                let backup = backup_path(data_path);
+                std::fs::copy(data_path, &backup).context(error::DataWriteSnafu)?;
+                plan.backup_path = Some(backup);
+            }
+            write_data_file(data_path, &codec, &migrated)?;
+        }
+
+        match direction {
+            MigrationDirection::Up => {
+                let applied_at = Utc::now();
+                history.applied.extend(selected.iter().map(|m| {
+                    AppliedMigration {
+                        name: m.name().to_string(),
+                        applied_at,
+                        backup_path: plan
+                            .backup_path
+                            .as_ref()
+                            .map(|path| path.display().to_string()),
+                    }
+                }));
+            }
+            MigrationDirection::Down => history
+                .applied
+                .retain(|applied| !plan.migrations.contains(&applied.name.as_str())),
+        }
+        history.save(&history_path)?;
+
+        tracing::info!(
+            direction = ?direction,
+            migrations = ?plan.migrations,
+            backup_path = ?plan.backup_path,
+            "creds data migrations completed"
+        );
+        Ok(plan)
+    }
+
+    /// Pending applicable migrations in order for `Up`, applied migrations
+    /// newest first for `Down`.
+    fn select(
+        &self,
+        config: &CredsManageConfig,
+        history: &MigrationHistory,
+        direction: MigrationDirection,
+        steps: Option<u32>,
+    ) -> CredsManageResult<Vec<&dyn MigrationTrait>> {
+        match direction {
+            MigrationDirection::Up => Ok(self
+                .migrations
+                .iter()
+                .map(Box::as_ref)
+                .filter(|m| history.get(m.name()).is_none() && m.is_applicable(config))
+                .take(steps.map_or(usize::MAX, |steps| steps as usize))
+                .collect()),
+            MigrationDirection::Down => history
+                .applied
+                .iter()
+                .rev()
+                .take(steps.unwrap_or(1) as usize)
+                .map(|applied| {
+                    self.migrations
+                        .iter()
+                        .map(Box::as_ref)
+                        .find(|m| m.name() == applied.name)
+                        .ok_or_else(|| CredsManageError::InvalidConfig {
+                            message: format!(
+                                "Applied migration {} is unknown to this version",
+                                applied.name
+                            ),
+                        })
+                })
+                .collect(),
+        }
+    }
 }
 
 impl MigratorTrait for Migrator {
-    fn up(&self, config: &CredsManageConfig, steps: Option<u32>) -> CredsManageResult<()> {
-        let max_steps = steps.unwrap_or(self.migrators.len() as u32);
-        for m in self.migrators.iter().take(max_steps as usize) {
-            m.up(config, steps)?;
-        }
-        Ok(())
+    fn up(
+        &self,
+        config: &CredsManageConfig,
+        steps: Option<u32>,
+    ) -> CredsManageResult<MigrationPlan> {
+        self.run(config, MigrationDirection::Up, steps, false)
+    }
+
+    fn down(
+        &self,
+        config: &CredsManageConfig,
+        steps: Option<u32>,
+    ) -> CredsManageResult<MigrationPlan> {
+        self.run(config, MigrationDirection::Down, steps, false)
+    }
+}
+
+/// The decrypted data file content, `None` when the file is missing or
+/// empty.
+fn read_data_file(path: &Path, codec: &DataFileCodec) -> CredsManageResult<Option<Value>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = std::fs::read(path).context(error::DataReadSnafu)?;
+    let raw = codec.open(&raw)?;
+    if raw.trim_ascii().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_slice(&raw)
+        .map(Some)
+        .context(error::DataParseSnafu)
+}
+
+fn write_data_file(path: &Path, codec: &DataFileCodec, data: &Value) -> CredsManageResult<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent).context(error::DataWriteSnafu)?;
+    }
+    let serialized = serde_json::to_vec_pretty(data).context(error::DataSerializeSnafu)?;
+    std::fs::write(path, codec.seal(serialized)?).context(error::DataWriteSnafu)
+}
+
+fn data_file_diff(original: Option<&Value>, migrated: Option<&Value>) -> CredsManageResult<String> {
+    let render = |data: Option<&Value>| match data {
+        Some(data) => serde_json::to_string_pretty(data)
+            .map(|json| json + "\n")
+            .context(error::DataSerializeSnafu),
+        None => Ok(String::new()),
+    };
+    let (original, migrated) = (render(original)?, render(migrated)?);
+    if original == migrated {
+        return Ok(String::new());
     }
+    Ok(similar::TextDiff::from_lines(&original, &migrated)
+        .unified_diff()
+        .header("current", "migrated")
+        .to_string())
 }
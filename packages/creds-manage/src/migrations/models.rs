@@ -1,5 +1,117 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
 use crate::{CredsManageConfig, CredsManageResult};
 
+/// A reversible step of the creds-manage storage layout.
+///
+/// Steps transform the data file content held by the [`MigrationManager`];
+/// the [`Migrator`](super::Migrator) reads, backs up and writes the file
+/// around them and records which steps were applied.
+pub trait MigrationTrait {
+    /// Name recorded in the migration history. Steps run in name order.
+    fn name(&self) -> &'static str;
+
+    /// Whether the step applies to `config`. Inapplicable steps stay
+    /// pending and are applied once they apply, e.g. after a backend switch.
+    fn is_applicable(&self, _config: &CredsManageConfig) -> bool {
+        true
+    }
+
+    fn up(&self, manager: &mut MigrationManager<'_>) -> CredsManageResult<()>;
+
+    fn down(&self, manager: &mut MigrationManager<'_>) -> CredsManageResult<()>;
+}
+
 pub trait MigratorTrait {
-    fn up(&self, config: &CredsManageConfig, steps: Option<u32>) -> CredsManageResult<()>;
+    /// Apply up to `steps` pending migrations, all of them when `None`.
+    fn up(
+        &self,
+        config: &CredsManageConfig,
+        steps: Option<u32>,
+    ) -> CredsManageResult<MigrationPlan>;
+
+    /// Revert the last `steps` applied migrations, one when `None`.
+    fn down(
+        &self,
+        config: &CredsManageConfig,
+        steps: Option<u32>,
+    ) -> CredsManageResult<MigrationPlan>;
+}
+
+/// What a [`MigrationTrait`] step works on.
+pub struct MigrationManager<'a> {
+    config: &'a CredsManageConfig,
+    data: Option<Value>,
+    dry_run: bool,
+}
+
+impl<'a> MigrationManager<'a> {
+    pub(crate) fn new(config: &'a CredsManageConfig, data: Option<Value>, dry_run: bool) -> Self {
+        Self {
+            config,
+            data,
+            dry_run,
+        }
+    }
+
+    pub fn config(&self) -> &'a CredsManageConfig {
+        self.config
+    }
+
+    /// The decrypted data file content, `None` when the file is missing or
+    /// empty.
+    pub fn data(&self) -> Option<&Value> {
+        self.data.as_ref()
+    }
+
+    pub fn set_data(&mut self, data: Value) {
+        self.data = Some(data);
+    }
+
+    /// Whether the run is only a plan. Steps must then leave everything but
+    /// the in-memory data file content untouched.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub(crate) fn into_data(self) -> Option<Value> {
+        self.data
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationDirection {
+    Up,
+    Down,
+}
+
+/// The migrations a run applied or, for a plan, would apply.
+#[derive(Debug, Clone)]
+pub struct MigrationPlan {
+    pub direction: MigrationDirection,
+    /// Migration names in the order they ran.
+    pub migrations: Vec<&'static str>,
+    /// Unified diff of the data file content; empty when it is unchanged.
+    pub data_file_diff: String,
+    /// Copy of the data file taken before it was rewritten.
+    pub backup_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub name: &'static str,
+    pub state: MigrationState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied {
+        applied_at: DateTime<Utc>,
+    },
+    Pending,
+    /// Pending, but not applicable to the current configuration.
+    NotApplicable,
 }
@@ -1,13 +1,18 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Days, NaiveDate, Utc};
 use securitydept_creds::{
-    Argon2BasicAuthCred, BasicAuthCred, Sha256TokenAuthCred, StaticTokenAuthCred,
+    BasicAuthCred, MultiFormatBasicAuthCred, Sha256TokenAuthCred, StaticTokenAuthCred,
     token::TokenAuthCred,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::error::{CredsManageError, CredsManageResult};
+
 /// The kind of authentication entry.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -26,13 +31,195 @@ pub struct AuthEntryMeta {
     pub group_ids: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Incremented on every change except recorded usage; clients pass it
+    /// back to update or delete the entry only if nobody changed it since.
+    #[serde(default)]
+    pub revision: u64,
+    #[serde(flatten)]
+    pub availability: Availability,
+    /// Forward-auth usage of the entry. Recording it does not bump
+    /// `updated_at`.
+    #[serde(default, skip_serializing_if = "AuthEntryUsage::is_empty")]
+    pub usage: AuthEntryUsage,
+}
+
+/// Manual suspension and scheduled expiry of an entry or group.
+///
+/// Disabled or expired entries and groups are kept with their credential
+/// material but never authenticate anyone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Availability {
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Why an entry or group cannot currently be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unavailable {
+    Disabled,
+    Expired,
+}
+
+impl Availability {
+    /// Disabled with an optional human-readable reason.
+    pub fn disabled(reason: Option<String>) -> Self {
+        Self {
+            disabled: true,
+            disabled_reason: reason,
+            expires_at: None,
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// Check that the entry or group may be used at `now`.
+    pub fn check(&self, now: DateTime<Utc>) -> Result<(), Unavailable> {
+        if self.disabled {
+            return Err(Unavailable::Disabled);
+        }
+        if self.is_expired(now) {
+            return Err(Unavailable::Expired);
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> CredsManageResult<()> {
+        if !self.disabled && self.disabled_reason.is_some() {
+            return Err(CredsManageError::InvalidEntry {
+                message: "disabled_reason requires disabled to be set".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// How many UTC days of [`AuthEntryUsage::daily`] counts are kept.
+pub const USAGE_RETENTION_DAYS: u64 = 30;
+
+/// When and how often an entry was presented to forward auth.
+///
+/// The same shape carries a pending batch of uses: counts are then
+/// increments and the timestamps and client IP those of the batch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuthEntryUsage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failure_at: Option<DateTime<Utc>>,
+    /// Client IP of the latest use, as resolved by the real-ip resolver.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_client_ip: Option<IpAddr>,
+    /// Successful uses since the entry was created.
+    #[serde(default)]
+    pub success_count: u64,
+    /// Failed uses since the entry was created.
+    #[serde(default)]
+    pub failure_count: u64,
+    /// Uses per UTC day, oldest first, covering the last
+    /// [`USAGE_RETENTION_DAYS`] days up to the latest use. Rolling counts
+    /// are read with [`Self::counts_within`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub daily: Vec<DailyUsage>,
+}
+
+/// Uses of an entry on one UTC day.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub success_count: u64,
+    #[serde(default)]
+    pub failure_count: u64,
+}
+
+impl AuthEntryUsage {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Count one use at `at`.
+    pub fn record(&mut self, success: bool, client_ip: Option<IpAddr>, at: DateTime<Utc>) {
+        if success {
+            self.success_count += 1;
+            self.last_success_at = self.last_success_at.max(Some(at));
+        } else {
+            self.failure_count += 1;
+            self.last_failure_at = self.last_failure_at.max(Some(at));
+        }
+        if client_ip.is_some() {
+            self.last_client_ip = client_ip;
+        }
+        self.add_daily(DailyUsage {
+            date: at.date_naive(),
+            success_count: u64::from(success),
+            failure_count: u64::from(!success),
+        });
+        self.prune_daily();
+    }
+
+    /// Fold a newer batch of uses into these statistics.
+    pub fn merge(&mut self, batch: &AuthEntryUsage) {
+        self.success_count += batch.success_count;
+        self.failure_count += batch.failure_count;
+        self.last_success_at = self.last_success_at.max(batch.last_success_at);
+        self.last_failure_at = self.last_failure_at.max(batch.last_failure_at);
+        if batch.last_client_ip.is_some() {
+            self.last_client_ip = batch.last_client_ip;
+        }
+        for day in &batch.daily {
+            self.add_daily(*day);
+        }
+        self.prune_daily();
+    }
+
+    /// Successful and failed uses over the `days` UTC days ending with the
+    /// day of `now`, e.g. `counts_within(now, 7)` for the last week. Only
+    /// the last [`USAGE_RETENTION_DAYS`] days are kept.
+    pub fn counts_within(&self, now: DateTime<Utc>, days: u64) -> (u64, u64) {
+        let today = now.date_naive();
+        let Some(first_day) = today.checked_sub_days(Days::new(days.saturating_sub(1))) else {
+            return (0, 0);
+        };
+        self.daily
+            .iter()
+            .filter(|day| (first_day..=today).contains(&day.date))
+            .fold((0, 0), |(success, failure), day| {
+                (success + day.success_count, failure + day.failure_count)
+            })
+    }
+
+    fn add_daily(&mut self, uses: DailyUsage) {
+        match self.daily.binary_search_by_key(&uses.date, |day| day.date) {
+            Ok(index) => {
+                self.daily[index].success_count += uses.success_count;
+                self.daily[index].failure_count += uses.failure_count;
+            }
+            Err(index) => self.daily.insert(index, uses),
+        }
+    }
+
+    /// Drop days that fell out of the retention window of the latest use.
+    fn prune_daily(&mut self) {
+        let Some(latest) = self.daily.last().map(|day| day.date) else {
+            return;
+        };
+        if let Some(first_kept) = latest.checked_sub_days(Days::new(USAGE_RETENTION_DAYS - 1)) {
+            self.daily.retain(|day| day.date >= first_kept);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", rename = "basic")]
 pub struct BasicAuthEntry {
     #[serde(flatten)]
-    pub cred: Argon2BasicAuthCred,
+    pub cred: MultiFormatBasicAuthCred,
     #[serde(flatten)]
     pub meta: AuthEntryMeta,
 }
@@ -44,6 +231,96 @@ pub struct TokenAuthEntry {
     pub cred: Sha256TokenAuthCred,
     #[serde(flatten)]
     pub meta: AuthEntryMeta,
+    /// Public, non-secret prefix (`sdt_<entry-id>`) of a structured token.
+    /// `None` for entries holding a bare legacy token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_prefix: Option<String>,
+    #[serde(flatten)]
+    pub constraints: TokenEntryConstraints,
+}
+
+/// Optional start of validity and scopes of a token entry. Its end of
+/// validity is the entry's [`Availability::expires_at`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TokenEntryConstraints {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    /// Scopes granted to the token. Forward-auth checks requesting scopes
+    /// only accept tokens that carry all of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+}
+
+/// Why a token entry's constraints rejected a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenConstraintViolation {
+    NotYetValid,
+    Expired,
+    Disabled,
+    MissingScopes,
+}
+
+impl TokenConstraintViolation {
+    pub fn as_reason(self) -> &'static str {
+        match self {
+            Self::NotYetValid => "token_not_yet_valid",
+            Self::Expired => "token_expired",
+            Self::Disabled => "token_disabled",
+            Self::MissingScopes => "token_scope_missing",
+        }
+    }
+}
+
+impl From<Unavailable> for TokenConstraintViolation {
+    fn from(value: Unavailable) -> Self {
+        match value {
+            Unavailable::Disabled => Self::Disabled,
+            Unavailable::Expired => Self::Expired,
+        }
+    }
+}
+
+impl TokenEntryConstraints {
+    /// Validate the constraints of a token entry expiring at `expires_at`.
+    pub fn validate(&self, expires_at: Option<DateTime<Utc>>) -> CredsManageResult<()> {
+        if let (Some(not_before), Some(expires_at)) = (self.not_before, expires_at)
+            && not_before >= expires_at
+        {
+            return Err(CredsManageError::InvalidEntry {
+                message: "Token not_before must be earlier than expires_at".to_string(),
+            });
+        }
+        if self
+            .scopes
+            .iter()
+            .any(|scope| scope.is_empty() || scope.contains(char::is_whitespace))
+        {
+            return Err(CredsManageError::InvalidEntry {
+                message: "Token scopes must be non-empty and must not contain whitespace"
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the start of validity at `now` and that every required scope is
+    /// granted. Expiry is checked with the entry's [`Availability`].
+    pub fn check(
+        &self,
+        now: DateTime<Utc>,
+        required_scopes: &[String],
+    ) -> Result<(), TokenConstraintViolation> {
+        if self.not_before.is_some_and(|not_before| now < not_before) {
+            return Err(TokenConstraintViolation::NotYetValid);
+        }
+        if !required_scopes
+            .iter()
+            .all(|required| self.scopes.contains(required))
+        {
+            return Err(TokenConstraintViolation::MissingScopes);
+        }
+        Ok(())
+    }
 }
 
 impl BasicAuthCred for BasicAuthEntry {
@@ -58,6 +335,10 @@ impl BasicAuthCred for BasicAuthEntry {
     fn verify_password(&self, password: &str) -> securitydept_creds::CredsResult<bool> {
         self.cred.verify_password(password)
     }
+
+    fn needs_rehash(&self, policy: &securitydept_creds::Argon2HashConfig) -> bool {
+        self.cred.needs_rehash(policy)
+    }
 }
 
 impl TokenAuthCred for TokenAuthEntry {
@@ -81,15 +362,41 @@ impl AuthEntryMeta {
             group_ids,
             created_at: now,
             updated_at: now,
+            revision: 1,
+            availability: Availability::default(),
+            usage: AuthEntryUsage::default(),
         }
     }
+
+    /// Record a change: bump `updated_at` and the revision.
+    pub(crate) fn mark_updated(&mut self) {
+        self.updated_at = Utc::now();
+        self.revision += 1;
+    }
 }
 
 /// A named group that auth entries can belong to.
+///
+/// A group is a subgroup of each of its `parent_ids`: members of a group are
+/// also accepted wherever one of its ancestors is required.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Group {
     pub id: String,
     pub name: String,
+    #[serde(default)]
+    pub parent_ids: Vec<String>,
+    /// Incremented on every change to the group itself or to its member
+    /// entries; see [`AuthEntryMeta::revision`].
+    #[serde(default)]
+    pub revision: u64,
+    #[serde(flatten)]
+    pub availability: Availability,
+    #[serde(default)]
+    pub identity_access: IdentityAccess,
+    /// Rules restricting which requests forward auth grants for the group,
+    /// in order; see [`crate::auth::decide_access`].
+    #[serde(default)]
+    pub access_rules: Vec<AccessRule>,
 }
 
 impl Group {
@@ -97,8 +404,425 @@ impl Group {
         Self {
             id: Uuid::new_v4().to_string(),
             name,
+            parent_ids: Vec::new(),
+            revision: 1,
+            availability: Availability::default(),
+            identity_access: IdentityAccess::default(),
+            access_rules: Vec::new(),
         }
     }
+
+    pub(crate) fn mark_updated(&mut self) {
+        self.revision += 1;
+    }
+}
+
+/// Identities besides its member entries that forward auth accepts for a
+/// group. They satisfy the group itself only, not its ancestors.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IdentityAccess {
+    /// Accept a signed-in OIDC session from the session cookie.
+    #[serde(default)]
+    pub session: bool,
+    /// Accept bearer access tokens verified by the OAuth resource server.
+    #[serde(default)]
+    pub bearer: bool,
+    /// Requirements on the session principal or the access token.
+    #[serde(default)]
+    pub claims: ClaimRequirements,
+}
+
+impl IdentityAccess {
+    pub fn is_enabled(&self) -> bool {
+        self.session || self.bearer
+    }
+
+    pub fn validate(&self) -> CredsManageResult<()> {
+        if !self.claims.any_of.is_empty() && self.claims.groups_claim.is_none() {
+            return Err(CredsManageError::InvalidEntry {
+                message: "claims.any_of requires claims.groups_claim to be set".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Claims a session principal or access token must carry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClaimRequirements {
+    /// Required issuer; a trailing `/` is ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// Claim listing the groups or roles of the subject, either an array
+    /// or a space-separated string. Dots select nested claims, e.g.
+    /// `realm_access.roles`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups_claim: Option<String>,
+    /// At least one of these must be in the groups claim; empty accepts any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub any_of: Vec<String>,
+}
+
+/// Why a session principal or access token does not meet
+/// [`ClaimRequirements`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimViolation {
+    IssuerMismatch,
+    MissingGroup,
+}
+
+impl ClaimViolation {
+    pub fn as_reason(self) -> &'static str {
+        match self {
+            Self::IssuerMismatch => "issuer_mismatch",
+            Self::MissingGroup => "groups_claim_mismatch",
+        }
+    }
+}
+
+impl ClaimRequirements {
+    /// Check an identity issued by `issuer` with `claims`.
+    pub fn check(
+        &self,
+        issuer: Option<&str>,
+        claims: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), ClaimViolation> {
+        if let Some(required) = &self.issuer
+            && issuer.map(|issuer| issuer.trim_end_matches('/'))
+                != Some(required.trim_end_matches('/'))
+        {
+            return Err(ClaimViolation::IssuerMismatch);
+        }
+        if self.any_of.is_empty() {
+            return Ok(());
+        }
+        // Without a claim to read them from, the groups can never be shown.
+        let Some(groups_claim) = &self.groups_claim else {
+            return Err(ClaimViolation::MissingGroup);
+        };
+
+        let mut path = groups_claim.split('.');
+        let mut value = path.next().and_then(|name| claims.get(name));
+        for name in path {
+            value = value.and_then(|value| value.get(name));
+        }
+        let granted: Vec<&str> = match value {
+            Some(serde_json::Value::Array(values)) => values
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect(),
+            Some(serde_json::Value::String(values)) => values.split_whitespace().collect(),
+            _ => Vec::new(),
+        };
+        if self
+            .any_of
+            .iter()
+            .any(|group| granted.contains(&group.as_str()))
+        {
+            Ok(())
+        } else {
+            Err(ClaimViolation::MissingGroup)
+        }
+    }
+}
+
+/// Whether a matching [`AccessRule`] grants or refuses the request.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessRuleEffect {
+    #[default]
+    Allow,
+    Deny,
+}
+
+/// A forward-auth access rule of a group. It matches a request when every
+/// condition it sets holds; a condition left empty matches anything.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AccessRule {
+    /// Reported by forward auth; generated when left empty.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub effect: AccessRuleEffect,
+    /// Host globs without port, e.g. `*.example.com`, compared
+    /// case-insensitively.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+    /// Methods, compared case-insensitively.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    /// Pattern for the path of the request, without its query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathPattern>,
+}
+
+/// What forward auth knows about the original request; `None` where the
+/// proxy did not tell.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccessRequest<'a> {
+    pub method: Option<&'a str>,
+    pub host: Option<&'a str>,
+    /// Path as forwarded by the proxy;
+    /// [`decide_access`](crate::auth::decide_access) normalizes it with
+    /// [`normalize_path`].
+    pub path: Option<&'a str>,
+}
+
+/// Validate each of a group's access rules and that their ids are distinct.
+pub(crate) fn validate_access_rules(access_rules: &[AccessRule]) -> CredsManageResult<()> {
+    let mut ids = HashSet::new();
+    for rule in access_rules {
+        rule.validate()?;
+        if !ids.insert(&rule.id) {
+            return Err(CredsManageError::InvalidEntry {
+                message: format!("duplicate access rule id: {}", rule.id),
+            });
+        }
+    }
+    Ok(())
+}
+
+impl AccessRule {
+    pub fn validate(&self) -> CredsManageResult<()> {
+        let invalid = |message: String| CredsManageError::InvalidEntry { message };
+        if self.id.trim().is_empty() {
+            return Err(invalid("access rule id must not be empty".to_string()));
+        }
+        if self
+            .hosts
+            .iter()
+            .chain(&self.methods)
+            .any(|value| value.trim().is_empty())
+        {
+            return Err(invalid(format!(
+                "access rule {} has an empty host or method",
+                self.id
+            )));
+        }
+        if let Some(PathPattern::Regex(regex)) = &self.path
+            && let Err(error) = &regex.compiled
+        {
+            return Err(invalid(format!(
+                "access rule {} has an invalid path regex: {error}",
+                self.id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the rule applies to `request`, whose path is expected to be
+    /// normalized already. A condition on something the proxy did not report
+    /// holds for deny rules only, so missing request details never widen
+    /// access.
+    pub fn matches(&self, request: &AccessRequest<'_>) -> bool {
+        let unknown = self.effect == AccessRuleEffect::Deny;
+        let host_matches = |host: &str| {
+            let host = match host.rsplit_once(':') {
+                Some((name, port)) if port.bytes().all(|b| b.is_ascii_digit()) => name,
+                _ => host,
+            }
+            .to_ascii_lowercase();
+            self.hosts
+                .iter()
+                .any(|pattern| glob_matches(&pattern.to_ascii_lowercase(), &host))
+        };
+
+        (self.hosts.is_empty() || request.host.map_or(unknown, host_matches))
+            && (self.methods.is_empty()
+                || request.method.map_or(unknown, |method| {
+                    self.methods
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(method))
+                }))
+            && self.path.as_ref().is_none_or(|pattern| {
+                request
+                    .path
+                    .map_or(unknown, |path| pattern.matches(path).unwrap_or(unknown))
+            })
+    }
+}
+
+/// Path pattern of an [`AccessRule`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum PathPattern {
+    /// `*` matches any run of characters, `/` included, and `?` any single
+    /// character, e.g. `/api/*`.
+    Glob(String),
+    /// A regular expression that must match the whole path.
+    Regex(PathRegex),
+}
+
+impl PathPattern {
+    /// Whether `path` matches; `None` when the pattern is a regex that does
+    /// not compile.
+    pub fn matches(&self, path: &str) -> Option<bool> {
+        match self {
+            Self::Glob(pattern) => Some(glob_matches(pattern, path)),
+            Self::Regex(regex) => regex
+                .compiled
+                .as_ref()
+                .ok()
+                .map(|compiled| compiled.is_match(path)),
+        }
+    }
+}
+
+/// A path regex, compiled once when the rule is built or loaded. A pattern
+/// that does not compile, e.g. from a hand-edited data file, is kept as
+/// written: [`AccessRule::validate`] rejects it, and a rule holding it
+/// matches only if it denies.
+#[derive(Debug, Clone)]
+pub struct PathRegex {
+    pattern: String,
+    compiled: Result<regex::Regex, regex::Error>,
+}
+
+impl PathRegex {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let compiled = anchored_regex(&pattern);
+        Self { pattern, compiled }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+
+impl PartialEq for PathRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Eq for PathRegex {}
+
+impl Serialize for PathRegex {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for PathRegex {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Normalize a request path the way upstreams resolve it, so rules see the
+/// resource that is actually served: percent-encoded unreserved characters
+/// are decoded (other escapes keep upper-case hex), runs of `/` collapse and
+/// `.`/`..` segments are resolved, never above the root.
+pub fn normalize_path(path: &str) -> String {
+    let decoded = decode_unreserved(path);
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    let trailing_slash = matches!(decoded.rsplit('/').next(), Some("" | "." | ".."));
+    if trailing_slash && !segments.is_empty() {
+        normalized.push('/');
+    }
+    normalized
+}
+
+fn decode_unreserved(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            Some(byte) => {
+                decoded.extend_from_slice(format!("%{byte:02X}").as_bytes());
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).expect("only ASCII escapes are rewritten")
+}
+
+fn anchored_regex(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    regex::Regex::new(&format!("^(?:{pattern})$"))
+}
+
+/// Match `text` against a glob where `*` matches any run of characters and
+/// `?` any single one.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at.
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Ids of `root_id` and of every group nested below it, skipping subgroups
+/// that are unavailable at `now` together with everything below them.
+pub fn member_group_ids(groups: &[Group], root_id: &str, now: DateTime<Utc>) -> Vec<String> {
+    let mut resolved = vec![root_id.to_string()];
+    let mut index = 0;
+    while let Some(parent_id) = resolved.get(index).cloned() {
+        for group in groups {
+            if group.parent_ids.contains(&parent_id)
+                && group.availability.check(now).is_ok()
+                && !resolved.contains(&group.id)
+            {
+                resolved.push(group.id.clone());
+            }
+        }
+        index += 1;
+    }
+    resolved
+}
+
+/// Entries and groups found expired by
+/// [`CredsManageStore::sweep_expired`](crate::store::CredsManageStore::sweep_expired).
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpirySweep {
+    pub swept_at: DateTime<Utc>,
+    pub expired_entry_ids: Vec<String>,
+    pub expired_group_ids: Vec<String>,
 }
 
 pub const DATA_FILE_VERSION: u32 = 2;
@@ -107,6 +831,9 @@ pub const DATA_FILE_VERSION: u32 = 2;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataFile {
     pub version: u32,
+    /// Incremented on every write of the data file.
+    #[serde(default)]
+    pub revision: u64,
     pub groups: Vec<Group>,
     pub basic_creds: Vec<BasicAuthEntry>,
     pub token_creds: Vec<TokenAuthEntry>,
@@ -116,6 +843,7 @@ impl Default for DataFile {
     fn default() -> Self {
         Self {
             version: DATA_FILE_VERSION,
+            revision: 0,
             groups: Vec::new(),
             basic_creds: Vec::new(),
             token_creds: Vec::new(),
@@ -131,6 +859,11 @@ pub struct AuthEntry {
     pub kind: AuthEntryKind,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_prefix: Option<String>,
+    /// Token start of validity and scopes; empty for basic entries.
+    #[serde(flatten, default)]
+    pub constraints: TokenEntryConstraints,
 }
 
 impl From<&BasicAuthEntry> for AuthEntry {
@@ -139,6 +872,8 @@ impl From<&BasicAuthEntry> for AuthEntry {
             meta: value.meta.clone(),
             kind: AuthEntryKind::Basic,
             username: Some(value.cred.username.clone()),
+            token_prefix: None,
+            constraints: TokenEntryConstraints::default(),
         }
     }
 }
@@ -149,6 +884,8 @@ impl From<&TokenAuthEntry> for AuthEntry {
             meta: value.meta.clone(),
             kind: AuthEntryKind::Token,
             username: None,
+            token_prefix: value.token_prefix.clone(),
+            constraints: value.constraints.clone(),
         }
     }
 }
@@ -177,6 +914,10 @@ pub struct CreateTokenEntryRequest {
     pub name: String,
     #[serde(default)]
     pub group_ids: Vec<String>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub constraints: TokenEntryConstraints,
 }
 
 /// Response after creating a token auth entry (includes the plaintext token
@@ -205,6 +946,8 @@ pub struct UpdateEntryRequest {
 #[derive(Debug, Deserialize)]
 pub struct CreateGroupRequest {
     pub name: String,
+    #[serde(default)]
+    pub parent_ids: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub entry_ids: Option<Vec<String>>,
 }
@@ -214,6 +957,8 @@ pub struct CreateGroupRequest {
 pub struct UpdateGroupRequest {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub entry_ids: Option<Vec<String>>,
 }
 
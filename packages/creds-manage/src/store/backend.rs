@@ -0,0 +1,321 @@
+use std::{collections::HashMap, future::Future, pin::Pin};
+
+use crate::{
+    archive::ImportReport,
+    changes::{ChangeNotifier, entry_states, group_members, group_states},
+    error::{CredsManageError, CredsManageResult},
+    models::{
+        AccessRule, AuthEntry, AuthEntryMeta, AuthEntryUsage, Availability, BasicAuthEntry,
+        DataFile, Group, IdentityAccess, TokenAuthEntry,
+    },
+};
+
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = CredsManageResult<T>> + Send + 'a>>;
+
+/// Rewrites the complete store content for
+/// [`CredsManageBackend::apply_import`].
+pub type ImportPlan = Box<dyn FnOnce(&mut DataFile) -> CredsManageResult<ImportReport> + Send>;
+
+/// Changes applied to an entry by [`CredsManageBackend::update_entry`].
+///
+/// `username` and `password_hash` only apply to basic entries and are
+/// ignored for token entries.
+#[derive(Debug, Clone, Default)]
+pub struct EntryUpdate {
+    pub name: Option<String>,
+    pub username: Option<String>,
+    pub password_hash: Option<String>,
+    pub group_ids: Option<Vec<String>>,
+    pub availability: Option<Availability>,
+    /// Only apply the update while the entry is at this revision.
+    pub expected_revision: Option<u64>,
+}
+
+/// Persistence for auth entries and groups behind
+/// [`CredsManageStore`](super::CredsManageStore).
+///
+/// Backends only store data: credential material is validated and hashed by
+/// the store before it reaches them. Each mutation must be atomic and must
+/// enforce unique entry and group names, that referenced groups and entries
+/// exist, and that removed groups disappear from every entry's `group_ids`.
+pub trait CredsManageBackend: Send + Sync {
+    /// All entries, oldest first.
+    fn list_entries(&self) -> BackendFuture<'_, Vec<AuthEntry>>;
+
+    fn get_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<AuthEntry>>;
+
+    fn get_basic_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<BasicAuthEntry>>;
+
+    fn get_token_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<TokenAuthEntry>>;
+
+    /// Find the token entry whose stored SHA-256 hash is `token_hash`.
+    fn find_token_entry_by_hash<'a>(
+        &'a self,
+        token_hash: &'a str,
+    ) -> BackendFuture<'a, Option<TokenAuthEntry>>;
+
+    /// Basic entries that are direct members of any of `group_ids`, each
+    /// returned once, oldest first.
+    fn basic_entries_by_group_ids<'a>(
+        &'a self,
+        group_ids: &'a [String],
+    ) -> BackendFuture<'a, Vec<BasicAuthEntry>>;
+
+    /// Token entries that are direct members of any of `group_ids`, each
+    /// returned once, oldest first.
+    fn token_entries_by_group_ids<'a>(
+        &'a self,
+        group_ids: &'a [String],
+    ) -> BackendFuture<'a, Vec<TokenAuthEntry>>;
+
+    fn insert_basic_entry(&self, entry: BasicAuthEntry) -> BackendFuture<'_, AuthEntry>;
+
+    fn insert_token_entry(&self, entry: TokenAuthEntry) -> BackendFuture<'_, AuthEntry>;
+
+    fn update_entry<'a>(&'a self, id: &'a str, update: EntryUpdate)
+    -> BackendFuture<'a, AuthEntry>;
+
+    /// Swap the password hash of a basic entry if it still equals
+    /// `current_hash`. Returns whether the hash was replaced.
+    fn replace_basic_password_hash<'a>(
+        &'a self,
+        id: &'a str,
+        current_hash: &'a str,
+        new_hash: String,
+    ) -> BackendFuture<'a, bool>;
+
+    /// Delete an entry, only while it is at `expected_revision` when set.
+    fn delete_entry<'a>(
+        &'a self,
+        id: &'a str,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, ()>;
+
+    /// Merge batches of uses, keyed by entry id, into the entries' usage
+    /// statistics. Entries deleted in the meantime are skipped.
+    fn record_usage(&self, usage: HashMap<String, AuthEntryUsage>) -> BackendFuture<'_, ()>;
+
+    /// All groups in creation order.
+    fn list_groups(&self) -> BackendFuture<'_, Vec<Group>>;
+
+    fn get_group<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<Group>>;
+
+    fn find_group_by_name<'a>(&'a self, name: &'a str) -> BackendFuture<'a, Option<Group>>;
+
+    /// Insert `group` and add it to the entries in `entry_ids`. Its
+    /// `parent_ids` must pass [`ensure_group_parents_valid`].
+    fn insert_group(&self, group: Group, entry_ids: Vec<String>) -> BackendFuture<'_, Group>;
+
+    /// Rename a group and, when `entry_ids` is set, make exactly those
+    /// entries its members. When `parent_ids` is set it replaces the group's
+    /// parents after passing [`ensure_group_parents_valid`]. Nothing changes
+    /// unless the group is at `expected_revision`, when set.
+    fn update_group<'a>(
+        &'a self,
+        id: &'a str,
+        name: String,
+        entry_ids: Option<Vec<String>>,
+        parent_ids: Option<Vec<String>>,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group>;
+
+    /// Nothing changes unless the group is at `expected_revision`, when set.
+    fn update_group_availability<'a>(
+        &'a self,
+        id: &'a str,
+        availability: Availability,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group>;
+
+    /// Nothing changes unless the group is at `expected_revision`, when set.
+    fn update_group_identity_access<'a>(
+        &'a self,
+        id: &'a str,
+        identity_access: IdentityAccess,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group>;
+
+    /// Nothing changes unless the group is at `expected_revision`, when set.
+    fn update_group_access_rules<'a>(
+        &'a self,
+        id: &'a str,
+        access_rules: Vec<AccessRule>,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group>;
+
+    /// Delete a group, removing it from its members and from the
+    /// `parent_ids` of its subgroups, only while it is at
+    /// `expected_revision` when set.
+    fn delete_group<'a>(
+        &'a self,
+        id: &'a str,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, ()>;
+
+    /// All groups and entries.
+    fn export_data(&self) -> BackendFuture<'_, DataFile>;
+
+    /// Run `plan` on the current groups and entries and store its result in
+    /// place of them, atomically with respect to other mutations. Nothing
+    /// is written when `plan` fails.
+    fn apply_import(&self, plan: ImportPlan) -> BackendFuture<'_, ImportReport>;
+
+    /// Publish changes that other writers, such as another process
+    /// replacing the data file, make to the storage. Backends that only
+    /// change through this process ignore it.
+    fn forward_external_changes(&self, _notifier: ChangeNotifier) {}
+}
+
+/// Check that every group in `parent_ids` exists in `groups` and that
+/// nesting `group_id` below them does not make it its own ancestor.
+pub(crate) fn ensure_group_parents_valid(
+    groups: &[Group],
+    group_id: &str,
+    parent_ids: &[String],
+) -> CredsManageResult<()> {
+    for parent_id in parent_ids {
+        if !groups.iter().any(|g| &g.id == parent_id) {
+            return Err(CredsManageError::GroupNotFound {
+                id: parent_id.clone(),
+            });
+        }
+    }
+
+    let mut pending = parent_ids.to_vec();
+    let mut visited: Vec<String> = Vec::new();
+    while let Some(ancestor_id) = pending.pop() {
+        if ancestor_id == group_id {
+            return Err(CredsManageError::GroupCycle {
+                id: group_id.to_string(),
+            });
+        }
+        if visited.contains(&ancestor_id) {
+            continue;
+        }
+        if let Some(ancestor) = groups.iter().find(|g| g.id == ancestor_id) {
+            pending.extend(ancestor.parent_ids.iter().cloned());
+        }
+        visited.push(ancestor_id);
+    }
+    Ok(())
+}
+
+/// Fail with [`CredsManageError::RevisionMismatch`] unless `current` is the
+/// `expected` revision of `id`.
+pub(crate) fn ensure_revision(
+    id: &str,
+    current: u64,
+    expected: Option<u64>,
+) -> CredsManageResult<()> {
+    match expected {
+        Some(expected) if expected != current => Err(CredsManageError::RevisionMismatch {
+            id: id.to_string(),
+            expected,
+            current,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Keep the revisions of the groups and entries in `data` consistent with
+/// `previous`, an earlier state of the same store, after `data` was changed
+/// without going through the backend, e.g. by an import or a hand edit of
+/// the data file: unchanged items keep their revision, and changed items
+/// move past the revision they had in `previous`. A group whose member
+/// entries changed counts as changed.
+pub(crate) fn carry_revisions(previous: &DataFile, data: &mut DataFile) {
+    let (previous_entries, entries) = (entry_states(previous), entry_states(data));
+    let previous_entry_revisions: HashMap<&str, u64> = entry_metas(previous)
+        .map(|meta| (meta.id.as_str(), meta.revision))
+        .collect();
+    let metas = data
+        .basic_creds
+        .iter_mut()
+        .map(|entry| &mut entry.meta)
+        .chain(data.token_creds.iter_mut().map(|entry| &mut entry.meta));
+    for meta in metas {
+        if let Some(&previous_revision) = previous_entry_revisions.get(meta.id.as_str()) {
+            let unchanged = previous_entries.get(&meta.id) == entries.get(&meta.id);
+            meta.revision = carried_revision(previous_revision, meta.revision, unchanged);
+        }
+    }
+
+    let (previous_groups, groups) = (group_states(previous), group_states(data));
+    let previous_group_revisions: HashMap<&str, u64> = previous
+        .groups
+        .iter()
+        .map(|group| (group.id.as_str(), group.revision))
+        .collect();
+    let (previous_members, members) = (group_members(previous), group_members(data));
+    for group in &mut data.groups {
+        if let Some(&previous_revision) = previous_group_revisions.get(group.id.as_str()) {
+            let unchanged = previous_groups.get(&group.id) == groups.get(&group.id)
+                && previous_members.get(&group.id) == members.get(&group.id);
+            group.revision = carried_revision(previous_revision, group.revision, unchanged);
+        }
+    }
+}
+
+fn carried_revision(previous_revision: u64, revision: u64, unchanged: bool) -> u64 {
+    if unchanged {
+        previous_revision
+    } else {
+        revision.max(previous_revision + 1)
+    }
+}
+
+fn entry_metas(data: &DataFile) -> impl Iterator<Item = &AuthEntryMeta> {
+    data.basic_creds
+        .iter()
+        .map(|entry| &entry.meta)
+        .chain(data.token_creds.iter().map(|entry| &entry.meta))
+}
+
+/// Replace the group list of `meta` with `group_ids`, if given, and return
+/// the ids of the groups it joined or left. Their revisions must be bumped
+/// along with the entry's, so an `If-Match` on a group notices a membership
+/// change made through one of its entries.
+pub(crate) fn replace_group_ids(
+    meta: &mut AuthEntryMeta,
+    group_ids: Option<Vec<String>>,
+) -> Vec<String> {
+    let Some(group_ids) = group_ids else {
+        return Vec::new();
+    };
+    let mut changed: Vec<String> = meta
+        .group_ids
+        .iter()
+        .filter(|group_id| !group_ids.contains(group_id))
+        .chain(
+            group_ids
+                .iter()
+                .filter(|group_id| !meta.group_ids.contains(group_id)),
+        )
+        .cloned()
+        .collect();
+    changed.sort();
+    changed.dedup();
+    meta.group_ids = group_ids;
+    changed
+}
+
+/// Add `meta` to or remove it from `target_group_id`, bumping `updated_at`
+/// when its group list changed. The caller bumps the target group.
+pub(crate) fn update_group_membership(
+    meta: &mut AuthEntryMeta,
+    target_group_id: &str,
+    target_member: bool,
+) {
+    let before = meta.group_ids.clone();
+    meta.group_ids
+        .retain(|group_id| group_id != target_group_id);
+    if target_member {
+        meta.group_ids.push(target_group_id.to_string());
+        meta.group_ids.sort();
+        meta.group_ids.dedup();
+    }
+
+    if meta.group_ids != before {
+        meta.mark_updated();
+    }
+}
@@ -0,0 +1,1162 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use atomic_write_file::AtomicWriteFile;
+use fs2::FileExt;
+use notify::RecursiveMode;
+use notify_debouncer_full::{DebounceEventResult, Debouncer, RecommendedCache, new_debouncer};
+use securitydept_creds::StaticTokenAuthCred;
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+use tokio::{sync::Mutex, task::JoinHandle};
+
+use super::backend::{
+    BackendFuture, CredsManageBackend, EntryUpdate, ImportPlan, carry_revisions,
+    ensure_group_parents_valid, ensure_revision, replace_group_ids, update_group_membership,
+};
+#[cfg(feature = "encryption")]
+use crate::encryption::{DataKeyring, open_data_file};
+use crate::{
+    archive::ImportReport,
+    changes::{ChangeNotifier, data_file_changes},
+    config::CredsManageConfig,
+    error::{self, CredsManageResult},
+    models::{
+        AccessRule, AuthEntry, AuthEntryUsage, Availability, BasicAuthEntry, DataFile, Group,
+        IdentityAccess, TokenAuthEntry,
+    },
+};
+
+fn content_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+fn lock_file_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| format!("{}.lock", name.to_string_lossy()))
+        .unwrap_or_else(|| "store.lock".to_string());
+
+    match path.parent() {
+        Some(parent) => parent.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// Converts the data file between its in-memory form and the bytes on disk,
+/// sealing them when a data encryption key is configured.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DataFileCodec {
+    #[cfg(feature = "encryption")]
+    keyring: Option<Arc<DataKeyring>>,
+    /// Accept a plaintext data file despite the keyring; only set while
+    /// sealing an existing file for the first time.
+    #[cfg(feature = "encryption")]
+    allow_plaintext: bool,
+}
+
+impl DataFileCodec {
+    /// Codec for the data file of `config`. Key commands run synchronously.
+    #[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+    pub(crate) fn from_config(config: &CredsManageConfig) -> CredsManageResult<Self> {
+        Ok(Self {
+            #[cfg(feature = "encryption")]
+            keyring: config
+                .data_encryption
+                .as_ref()
+                .map(DataKeyring::load)
+                .transpose()?
+                .map(Arc::new),
+            #[cfg(feature = "encryption")]
+            allow_plaintext: false,
+        })
+    }
+
+    /// [`Self::from_config`] without blocking the async runtime.
+    pub(crate) async fn load(config: &CredsManageConfig) -> CredsManageResult<Self> {
+        let config = config.clone();
+        tokio::task::spawn_blocking(move || Self::from_config(&config))
+            .await
+            .expect("data key load task panicked")
+    }
+
+    /// The plaintext of data file bytes, which may or may not be sealed.
+    pub(crate) fn open<'a>(&self, raw: &'a [u8]) -> CredsManageResult<Cow<'a, [u8]>> {
+        #[cfg(feature = "encryption")]
+        {
+            open_data_file(raw, self.keyring.as_deref(), self.allow_plaintext)
+        }
+        #[cfg(not(feature = "encryption"))]
+        {
+            Ok(Cow::Borrowed(raw))
+        }
+    }
+
+    /// The bytes to write for a serialized data file.
+    pub(crate) fn seal(&self, plaintext: Vec<u8>) -> CredsManageResult<Vec<u8>> {
+        #[cfg(feature = "encryption")]
+        if let Some(keyring) = &self.keyring {
+            return keyring.seal(&plaintext);
+        }
+        Ok(plaintext)
+    }
+
+    fn decode(&self, raw: &[u8]) -> CredsManageResult<DataFile> {
+        parse_data_file_bytes(&self.open(raw)?)
+    }
+
+    fn encode(&self, data: &DataFile) -> CredsManageResult<Vec<u8>> {
+        self.seal(serde_json::to_vec_pretty(data).context(error::DataSerializeSnafu)?)
+    }
+}
+
+/// File-backed storage for auth entries and groups.
+///
+/// The backend keeps an in-memory snapshot and synchronizes it with disk:
+/// - Reads use `ArcSwap` for lock-free access.
+/// - Writes are serialized via an async mutex and use atomic file replacement.
+/// - External file changes are ingested via debounced FS events.
+/// - If FS events are unavailable, we fall back to 1s polling.
+/// - Self-writes are detected via content hash to avoid recursive reloads.
+/// - With a data encryption key the file is sealed at rest; see
+///   [`crate::encryption`].
+pub struct FileBackend {
+    path: PathBuf,
+    codec: DataFileCodec,
+    data: Arc<ArcSwap<DataFile>>,
+    /// Guards all write operations so only one mutate runs at a time. The
+    /// watcher holds it while reloading, so it never sees a written file
+    /// before the hash marker and the cache are updated.
+    io_lock: Arc<Mutex<()>>,
+    /// After a successful save(), we record the hash of what we just wrote.
+    /// The watcher checks incoming file content against this to skip one
+    /// self-triggered event and then clears the marker.
+    last_committed_hash: Arc<Mutex<Option<[u8; 32]>>>,
+    /// Receives the changes found when an external file change is ingested.
+    external_changes: Arc<OnceLock<ChangeNotifier>>,
+    sync_task: JoinHandle<()>,
+}
+
+impl FileBackend {
+    /// Load (or create) the data file and start watching it.
+    pub async fn load(path: impl AsRef<Path>) -> CredsManageResult<Self> {
+        Self::load_with_codec(path, DataFileCodec::default()).await
+    }
+
+    /// Like [`Self::load`], keeping the data file sealed with `keyring`.
+    #[cfg(feature = "encryption")]
+    pub async fn load_encrypted(
+        path: impl AsRef<Path>,
+        keyring: DataKeyring,
+    ) -> CredsManageResult<Self> {
+        let codec = DataFileCodec {
+            keyring: Some(Arc::new(keyring)),
+            allow_plaintext: false,
+        };
+        Self::load_with_codec(path, codec).await
+    }
+
+    /// Re-seal the data file of `config` with the current data encryption
+    /// key and return that key's id.
+    ///
+    /// Completes a key rotation: after the old key is moved to
+    /// `previous_keys` and the file is re-sealed, the old key can be
+    /// dropped from the configuration. This is also the only way a plaintext
+    /// data file gets sealed for the first time; loading it with a key
+    /// configured fails with
+    /// [`error::CredsManageError::DataNotEncrypted`].
+    #[cfg(feature = "encryption")]
+    pub async fn reseal(config: &CredsManageConfig) -> CredsManageResult<String> {
+        let codec = DataFileCodec {
+            allow_plaintext: true,
+            ..DataFileCodec::load(config).await?
+        };
+        let key_id = codec
+            .keyring
+            .as_ref()
+            .map(|keyring| keyring.current_key_id().to_string())
+            .ok_or_else(|| error::CredsManageError::InvalidConfig {
+                message: "data_encryption is not configured".to_string(),
+            })?;
+
+        let backend = Self::load_with_codec(&config.data_path, codec).await?;
+        backend.mutate(|_| Ok(())).await?;
+        Ok(key_id)
+    }
+
+    pub(crate) async fn load_with_codec(
+        path: impl AsRef<Path>,
+        codec: DataFileCodec,
+    ) -> CredsManageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let initial_data = read_data_file_with_lock(&path, &codec).await?;
+
+        let data = Arc::new(ArcSwap::from_pointee(initial_data));
+        let io_lock = Arc::new(Mutex::new(()));
+        let last_committed_hash: Arc<Mutex<Option<[u8; 32]>>> = Arc::new(Mutex::new(None));
+        let external_changes = Arc::new(OnceLock::new());
+
+        let sync_task = Self::spawn_sync_task(
+            path.clone(),
+            codec.clone(),
+            Arc::clone(&data),
+            Arc::clone(&io_lock),
+            Arc::clone(&last_committed_hash),
+            Arc::clone(&external_changes),
+        );
+
+        Ok(Self {
+            path,
+            codec,
+            data,
+            io_lock,
+            last_committed_hash,
+            external_changes,
+            sync_task,
+        })
+    }
+
+    fn spawn_sync_task(
+        path: PathBuf,
+        codec: DataFileCodec,
+        data: Arc<ArcSwap<DataFile>>,
+        io_lock: Arc<Mutex<()>>,
+        last_committed_hash: Arc<Mutex<Option<[u8; 32]>>>,
+        external_changes: Arc<OnceLock<ChangeNotifier>>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            match run_debounced_watch(
+                path.clone(),
+                codec.clone(),
+                Arc::clone(&data),
+                Arc::clone(&io_lock),
+                Arc::clone(&last_committed_hash),
+                Arc::clone(&external_changes),
+            )
+            .await
+            {
+                Ok(()) => {}
+                Err(err) => {
+                    tracing::warn!(
+                        path = %path.display(),
+                        error = %err,
+                        "filesystem watch unavailable; fallback to 1s polling"
+                    );
+                    run_poll_loop(
+                        path,
+                        codec,
+                        data,
+                        io_lock,
+                        last_committed_hash,
+                        external_changes,
+                    )
+                    .await;
+                }
+            }
+        })
+    }
+
+    /// The current in-memory snapshot of the data file.
+    pub fn snapshot(&self) -> Arc<DataFile> {
+        self.data.load_full()
+    }
+
+    /// Apply `op` to the data file under the write lock and publish the
+    /// resulting snapshot.
+    async fn mutate<T, F>(&self, op: F) -> CredsManageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut DataFile) -> CredsManageResult<T> + Send + 'static,
+    {
+        let _io_guard = self.io_lock.lock().await;
+        let (result, snapshot) = atomic_mutate_data_file(
+            &self.path,
+            &self.codec,
+            &self.last_committed_hash,
+            self.data.load_full(),
+            op,
+        )
+        .await?;
+        self.data.store(Arc::new(snapshot));
+        Ok(result)
+    }
+}
+
+impl CredsManageBackend for FileBackend {
+    fn list_entries(&self) -> BackendFuture<'_, Vec<AuthEntry>> {
+        Box::pin(async move { Ok(collect_all_entries(&self.data.load())) })
+    }
+
+    fn get_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<AuthEntry>> {
+        Box::pin(async move { Ok(find_entry_by_id(&self.data.load(), id)) })
+    }
+
+    fn get_basic_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<BasicAuthEntry>> {
+        Box::pin(async move {
+            Ok(self
+                .data
+                .load()
+                .basic_creds
+                .iter()
+                .find(|e| e.meta.id == id)
+                .cloned())
+        })
+    }
+
+    fn get_token_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<TokenAuthEntry>> {
+        Box::pin(async move {
+            Ok(self
+                .data
+                .load()
+                .token_creds
+                .iter()
+                .find(|e| e.meta.id == id)
+                .cloned())
+        })
+    }
+
+    fn find_token_entry_by_hash<'a>(
+        &'a self,
+        token_hash: &'a str,
+    ) -> BackendFuture<'a, Option<TokenAuthEntry>> {
+        Box::pin(async move {
+            Ok(self
+                .data
+                .load()
+                .token_creds
+                .iter()
+                .find(|e| e.token_hash() == token_hash)
+                .cloned())
+        })
+    }
+
+    fn basic_entries_by_group_ids<'a>(
+        &'a self,
+        group_ids: &'a [String],
+    ) -> BackendFuture<'a, Vec<BasicAuthEntry>> {
+        Box::pin(async move {
+            Ok(self
+                .data
+                .load()
+                .basic_creds
+                .iter()
+                .filter(|e| e.meta.group_ids.iter().any(|g| group_ids.contains(g)))
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn token_entries_by_group_ids<'a>(
+        &'a self,
+        group_ids: &'a [String],
+    ) -> BackendFuture<'a, Vec<TokenAuthEntry>> {
+        Box::pin(async move {
+            Ok(self
+                .data
+                .load()
+                .token_creds
+                .iter()
+                .filter(|e| e.meta.group_ids.iter().any(|g| group_ids.contains(g)))
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn insert_basic_entry(&self, entry: BasicAuthEntry) -> BackendFuture<'_, AuthEntry> {
+        Box::pin(self.mutate(move |data| {
+            ensure_entry_name_is_unique(data, &entry.meta.name, None)?;
+            ensure_groups_exist(data, &entry.meta.group_ids)?;
+
+            let created = AuthEntry::from(&entry);
+            mark_groups_updated(data, &entry.meta.group_ids);
+            data.basic_creds.push(entry);
+            Ok(created)
+        }))
+    }
+
+    fn insert_token_entry(&self, entry: TokenAuthEntry) -> BackendFuture<'_, AuthEntry> {
+        Box::pin(self.mutate(move |data| {
+            ensure_entry_name_is_unique(data, &entry.meta.name, None)?;
+            ensure_groups_exist(data, &entry.meta.group_ids)?;
+
+            let created = AuthEntry::from(&entry);
+            mark_groups_updated(data, &entry.meta.group_ids);
+            data.token_creds.push(entry);
+            Ok(created)
+        }))
+    }
+
+    fn record_usage(&self, usage: HashMap<String, AuthEntryUsage>) -> BackendFuture<'_, ()> {
+        Box::pin(self.mutate(move |data| {
+            let metas = data
+                .basic_creds
+                .iter_mut()
+                .map(|e| &mut e.meta)
+                .chain(data.token_creds.iter_mut().map(|e| &mut e.meta));
+            for meta in metas {
+                if let Some(batch) = usage.get(&meta.id) {
+                    meta.usage.merge(batch);
+                }
+            }
+            Ok(())
+        }))
+    }
+
+    fn update_entry<'a>(
+        &'a self,
+        id: &'a str,
+        update: EntryUpdate,
+    ) -> BackendFuture<'a, AuthEntry> {
+        let id = id.to_string();
+        Box::pin(self.mutate(move |data| {
+            let EntryUpdate {
+                name,
+                username,
+                password_hash,
+                group_ids,
+                availability,
+                expected_revision,
+            } = update;
+            if let Some(entry) = find_entry_by_id(data, &id) {
+                ensure_revision(&id, entry.meta.revision, expected_revision)?;
+            }
+            if let Some(ref new_name) = name {
+                ensure_entry_name_is_unique(data, new_name, Some(&id))?;
+            }
+            if let Some(ref gids) = group_ids {
+                ensure_groups_exist(data, gids)?;
+            }
+
+            let (updated, changed_group_ids) =
+                if let Some(entry) = data.basic_creds.iter_mut().find(|e| e.meta.id == id) {
+                    if let Some(new_name) = name {
+                        entry.meta.name = new_name;
+                    }
+                    if let Some(new_username) = username {
+                        entry.cred.username = new_username;
+                    }
+                    if let Some(new_password_hash) = password_hash {
+                        entry.cred.password_hash = new_password_hash;
+                    }
+                    let changed_group_ids = replace_group_ids(&mut entry.meta, group_ids);
+                    if let Some(availability) = availability {
+                        entry.meta.availability = availability;
+                    }
+                    entry.meta.mark_updated();
+                    (AuthEntry::from(&*entry), changed_group_ids)
+                } else if let Some(entry) = data.token_creds.iter_mut().find(|e| e.meta.id == id) {
+                    if let Some(new_name) = name {
+                        entry.meta.name = new_name;
+                    }
+                    let changed_group_ids = replace_group_ids(&mut entry.meta, group_ids);
+                    if let Some(availability) = availability {
+                        entry.meta.availability = availability;
+                    }
+                    entry.meta.mark_updated();
+                    (AuthEntry::from(&*entry), changed_group_ids)
+                } else {
+                    return Err(error::CredsManageError::EntryNotFound { id });
+                };
+
+            mark_groups_updated(data, &changed_group_ids);
+            Ok(updated)
+        }))
+    }
+
+    fn replace_basic_password_hash<'a>(
+        &'a self,
+        id: &'a str,
+        current_hash: &'a str,
+        new_hash: String,
+    ) -> BackendFuture<'a, bool> {
+        let id = id.to_string();
+        let current_hash = current_hash.to_string();
+        Box::pin(self.mutate(move |data| {
+            let entry = data
+                .basic_creds
+                .iter_mut()
+                .find(|e| e.meta.id == id)
+                .ok_or(error::CredsManageError::EntryNotFound { id })?;
+            if entry.cred.password_hash != current_hash {
+                return Ok(false);
+            }
+            entry.cred.password_hash = new_hash;
+            entry.meta.mark_updated();
+            Ok(true)
+        }))
+    }
+
+    fn delete_entry<'a>(
+        &'a self,
+        id: &'a str,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, ()> {
+        let id = id.to_string();
+        Box::pin(self.mutate(move |data| {
+            let group_ids = match find_entry_by_id(data, &id) {
+                Some(entry) => {
+                    ensure_revision(&id, entry.meta.revision, expected_revision)?;
+                    entry.meta.group_ids
+                }
+                None => Vec::new(),
+            };
+            let basic_len_before = data.basic_creds.len();
+            data.basic_creds.retain(|e| e.meta.id != id);
+
+            let token_len_before = data.token_creds.len();
+            data.token_creds.retain(|e| e.meta.id != id);
+
+            if data.basic_creds.len() == basic_len_before
+                && data.token_creds.len() == token_len_before
+            {
+                return Err(error::CredsManageError::EntryNotFound { id: id.clone() });
+            }
+
+            mark_groups_updated(data, &group_ids);
+            Ok(())
+        }))
+    }
+
+    fn list_groups(&self) -> BackendFuture<'_, Vec<Group>> {
+        Box::pin(async move { Ok(self.data.load().groups.clone()) })
+    }
+
+    fn get_group<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<Group>> {
+        Box::pin(async move { Ok(self.data.load().groups.iter().find(|g| g.id == id).cloned()) })
+    }
+
+    fn find_group_by_name<'a>(&'a self, name: &'a str) -> BackendFuture<'a, Option<Group>> {
+        Box::pin(async move {
+            Ok(self
+                .data
+                .load()
+                .groups
+                .iter()
+                .find(|g| g.name == name)
+                .cloned())
+        })
+    }
+
+    fn insert_group(&self, group: Group, entry_ids: Vec<String>) -> BackendFuture<'_, Group> {
+        Box::pin(self.mutate(move |data| {
+            if data.groups.iter().any(|g| g.name == group.name) {
+                return Err(error::CredsManageError::DuplicateGroupName {
+                    name: group.name.clone(),
+                });
+            }
+            ensure_group_parents_valid(&data.groups, &group.id, &group.parent_ids)?;
+
+            for entry_id in &entry_ids {
+                if !entry_exists(data, entry_id) {
+                    return Err(error::CredsManageError::EntryNotFound {
+                        id: entry_id.clone(),
+                    });
+                }
+            }
+
+            data.groups.push(group.clone());
+            if !entry_ids.is_empty() {
+                for entry in &mut data.basic_creds {
+                    if entry_ids.iter().any(|id| id == &entry.meta.id)
+                        && !entry.meta.group_ids.iter().any(|gid| gid == &group.id)
+                    {
+                        entry.meta.group_ids.push(group.id.clone());
+                        entry.meta.mark_updated();
+                    }
+                }
+                for entry in &mut data.token_creds {
+                    if entry_ids.iter().any(|id| id == &entry.meta.id)
+                        && !entry.meta.group_ids.iter().any(|gid| gid == &group.id)
+                    {
+                        entry.meta.group_ids.push(group.id.clone());
+                        entry.meta.mark_updated();
+                    }
+                }
+            }
+            Ok(group)
+        }))
+    }
+
+    fn update_group<'a>(
+        &'a self,
+        id: &'a str,
+        name: String,
+        entry_ids: Option<Vec<String>>,
+        parent_ids: Option<Vec<String>>,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        let selected_entry_ids = entry_ids;
+        Box::pin(self.mutate(move |data| {
+            if let Some(group) = data.groups.iter().find(|g| g.id == id) {
+                ensure_revision(&id, group.revision, expected_revision)?;
+            }
+            if data.groups.iter().any(|g| g.id != id && g.name == name) {
+                return Err(error::CredsManageError::DuplicateGroupName { name: name.clone() });
+            }
+            if let Some(ref parent_ids) = parent_ids {
+                ensure_group_parents_valid(&data.groups, &id, parent_ids)?;
+            }
+
+            if let Some(ref entry_ids) = selected_entry_ids {
+                for entry_id in entry_ids {
+                    if !entry_exists(data, entry_id) {
+                        return Err(error::CredsManageError::EntryNotFound {
+                            id: entry_id.clone(),
+                        });
+                    }
+                }
+            }
+
+            let target_group_id = {
+                let group = data
+                    .groups
+                    .iter_mut()
+                    .find(|g| g.id == id)
+                    .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+                group.name = name;
+                if let Some(parent_ids) = parent_ids {
+                    group.parent_ids = parent_ids;
+                }
+                group.mark_updated();
+                group.id.clone()
+            };
+
+            for entry in &mut data.basic_creds {
+                let was_member = entry.meta.group_ids.iter().any(|g| g == &target_group_id);
+                let target_member = if let Some(ref entry_ids) = selected_entry_ids {
+                    entry_ids.iter().any(|entry_id| entry_id == &entry.meta.id)
+                } else {
+                    was_member
+                };
+                update_group_membership(&mut entry.meta, &target_group_id, target_member);
+            }
+
+            for entry in &mut data.token_creds {
+                let was_member = entry.meta.group_ids.iter().any(|g| g == &target_group_id);
+                let target_member = if let Some(ref entry_ids) = selected_entry_ids {
+                    entry_ids.iter().any(|entry_id| entry_id == &entry.meta.id)
+                } else {
+                    was_member
+                };
+                update_group_membership(&mut entry.meta, &target_group_id, target_member);
+            }
+
+            data.groups
+                .iter()
+                .find(|g| g.id == id)
+                .cloned()
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })
+        }))
+    }
+
+    fn update_group_availability<'a>(
+        &'a self,
+        id: &'a str,
+        availability: Availability,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        Box::pin(self.mutate(move |data| {
+            let group = data
+                .groups
+                .iter_mut()
+                .find(|g| g.id == id)
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+            ensure_revision(&id, group.revision, expected_revision)?;
+            group.availability = availability;
+            group.mark_updated();
+            Ok(group.clone())
+        }))
+    }
+
+    fn update_group_identity_access<'a>(
+        &'a self,
+        id: &'a str,
+        identity_access: IdentityAccess,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        Box::pin(self.mutate(move |data| {
+            let group = data
+                .groups
+                .iter_mut()
+                .find(|g| g.id == id)
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+            ensure_revision(&id, group.revision, expected_revision)?;
+            group.identity_access = identity_access;
+            group.mark_updated();
+            Ok(group.clone())
+        }))
+    }
+
+    fn update_group_access_rules<'a>(
+        &'a self,
+        id: &'a str,
+        access_rules: Vec<AccessRule>,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        Box::pin(self.mutate(move |data| {
+            let group = data
+                .groups
+                .iter_mut()
+                .find(|g| g.id == id)
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+            ensure_revision(&id, group.revision, expected_revision)?;
+            group.access_rules = access_rules;
+            group.mark_updated();
+            Ok(group.clone())
+        }))
+    }
+
+    fn delete_group<'a>(
+        &'a self,
+        id: &'a str,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, ()> {
+        let id = id.to_string();
+        Box::pin(self.mutate(move |data| {
+            let removed_group = data.groups.iter().find(|g| g.id == id).cloned();
+            let Some(removed_group) = removed_group else {
+                return Err(error::CredsManageError::GroupNotFound { id: id.clone() });
+            };
+            ensure_revision(&id, removed_group.revision, expected_revision)?;
+
+            data.groups.retain(|g| g.id != id);
+            for group in &mut data.groups {
+                if group.parent_ids.contains(&id) {
+                    group.parent_ids.retain(|parent_id| parent_id != &id);
+                    group.mark_updated();
+                }
+            }
+
+            for entry in &mut data.basic_creds {
+                let len_before = entry.meta.group_ids.len();
+                entry.meta.group_ids.retain(|gid| gid != &removed_group.id);
+                if entry.meta.group_ids.len() != len_before {
+                    entry.meta.mark_updated();
+                }
+            }
+            for entry in &mut data.token_creds {
+                let len_before = entry.meta.group_ids.len();
+                entry.meta.group_ids.retain(|gid| gid != &removed_group.id);
+                if entry.meta.group_ids.len() != len_before {
+                    entry.meta.mark_updated();
+                }
+            }
+            Ok(())
+        }))
+    }
+
+    fn export_data(&self) -> BackendFuture<'_, DataFile> {
+        Box::pin(async move { Ok(DataFile::clone(&self.data.load())) })
+    }
+
+    fn apply_import(&self, plan: ImportPlan) -> BackendFuture<'_, ImportReport> {
+        Box::pin(self.mutate(plan))
+    }
+
+    fn forward_external_changes(&self, notifier: ChangeNotifier) {
+        let _ = self.external_changes.set(notifier);
+    }
+}
+
+impl Drop for FileBackend {
+    fn drop(&mut self) {
+        self.sync_task.abort();
+    }
+}
+
+fn collect_all_entries(data: &DataFile) -> Vec<AuthEntry> {
+    let mut entries = Vec::new();
+    entries.extend(data.basic_creds.iter().map(AuthEntry::from));
+    entries.extend(data.token_creds.iter().map(AuthEntry::from));
+    entries.sort_by_key(|e| e.meta.created_at);
+    entries
+}
+
+fn find_entry_by_id(data: &DataFile, id: &str) -> Option<AuthEntry> {
+    if let Some(entry) = data.basic_creds.iter().find(|e| e.meta.id == id) {
+        return Some(AuthEntry::from(entry));
+    }
+    if let Some(entry) = data.token_creds.iter().find(|e| e.meta.id == id) {
+        return Some(AuthEntry::from(entry));
+    }
+    None
+}
+
+fn ensure_entry_name_is_unique(
+    data: &DataFile,
+    candidate_name: &str,
+    current_entry_id: Option<&str>,
+) -> CredsManageResult<()> {
+    let exists_in_basic = data
+        .basic_creds
+        .iter()
+        .any(|e| e.meta.name == candidate_name && current_entry_id != Some(e.meta.id.as_str()));
+
+    let exists_in_token = data
+        .token_creds
+        .iter()
+        .any(|e| e.meta.name == candidate_name && current_entry_id != Some(e.meta.id.as_str()));
+
+    if exists_in_basic || exists_in_token {
+        return Err(error::CredsManageError::DuplicateEntryName {
+            name: candidate_name.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+fn ensure_groups_exist(data: &DataFile, group_ids: &[String]) -> CredsManageResult<()> {
+    for group_id in group_ids {
+        if !data.groups.iter().any(|g| &g.id == group_id) {
+            return Err(error::CredsManageError::GroupNotFound {
+                id: group_id.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Bump the revisions of `group_ids` after entries joined or left them.
+fn mark_groups_updated(data: &mut DataFile, group_ids: &[String]) {
+    for group in &mut data.groups {
+        if group_ids.contains(&group.id) {
+            group.mark_updated();
+        }
+    }
+}
+
+fn entry_exists(data: &DataFile, entry_id: &str) -> bool {
+    data.basic_creds.iter().any(|e| e.meta.id == entry_id)
+        || data.token_creds.iter().any(|e| e.meta.id == entry_id)
+}
+
+// ---------------------------------------------------------------------------
+// Watcher: debounced FS events on the parent directory
+// ---------------------------------------------------------------------------
+
+/// Start a debounced FS watcher on the parent directory of `path`.
+/// Returns `Err` if the watcher cannot be created.
+async fn run_debounced_watch(
+    path: PathBuf,
+    codec: DataFileCodec,
+    data: Arc<ArcSwap<DataFile>>,
+    io_lock: Arc<Mutex<()>>,
+    last_committed_hash: Arc<Mutex<Option<[u8; 32]>>>,
+    external_changes: Arc<OnceLock<ChangeNotifier>>,
+) -> Result<(), String> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<DebounceEventResult>();
+
+    let target_path = path.canonicalize().map_err(|e| e.to_string())?;
+    let watch_dir = target_path
+        .parent()
+        .ok_or_else(|| "data file has no parent directory".to_string())?
+        .to_path_buf();
+
+    let mut debouncer: Debouncer<notify::RecommendedWatcher, RecommendedCache> = new_debouncer(
+        Duration::from_secs(1),
+        None,
+        move |event: DebounceEventResult| {
+            let _ = tx.send(event);
+        },
+    )
+    .map_err(|e| e.to_string())?;
+
+    debouncer
+        .watch(&watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(path = %path.display(), "filesystem watch enabled for store file (parent dir)");
+
+    while let Some(event) = rx.recv().await {
+        let events = match event {
+            Ok(events) => events,
+            Err(errs) => {
+                for err in errs {
+                    tracing::warn!(error = %err, "filesystem watch event error");
+                }
+                continue;
+            }
+        };
+
+        let target_touched = events
+            .iter()
+            .any(|e| e.event.paths.iter().any(|p| is_same_file(p, &target_path)));
+
+        if !target_touched {
+            continue;
+        }
+
+        if let Err(err) = reload_if_external(
+            &path,
+            &codec,
+            &data,
+            &io_lock,
+            &last_committed_hash,
+            external_changes.get(),
+        )
+        .await
+        {
+            tracing::warn!(path = %path.display(), error = %err, "failed to sync store cache from disk");
+        }
+    }
+
+    // Keep debouncer alive; if we reach here the channel closed.
+    drop(debouncer);
+    Err("filesystem watch channel closed".to_string())
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    // Try canonical comparison; fall back to name comparison.
+    if let (Ok(ac), Ok(bc)) = (a.canonicalize(), b.canonicalize()) {
+        return ac == bc;
+    }
+    a.file_name() == b.file_name()
+}
+
+// ---------------------------------------------------------------------------
+// Fallback: 1-second polling
+// ---------------------------------------------------------------------------
+
+async fn run_poll_loop(
+    path: PathBuf,
+    codec: DataFileCodec,
+    data: Arc<ArcSwap<DataFile>>,
+    io_lock: Arc<Mutex<()>>,
+    last_committed_hash: Arc<Mutex<Option<[u8; 32]>>>,
+    external_changes: Arc<OnceLock<ChangeNotifier>>,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        ticker.tick().await;
+        if let Err(err) = reload_if_external(
+            &path,
+            &codec,
+            &data,
+            &io_lock,
+            &last_committed_hash,
+            external_changes.get(),
+        )
+        .await
+        {
+            tracing::warn!(path = %path.display(), error = %err, "polling sync failed");
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Reload: read file, check hash, publish new snapshot
+// ---------------------------------------------------------------------------
+
+async fn reload_if_external(
+    path: &Path,
+    codec: &DataFileCodec,
+    data: &ArcSwap<DataFile>,
+    io_lock: &Mutex<()>,
+    last_committed_hash: &Mutex<Option<[u8; 32]>>,
+    external_changes: Option<&ChangeNotifier>,
+) -> CredsManageResult<()> {
+    // Wait out an in-flight mutate so its write is matched by the
+    // self-write marker or the swapped cache, not taken as external.
+    let _io_guard = io_lock.lock().await;
+    let raw = read_raw_file_with_lock(path).await?;
+    let hash = content_hash(&raw);
+
+    // Check self-write marker
+    {
+        let mut committed = last_committed_hash.lock().await;
+        if *committed == Some(hash) {
+            // This is the file we just wrote ourselves — skip once.
+            *committed = None;
+            return Ok(());
+        }
+    }
+
+    // Sealed files differ on every write, so compare plaintext.
+    let plaintext = codec.open(&raw)?;
+    let mut disk_data = parse_data_file_bytes(&plaintext)?;
+    let known = data.load_full();
+    revise_external_edits(&known, &mut disk_data);
+
+    // Only swap if content actually changed.
+    let current_serialized = serde_json::to_string_pretty(&*known).unwrap_or_default();
+    let disk_serialized = serde_json::to_string_pretty(&disk_data).unwrap_or_default();
+    if content_hash(current_serialized.as_bytes()) == content_hash(disk_serialized.as_bytes()) {
+        return Ok(());
+    }
+
+    let previous = data.swap(Arc::new(disk_data));
+    tracing::info!(path = %path.display(), "store cache synced from external file change");
+    if let Some(notifier) = external_changes.filter(|notifier| notifier.has_subscribers()) {
+        notifier.send(data_file_changes(&previous, &data.load()));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// File I/O helpers
+// ---------------------------------------------------------------------------
+
+/// Read the data file contents under a shared sidecar lock.
+async fn read_raw_file_with_lock(path: &Path) -> CredsManageResult<Vec<u8>> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> CredsManageResult<Vec<u8>> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).context(error::DataReadSnafu)?;
+        }
+
+        let lock_path = lock_file_path(&path);
+        let lock_file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .context(error::DataReadSnafu)?;
+
+        lock_file.lock_shared().context(error::DataReadSnafu)?;
+        let result = (|| -> CredsManageResult<Vec<u8>> {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .context(error::DataReadSnafu)?;
+
+            std::fs::read(&path).context(error::DataReadSnafu)
+        })();
+        let _ = lock_file.unlock();
+        result
+    })
+    .await
+    .expect("store read task panicked")
+}
+
+/// Read, open and parse the data file.
+async fn read_data_file_with_lock(
+    path: &Path,
+    codec: &DataFileCodec,
+) -> CredsManageResult<DataFile> {
+    let raw = read_raw_file_with_lock(path).await?;
+    codec.decode(&raw)
+}
+
+/// Atomically read-modify-write the data file.
+///
+/// 1. Read current file under an exclusive sidecar lock.
+/// 2. Apply the mutation closure.
+/// 3. Serialize → seal → temp file → fsync → rename (via `AtomicWriteFile`).
+/// 4. Record the content hash so the watcher can skip the self-event.
+async fn atomic_mutate_data_file<T, F>(
+    path: &Path,
+    codec: &DataFileCodec,
+    last_committed_hash: &Mutex<Option<[u8; 32]>>,
+    known: Arc<DataFile>,
+    op: F,
+) -> CredsManageResult<(T, DataFile)>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut DataFile) -> CredsManageResult<T> + Send + 'static,
+{
+    let path = path.to_path_buf();
+    let codec = codec.clone();
+    let (op_result, data, serialized_bytes) =
+        tokio::task::spawn_blocking(move || -> CredsManageResult<(T, DataFile, Vec<u8>)> {
+            if let Some(parent) = path.parent()
+                && !parent.as_os_str().is_empty()
+            {
+                std::fs::create_dir_all(parent).context(error::DataWriteSnafu)?;
+            }
+
+            let lock_path = lock_file_path(&path);
+
+            // Acquire an exclusive lock on a sidecar file for the duration
+            // of read + write. This serializes writers while keeping the
+            // target file replaceable by the atomic writer.
+            let lock_file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)
+                .context(error::DataWriteSnafu)?;
+
+            lock_file.lock_exclusive().context(error::DataWriteSnafu)?;
+
+            let result = (|| -> CredsManageResult<(T, DataFile, Vec<u8>)> {
+                std::fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(&path)
+                    .context(error::DataReadSnafu)?;
+
+                let content = std::fs::read(&path).context(error::DataReadSnafu)?;
+
+                let mut data = codec.decode(&content)?;
+                revise_external_edits(&known, &mut data);
+                let op_result = op(&mut data)?;
+                data.revision = data.revision.max(known.revision) + 1;
+
+                let serialized_bytes = codec.encode(&data)?;
+
+                // Atomic write: temp file → fsync → rename
+                let mut atomic_file = AtomicWriteFile::options()
+                    .open(&path)
+                    .context(error::DataWriteSnafu)?;
+                atomic_file
+                    .write_all(&serialized_bytes)
+                    .context(error::DataWriteSnafu)?;
+                atomic_file.flush().context(error::DataWriteSnafu)?;
+                atomic_file.commit().context(error::DataWriteSnafu)?;
+
+                Ok((op_result, data, serialized_bytes))
+            })();
+
+            let _ = lock_file.unlock();
+            result
+        })
+        .await
+        .expect("store mutate task panicked")?;
+
+    // Record the hash only after a successful write.
+    *last_committed_hash.lock().await = Some(content_hash(&serialized_bytes));
+
+    Ok((op_result, data))
+}
+
+/// Bump the revisions of items changed by someone editing the data file by
+/// hand, which leaves the data file revision as it was; writes through a
+/// backend always increase it.
+fn revise_external_edits(known: &DataFile, data: &mut DataFile) {
+    if data.revision <= known.revision {
+        carry_revisions(known, data);
+    }
+}
+
+fn parse_data_file_bytes(content: &[u8]) -> CredsManageResult<DataFile> {
+    if content.is_empty() || content.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(DataFile::default());
+    }
+
+    serde_json::from_slice(content).context(error::DataParseSnafu)
+}
@@ -0,0 +1,2337 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::IpAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use securitydept_creds::{
+    Argon2HashConfig, BasicAuthCred, MultiFormatBasicAuthCred, STRUCTURED_STATIC_TOKEN_PREFIX,
+    Sha256TokenAuthCred, StructuredStaticToken, generate_structured_static_token,
+    hash_password_argon2_with_config, hash_token_sha256, token::TokenAuthCred,
+};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{
+    archive::{ImportOptions, ImportReport, StoreArchive, apply_archive},
+    audit::{AuditActor, AuditLog, AuditOperation, entry_snapshot, group_snapshot},
+    changes::{
+        ChangeNotifier, StoreChangeEvent, data_file_changes, entry_membership_changes,
+        group_membership_change,
+    },
+    config::{CredsManageBackendKind, CredsManageConfig},
+    error::{self, CredsManageResult},
+    models::{
+        AccessRule, AuthEntry, AuthEntryMeta, AuthEntryUsage, Availability, BasicAuthEntry,
+        ExpirySweep, Group, IdentityAccess, TokenAuthEntry, TokenEntryConstraints,
+        UpdateEntryRequest, UpdateGroupRequest, member_group_ids, validate_access_rules,
+    },
+};
+
+pub mod backend;
+pub mod file;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use backend::{BackendFuture, CredsManageBackend, EntryUpdate};
+use file::DataFileCodec;
+pub use file::FileBackend;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteBackend;
+
+/// Audit entity id of operations on the store as a whole.
+const STORE_AUDIT_ENTITY_ID: &str = "store";
+
+/// Store for auth entries and groups.
+///
+/// Validates and hashes credential material and delegates persistence to a
+/// [`CredsManageBackend`]: a JSON data file by default, or an SQLite database
+/// with the `sqlite` feature. Mutations are recorded in the [`AuditLog`]
+/// when one is attached and published to [`Self::subscribe`]rs.
+pub struct CredsManageStore {
+    backend: Arc<dyn CredsManageBackend>,
+    changes: ChangeNotifier,
+    /// Cost policy for hashing passwords and detecting outdated hashes.
+    argon2: Argon2HashConfig,
+    audit_log: Option<Arc<AuditLog>>,
+    /// Entry uses recorded since the last flush, keyed by entry id.
+    pending_usage: Mutex<HashMap<String, AuthEntryUsage>>,
+    last_expiry_sweep: Mutex<Option<ExpirySweep>>,
+    /// Held by audited mutations from their before snapshot to their audit
+    /// record, so snapshots pair up and records follow the order of writes.
+    mutation_lock: tokio::sync::Mutex<()>,
+}
+
+fn invalid_basic_entry_material_error(message: impl Into<String>) -> error::CredsManageError {
+    error::CredsManageError::Creds {
+        source: securitydept_creds::CredsError::InvalidCredentialsFormat {
+            message: message.into(),
+        },
+    }
+}
+
+fn ensure_basic_entry_material_valid(username: &str, password: &str) -> CredsManageResult<()> {
+    if username.trim().is_empty() {
+        return Err(invalid_basic_entry_material_error(
+            "Basic entry username must not be empty",
+        ));
+    }
+
+    if password.is_empty() {
+        return Err(invalid_basic_entry_material_error(
+            "Basic entry password must not be empty",
+        ));
+    }
+
+    Ok(())
+}
+
+fn ensure_basic_entry_update_material_valid(
+    username: Option<&str>,
+    password: Option<&str>,
+) -> CredsManageResult<()> {
+    if let Some(username) = username
+        && username.trim().is_empty()
+    {
+        return Err(invalid_basic_entry_material_error(
+            "Basic entry username must not be empty",
+        ));
+    }
+
+    if let Some(password) = password
+        && password.is_empty()
+    {
+        return Err(invalid_basic_entry_material_error(
+            "Basic entry password must not be empty",
+        ));
+    }
+
+    Ok(())
+}
+
+impl CredsManageStore {
+    /// Load (or create) the data file at `path` and return a Store.
+    pub async fn load(path: impl AsRef<Path>) -> CredsManageResult<Self> {
+        Self::with_backend(FileBackend::load(path).await?, Argon2HashConfig::default())
+    }
+
+    /// Load the store described by `config`, including its backend and
+    /// argon2 policy.
+    pub async fn load_from_config(config: &CredsManageConfig) -> CredsManageResult<Self> {
+        let store = match config.backend {
+            CredsManageBackendKind::File => {
+                let codec = DataFileCodec::load(config).await?;
+                Self::with_backend(
+                    FileBackend::load_with_codec(&config.data_path, codec).await?,
+                    config.argon2,
+                )
+            }
+            #[cfg(feature = "sqlite")]
+            CredsManageBackendKind::Sqlite => Self::with_backend(
+                SqliteBackend::open(&config.sqlite_path).await?,
+                config.argon2,
+            ),
+        }?;
+        Ok(match &config.audit_log_path {
+            Some(path) => store.with_audit_log(AuditLog::new(path)),
+            None => store,
+        })
+    }
+
+    /// Build a store on top of a custom backend.
+    pub fn with_backend(
+        backend: impl CredsManageBackend + 'static,
+        argon2: Argon2HashConfig,
+    ) -> CredsManageResult<Self> {
+        argon2.validate()?;
+        let changes = ChangeNotifier::default();
+        backend.forward_external_changes(changes.clone());
+        Ok(Self {
+            backend: Arc::new(backend),
+            changes,
+            argon2,
+            audit_log: None,
+            pending_usage: Mutex::new(HashMap::new()),
+            last_expiry_sweep: Mutex::new(None),
+            mutation_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Record every entry and group mutation in `audit_log`.
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Some(Arc::new(audit_log));
+        self
+    }
+
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit_log.as_deref()
+    }
+
+    /// Receive an event for every change to entries and groups, whether made
+    /// through this store or, with the file backend, by another writer of
+    /// the data file.
+    pub fn subscribe(&self) -> broadcast::Receiver<StoreChangeEvent> {
+        self.changes.subscribe()
+    }
+
+    /// The argon2 policy this store hashes passwords with.
+    pub fn argon2_config(&self) -> &Argon2HashConfig {
+        &self.argon2
+    }
+
+    // -- Entry operations --
+
+    pub async fn list_entries(&self) -> CredsManageResult<Vec<AuthEntry>> {
+        self.backend.list_entries().await
+    }
+
+    pub async fn get_entry(&self, id: &str) -> CredsManageResult<AuthEntry> {
+        self.backend
+            .get_entry(id)
+            .await?
+            .ok_or_else(|| error::CredsManageError::EntryNotFound { id: id.to_string() })
+    }
+
+    pub async fn create_basic_entry(
+        &self,
+        actor: &AuditActor,
+        name: String,
+        username: String,
+        password: String,
+        group_ids: Vec<String>,
+    ) -> CredsManageResult<AuthEntry> {
+        ensure_basic_entry_material_valid(&username, &password)?;
+
+        let entry = BasicAuthEntry {
+            cred: MultiFormatBasicAuthCred::new_with_config(username, password, &self.argon2)?,
+            meta: AuthEntryMeta::new(name, group_ids),
+        };
+        let _mutation = self.mutation_lock.lock().await;
+        let created = self.backend.insert_basic_entry(entry).await?;
+        self.audit_entry(actor, AuditOperation::EntryCreate, None, &created)
+            .await?;
+        self.publish_entry_change(
+            StoreChangeEvent::EntryCreated {
+                entry_id: created.meta.id.clone(),
+            },
+            &[],
+            &created.meta.group_ids,
+        );
+        Ok(created)
+    }
+
+    /// Create a basic entry from an already hashed password, e.g. when
+    /// importing an htpasswd file. Legacy hash formats are accepted and get
+    /// re-hashed to argon2 on the first successful login.
+    pub async fn create_basic_entry_with_password_hash(
+        &self,
+        actor: &AuditActor,
+        name: String,
+        username: String,
+        password_hash: String,
+        group_ids: Vec<String>,
+    ) -> CredsManageResult<AuthEntry> {
+        ensure_basic_entry_material_valid(&username, &password_hash)?;
+
+        let entry = BasicAuthEntry {
+            cred: MultiFormatBasicAuthCred::from_password_hash(username, password_hash)?,
+            meta: AuthEntryMeta::new(name, group_ids),
+        };
+        let _mutation = self.mutation_lock.lock().await;
+        let created = self.backend.insert_basic_entry(entry).await?;
+        self.audit_entry(actor, AuditOperation::EntryCreate, None, &created)
+            .await?;
+        self.publish_entry_change(
+            StoreChangeEvent::EntryCreated {
+                entry_id: created.meta.id.clone(),
+            },
+            &[],
+            &created.meta.group_ids,
+        );
+        Ok(created)
+    }
+
+    /// Replace a legacy or below-policy password hash with a fresh argon2
+    /// hash after a successful login. The upgrade is audited as a system
+    /// mutation.
+    ///
+    /// Returns `Ok(false)` when there was nothing to upgrade, e.g. the entry
+    /// was already re-hashed by a concurrent request or the password no
+    /// longer matches the stored hash.
+    pub async fn upgrade_basic_entry_password_hash(
+        &self,
+        id: &str,
+        password: &str,
+    ) -> CredsManageResult<bool> {
+        let entry = self
+            .backend
+            .get_basic_entry(id)
+            .await?
+            .ok_or_else(|| error::CredsManageError::EntryNotFound { id: id.to_string() })?;
+        if !entry.needs_rehash(&self.argon2) || !entry.verify_password(password)? {
+            return Ok(false);
+        }
+
+        let new_hash = hash_password_argon2_with_config(password, &self.argon2)?;
+        let _mutation = self.mutation_lock.lock().await;
+        let replaced = self
+            .backend
+            .replace_basic_password_hash(id, &entry.cred.password_hash, new_hash)
+            .await?;
+        if replaced {
+            self.audit(
+                &AuditActor::system(),
+                AuditOperation::EntryPasswordRehash,
+                id,
+                None,
+                None,
+            )
+            .await?;
+            self.changes.send([StoreChangeEvent::EntryUpdated {
+                entry_id: id.to_string(),
+            }]);
+        }
+        Ok(replaced)
+    }
+
+    pub async fn create_token_entry(
+        &self,
+        actor: &AuditActor,
+        name: String,
+        group_ids: Vec<String>,
+    ) -> CredsManageResult<(AuthEntry, String)> {
+        self.create_token_entry_with_constraints(
+            actor,
+            name,
+            group_ids,
+            None,
+            TokenEntryConstraints::default(),
+        )
+        .await
+    }
+
+    /// Create a token entry limited to a validity window and/or scopes.
+    pub async fn create_token_entry_with_constraints(
+        &self,
+        actor: &AuditActor,
+        name: String,
+        group_ids: Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+        constraints: TokenEntryConstraints,
+    ) -> CredsManageResult<(AuthEntry, String)> {
+        constraints.validate(expires_at)?;
+
+        let mut meta = AuthEntryMeta::new(name, group_ids);
+        meta.availability.expires_at = expires_at;
+        let token = generate_structured_static_token(&meta.id)?;
+        let entry = TokenAuthEntry {
+            cred: Sha256TokenAuthCred::new(token.clone())?,
+            token_prefix: Some(format!("{STRUCTURED_STATIC_TOKEN_PREFIX}{}", meta.id)),
+            meta,
+            constraints,
+        };
+
+        let _mutation = self.mutation_lock.lock().await;
+        let created = self.backend.insert_token_entry(entry).await?;
+        self.audit_entry(actor, AuditOperation::EntryCreate, None, &created)
+            .await?;
+        self.publish_entry_change(
+            StoreChangeEvent::EntryCreated {
+                entry_id: created.meta.id.clone(),
+            },
+            &[],
+            &created.meta.group_ids,
+        );
+        Ok((created, token))
+    }
+
+    pub async fn update_entry(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        name: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+        group_ids: Option<Vec<String>>,
+    ) -> CredsManageResult<AuthEntry> {
+        self.update_entry_at_revision(
+            actor,
+            id,
+            None,
+            UpdateEntryRequest {
+                name,
+                username,
+                password,
+                group_ids,
+            },
+        )
+        .await
+    }
+
+    /// Like [`Self::update_entry`], but fails with
+    /// [`CredsManageError::RevisionMismatch`](crate::CredsManageError::RevisionMismatch)
+    /// unless the entry is still at `expected_revision`, when set.
+    pub async fn update_entry_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+        update: UpdateEntryRequest,
+    ) -> CredsManageResult<AuthEntry> {
+        let UpdateEntryRequest {
+            name,
+            username,
+            password,
+            group_ids,
+        } = update;
+        ensure_basic_entry_update_material_valid(username.as_deref(), password.as_deref())?;
+
+        let password_hash = password
+            .map(|password| hash_password_argon2_with_config(&password, &self.argon2))
+            .transpose()?;
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.entry_before_mutation(id).await?;
+        let groups_before = self.entry_groups_before_mutation(id).await?;
+        let updated = self
+            .backend
+            .update_entry(
+                id,
+                EntryUpdate {
+                    name,
+                    username,
+                    password_hash,
+                    group_ids,
+                    availability: None,
+                    expected_revision,
+                },
+            )
+            .await?;
+        self.audit_entry(actor, AuditOperation::EntryUpdate, before, &updated)
+            .await?;
+        self.publish_entry_change(
+            StoreChangeEvent::EntryUpdated {
+                entry_id: updated.meta.id.clone(),
+            },
+            groups_before.as_deref().unwrap_or(&updated.meta.group_ids),
+            &updated.meta.group_ids,
+        );
+        Ok(updated)
+    }
+
+    /// Disable, re-enable or schedule the expiry of an entry. The credential
+    /// material is kept either way.
+    pub async fn set_entry_availability(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        availability: Availability,
+    ) -> CredsManageResult<AuthEntry> {
+        self.set_entry_availability_at_revision(actor, id, None, availability)
+            .await
+    }
+
+    /// Like [`Self::set_entry_availability`], only while the entry is at
+    /// `expected_revision`, when set.
+    pub async fn set_entry_availability_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+        availability: Availability,
+    ) -> CredsManageResult<AuthEntry> {
+        availability.validate()?;
+        if let Some(expires_at) = availability.expires_at
+            && let Some(token_entry) = self.backend.get_token_entry(id).await?
+        {
+            token_entry.constraints.validate(Some(expires_at))?;
+        }
+
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.entry_before_mutation(id).await?;
+        let updated = self
+            .backend
+            .update_entry(
+                id,
+                EntryUpdate {
+                    availability: Some(availability),
+                    expected_revision,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        self.audit_entry(actor, AuditOperation::EntryUpdate, before, &updated)
+            .await?;
+        self.changes.send([StoreChangeEvent::EntryUpdated {
+            entry_id: updated.meta.id.clone(),
+        }]);
+        Ok(updated)
+    }
+
+    pub async fn delete_entry(&self, actor: &AuditActor, id: &str) -> CredsManageResult<()> {
+        self.delete_entry_at_revision(actor, id, None).await
+    }
+
+    /// Like [`Self::delete_entry`], only while the entry is at
+    /// `expected_revision`, when set.
+    pub async fn delete_entry_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+    ) -> CredsManageResult<()> {
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.entry_before_mutation(id).await?;
+        let groups_before = self.entry_groups_before_mutation(id).await?;
+        self.backend.delete_entry(id, expected_revision).await?;
+        self.audit(actor, AuditOperation::EntryDelete, id, before, None)
+            .await?;
+        self.publish_entry_change(
+            StoreChangeEvent::EntryDeleted {
+                entry_id: id.to_string(),
+            },
+            groups_before.as_deref().unwrap_or_default(),
+            &[],
+        );
+        Ok(())
+    }
+
+    /// Find all entry metadata that directly belong to a given group id.
+    pub async fn entries_by_group_id(&self, group_id: &str) -> CredsManageResult<Vec<AuthEntry>> {
+        let group_ids = [group_id.to_string()];
+        let mut entries: Vec<AuthEntry> = self
+            .backend
+            .basic_entries_by_group_ids(&group_ids)
+            .await?
+            .iter()
+            .map(AuthEntry::from)
+            .collect();
+        entries.extend(
+            self.backend
+                .token_entries_by_group_ids(&group_ids)
+                .await?
+                .iter()
+                .map(AuthEntry::from),
+        );
+        Ok(entries)
+    }
+
+    /// Find all basic auth entries that belong to a given group id, directly
+    /// or through an available subgroup.
+    pub async fn basic_entries_by_group_id(
+        &self,
+        group_id: &str,
+    ) -> CredsManageResult<Vec<BasicAuthEntry>> {
+        let group_ids = self.member_group_ids(group_id).await?;
+        self.backend.basic_entries_by_group_ids(&group_ids).await
+    }
+
+    /// Find all token auth entries that belong to a given group id, directly
+    /// or through an available subgroup.
+    pub async fn token_entries_by_group_id(
+        &self,
+        group_id: &str,
+    ) -> CredsManageResult<Vec<TokenAuthEntry>> {
+        let group_ids = self.member_group_ids(group_id).await?;
+        self.backend.token_entries_by_group_ids(&group_ids).await
+    }
+
+    /// Ids of `group_id` and of its available subgroups at any depth.
+    async fn member_group_ids(&self, group_id: &str) -> CredsManageResult<Vec<String>> {
+        let groups = self.backend.list_groups().await?;
+        Ok(member_group_ids(&groups, group_id, Utc::now()))
+    }
+
+    /// Find the token entry a bearer token belongs to.
+    ///
+    /// Structured tokens are looked up by their entry id, bare tokens by
+    /// hash, so no group's entries need to be loaded. Constraints are not
+    /// checked.
+    pub async fn find_token_entry(&self, token: &str) -> CredsManageResult<Option<TokenAuthEntry>> {
+        let entry = match StructuredStaticToken::parse(token)? {
+            Some(structured) => self.backend.get_token_entry(structured.id).await?,
+            None => {
+                self.backend
+                    .find_token_entry_by_hash(&hash_token_sha256(token))
+                    .await?
+            }
+        };
+
+        match entry {
+            Some(entry) if entry.verify_token(token)? => Ok(Some(entry)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Count a successful or failed use of an entry.
+    ///
+    /// Uses are only kept in memory until [`Self::flush_entry_usage`] writes
+    /// them in one batch, so recording is cheap on the request path.
+    pub fn record_entry_use(&self, id: &str, success: bool, client_ip: Option<IpAddr>) {
+        self.pending_usage
+            .lock()
+            .expect("entry usage lock poisoned")
+            .entry(id.to_string())
+            .or_default()
+            .record(success, client_ip, Utc::now());
+    }
+
+    /// Persist the uses recorded since the last flush and return how many
+    /// entries had pending uses. Entries deleted in the meantime are skipped.
+    /// On failure the uses are kept for the next flush.
+    pub async fn flush_entry_usage(&self) -> CredsManageResult<usize> {
+        let batch = std::mem::take(
+            &mut *self
+                .pending_usage
+                .lock()
+                .expect("entry usage lock poisoned"),
+        );
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let count = batch.len();
+        if let Err(error) = self.backend.record_usage(batch.clone()).await {
+            let mut pending = self
+                .pending_usage
+                .lock()
+                .expect("entry usage lock poisoned");
+            for (id, mut usage) in batch {
+                if let Some(newer) = pending.remove(&id) {
+                    usage.merge(&newer);
+                }
+                pending.insert(id, usage);
+            }
+            return Err(error);
+        }
+        Ok(count)
+    }
+
+    // -- Group operations --
+
+    pub async fn list_groups(&self) -> CredsManageResult<Vec<Group>> {
+        self.backend.list_groups().await
+    }
+
+    pub async fn get_group(&self, id: &str) -> CredsManageResult<Group> {
+        self.backend
+            .get_group(id)
+            .await?
+            .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.to_string() })
+    }
+
+    pub async fn create_group(
+        &self,
+        actor: &AuditActor,
+        mut group: Group,
+        entry_ids: Option<Vec<String>>,
+    ) -> CredsManageResult<Group> {
+        group.parent_ids.sort();
+        group.parent_ids.dedup();
+        let _mutation = self.mutation_lock.lock().await;
+        let created = self
+            .backend
+            .insert_group(group, entry_ids.unwrap_or_default())
+            .await?;
+        let after = self.group_state(&created.id).await?;
+        self.audit(actor, AuditOperation::GroupCreate, &created.id, None, after)
+            .await?;
+        let members = self.group_members(&created.id).await?;
+        self.publish_group_change(
+            StoreChangeEvent::GroupCreated {
+                group_id: created.id.clone(),
+            },
+            Some(BTreeSet::new()),
+            members,
+        );
+        Ok(created)
+    }
+
+    pub async fn update_group(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        name: String,
+        entry_ids: Option<Vec<String>>,
+        parent_ids: Option<Vec<String>>,
+    ) -> CredsManageResult<Group> {
+        self.update_group_at_revision(
+            actor,
+            id,
+            None,
+            UpdateGroupRequest {
+                name,
+                parent_ids,
+                entry_ids,
+            },
+        )
+        .await
+    }
+
+    /// Like [`Self::update_group`], only while the group is at
+    /// `expected_revision`, when set.
+    pub async fn update_group_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+        update: UpdateGroupRequest,
+    ) -> CredsManageResult<Group> {
+        let UpdateGroupRequest {
+            name,
+            parent_ids,
+            entry_ids,
+        } = update;
+        let parent_ids = parent_ids.map(|mut parent_ids| {
+            parent_ids.sort();
+            parent_ids.dedup();
+            parent_ids
+        });
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.group_state(id).await?;
+        let members_before = self.group_members(id).await?;
+        let updated = self
+            .backend
+            .update_group(id, name, entry_ids, parent_ids, expected_revision)
+            .await?;
+        let after = self.group_state(id).await?;
+        self.audit(actor, AuditOperation::GroupUpdate, id, before, after)
+            .await?;
+        let members = self.group_members(id).await?;
+        self.publish_group_change(
+            StoreChangeEvent::GroupUpdated {
+                group_id: id.to_string(),
+            },
+            members_before,
+            members,
+        );
+        Ok(updated)
+    }
+
+    /// Disable, re-enable or schedule the expiry of a group. Entries of an
+    /// unavailable group stay usable through their other groups.
+    pub async fn set_group_availability(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        availability: Availability,
+    ) -> CredsManageResult<Group> {
+        self.set_group_availability_at_revision(actor, id, None, availability)
+            .await
+    }
+
+    /// Like [`Self::set_group_availability`], only while the group is at
+    /// `expected_revision`, when set.
+    pub async fn set_group_availability_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+        availability: Availability,
+    ) -> CredsManageResult<Group> {
+        availability.validate()?;
+
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.group_state(id).await?;
+        let updated = self
+            .backend
+            .update_group_availability(id, availability, expected_revision)
+            .await?;
+        let after = self.group_state(id).await?;
+        self.audit(actor, AuditOperation::GroupUpdate, id, before, after)
+            .await?;
+        self.changes.send([StoreChangeEvent::GroupUpdated {
+            group_id: id.to_string(),
+        }]);
+        Ok(updated)
+    }
+
+    /// Set the identities besides its member entries that forward auth
+    /// accepts for a group.
+    pub async fn set_group_identity_access(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        identity_access: IdentityAccess,
+    ) -> CredsManageResult<Group> {
+        self.set_group_identity_access_at_revision(actor, id, None, identity_access)
+            .await
+    }
+
+    /// Like [`Self::set_group_identity_access`], only while the group is at
+    /// `expected_revision`, when set.
+    pub async fn set_group_identity_access_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+        identity_access: IdentityAccess,
+    ) -> CredsManageResult<Group> {
+        identity_access.validate()?;
+
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.group_state(id).await?;
+        let updated = self
+            .backend
+            .update_group_identity_access(id, identity_access, expected_revision)
+            .await?;
+        let after = self.group_state(id).await?;
+        self.audit(actor, AuditOperation::GroupUpdate, id, before, after)
+            .await?;
+        self.changes.send([StoreChangeEvent::GroupUpdated {
+            group_id: id.to_string(),
+        }]);
+        Ok(updated)
+    }
+
+    /// Replace the access rules of a group, giving rules without an id a
+    /// generated one.
+    pub async fn set_group_access_rules(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        access_rules: Vec<AccessRule>,
+    ) -> CredsManageResult<Group> {
+        self.set_group_access_rules_at_revision(actor, id, None, access_rules)
+            .await
+    }
+
+    /// Like [`Self::set_group_access_rules`], only while the group is at
+    /// `expected_revision`, when set.
+    pub async fn set_group_access_rules_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+        mut access_rules: Vec<AccessRule>,
+    ) -> CredsManageResult<Group> {
+        for rule in &mut access_rules {
+            if rule.id.is_empty() {
+                rule.id = Uuid::new_v4().to_string();
+            }
+        }
+        validate_access_rules(&access_rules)?;
+
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.group_state(id).await?;
+        let updated = self
+            .backend
+            .update_group_access_rules(id, access_rules, expected_revision)
+            .await?;
+        let after = self.group_state(id).await?;
+        self.audit(actor, AuditOperation::GroupUpdate, id, before, after)
+            .await?;
+        self.changes.send([StoreChangeEvent::GroupUpdated {
+            group_id: id.to_string(),
+        }]);
+        Ok(updated)
+    }
+
+    pub async fn delete_group(&self, actor: &AuditActor, id: &str) -> CredsManageResult<()> {
+        self.delete_group_at_revision(actor, id, None).await
+    }
+
+    /// Like [`Self::delete_group`], only while the group is at
+    /// `expected_revision`, when set.
+    pub async fn delete_group_at_revision(
+        &self,
+        actor: &AuditActor,
+        id: &str,
+        expected_revision: Option<u64>,
+    ) -> CredsManageResult<()> {
+        let _mutation = self.mutation_lock.lock().await;
+        let before = self.group_state(id).await?;
+        let members_before = self.group_members(id).await?;
+        let subgroup_ids: Vec<String> = if self.changes.has_subscribers() {
+            self.backend
+                .list_groups()
+                .await?
+                .into_iter()
+                .filter(|group| group.parent_ids.iter().any(|parent_id| parent_id == id))
+                .map(|group| group.id)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.backend.delete_group(id, expected_revision).await?;
+        self.audit(actor, AuditOperation::GroupDelete, id, before, None)
+            .await?;
+        self.publish_group_change(
+            StoreChangeEvent::GroupDeleted {
+                group_id: id.to_string(),
+            },
+            members_before,
+            Some(BTreeSet::new()),
+        );
+        // Subgroups lost the deleted group from their `parent_ids`.
+        self.changes.send(
+            subgroup_ids
+                .into_iter()
+                .map(|group_id| StoreChangeEvent::GroupUpdated { group_id }),
+        );
+        Ok(())
+    }
+
+    /// Find a group by name.
+    pub async fn find_group_by_name(&self, name: &str) -> CredsManageResult<Option<Group>> {
+        self.backend.find_group_by_name(name).await
+    }
+
+    // -- Backup --
+
+    /// Export all groups and entries, hashes included.
+    pub async fn export_archive(&self) -> CredsManageResult<StoreArchive> {
+        Ok(StoreArchive::from_data(self.backend.export_data().await?))
+    }
+
+    /// Import an archive exported by [`Self::export_archive`], possibly from
+    /// another environment. The import is all or nothing; with
+    /// `options.dry_run` it is only validated and reported.
+    pub async fn import_archive(
+        &self,
+        actor: &AuditActor,
+        archive: StoreArchive,
+        options: ImportOptions,
+    ) -> CredsManageResult<ImportReport> {
+        archive.validate()?;
+        if options.dry_run {
+            let mut data = self.backend.export_data().await?;
+            return apply_archive(&mut data, &archive, options);
+        }
+
+        let _mutation = self.mutation_lock.lock().await;
+        let before = if self.changes.has_subscribers() {
+            Some(self.backend.export_data().await?)
+        } else {
+            None
+        };
+        let report = self
+            .backend
+            .apply_import(Box::new(move |data| apply_archive(data, &archive, options)))
+            .await?;
+        if let Some(before) = before {
+            let after = self.backend.export_data().await?;
+            self.changes.send(data_file_changes(&before, &after));
+        }
+        self.audit(
+            actor,
+            AuditOperation::StoreImport,
+            STORE_AUDIT_ENTITY_ID,
+            None,
+            serde_json::to_value(&report).ok(),
+        )
+        .await?;
+        Ok(report)
+    }
+
+    // -- Expiry --
+
+    /// Collect the entries and groups whose `expires_at` has passed and
+    /// remember the result for [`Self::last_expiry_sweep`].
+    ///
+    /// Expired entries are already refused at authentication time; the
+    /// sweep only reports them so they can be renewed or cleaned up.
+    pub async fn sweep_expired(&self) -> CredsManageResult<ExpirySweep> {
+        let now = Utc::now();
+        let expired_entry_ids = self
+            .backend
+            .list_entries()
+            .await?
+            .into_iter()
+            .filter(|entry| entry.meta.availability.is_expired(now))
+            .map(|entry| entry.meta.id)
+            .collect();
+        let expired_group_ids = self
+            .backend
+            .list_groups()
+            .await?
+            .into_iter()
+            .filter(|group| group.availability.is_expired(now))
+            .map(|group| group.id)
+            .collect();
+        let sweep = ExpirySweep {
+            swept_at: now,
+            expired_entry_ids,
+            expired_group_ids,
+        };
+
+        let previous = self
+            .last_expiry_sweep
+            .lock()
+            .expect("expiry sweep lock poisoned")
+            .replace(sweep.clone());
+        let newly_expired = |ids: &[String], previous_ids: Option<&Vec<String>>| {
+            ids.iter()
+                .filter(|id| previous_ids.is_none_or(|previous| !previous.contains(id)))
+                .count()
+        };
+        let new_entries = newly_expired(
+            &sweep.expired_entry_ids,
+            previous.as_ref().map(|p| &p.expired_entry_ids),
+        );
+        let new_groups = newly_expired(
+            &sweep.expired_group_ids,
+            previous.as_ref().map(|p| &p.expired_group_ids),
+        );
+        if new_entries > 0 || new_groups > 0 {
+            tracing::info!(
+                expired_entries = sweep.expired_entry_ids.len(),
+                expired_groups = sweep.expired_group_ids.len(),
+                new_entries,
+                new_groups,
+                "creds-manage entries or groups expired"
+            );
+        }
+        Ok(sweep)
+    }
+
+    /// Result of the latest [`Self::sweep_expired`], if any.
+    pub fn last_expiry_sweep(&self) -> Option<ExpirySweep> {
+        self.last_expiry_sweep
+            .lock()
+            .expect("expiry sweep lock poisoned")
+            .clone()
+    }
+
+    // -- Change helpers --
+
+    /// Group ids of an entry ahead of a mutation; skipped without
+    /// subscribers.
+    async fn entry_groups_before_mutation(
+        &self,
+        id: &str,
+    ) -> CredsManageResult<Option<Vec<String>>> {
+        if !self.changes.has_subscribers() {
+            return Ok(None);
+        }
+        Ok(self
+            .backend
+            .get_entry(id)
+            .await?
+            .map(|entry| entry.meta.group_ids))
+    }
+
+    /// Ids of the entries directly in a group; skipped without subscribers.
+    async fn group_members(&self, id: &str) -> CredsManageResult<Option<BTreeSet<String>>> {
+        if !self.changes.has_subscribers() {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.entries_by_group_id(id)
+                .await?
+                .into_iter()
+                .map(|entry| entry.meta.id)
+                .collect(),
+        ))
+    }
+
+    /// Publish `event` followed by the membership changes of an entry whose
+    /// groups went from `groups_before` to `groups_after`.
+    fn publish_entry_change(
+        &self,
+        event: StoreChangeEvent,
+        groups_before: &[String],
+        groups_after: &[String],
+    ) {
+        let (StoreChangeEvent::EntryCreated { entry_id }
+        | StoreChangeEvent::EntryUpdated { entry_id }
+        | StoreChangeEvent::EntryDeleted { entry_id }) = &event
+        else {
+            unreachable!("entry change expected");
+        };
+        let memberships = entry_membership_changes(entry_id, groups_before, groups_after);
+        self.changes.send(std::iter::once(event).chain(memberships));
+    }
+
+    /// Publish `event` followed by the membership change of its group when
+    /// both member sets are known.
+    fn publish_group_change(
+        &self,
+        event: StoreChangeEvent,
+        members_before: Option<BTreeSet<String>>,
+        members_after: Option<BTreeSet<String>>,
+    ) {
+        let membership = match (&event, members_before, members_after) {
+            (
+                StoreChangeEvent::GroupCreated { group_id }
+                | StoreChangeEvent::GroupUpdated { group_id }
+                | StoreChangeEvent::GroupDeleted { group_id },
+                Some(before),
+                Some(after),
+            ) => group_membership_change(group_id, &before, &after),
+            _ => None,
+        };
+        self.changes.send(std::iter::once(event).chain(membership));
+    }
+
+    // -- Audit helpers --
+
+    /// Snapshot an entry ahead of a mutation; skipped when not auditing.
+    async fn entry_before_mutation(&self, id: &str) -> CredsManageResult<Option<Value>> {
+        if self.audit_log.is_none() {
+            return Ok(None);
+        }
+        Ok(self
+            .backend
+            .get_entry(id)
+            .await?
+            .as_ref()
+            .and_then(entry_snapshot))
+    }
+
+    /// Snapshot a group and its members; skipped when not auditing.
+    async fn group_state(&self, id: &str) -> CredsManageResult<Option<Value>> {
+        if self.audit_log.is_none() {
+            return Ok(None);
+        }
+        let Some(group) = self.backend.get_group(id).await? else {
+            return Ok(None);
+        };
+        let entry_ids: Vec<String> = self
+            .entries_by_group_id(id)
+            .await?
+            .into_iter()
+            .map(|entry| entry.meta.id)
+            .collect();
+        Ok(group_snapshot(&group, &entry_ids))
+    }
+
+    async fn audit_entry(
+        &self,
+        actor: &AuditActor,
+        operation: AuditOperation,
+        before: Option<Value>,
+        after: &AuthEntry,
+    ) -> CredsManageResult<()> {
+        self.audit(
+            actor,
+            operation,
+            &after.meta.id,
+            before,
+            entry_snapshot(after),
+        )
+        .await
+    }
+
+    /// Append to the audit log. The mutation already happened, so a failed
+    /// append is returned as
+    /// [`CredsManageError::UnauditedMutation`](crate::CredsManageError::UnauditedMutation)
+    /// telling the caller the change was applied without a record.
+    async fn audit(
+        &self,
+        actor: &AuditActor,
+        operation: AuditOperation,
+        entity_id: &str,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> CredsManageResult<()> {
+        let Some(audit_log) = &self.audit_log else {
+            return Ok(());
+        };
+        if let Err(error) = audit_log
+            .append(
+                actor.clone(),
+                operation,
+                entity_id.to_string(),
+                before,
+                after,
+            )
+            .await
+        {
+            tracing::error!(
+                ?operation,
+                entity_id,
+                error = %error,
+                "Failed to append creds-manage audit record"
+            );
+            return Err(error::CredsManageError::UnauditedMutation {
+                operation,
+                entity_id: entity_id.to_string(),
+                source: Box::new(error),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use chrono::Utc;
+    use securitydept_creds::CredsError;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn unique_store_path(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!("securitydept-creds-manage-{label}-{nanos}.json"))
+    }
+
+    async fn load_test_store(label: &str) -> CredsManageStore {
+        CredsManageStore::load(unique_store_path(label))
+            .await
+            .expect("test store should load")
+    }
+
+    #[tokio::test]
+    async fn create_basic_entry_rejects_empty_username_as_invalid_credentials_format() {
+        let store = load_test_store("empty-username").await;
+
+        let error = store
+            .create_basic_entry(
+                &AuditActor::system(),
+                "ops-user".to_string(),
+                "   ".to_string(),
+                "secret123".to_string(),
+                Vec::new(),
+            )
+            .await
+            .expect_err("empty username should be rejected");
+
+        match error {
+            error::CredsManageError::Creds {
+                source: CredsError::InvalidCredentialsFormat { message },
+            } => {
+                assert_eq!(message, "Basic entry username must not be empty");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn legacy_password_hash_is_upgraded_to_argon2() {
+        let store = load_test_store("legacy-hash-upgrade").await;
+
+        let created = store
+            .create_basic_entry_with_password_hash(
+                &AuditActor::system(),
+                "legacy-user".to_string(),
+                "legacy".to_string(),
+                "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=".to_string(),
+                Vec::new(),
+            )
+            .await
+            .expect("legacy hash should be accepted");
+
+        assert!(
+            !store
+                .upgrade_basic_entry_password_hash(&created.meta.id, "wrong")
+                .await
+                .expect("wrong password should not fail the upgrade")
+        );
+        assert!(
+            store
+                .upgrade_basic_entry_password_hash(&created.meta.id, "password")
+                .await
+                .expect("upgrade should succeed")
+        );
+
+        let entry = store
+            .backend
+            .get_basic_entry(&created.meta.id)
+            .await
+            .unwrap()
+            .expect("upgraded entry should exist");
+        assert!(entry.cred.password_hash.starts_with("$argon2"));
+        assert!(!entry.needs_rehash(store.argon2_config()));
+        assert!(entry.verify_password("password").unwrap());
+        assert!(
+            !store
+                .upgrade_basic_entry_password_hash(&created.meta.id, "password")
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn structured_and_bare_tokens_both_verify() {
+        use crate::auth::check_token_auth;
+
+        let store = load_test_store("structured-token").await;
+        let (created, token) = store
+            .create_token_entry(&AuditActor::system(), "ci".to_string(), Vec::new())
+            .await
+            .expect("token entry should be created");
+        assert_eq!(
+            created.token_prefix.as_deref(),
+            Some(format!("sdt_{}", created.meta.id).as_str())
+        );
+        assert!(token.starts_with(&format!("sdt_{}_", created.meta.id)));
+
+        let bare_token = securitydept_creds::generate_static_token().unwrap();
+        assert_eq!(
+            store
+                .find_token_entry(&token)
+                .await
+                .unwrap()
+                .map(|entry| entry.meta.id),
+            Some(created.meta.id.clone())
+        );
+
+        let mut entries = vec![
+            store
+                .backend
+                .get_token_entry(&created.meta.id)
+                .await
+                .unwrap()
+                .expect("token entry should exist"),
+        ];
+        entries.push(TokenAuthEntry {
+            cred: Sha256TokenAuthCred::new(bare_token.clone()).unwrap(),
+            meta: AuthEntryMeta::new("legacy".to_string(), Vec::new()),
+            token_prefix: None,
+            constraints: TokenEntryConstraints::default(),
+        });
+
+        assert_eq!(
+            check_token_auth(&entries, &token).unwrap().as_deref(),
+            Some("ci")
+        );
+        assert_eq!(
+            check_token_auth(&entries, &bare_token).unwrap().as_deref(),
+            Some("legacy")
+        );
+
+        let mut tampered = token.clone();
+        tampered.pop();
+        tampered.push(if token.ends_with('0') { '1' } else { '0' });
+        assert!(check_token_auth(&entries, &tampered).is_err());
+    }
+
+    #[tokio::test]
+    async fn token_constraints_are_enforced() {
+        use chrono::Duration as ChronoDuration;
+
+        use crate::{
+            auth::{TokenAuthDecision, decide_token_auth},
+            models::TokenConstraintViolation,
+        };
+
+        let store = load_test_store("token-constraints").await;
+        let now = Utc::now();
+        let (created, token) = store
+            .create_token_entry_with_constraints(
+                &AuditActor::system(),
+                "deploy".to_string(),
+                Vec::new(),
+                Some(now + ChronoDuration::hours(1)),
+                TokenEntryConstraints {
+                    not_before: Some(now),
+                    scopes: vec!["deploy".to_string()],
+                },
+            )
+            .await
+            .expect("constrained token entry should be created");
+        let entries = vec![
+            store
+                .backend
+                .get_token_entry(&created.meta.id)
+                .await
+                .unwrap()
+                .expect("token entry should exist"),
+        ];
+        let deploy = vec!["deploy".to_string()];
+
+        assert_eq!(
+            decide_token_auth(&entries, &token, &deploy, now).unwrap(),
+            TokenAuthDecision::Accepted("deploy".to_string())
+        );
+        assert_eq!(
+            decide_token_auth(&entries, &token, &[], now - ChronoDuration::seconds(1)).unwrap(),
+            TokenAuthDecision::Rejected(TokenConstraintViolation::NotYetValid)
+        );
+        assert_eq!(
+            decide_token_auth(&entries, &token, &[], now + ChronoDuration::hours(2)).unwrap(),
+            TokenAuthDecision::Rejected(TokenConstraintViolation::Expired)
+        );
+        assert_eq!(
+            decide_token_auth(&entries, &token, &["admin".to_string()], now).unwrap(),
+            TokenAuthDecision::Rejected(TokenConstraintViolation::MissingScopes)
+        );
+
+        let error = store
+            .create_token_entry_with_constraints(
+                &AuditActor::system(),
+                "backwards".to_string(),
+                Vec::new(),
+                Some(now),
+                TokenEntryConstraints {
+                    not_before: Some(now),
+                    scopes: Vec::new(),
+                },
+            )
+            .await
+            .expect_err("empty validity window should be rejected");
+        assert!(matches!(
+            error,
+            error::CredsManageError::InvalidEntry { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn disabled_and_expired_entries_and_groups_are_refused() {
+        use chrono::Duration as ChronoDuration;
+
+        use crate::{
+            auth::{TokenAuthDecision, check_basic_auth, decide_token_auth},
+            models::TokenConstraintViolation,
+        };
+
+        let store = load_test_store("availability").await;
+        let actor = AuditActor::system();
+        let basic = store
+            .create_basic_entry(
+                &actor,
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                Vec::new(),
+            )
+            .await
+            .unwrap();
+        let (token_entry, token) = store
+            .create_token_entry(&actor, "deploy".to_string(), Vec::new())
+            .await
+            .unwrap();
+
+        let disabled = store
+            .set_entry_availability(
+                &actor,
+                &token_entry.meta.id,
+                Availability::disabled(Some("rotated".to_string())),
+            )
+            .await
+            .unwrap();
+        assert!(disabled.meta.availability.disabled);
+        assert!(matches!(
+            store
+                .set_entry_availability_at_revision(
+                    &actor,
+                    &token_entry.meta.id,
+                    Some(token_entry.meta.revision),
+                    Availability::default()
+                )
+                .await,
+            Err(error::CredsManageError::RevisionMismatch { .. })
+        ));
+        let entries = vec![
+            store
+                .backend
+                .get_token_entry(&token_entry.meta.id)
+                .await
+                .unwrap()
+                .unwrap(),
+        ];
+        assert_eq!(
+            decide_token_auth(&entries, &token, &[], Utc::now()).unwrap(),
+            TokenAuthDecision::Rejected(TokenConstraintViolation::Disabled)
+        );
+        store
+            .set_entry_availability(&actor, &token_entry.meta.id, Availability::default())
+            .await
+            .unwrap();
+        let entries = vec![
+            store
+                .backend
+                .get_token_entry(&token_entry.meta.id)
+                .await
+                .unwrap()
+                .unwrap(),
+        ];
+        assert_eq!(
+            decide_token_auth(&entries, &token, &[], Utc::now()).unwrap(),
+            TokenAuthDecision::Accepted("deploy".to_string())
+        );
+
+        let expired = Availability {
+            expires_at: Some(Utc::now() - ChronoDuration::minutes(1)),
+            ..Availability::default()
+        };
+        store
+            .set_entry_availability(&actor, &basic.meta.id, expired.clone())
+            .await
+            .unwrap();
+        let basic_entries = vec![
+            store
+                .backend
+                .get_basic_entry(&basic.meta.id)
+                .await
+                .unwrap()
+                .unwrap(),
+        ];
+        assert_eq!(
+            check_basic_auth(&basic_entries, "ops", "secret123").unwrap(),
+            None
+        );
+
+        let group = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .set_group_availability_at_revision(
+                    &actor,
+                    &group.id,
+                    Some(group.revision + 1),
+                    expired.clone()
+                )
+                .await,
+            Err(error::CredsManageError::RevisionMismatch { .. })
+        ));
+        store
+            .set_group_availability_at_revision(&actor, &group.id, Some(group.revision), expired)
+            .await
+            .unwrap();
+        assert!(store.last_expiry_sweep().is_none());
+        let sweep = store.sweep_expired().await.unwrap();
+        assert_eq!(sweep.expired_entry_ids, vec![basic.meta.id.clone()]);
+        assert_eq!(sweep.expired_group_ids, vec![group.id.clone()]);
+        assert_eq!(store.last_expiry_sweep().unwrap().swept_at, sweep.swept_at);
+
+        let error = store
+            .set_entry_availability(
+                &actor,
+                &basic.meta.id,
+                Availability {
+                    disabled_reason: Some("no flag".to_string()),
+                    ..Availability::default()
+                },
+            )
+            .await
+            .expect_err("a reason without disabling should be rejected");
+        assert!(matches!(
+            error,
+            error::CredsManageError::InvalidEntry { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn nested_groups_resolve_members_transitively() {
+        let store = load_test_store("nested-groups").await;
+        let actor = AuditActor::system();
+        let readers = store
+            .create_group(&actor, Group::new("readers".to_string()), None)
+            .await
+            .unwrap();
+        let mut admins = Group::new("admins".to_string());
+        admins.parent_ids = vec![readers.id.clone()];
+        let admins = store.create_group(&actor, admins, None).await.unwrap();
+        let mut oncall = Group::new("oncall".to_string());
+        oncall.parent_ids = vec![admins.id.clone()];
+        let oncall = store.create_group(&actor, oncall, None).await.unwrap();
+
+        for (name, group_id) in [("reader", &readers.id), ("pager", &oncall.id)] {
+            store
+                .create_basic_entry(
+                    &actor,
+                    name.to_string(),
+                    name.to_string(),
+                    "secret123".to_string(),
+                    vec![group_id.clone()],
+                )
+                .await
+                .unwrap();
+        }
+        store
+            .create_token_entry(&actor, "deploy".to_string(), vec![admins.id.clone()])
+            .await
+            .unwrap();
+
+        let names = |entries: Vec<BasicAuthEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.meta.name).collect()
+        };
+        assert_eq!(
+            names(store.basic_entries_by_group_id(&readers.id).await.unwrap()),
+            vec!["reader", "pager"]
+        );
+        assert_eq!(
+            store
+                .token_entries_by_group_id(&readers.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store.entries_by_group_id(&readers.id).await.unwrap().len(),
+            1
+        );
+
+        store
+            .set_group_availability(&actor, &admins.id, Availability::disabled(None))
+            .await
+            .unwrap();
+        assert_eq!(
+            names(store.basic_entries_by_group_id(&readers.id).await.unwrap()),
+            vec!["reader"]
+        );
+
+        for parent_ids in [vec![oncall.id.clone()], vec![readers.id.clone()]] {
+            let error = store
+                .update_group(
+                    &actor,
+                    &readers.id,
+                    "readers".to_string(),
+                    None,
+                    Some(parent_ids),
+                )
+                .await
+                .expect_err("nesting a group below itself should be rejected");
+            assert!(matches!(error, error::CredsManageError::GroupCycle { .. }));
+        }
+        let mut orphan = Group::new("orphan".to_string());
+        orphan.parent_ids = vec!["missing".to_string()];
+        assert!(matches!(
+            store.create_group(&actor, orphan, None).await,
+            Err(error::CredsManageError::GroupNotFound { .. })
+        ));
+
+        store.delete_group(&actor, &admins.id).await.unwrap();
+        assert!(
+            store
+                .get_group(&oncall.id)
+                .await
+                .unwrap()
+                .parent_ids
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn entry_membership_changes_bump_group_revisions() {
+        let store = load_test_store("membership-revisions").await;
+        let actor = AuditActor::system();
+        let ops = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let entry = store
+            .create_basic_entry(
+                &actor,
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                vec![ops.id.clone()],
+            )
+            .await
+            .unwrap();
+        let joined = store.get_group(&ops.id).await.unwrap();
+        assert_eq!(joined.revision, ops.revision + 1);
+
+        store
+            .update_entry(&actor, &entry.meta.id, None, None, None, Some(Vec::new()))
+            .await
+            .unwrap();
+        let left = store.get_group(&ops.id).await.unwrap();
+        assert_eq!(left.revision, joined.revision + 1);
+
+        let error = store
+            .update_group_at_revision(
+                &actor,
+                &ops.id,
+                Some(joined.revision),
+                UpdateGroupRequest {
+                    name: "ops".to_string(),
+                    parent_ids: None,
+                    entry_ids: Some(vec![entry.meta.id.clone()]),
+                },
+            )
+            .await
+            .expect_err("a group revision from before the membership change is stale");
+        assert!(matches!(
+            error,
+            error::CredsManageError::RevisionMismatch { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn archive_import_merges_replaces_and_validates() {
+        use crate::{
+            archive::{ImportConflictPolicy, ImportCounts, ImportMode},
+            models::{AccessRuleEffect, PathPattern, PathRegex},
+        };
+
+        let actor = AuditActor::system();
+        let source = load_test_store("archive-source").await;
+        let ops = source
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        source
+            .create_basic_entry(
+                &actor,
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                vec![ops.id.clone()],
+            )
+            .await
+            .unwrap();
+        let (_, token) = source
+            .create_token_entry(&actor, "deploy".to_string(), vec![ops.id.clone()])
+            .await
+            .unwrap();
+        let archive = source.export_archive().await.unwrap();
+
+        let target = load_test_store("archive-target").await;
+        target
+            .create_group(&actor, Group::new("local".to_string()), None)
+            .await
+            .unwrap();
+        let merge = ImportOptions::default();
+
+        let dry_run = target
+            .import_archive(
+                &actor,
+                archive.clone(),
+                ImportOptions {
+                    dry_run: true,
+                    ..merge
+                },
+            )
+            .await
+            .unwrap();
+        assert!(dry_run.dry_run);
+        assert_eq!(dry_run.entries.created, 2);
+        assert!(target.list_entries().await.unwrap().is_empty());
+
+        let report = target
+            .import_archive(&actor, archive.clone(), merge)
+            .await
+            .unwrap();
+        assert_eq!(report.groups.created, 1);
+        assert_eq!(report.entries.created, 2);
+        assert_eq!(target.list_groups().await.unwrap().len(), 2);
+        let imported = target
+            .find_token_entry(&token)
+            .await
+            .unwrap()
+            .expect("imported token should verify");
+        assert_eq!(imported.meta.name, "deploy");
+
+        let error = target
+            .import_archive(&actor, archive.clone(), merge)
+            .await
+            .expect_err("existing ids should conflict");
+        assert!(matches!(
+            error,
+            error::CredsManageError::ImportConflict { .. }
+        ));
+        let skipped = target
+            .import_archive(
+                &actor,
+                archive.clone(),
+                ImportOptions {
+                    on_conflict: ImportConflictPolicy::Skip,
+                    ..merge
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            skipped.entries,
+            ImportCounts {
+                skipped: 2,
+                ..ImportCounts::default()
+            }
+        );
+
+        let replaced = target
+            .import_archive(
+                &actor,
+                archive.clone(),
+                ImportOptions {
+                    mode: ImportMode::Replace,
+                    ..merge
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(replaced.groups.deleted, 1);
+        assert_eq!(replaced.entries.updated, 2);
+        assert_eq!(target.list_groups().await.unwrap().len(), 1);
+
+        let mut dangling = archive.clone();
+        dangling.groups.clear();
+        let error = load_test_store("archive-dangling")
+            .await
+            .import_archive(&actor, dangling, merge)
+            .await
+            .expect_err("memberships of missing groups should be rejected");
+        assert!(matches!(
+            error,
+            error::CredsManageError::GroupNotFound { .. }
+        ));
+
+        let mut copied_token = archive.clone();
+        copied_token.groups.clear();
+        copied_token.basic_creds.clear();
+        let copy = &mut copied_token.token_creds[0];
+        copy.meta.id = Uuid::new_v4().to_string();
+        copy.meta.name = "deploy-copy".to_string();
+        copy.meta.group_ids.clear();
+        let error = target
+            .import_archive(&actor, copied_token.clone(), merge)
+            .await
+            .expect_err("a token already held by an entry should be rejected");
+        assert!(matches!(
+            error,
+            error::CredsManageError::DuplicateToken { .. }
+        ));
+        let mut doubled_token = copied_token.clone();
+        let mut second = doubled_token.token_creds[0].clone();
+        second.meta.id = Uuid::new_v4().to_string();
+        second.meta.name = "deploy-copy-2".to_string();
+        doubled_token.token_creds.push(second);
+        let error = load_test_store("archive-duplicate-token")
+            .await
+            .import_archive(&actor, doubled_token, merge)
+            .await
+            .expect_err("two archived entries with one token should be rejected");
+        assert!(matches!(
+            error,
+            error::CredsManageError::DuplicateToken { .. }
+        ));
+
+        let mut without_claim = archive.clone();
+        without_claim.groups[0].identity_access.claims.any_of = vec!["admins".to_string()];
+        assert!(matches!(
+            load_test_store("archive-identity-access")
+                .await
+                .import_archive(&actor, without_claim, merge)
+                .await,
+            Err(error::CredsManageError::InvalidEntry { .. })
+        ));
+
+        let mut invalid_rule = archive.clone();
+        invalid_rule.groups[0].access_rules = vec![AccessRule {
+            id: String::new(),
+            effect: AccessRuleEffect::Deny,
+            hosts: Vec::new(),
+            methods: Vec::new(),
+            path: Some(PathPattern::Regex(PathRegex::new("/admin/("))),
+        }];
+        assert!(matches!(
+            load_test_store("archive-access-rules")
+                .await
+                .import_archive(&actor, invalid_rule, merge)
+                .await,
+            Err(error::CredsManageError::InvalidEntry { .. })
+        ));
+
+        let mut future = archive;
+        future.version += 1;
+        assert!(matches!(
+            target.import_archive(&actor, future, merge).await,
+            Err(error::CredsManageError::InvalidArchive { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn entry_usage_is_batched_until_flush() {
+        let store = load_test_store("entry-usage").await;
+        let actor = AuditActor::system();
+        let created = store
+            .create_basic_entry(
+                &actor,
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                Vec::new(),
+            )
+            .await
+            .expect("basic entry should be created");
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        store.record_entry_use(&created.meta.id, true, Some(ip));
+        store.record_entry_use(&created.meta.id, false, None);
+        store.record_entry_use("missing-entry", true, None);
+        assert!(
+            store
+                .get_entry(&created.meta.id)
+                .await
+                .unwrap()
+                .meta
+                .usage
+                .is_empty(),
+            "uses must not be persisted before a flush"
+        );
+
+        assert_eq!(store.flush_entry_usage().await.unwrap(), 2);
+        store.record_entry_use(&created.meta.id, true, None);
+        store.flush_entry_usage().await.unwrap();
+
+        let entry = store.get_entry(&created.meta.id).await.unwrap();
+        assert_eq!(entry.meta.usage.success_count, 2);
+        assert_eq!(entry.meta.usage.failure_count, 1);
+        assert_eq!(entry.meta.usage.last_client_ip, Some(ip));
+        assert!(entry.meta.usage.last_success_at.is_some());
+        assert!(entry.meta.usage.last_failure_at.is_some());
+        assert_eq!(entry.meta.updated_at, created.meta.updated_at);
+        assert_eq!(entry.meta.usage.counts_within(Utc::now(), 1), (2, 1));
+        assert_eq!(store.flush_entry_usage().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_entry_and_membership_changes() {
+        let store = load_test_store("change-events").await;
+        let actor = AuditActor::system();
+        let mut changes = store.subscribe();
+        let mut drain =
+            || std::iter::from_fn(|| changes.try_recv().ok()).collect::<Vec<StoreChangeEvent>>();
+
+        let ops = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let mut oncall = Group::new("oncall".to_string());
+        oncall.parent_ids = vec![ops.id.clone()];
+        let oncall = store.create_group(&actor, oncall, None).await.unwrap();
+        let created = store
+            .create_basic_entry(
+                &actor,
+                "ops-user".to_string(),
+                "ops".to_string(),
+                "secret123".to_string(),
+                vec![ops.id.clone()],
+            )
+            .await
+            .unwrap();
+        let entry_id = created.meta.id.clone();
+        assert_eq!(
+            drain(),
+            vec![
+                StoreChangeEvent::GroupCreated {
+                    group_id: ops.id.clone()
+                },
+                StoreChangeEvent::GroupCreated {
+                    group_id: oncall.id.clone()
+                },
+                StoreChangeEvent::EntryCreated {
+                    entry_id: entry_id.clone()
+                },
+                StoreChangeEvent::GroupMembershipChanged {
+                    group_id: ops.id.clone(),
+                    added_entry_ids: vec![entry_id.clone()],
+                    removed_entry_ids: Vec::new(),
+                },
+            ]
+        );
+
+        store.record_entry_use(&entry_id, true, None);
+        store.flush_entry_usage().await.unwrap();
+        assert!(drain().is_empty(), "usage must not be published");
+
+        store
+            .update_entry(
+                &actor,
+                &entry_id,
+                None,
+                None,
+                None,
+                Some(vec![oncall.id.clone()]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            drain(),
+            vec![
+                StoreChangeEvent::EntryUpdated {
+                    entry_id: entry_id.clone()
+                },
+                StoreChangeEvent::GroupMembershipChanged {
+                    group_id: ops.id.clone(),
+                    added_entry_ids: Vec::new(),
+                    removed_entry_ids: vec![entry_id.clone()],
+                },
+                StoreChangeEvent::GroupMembershipChanged {
+                    group_id: oncall.id.clone(),
+                    added_entry_ids: vec![entry_id.clone()],
+                    removed_entry_ids: Vec::new(),
+                },
+            ]
+        );
+
+        store.delete_group(&actor, &ops.id).await.unwrap();
+        store.delete_entry(&actor, &entry_id).await.unwrap();
+        assert_eq!(
+            drain(),
+            vec![
+                StoreChangeEvent::GroupDeleted {
+                    group_id: ops.id.clone()
+                },
+                StoreChangeEvent::GroupUpdated {
+                    group_id: oncall.id.clone()
+                },
+                StoreChangeEvent::EntryDeleted {
+                    entry_id: entry_id.clone()
+                },
+                StoreChangeEvent::GroupMembershipChanged {
+                    group_id: oncall.id.clone(),
+                    added_entry_ids: Vec::new(),
+                    removed_entry_ids: vec![entry_id],
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn group_identity_access_is_validated_and_checks_nested_claims() {
+        use std::collections::HashMap;
+
+        use crate::{
+            error::CredsManageError,
+            models::{ClaimRequirements, ClaimViolation, IdentityAccess},
+        };
+
+        let store = load_test_store("identity-access").await;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new("apps".to_string()), None)
+            .await
+            .unwrap();
+
+        let without_claim = IdentityAccess {
+            session: true,
+            claims: ClaimRequirements {
+                any_of: vec!["admins".to_string()],
+                ..ClaimRequirements::default()
+            },
+            ..IdentityAccess::default()
+        };
+        assert_eq!(
+            without_claim.claims.check(None, &HashMap::new()),
+            Err(ClaimViolation::MissingGroup)
+        );
+        assert!(matches!(
+            store
+                .set_group_identity_access(&actor, &group.id, without_claim)
+                .await,
+            Err(CredsManageError::InvalidEntry { .. })
+        ));
+
+        let identity_access = IdentityAccess {
+            session: true,
+            bearer: true,
+            claims: ClaimRequirements {
+                issuer: Some("https://issuer.example.com/".to_string()),
+                groups_claim: Some("realm_access.roles".to_string()),
+                any_of: vec!["admins".to_string(), "ops".to_string()],
+            },
+        };
+        let updated = store
+            .set_group_identity_access(&actor, &group.id, identity_access.clone())
+            .await
+            .unwrap();
+        assert_eq!(updated.identity_access, identity_access);
+        assert_eq!(updated.revision, group.revision + 1);
+        assert!(matches!(
+            store
+                .set_group_identity_access_at_revision(
+                    &actor,
+                    &group.id,
+                    Some(group.revision),
+                    IdentityAccess::default()
+                )
+                .await,
+            Err(CredsManageError::RevisionMismatch { .. })
+        ));
+
+        let claims: HashMap<String, serde_json::Value> = serde_json::from_value(
+            serde_json::json!({ "realm_access": { "roles": ["viewers", "ops"] } }),
+        )
+        .unwrap();
+        let requirements = &updated.identity_access.claims;
+        assert_eq!(
+            requirements.check(Some("https://issuer.example.com"), &claims),
+            Ok(())
+        );
+        assert_eq!(
+            requirements.check(Some("https://other.example.com"), &claims),
+            Err(ClaimViolation::IssuerMismatch)
+        );
+        let viewer: HashMap<String, serde_json::Value> =
+            serde_json::from_value(serde_json::json!({ "realm_access": { "roles": "viewers" } }))
+                .unwrap();
+        assert_eq!(
+            requirements.check(Some("https://issuer.example.com"), &viewer),
+            Err(ClaimViolation::MissingGroup)
+        );
+    }
+
+    #[tokio::test]
+    async fn group_access_rules_get_ids_and_first_match_decides() {
+        use crate::{
+            auth::{AccessDecision, decide_access},
+            error::CredsManageError,
+            models::{AccessRequest, AccessRule, AccessRuleEffect, PathPattern, PathRegex},
+        };
+
+        let store = load_test_store("access-rules").await;
+        let actor = AuditActor::system();
+        let group = store
+            .create_group(&actor, Group::new("apps".to_string()), None)
+            .await
+            .unwrap();
+        let rule = |id: &str, effect, methods: &[&str], path: PathPattern| AccessRule {
+            id: id.to_string(),
+            effect,
+            hosts: vec!["*.example.com".to_string()],
+            methods: methods.iter().map(|method| method.to_string()).collect(),
+            path: Some(path),
+        };
+
+        let invalid_regex = rule(
+            "bad",
+            AccessRuleEffect::Allow,
+            &[],
+            PathPattern::Regex(PathRegex::new("/api/(")),
+        );
+        assert!(matches!(
+            store
+                .set_group_access_rules(&actor, &group.id, vec![invalid_regex])
+                .await,
+            Err(CredsManageError::InvalidEntry { .. })
+        ));
+        let duplicate = rule(
+            "dup",
+            AccessRuleEffect::Allow,
+            &[],
+            PathPattern::Glob("/*".to_string()),
+        );
+        assert!(matches!(
+            store
+                .set_group_access_rules(&actor, &group.id, vec![duplicate.clone(), duplicate])
+                .await,
+            Err(CredsManageError::InvalidEntry { .. })
+        ));
+
+        let updated = store
+            .set_group_access_rules(
+                &actor,
+                &group.id,
+                vec![
+                    rule(
+                        "no-admin",
+                        AccessRuleEffect::Deny,
+                        &[],
+                        PathPattern::Regex(PathRegex::new("/api/admin(/.*)?")),
+                    ),
+                    rule(
+                        "",
+                        AccessRuleEffect::Allow,
+                        &["get", "HEAD"],
+                        PathPattern::Glob("/api/*".to_string()),
+                    ),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.revision, group.revision + 1);
+        let generated_id = updated.access_rules[1].id.clone();
+        assert!(!generated_id.is_empty());
+        assert!(matches!(
+            store
+                .set_group_access_rules_at_revision(
+                    &actor,
+                    &group.id,
+                    Some(group.revision),
+                    Vec::new()
+                )
+                .await,
+            Err(CredsManageError::RevisionMismatch { .. })
+        ));
+
+        let decide = |method, host, path| match decide_access(
+            &updated.access_rules,
+            &AccessRequest { method, host, path },
+        ) {
+            AccessDecision::Allowed(rule) => format!("allowed:{}", rule.id),
+            AccessDecision::Denied(rule) => format!("denied:{}", rule.id),
+            AccessDecision::NoMatch => "no_match".to_string(),
+            AccessDecision::Unrestricted => "unrestricted".to_string(),
+        };
+        assert_eq!(
+            decide(
+                Some("GET"),
+                Some("App.Example.com:8443"),
+                Some("/api/items")
+            ),
+            format!("allowed:{generated_id}")
+        );
+        assert_eq!(
+            decide(Some("POST"), Some("app.example.com"), Some("/api/items")),
+            "no_match"
+        );
+        assert_eq!(
+            decide(Some("GET"), Some("app.example.org"), Some("/api/items")),
+            "no_match"
+        );
+        assert_eq!(
+            decide(
+                Some("GET"),
+                Some("app.example.com"),
+                Some("/api/admin/users")
+            ),
+            "denied:no-admin"
+        );
+        // A request whose path the proxy did not report is caught by the
+        // deny rule rather than allowed.
+        assert_eq!(
+            decide(Some("GET"), Some("app.example.com"), None),
+            "denied:no-admin"
+        );
+        assert_eq!(
+            decide_access(&[], &AccessRequest::default()),
+            AccessDecision::Unrestricted
+        );
+    }
+
+    #[test]
+    fn access_rules_match_the_normalized_path() {
+        use crate::{
+            auth::{AccessDecision, decide_access},
+            models::{AccessRequest, AccessRule, AccessRuleEffect, PathPattern, normalize_path},
+        };
+
+        let rules = vec![
+            AccessRule {
+                id: "no-admin".to_string(),
+                effect: AccessRuleEffect::Deny,
+                hosts: Vec::new(),
+                methods: Vec::new(),
+                path: Some(PathPattern::Glob("/admin*".to_string())),
+            },
+            AccessRule {
+                id: "api".to_string(),
+                effect: AccessRuleEffect::Allow,
+                hosts: Vec::new(),
+                methods: Vec::new(),
+                path: Some(PathPattern::Glob("/api/*".to_string())),
+            },
+        ];
+        let decide = |path| match decide_access(
+            &rules,
+            &AccessRequest {
+                path: Some(path),
+                ..Default::default()
+            },
+        ) {
+            AccessDecision::Allowed(rule) => format!("allowed:{}", rule.id),
+            AccessDecision::Denied(rule) => format!("denied:{}", rule.id),
+            AccessDecision::NoMatch => "no_match".to_string(),
+            AccessDecision::Unrestricted => "unrestricted".to_string(),
+        };
+
+        // Dot segments cannot climb out of an allowed prefix.
+        assert_eq!(decide("/api/../admin"), "denied:no-admin");
+        assert_eq!(decide("/api/%2e%2E/admin"), "denied:no-admin");
+        assert_eq!(decide("/api/../../secret"), "no_match");
+        // Duplicate slashes and encoded unreserved characters cannot slip
+        // past a deny rule.
+        assert_eq!(decide("//admin"), "denied:no-admin");
+        assert_eq!(decide("/%61dmin/users"), "denied:no-admin");
+        assert_eq!(decide("/api/./items//1"), "allowed:api");
+
+        assert_eq!(normalize_path("/api/./items//1/"), "/api/items/1/");
+        assert_eq!(normalize_path("/api/items/.."), "/api/");
+        assert_eq!(normalize_path("/a%2fb/%7e%zz"), "/a%2Fb/~%zz");
+        assert_eq!(normalize_path("/../.."), "/");
+    }
+
+    #[test]
+    fn invalid_path_regex_from_the_data_file_fails_closed() {
+        use crate::{
+            auth::{AccessDecision, decide_access},
+            models::{AccessRequest, AccessRule},
+        };
+
+        // Hand-edited rules never went through `validate`.
+        let rules: Vec<AccessRule> = serde_json::from_value(serde_json::json!([
+            {"id": "no-admin", "effect": "deny", "path": {"type": "regex", "value": "/admin("}},
+            {"id": "open", "effect": "allow", "path": {"type": "regex", "value": "/api/(x"}},
+            {"id": "rest", "effect": "allow"},
+        ]))
+        .unwrap();
+        assert!(rules[0].validate().is_err());
+        assert_eq!(
+            serde_json::to_value(&rules[0]).unwrap()["path"]["value"],
+            "/admin("
+        );
+
+        let decision = decide_access(
+            &rules,
+            &AccessRequest {
+                path: Some("/api/x"),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(decision, AccessDecision::Denied(rule) if rule.id == "no-admin"));
+
+        let decision = decide_access(
+            &rules[1..],
+            &AccessRequest {
+                path: Some("/api/x"),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(decision, AccessDecision::Allowed(rule) if rule.id == "rest"));
+    }
+
+    #[test]
+    fn rolling_usage_counts_cover_the_retention_window() {
+        use chrono::{Duration, TimeZone};
+
+        use crate::models::USAGE_RETENTION_DAYS;
+
+        let start = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let mut usage = AuthEntryUsage::default();
+        usage.record(true, None, start);
+        usage.record(false, None, start + Duration::hours(1));
+
+        let mut batch = AuthEntryUsage::default();
+        batch.record(true, None, start + Duration::days(6));
+        batch.record(true, None, start + Duration::days(6));
+        usage.merge(&batch);
+
+        let last_use = start + Duration::days(6);
+        assert_eq!(usage.counts_within(last_use, 1), (2, 0));
+        assert_eq!(usage.counts_within(last_use, 7), (3, 1));
+        assert_eq!(usage.counts_within(last_use + Duration::days(7), 7), (0, 0));
+
+        usage.record(true, None, start + Duration::days(40));
+        assert_eq!(usage.daily.len(), 1);
+        assert_eq!(
+            usage.counts_within(start + Duration::days(40), USAGE_RETENTION_DAYS),
+            (1, 0)
+        );
+        // Lifetime totals are not windowed.
+        assert_eq!((usage.success_count, usage.failure_count), (4, 1));
+    }
+
+    #[test]
+    fn update_entry_rejects_empty_password_as_invalid_credentials_format() {
+        let error = ensure_basic_entry_update_material_valid(None, Some(""))
+            .expect_err("empty password should be rejected");
+
+        match error {
+            error::CredsManageError::Creds {
+                source: CredsError::InvalidCredentialsFormat { message },
+            } => {
+                assert_eq!(message, "Basic entry password must not be empty");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+}
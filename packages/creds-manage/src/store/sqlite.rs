@@ -0,0 +1,1412 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior, params};
+use securitydept_creds::StaticTokenAuthCred;
+use snafu::ResultExt;
+
+use super::backend::{
+    BackendFuture, CredsManageBackend, EntryUpdate, ImportPlan, ensure_group_parents_valid,
+    ensure_revision, replace_group_ids, update_group_membership,
+};
+use crate::{
+    archive::ImportReport,
+    error::{self, CredsManageResult},
+    models::{
+        AccessRule, AuthEntry, AuthEntryKind, AuthEntryMeta, AuthEntryUsage, Availability,
+        BasicAuthEntry, DataFile, Group, IdentityAccess, TokenAuthEntry,
+    },
+};
+
+const SCHEMA_VERSION: i32 = 1;
+
+/// Entries and groups are stored as their serialized JSON next to the
+/// columns that are looked up or kept unique. `entry_groups` mirrors each
+/// entry's `group_ids` so entries can be found by group without scanning.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS groups (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS entries (
+    id TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    token_hash TEXT UNIQUE,
+    created_at INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS entries_created_at ON entries (created_at);
+CREATE TABLE IF NOT EXISTS entry_groups (
+    entry_id TEXT NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+    group_id TEXT NOT NULL REFERENCES groups (id) ON DELETE CASCADE,
+    PRIMARY KEY (entry_id, group_id)
+);
+CREATE INDEX IF NOT EXISTS entry_groups_group_id ON entry_groups (group_id);
+";
+
+/// SQLite-backed storage for auth entries and groups.
+///
+/// Every operation runs on a blocking thread against one shared connection;
+/// writes use immediate transactions so other processes using the same
+/// database (e.g. the CLI next to the server) are serialized by SQLite.
+pub struct SqliteBackend {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteBackend {
+    /// Open (or create) the database at `path`.
+    pub async fn open(path: impl AsRef<Path>) -> CredsManageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let connection = tokio::task::spawn_blocking(move || -> CredsManageResult<Connection> {
+            let mut connection = connect(&path)?;
+            initialize(&mut connection, None)?;
+            Ok(connection)
+        })
+        .await
+        .expect("store open task panicked")?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    async fn read<T, F>(&self, op: F) -> CredsManageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> CredsManageResult<T> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let connection = connection.lock().expect("sqlite connection lock poisoned");
+            op(&connection)
+        })
+        .await
+        .expect("store read task panicked")
+    }
+
+    async fn write<T, F>(&self, op: F) -> CredsManageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Transaction<'_>) -> CredsManageResult<T> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection.lock().expect("sqlite connection lock poisoned");
+            let tx = connection
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .context(error::DatabaseSnafu)?;
+            let result = op(&tx)?;
+            tx.commit().context(error::DatabaseSnafu)?;
+            Ok(result)
+        })
+        .await
+        .expect("store mutate task panicked")
+    }
+}
+
+/// Create the database at `path` from an existing data file.
+///
+/// Does nothing and returns `Ok(false)` when the database was already
+/// initialized, so the data file is imported at most once.
+pub fn import_data_file(path: &Path, data: &DataFile) -> CredsManageResult<bool> {
+    let mut connection = connect(path)?;
+    initialize(&mut connection, Some(data))
+}
+
+/// All groups and entries of the database at `path`, `None` when there is
+/// no database.
+pub fn export_database(path: &Path) -> CredsManageResult<Option<DataFile>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut connection = connect(path)?;
+    initialize(&mut connection, None)?;
+    load_data(&connection).map(Some)
+}
+
+fn connect(path: &Path) -> CredsManageResult<Connection> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent).context(error::DataWriteSnafu)?;
+    }
+
+    let connection = Connection::open(path).context(error::DatabaseSnafu)?;
+    connection
+        .busy_timeout(Duration::from_secs(5))
+        .context(error::DatabaseSnafu)?;
+    connection
+        .pragma_update(None, "journal_mode", "WAL")
+        .context(error::DatabaseSnafu)?;
+    connection
+        .pragma_update(None, "foreign_keys", true)
+        .context(error::DatabaseSnafu)?;
+    Ok(connection)
+}
+
+/// Create the schema if the database is new, filling it from `data` when
+/// given. Returns whether the schema was created.
+fn initialize(connection: &mut Connection, data: Option<&DataFile>) -> CredsManageResult<bool> {
+    let tx = connection
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context(error::DatabaseSnafu)?;
+    let version: i32 = tx
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .context(error::DatabaseSnafu)?;
+    if version > SCHEMA_VERSION {
+        return Err(error::CredsManageError::InvalidConfig {
+            message: format!(
+                "Unsupported creds database schema version: {version} (expected at most \
+                 {SCHEMA_VERSION})"
+            ),
+        });
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(false);
+    }
+
+    tx.execute_batch(SCHEMA).context(error::DatabaseSnafu)?;
+    if let Some(data) = data {
+        insert_data(&tx, data)?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+        .context(error::DatabaseSnafu)?;
+    tx.commit().context(error::DatabaseSnafu)?;
+    Ok(true)
+}
+
+fn insert_data(tx: &Transaction<'_>, data: &DataFile) -> CredsManageResult<()> {
+    for group in &data.groups {
+        insert_group_row(tx, group)?;
+    }
+    for entry in &data.basic_creds {
+        insert_entry(tx, &StoredEntry::Basic(entry.clone()))?;
+    }
+    for entry in &data.token_creds {
+        insert_entry(tx, &StoredEntry::Token(entry.clone()))?;
+    }
+    Ok(())
+}
+
+/// All groups and entries, in the order of a data file.
+fn load_data(connection: &Connection) -> CredsManageResult<DataFile> {
+    let entries = query_entries(
+        connection,
+        "SELECT kind, data FROM entries ORDER BY created_at, rowid",
+        [],
+    )?;
+    let mut data = DataFile {
+        groups: all_groups(connection)?,
+        ..DataFile::default()
+    };
+    for entry in entries {
+        match entry {
+            StoredEntry::Basic(entry) => data.basic_creds.push(entry),
+            StoredEntry::Token(entry) => data.token_creds.push(entry),
+        }
+    }
+    Ok(data)
+}
+
+enum StoredEntry {
+    Basic(BasicAuthEntry),
+    Token(TokenAuthEntry),
+}
+
+impl StoredEntry {
+    fn decode(kind: &str, data: &str) -> CredsManageResult<Self> {
+        match kind {
+            "basic" => Ok(Self::Basic(
+                serde_json::from_str(data).context(error::DataParseSnafu)?,
+            )),
+            "token" => Ok(Self::Token(
+                serde_json::from_str(data).context(error::DataParseSnafu)?,
+            )),
+            other => Err(error::CredsManageError::InvalidEntry {
+                message: format!("Unknown stored entry kind: {other}"),
+            }),
+        }
+    }
+
+    fn kind(&self) -> AuthEntryKind {
+        match self {
+            Self::Basic(_) => AuthEntryKind::Basic,
+            Self::Token(_) => AuthEntryKind::Token,
+        }
+    }
+
+    fn meta(&self) -> &AuthEntryMeta {
+        match self {
+            Self::Basic(entry) => &entry.meta,
+            Self::Token(entry) => &entry.meta,
+        }
+    }
+
+    fn meta_mut(&mut self) -> &mut AuthEntryMeta {
+        match self {
+            Self::Basic(entry) => &mut entry.meta,
+            Self::Token(entry) => &mut entry.meta,
+        }
+    }
+
+    fn token_hash(&self) -> Option<&str> {
+        match self {
+            Self::Basic(_) => None,
+            Self::Token(entry) => Some(entry.token_hash()),
+        }
+    }
+
+    fn encode(&self) -> CredsManageResult<String> {
+        match self {
+            Self::Basic(entry) => serde_json::to_string(entry),
+            Self::Token(entry) => serde_json::to_string(entry),
+        }
+        .context(error::DataSerializeSnafu)
+    }
+
+    fn to_auth_entry(&self) -> AuthEntry {
+        match self {
+            Self::Basic(entry) => AuthEntry::from(entry),
+            Self::Token(entry) => AuthEntry::from(entry),
+        }
+    }
+}
+
+fn kind_column(kind: AuthEntryKind) -> &'static str {
+    match kind {
+        AuthEntryKind::Basic => "basic",
+        AuthEntryKind::Token => "token",
+    }
+}
+
+fn query_entries(
+    connection: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> CredsManageResult<Vec<StoredEntry>> {
+    let mut statement = connection
+        .prepare_cached(sql)
+        .context(error::DatabaseSnafu)?;
+    let rows = statement
+        .query_map(params, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .context(error::DatabaseSnafu)?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (kind, data) = row.context(error::DatabaseSnafu)?;
+        entries.push(StoredEntry::decode(&kind, &data)?);
+    }
+    Ok(entries)
+}
+
+fn load_entry(connection: &Connection, id: &str) -> CredsManageResult<Option<StoredEntry>> {
+    Ok(query_entries(
+        connection,
+        "SELECT kind, data FROM entries WHERE id = ?1",
+        params![id],
+    )?
+    .pop())
+}
+
+fn load_entries_by_groups(
+    connection: &Connection,
+    group_ids: &[String],
+    kind: AuthEntryKind,
+) -> CredsManageResult<Vec<StoredEntry>> {
+    query_entries(
+        connection,
+        "SELECT e.kind, e.data FROM entries e
+         WHERE e.kind = ?2 AND EXISTS (
+             SELECT 1 FROM entry_groups g
+             WHERE g.entry_id = e.id AND g.group_id IN (SELECT value FROM json_each(?1))
+         )
+         ORDER BY e.created_at, e.rowid",
+        params![
+            serde_json::to_string(group_ids).context(error::DataSerializeSnafu)?,
+            kind_column(kind)
+        ],
+    )
+}
+
+fn query_groups(
+    connection: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> CredsManageResult<Vec<Group>> {
+    let mut statement = connection
+        .prepare_cached(sql)
+        .context(error::DatabaseSnafu)?;
+    let rows = statement
+        .query_map(params, |row| row.get::<_, String>(0))
+        .context(error::DatabaseSnafu)?;
+
+    let mut groups = Vec::new();
+    for row in rows {
+        let data = row.context(error::DatabaseSnafu)?;
+        groups.push(serde_json::from_str(&data).context(error::DataParseSnafu)?);
+    }
+    Ok(groups)
+}
+
+fn load_group(connection: &Connection, id: &str) -> CredsManageResult<Option<Group>> {
+    Ok(query_groups(
+        connection,
+        "SELECT data FROM groups WHERE id = ?1",
+        params![id],
+    )?
+    .pop())
+}
+
+fn all_groups(connection: &Connection) -> CredsManageResult<Vec<Group>> {
+    query_groups(connection, "SELECT data FROM groups ORDER BY rowid", [])
+}
+
+fn save_group(tx: &Transaction<'_>, group: &Group) -> CredsManageResult<()> {
+    tx.execute(
+        "UPDATE groups SET name = ?2, data = ?3 WHERE id = ?1",
+        params![
+            group.id,
+            group.name,
+            serde_json::to_string(group).context(error::DataSerializeSnafu)?,
+        ],
+    )
+    .context(error::DatabaseSnafu)?;
+    Ok(())
+}
+
+/// Bump the revisions of `group_ids` after entries joined or left them.
+fn mark_groups_updated(tx: &Transaction<'_>, group_ids: &[String]) -> CredsManageResult<()> {
+    for group_id in group_ids {
+        if let Some(mut group) = load_group(tx, group_id)? {
+            group.mark_updated();
+            save_group(tx, &group)?;
+        }
+    }
+    Ok(())
+}
+
+fn exists(
+    connection: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> CredsManageResult<bool> {
+    connection
+        .query_row(sql, params, |_| Ok(()))
+        .optional()
+        .map(|row| row.is_some())
+        .context(error::DatabaseSnafu)
+}
+
+fn ensure_entry_name_is_unique(
+    connection: &Connection,
+    candidate_name: &str,
+    current_entry_id: &str,
+) -> CredsManageResult<()> {
+    if exists(
+        connection,
+        "SELECT 1 FROM entries WHERE name = ?1 AND id != ?2",
+        params![candidate_name, current_entry_id],
+    )? {
+        return Err(error::CredsManageError::DuplicateEntryName {
+            name: candidate_name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn ensure_groups_exist(connection: &Connection, group_ids: &[String]) -> CredsManageResult<()> {
+    for group_id in group_ids {
+        if !exists(
+            connection,
+            "SELECT 1 FROM groups WHERE id = ?1",
+            params![group_id],
+        )? {
+            return Err(error::CredsManageError::GroupNotFound {
+                id: group_id.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn ensure_group_name_is_unique(
+    connection: &Connection,
+    candidate_name: &str,
+    current_group_id: &str,
+) -> CredsManageResult<()> {
+    if exists(
+        connection,
+        "SELECT 1 FROM groups WHERE name = ?1 AND id != ?2",
+        params![candidate_name, current_group_id],
+    )? {
+        return Err(error::CredsManageError::DuplicateGroupName {
+            name: candidate_name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn insert_group_row(tx: &Transaction<'_>, group: &Group) -> CredsManageResult<()> {
+    ensure_group_name_is_unique(tx, &group.name, &group.id)?;
+    tx.execute(
+        "INSERT INTO groups (id, name, data) VALUES (?1, ?2, ?3)",
+        params![
+            group.id,
+            group.name,
+            serde_json::to_string(group).context(error::DataSerializeSnafu)?,
+        ],
+    )
+    .context(error::DatabaseSnafu)?;
+    Ok(())
+}
+
+fn write_memberships(tx: &Transaction<'_>, entry: &StoredEntry) -> CredsManageResult<()> {
+    let meta = entry.meta();
+    tx.execute(
+        "DELETE FROM entry_groups WHERE entry_id = ?1",
+        params![meta.id],
+    )
+    .context(error::DatabaseSnafu)?;
+    for group_id in &meta.group_ids {
+        tx.execute(
+            "INSERT OR IGNORE INTO entry_groups (entry_id, group_id) VALUES (?1, ?2)",
+            params![meta.id, group_id],
+        )
+        .context(error::DatabaseSnafu)?;
+    }
+    Ok(())
+}
+
+fn insert_entry(tx: &Transaction<'_>, entry: &StoredEntry) -> CredsManageResult<()> {
+    let meta = entry.meta();
+    ensure_entry_name_is_unique(tx, &meta.name, &meta.id)?;
+    ensure_groups_exist(tx, &meta.group_ids)?;
+
+    tx.execute(
+        "INSERT INTO entries (id, kind, name, token_hash, created_at, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            meta.id,
+            kind_column(entry.kind()),
+            meta.name,
+            entry.token_hash(),
+            meta.created_at.timestamp_micros(),
+            entry.encode()?,
+        ],
+    )
+    .context(error::DatabaseSnafu)?;
+    write_memberships(tx, entry)
+}
+
+fn save_entry(tx: &Transaction<'_>, entry: &StoredEntry) -> CredsManageResult<()> {
+    let meta = entry.meta();
+    tx.execute(
+        "UPDATE entries SET name = ?2, token_hash = ?3, data = ?4 WHERE id = ?1",
+        params![meta.id, meta.name, entry.token_hash(), entry.encode()?],
+    )
+    .context(error::DatabaseSnafu)?;
+    write_memberships(tx, entry)
+}
+
+fn group_member_ids(connection: &Connection, group_id: &str) -> CredsManageResult<Vec<String>> {
+    let mut statement = connection
+        .prepare_cached("SELECT entry_id FROM entry_groups WHERE group_id = ?1")
+        .context(error::DatabaseSnafu)?;
+    let rows = statement
+        .query_map(params![group_id], |row| row.get(0))
+        .context(error::DatabaseSnafu)?;
+    rows.collect::<Result<_, _>>().context(error::DatabaseSnafu)
+}
+
+fn basic_entries(entries: Vec<StoredEntry>) -> Vec<BasicAuthEntry> {
+    entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            StoredEntry::Basic(entry) => Some(entry),
+            StoredEntry::Token(_) => None,
+        })
+        .collect()
+}
+
+fn token_entries(entries: Vec<StoredEntry>) -> Vec<TokenAuthEntry> {
+    entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            StoredEntry::Token(entry) => Some(entry),
+            StoredEntry::Basic(_) => None,
+        })
+        .collect()
+}
+
+impl CredsManageBackend for SqliteBackend {
+    fn list_entries(&self) -> BackendFuture<'_, Vec<AuthEntry>> {
+        Box::pin(self.read(|connection| {
+            Ok(query_entries(
+                connection,
+                "SELECT kind, data FROM entries ORDER BY created_at, rowid",
+                [],
+            )?
+            .iter()
+            .map(StoredEntry::to_auth_entry)
+            .collect())
+        }))
+    }
+
+    fn get_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<AuthEntry>> {
+        let id = id.to_string();
+        Box::pin(self.read(move |connection| {
+            Ok(load_entry(connection, &id)?.map(|entry| entry.to_auth_entry()))
+        }))
+    }
+
+    fn get_basic_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<BasicAuthEntry>> {
+        let id = id.to_string();
+        Box::pin(self.read(move |connection| {
+            Ok(basic_entries(load_entry(connection, &id)?.into_iter().collect()).pop())
+        }))
+    }
+
+    fn get_token_entry<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<TokenAuthEntry>> {
+        let id = id.to_string();
+        Box::pin(self.read(move |connection| {
+            Ok(token_entries(load_entry(connection, &id)?.into_iter().collect()).pop())
+        }))
+    }
+
+    fn find_token_entry_by_hash<'a>(
+        &'a self,
+        token_hash: &'a str,
+    ) -> BackendFuture<'a, Option<TokenAuthEntry>> {
+        let token_hash = token_hash.to_string();
+        Box::pin(self.read(move |connection| {
+            let entries = query_entries(
+                connection,
+                "SELECT kind, data FROM entries WHERE token_hash = ?1",
+                params![token_hash],
+            )?;
+            Ok(token_entries(entries).pop())
+        }))
+    }
+
+    fn basic_entries_by_group_ids<'a>(
+        &'a self,
+        group_ids: &'a [String],
+    ) -> BackendFuture<'a, Vec<BasicAuthEntry>> {
+        let group_ids = group_ids.to_vec();
+        Box::pin(self.read(move |connection| {
+            Ok(basic_entries(load_entries_by_groups(
+                connection,
+                &group_ids,
+                AuthEntryKind::Basic,
+            )?))
+        }))
+    }
+
+    fn token_entries_by_group_ids<'a>(
+        &'a self,
+        group_ids: &'a [String],
+    ) -> BackendFuture<'a, Vec<TokenAuthEntry>> {
+        let group_ids = group_ids.to_vec();
+        Box::pin(self.read(move |connection| {
+            Ok(token_entries(load_entries_by_groups(
+                connection,
+                &group_ids,
+                AuthEntryKind::Token,
+            )?))
+        }))
+    }
+
+    fn insert_basic_entry(&self, entry: BasicAuthEntry) -> BackendFuture<'_, AuthEntry> {
+        Box::pin(self.write(move |tx| {
+            let entry = StoredEntry::Basic(entry);
+            insert_entry(tx, &entry)?;
+            mark_groups_updated(tx, &entry.meta().group_ids)?;
+            Ok(entry.to_auth_entry())
+        }))
+    }
+
+    fn insert_token_entry(&self, entry: TokenAuthEntry) -> BackendFuture<'_, AuthEntry> {
+        Box::pin(self.write(move |tx| {
+            let entry = StoredEntry::Token(entry);
+            insert_entry(tx, &entry)?;
+            mark_groups_updated(tx, &entry.meta().group_ids)?;
+            Ok(entry.to_auth_entry())
+        }))
+    }
+
+    fn update_entry<'a>(
+        &'a self,
+        id: &'a str,
+        update: EntryUpdate,
+    ) -> BackendFuture<'a, AuthEntry> {
+        let id = id.to_string();
+        Box::pin(self.write(move |tx| {
+            let EntryUpdate {
+                name,
+                username,
+                password_hash,
+                group_ids,
+                availability,
+                expected_revision,
+            } = update;
+            let mut entry = load_entry(tx, &id)?
+                .ok_or_else(|| error::CredsManageError::EntryNotFound { id: id.clone() })?;
+            ensure_revision(&id, entry.meta().revision, expected_revision)?;
+            if let Some(ref new_name) = name {
+                ensure_entry_name_is_unique(tx, new_name, &id)?;
+            }
+            if let Some(ref gids) = group_ids {
+                ensure_groups_exist(tx, gids)?;
+            }
+
+            if let StoredEntry::Basic(basic) = &mut entry {
+                if let Some(new_username) = username {
+                    basic.cred.username = new_username;
+                }
+                if let Some(new_password_hash) = password_hash {
+                    basic.cred.password_hash = new_password_hash;
+                }
+            }
+            let meta = entry.meta_mut();
+            if let Some(new_name) = name {
+                meta.name = new_name;
+            }
+            let changed_group_ids = replace_group_ids(meta, group_ids);
+            if let Some(availability) = availability {
+                meta.availability = availability;
+            }
+            meta.mark_updated();
+
+            save_entry(tx, &entry)?;
+            mark_groups_updated(tx, &changed_group_ids)?;
+            Ok(entry.to_auth_entry())
+        }))
+    }
+
+    fn replace_basic_password_hash<'a>(
+        &'a self,
+        id: &'a str,
+        current_hash: &'a str,
+        new_hash: String,
+    ) -> BackendFuture<'a, bool> {
+        let id = id.to_string();
+        let current_hash = current_hash.to_string();
+        Box::pin(self.write(move |tx| {
+            let Some(StoredEntry::Basic(mut entry)) = load_entry(tx, &id)? else {
+                return Err(error::CredsManageError::EntryNotFound { id });
+            };
+            if entry.cred.password_hash != current_hash {
+                return Ok(false);
+            }
+            entry.cred.password_hash = new_hash;
+            entry.meta.mark_updated();
+            save_entry(tx, &StoredEntry::Basic(entry))?;
+            Ok(true)
+        }))
+    }
+
+    fn record_usage(&self, usage: HashMap<String, AuthEntryUsage>) -> BackendFuture<'_, ()> {
+        Box::pin(self.write(move |tx| {
+            for (id, batch) in &usage {
+                let Some(mut entry) = load_entry(tx, id)? else {
+                    continue;
+                };
+                entry.meta_mut().usage.merge(batch);
+                tx.execute(
+                    "UPDATE entries SET data = ?2 WHERE id = ?1",
+                    params![id, entry.encode()?],
+                )
+                .context(error::DatabaseSnafu)?;
+            }
+            Ok(())
+        }))
+    }
+
+    fn delete_entry<'a>(
+        &'a self,
+        id: &'a str,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, ()> {
+        let id = id.to_string();
+        Box::pin(self.write(move |tx| {
+            let Some(entry) = load_entry(tx, &id)? else {
+                return Err(error::CredsManageError::EntryNotFound { id });
+            };
+            ensure_revision(&id, entry.meta().revision, expected_revision)?;
+            tx.execute("DELETE FROM entries WHERE id = ?1", params![id])
+                .context(error::DatabaseSnafu)?;
+            mark_groups_updated(tx, &entry.meta().group_ids)
+        }))
+    }
+
+    fn list_groups(&self) -> BackendFuture<'_, Vec<Group>> {
+        Box::pin(self.read(all_groups))
+    }
+
+    fn get_group<'a>(&'a self, id: &'a str) -> BackendFuture<'a, Option<Group>> {
+        let id = id.to_string();
+        Box::pin(self.read(move |connection| load_group(connection, &id)))
+    }
+
+    fn find_group_by_name<'a>(&'a self, name: &'a str) -> BackendFuture<'a, Option<Group>> {
+        let name = name.to_string();
+        Box::pin(self.read(move |connection| {
+            Ok(query_groups(
+                connection,
+                "SELECT data FROM groups WHERE name = ?1",
+                params![name],
+            )?
+            .pop())
+        }))
+    }
+
+    fn insert_group(&self, group: Group, entry_ids: Vec<String>) -> BackendFuture<'_, Group> {
+        Box::pin(self.write(move |tx| {
+            ensure_group_name_is_unique(tx, &group.name, &group.id)?;
+            ensure_group_parents_valid(&all_groups(tx)?, &group.id, &group.parent_ids)?;
+
+            let mut entries = Vec::with_capacity(entry_ids.len());
+            for entry_id in &entry_ids {
+                let entry = load_entry(tx, entry_id)?.ok_or_else(|| {
+                    error::CredsManageError::EntryNotFound {
+                        id: entry_id.clone(),
+                    }
+                })?;
+                entries.push(entry);
+            }
+
+            insert_group_row(tx, &group)?;
+            for mut entry in entries {
+                let meta = entry.meta_mut();
+                if !meta.group_ids.iter().any(|gid| gid == &group.id) {
+                    meta.group_ids.push(group.id.clone());
+                    meta.mark_updated();
+                    save_entry(tx, &entry)?;
+                }
+            }
+            Ok(group)
+        }))
+    }
+
+    fn update_group<'a>(
+        &'a self,
+        id: &'a str,
+        name: String,
+        entry_ids: Option<Vec<String>>,
+        parent_ids: Option<Vec<String>>,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        Box::pin(self.write(move |tx| {
+            let mut group = load_group(tx, &id)?
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+            ensure_revision(&id, group.revision, expected_revision)?;
+            ensure_group_name_is_unique(tx, &name, &id)?;
+            if let Some(ref parent_ids) = parent_ids {
+                ensure_group_parents_valid(&all_groups(tx)?, &id, parent_ids)?;
+            }
+
+            if let Some(ref entry_ids) = entry_ids {
+                for entry_id in entry_ids {
+                    if !exists(tx, "SELECT 1 FROM entries WHERE id = ?1", params![entry_id])? {
+                        return Err(error::CredsManageError::EntryNotFound {
+                            id: entry_id.clone(),
+                        });
+                    }
+                }
+            }
+
+            group.name = name;
+            if let Some(parent_ids) = parent_ids {
+                group.parent_ids = parent_ids;
+            }
+            group.mark_updated();
+            save_group(tx, &group)?;
+
+            if let Some(entry_ids) = entry_ids {
+                let mut affected = group_member_ids(tx, &id)?;
+                affected.extend(entry_ids.iter().cloned());
+                affected.sort();
+                affected.dedup();
+
+                for entry_id in affected {
+                    let Some(mut entry) = load_entry(tx, &entry_id)? else {
+                        continue;
+                    };
+                    let before = entry.meta().group_ids.clone();
+                    let target_member = entry_ids.contains(&entry_id);
+                    update_group_membership(entry.meta_mut(), &id, target_member);
+                    if entry.meta().group_ids != before {
+                        save_entry(tx, &entry)?;
+                    }
+                }
+            }
+
+            Ok(group)
+        }))
+    }
+
+    fn update_group_availability<'a>(
+        &'a self,
+        id: &'a str,
+        availability: Availability,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        Box::pin(self.write(move |tx| {
+            let mut group = load_group(tx, &id)?
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+            ensure_revision(&id, group.revision, expected_revision)?;
+            group.availability = availability;
+            group.mark_updated();
+            save_group(tx, &group)?;
+            Ok(group)
+        }))
+    }
+
+    fn update_group_identity_access<'a>(
+        &'a self,
+        id: &'a str,
+        identity_access: IdentityAccess,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        Box::pin(self.write(move |tx| {
+            let mut group = load_group(tx, &id)?
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+            ensure_revision(&id, group.revision, expected_revision)?;
+            group.identity_access = identity_access;
+            group.mark_updated();
+            save_group(tx, &group)?;
+            Ok(group)
+        }))
+    }
+
+    fn update_group_access_rules<'a>(
+        &'a self,
+        id: &'a str,
+        access_rules: Vec<AccessRule>,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, Group> {
+        let id = id.to_string();
+        Box::pin(self.write(move |tx| {
+            let mut group = load_group(tx, &id)?
+                .ok_or_else(|| error::CredsManageError::GroupNotFound { id: id.clone() })?;
+            ensure_revision(&id, group.revision, expected_revision)?;
+            group.access_rules = access_rules;
+            group.mark_updated();
+            save_group(tx, &group)?;
+            Ok(group)
+        }))
+    }
+
+    fn delete_group<'a>(
+        &'a self,
+        id: &'a str,
+        expected_revision: Option<u64>,
+    ) -> BackendFuture<'a, ()> {
+        let id = id.to_string();
+        Box::pin(self.write(move |tx| {
+            let Some(group) = load_group(tx, &id)? else {
+                return Err(error::CredsManageError::GroupNotFound { id });
+            };
+            ensure_revision(&id, group.revision, expected_revision)?;
+
+            for entry_id in group_member_ids(tx, &id)? {
+                let Some(mut entry) = load_entry(tx, &entry_id)? else {
+                    continue;
+                };
+                let meta = entry.meta_mut();
+                meta.group_ids.retain(|gid| gid != &id);
+                meta.mark_updated();
+                save_entry(tx, &entry)?;
+            }
+            for mut group in all_groups(tx)? {
+                if group.parent_ids.contains(&id) {
+                    group.parent_ids.retain(|parent_id| parent_id != &id);
+                    group.mark_updated();
+                    save_group(tx, &group)?;
+                }
+            }
+
+            tx.execute("DELETE FROM groups WHERE id = ?1", params![id])
+                .context(error::DatabaseSnafu)?;
+            Ok(())
+        }))
+    }
+
+    fn export_data(&self) -> BackendFuture<'_, DataFile> {
+        Box::pin(self.read(load_data))
+    }
+
+    fn apply_import(&self, plan: ImportPlan) -> BackendFuture<'_, ImportReport> {
+        Box::pin(self.write(move |tx| {
+            let mut data = load_data(tx)?;
+            let report = plan(&mut data)?;
+            tx.execute_batch("DELETE FROM entry_groups; DELETE FROM entries; DELETE FROM groups;")
+                .context(error::DatabaseSnafu)?;
+            insert_data(tx, &data)?;
+            Ok(report)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use securitydept_creds::{
+        Argon2HashConfig, BasicAuthCred, Sha256TokenAuthCred, generate_static_token,
+    };
+
+    use super::*;
+    use crate::{
+        audit::AuditActor,
+        error::CredsManageError,
+        models::TokenEntryConstraints,
+        store::{CredsManageStore, FileBackend},
+    };
+
+    fn unique_path(label: &str, extension: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after unix epoch")
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "securitydept-creds-manage-{label}-{nanos}.{extension}"
+        ))
+    }
+
+    async fn sqlite_store(label: &str) -> CredsManageStore {
+        let backend = SqliteBackend::open(unique_path(label, "sqlite3"))
+            .await
+            .expect("sqlite backend should open");
+        CredsManageStore::with_backend(backend, Argon2HashConfig::default())
+            .expect("store should build")
+    }
+
+    #[tokio::test]
+    async fn archive_replace_rewrites_tables() {
+        use crate::archive::{ImportMode, ImportOptions};
+
+        let actor = AuditActor::system();
+        let source = sqlite_store("sqlite-archive-source").await;
+        let ops = source
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let (_, token) = source
+            .create_token_entry(&actor, "deploy".to_string(), vec![ops.id.clone()])
+            .await
+            .unwrap();
+        let archive = source.export_archive().await.unwrap();
+
+        let target = sqlite_store("sqlite-archive-target").await;
+        target
+            .create_token_entry(&actor, "stale".to_string(), Vec::new())
+            .await
+            .unwrap();
+        let report = target
+            .import_archive(
+                &actor,
+                archive,
+                ImportOptions {
+                    mode: ImportMode::Replace,
+                    ..ImportOptions::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(report.entries.deleted, 1);
+        assert_eq!(report.entries.created, 1);
+
+        let entries = target.list_entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].meta.name, "deploy");
+        assert!(target.find_token_entry(&token).await.unwrap().is_some());
+        assert_eq!(
+            target
+                .token_entries_by_group_id(&ops.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn entry_membership_changes_bump_group_revisions() {
+        let store = sqlite_store("sqlite-membership-revisions").await;
+        let actor = AuditActor::system();
+        let ops = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let (entry, _) = store
+            .create_token_entry(&actor, "deploy".to_string(), vec![ops.id.clone()])
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_group(&ops.id).await.unwrap().revision,
+            ops.revision + 1
+        );
+
+        store
+            .update_entry(&actor, &entry.meta.id, None, None, None, Some(Vec::new()))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_group(&ops.id).await.unwrap().revision,
+            ops.revision + 2
+        );
+
+        store
+            .update_entry(
+                &actor,
+                &entry.meta.id,
+                None,
+                None,
+                None,
+                Some(vec![ops.id.clone()]),
+            )
+            .await
+            .unwrap();
+        store.delete_entry(&actor, &entry.meta.id).await.unwrap();
+        assert_eq!(
+            store.get_group(&ops.id).await.unwrap().revision,
+            ops.revision + 4
+        );
+    }
+
+    #[tokio::test]
+    async fn nested_group_members_are_returned_once() {
+        let store = sqlite_store("sqlite-nested").await;
+        let actor = AuditActor::system();
+        let ops = store
+            .create_group(&actor, Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        let mut sre = Group::new("sre".to_string());
+        sre.parent_ids = vec![ops.id.clone()];
+        let sre = store.create_group(&actor, sre, None).await.unwrap();
+
+        store
+            .create_basic_entry(
+                &actor,
+                "admin".to_string(),
+                "admin".to_string(),
+                "secret123".to_string(),
+                vec![ops.id.clone(), sre.id.clone()],
+            )
+            .await
+            .unwrap();
+        store
+            .create_token_entry(&actor, "deploy".to_string(), vec![sre.id.clone()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store
+                .basic_entries_by_group_id(&ops.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store
+                .token_entries_by_group_id(&ops.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(
+            store
+                .token_entries_by_group_id(&sre.id)
+                .await
+                .unwrap()
+                .iter()
+                .all(|entry| entry.meta.name == "deploy")
+        );
+
+        let error = store
+            .update_group(
+                &actor,
+                &ops.id,
+                "ops".to_string(),
+                None,
+                Some(vec![sre.id.clone()]),
+            )
+            .await
+            .expect_err("a cycle should be rejected");
+        assert!(matches!(error, error::CredsManageError::GroupCycle { .. }));
+
+        store.delete_group(&actor, &ops.id).await.unwrap();
+        assert!(
+            store
+                .get_group(&sre.id)
+                .await
+                .unwrap()
+                .parent_ids
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn entries_are_found_by_group_and_token() {
+        let store = sqlite_store("sqlite-lookups").await;
+        let ops = store
+            .create_group(&AuditActor::system(), Group::new("ops".to_string()), None)
+            .await
+            .expect("group should be created");
+        let ci = store
+            .create_group(&AuditActor::system(), Group::new("ci".to_string()), None)
+            .await
+            .expect("group should be created");
+
+        let admin = store
+            .create_basic_entry(
+                &AuditActor::system(),
+                "admin".to_string(),
+                "admin".to_string(),
+                "secret123".to_string(),
+                vec![ops.id.clone()],
+            )
+            .await
+            .expect("basic entry should be created");
+        let (deploy, token) = store
+            .create_token_entry(
+                &AuditActor::system(),
+                "deploy".to_string(),
+                vec![ops.id.clone(), ci.id.clone()],
+            )
+            .await
+            .expect("token entry should be created");
+
+        let bare_token = generate_static_token().unwrap();
+        store
+            .backend
+            .insert_token_entry(TokenAuthEntry {
+                cred: Sha256TokenAuthCred::new(bare_token.clone()).unwrap(),
+                meta: AuthEntryMeta::new("legacy".to_string(), Vec::new()),
+                token_prefix: None,
+                constraints: TokenEntryConstraints::default(),
+            })
+            .await
+            .expect("bare token entry should be inserted");
+
+        let basic = store.basic_entries_by_group_id(&ops.id).await.unwrap();
+        assert_eq!(basic.len(), 1);
+        assert_eq!(basic[0].meta.id, admin.meta.id);
+        assert!(basic[0].verify_password("secret123").unwrap());
+        assert_eq!(
+            store.token_entries_by_group_id(&ci.id).await.unwrap().len(),
+            1
+        );
+        assert_eq!(store.entries_by_group_id(&ops.id).await.unwrap().len(), 2);
+        assert_eq!(store.list_entries().await.unwrap().len(), 3);
+
+        assert_eq!(
+            store
+                .find_token_entry(&token)
+                .await
+                .unwrap()
+                .map(|entry| entry.meta.id),
+            Some(deploy.meta.id.clone())
+        );
+        assert_eq!(
+            store
+                .find_token_entry(&bare_token)
+                .await
+                .unwrap()
+                .map(|entry| entry.meta.name),
+            Some("legacy".to_string())
+        );
+        assert!(
+            store
+                .find_token_entry(&generate_static_token().unwrap())
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let error = store
+            .create_token_entry(&AuditActor::system(), "admin".to_string(), Vec::new())
+            .await
+            .expect_err("duplicate entry name should be rejected");
+        assert!(matches!(error, CredsManageError::DuplicateEntryName { .. }));
+        let error = store
+            .create_token_entry(
+                &AuditActor::system(),
+                "other".to_string(),
+                vec!["missing".to_string()],
+            )
+            .await
+            .expect_err("unknown group should be rejected");
+        assert!(matches!(error, CredsManageError::GroupNotFound { .. }));
+
+        store
+            .update_group(
+                &AuditActor::system(),
+                &ops.id,
+                "ops".to_string(),
+                Some(vec![deploy.meta.id.clone()]),
+                None,
+            )
+            .await
+            .expect("group members should be replaced");
+        assert!(
+            store
+                .basic_entries_by_group_id(&ops.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            store
+                .get_entry(&admin.meta.id)
+                .await
+                .unwrap()
+                .meta
+                .group_ids
+                .is_empty()
+        );
+
+        store
+            .delete_group(&AuditActor::system(), &ci.id)
+            .await
+            .expect("group should be deleted");
+        assert_eq!(
+            store
+                .get_entry(&deploy.meta.id)
+                .await
+                .unwrap()
+                .meta
+                .group_ids,
+            vec![ops.id.clone()]
+        );
+
+        store
+            .delete_entry(&AuditActor::system(), &deploy.meta.id)
+            .await
+            .expect("entry should be deleted");
+        assert!(store.find_token_entry(&token).await.unwrap().is_none());
+        assert!(
+            store
+                .token_entries_by_group_id(&ops.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn legacy_password_hash_is_upgraded_once() {
+        let store = sqlite_store("sqlite-hash-upgrade").await;
+        let created = store
+            .create_basic_entry_with_password_hash(
+                &AuditActor::system(),
+                "legacy-user".to_string(),
+                "legacy".to_string(),
+                "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=".to_string(),
+                Vec::new(),
+            )
+            .await
+            .expect("legacy hash should be accepted");
+
+        assert!(
+            store
+                .upgrade_basic_entry_password_hash(&created.meta.id, "password")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .upgrade_basic_entry_password_hash(&created.meta.id, "password")
+                .await
+                .unwrap()
+        );
+        let entry = store
+            .backend
+            .get_basic_entry(&created.meta.id)
+            .await
+            .unwrap()
+            .expect("entry should exist");
+        assert!(entry.cred.password_hash.starts_with("$argon2"));
+    }
+
+    #[tokio::test]
+    async fn data_file_is_imported_once() {
+        let data_path = unique_path("sqlite-import", "json");
+        let file_store = CredsManageStore::with_backend(
+            FileBackend::load(&data_path)
+                .await
+                .expect("file backend should load"),
+            Argon2HashConfig::default(),
+        )
+        .expect("store should build");
+        let group = file_store
+            .create_group(&AuditActor::system(), Group::new("ops".to_string()), None)
+            .await
+            .unwrap();
+        file_store
+            .create_basic_entry(
+                &AuditActor::system(),
+                "admin".to_string(),
+                "admin".to_string(),
+                "secret123".to_string(),
+                vec![group.id.clone()],
+            )
+            .await
+            .unwrap();
+        let (_, token) = file_store
+            .create_token_entry(
+                &AuditActor::system(),
+                "deploy".to_string(),
+                vec![group.id.clone()],
+            )
+            .await
+            .unwrap();
+
+        let data: DataFile =
+            serde_json::from_str(&std::fs::read_to_string(&data_path).unwrap()).unwrap();
+        let sqlite_path = unique_path("sqlite-import", "sqlite3");
+        assert!(import_data_file(&sqlite_path, &data).expect("import should succeed"));
+        assert!(!import_data_file(&sqlite_path, &DataFile::default()).unwrap());
+
+        let store = CredsManageStore::with_backend(
+            SqliteBackend::open(&sqlite_path)
+                .await
+                .expect("sqlite backend should open"),
+            Argon2HashConfig::default(),
+        )
+        .unwrap();
+        let imported_group = store
+            .find_group_by_name("ops")
+            .await
+            .unwrap()
+            .expect("group should be imported");
+        assert_eq!(imported_group.id, group.id);
+        assert_eq!(store.entries_by_group_id(&group.id).await.unwrap().len(), 2);
+        assert_eq!(
+            store
+                .find_token_entry(&token)
+                .await
+                .unwrap()
+                .map(|entry| entry.meta.name),
+            Some("deploy".to_string())
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use securitydept_creds_manage::{
+    AuditActor, CredsManageConfig, CredsManageError,
+    config::{DataEncryptionConfig, DataKeySource},
+    encryption::{DataEnvelope, DataKey, DataKeyring},
+    store::{CredsManageStore, FileBackend},
+};
+
+fn temp_path(name: &str, extension: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time before unix epoch")
+        .as_nanos();
+
+    std::env::temp_dir().join(format!("securitydept-{name}-{nanos}.{extension}"))
+}
+
+fn write_key_file(name: &str, byte: u8) -> (PathBuf, DataKey) {
+    let encoded = BASE64.encode([byte; 32]);
+    let path = temp_path(name, "key");
+    std::fs::write(&path, format!("{encoded}\n")).expect("write key file");
+    (path, DataKey::from_base64(&encoded).expect("parse key"))
+}
+
+fn key_source(path: &Path) -> DataKeySource {
+    DataKeySource::File {
+        path: path.display().to_string(),
+    }
+}
+
+fn sealed_key_id(path: &Path) -> Option<String> {
+    DataEnvelope::parse(&std::fs::read(path).expect("read data file")).map(|e| e.key_id)
+}
+
+#[tokio::test]
+async fn data_file_is_sealed_and_key_rotation_reseals_it() {
+    let data_path = temp_path("encrypted-store", "json");
+    let (old_key_path, old_key) = write_key_file("old-data-key", 7);
+    let (new_key_path, new_key) = write_key_file("new-data-key", 9);
+    let old_key = DataKeyring::new(old_key);
+    let old_key_id = old_key.current_key_id().to_string();
+
+    // An existing plaintext data file is only sealed by an explicit reseal.
+    let store = CredsManageStore::load(&data_path)
+        .await
+        .expect("load plaintext store");
+    store
+        .create_basic_entry(
+            &AuditActor::system(),
+            "plain-entry".to_string(),
+            "plain-user".to_string(),
+            "secret123".to_string(),
+            vec![],
+        )
+        .await
+        .expect("create plaintext entry");
+    drop(store);
+    assert!(matches!(
+        FileBackend::load_encrypted(&data_path, old_key).await,
+        Err(CredsManageError::DataNotEncrypted)
+    ));
+
+    let old_key_config = CredsManageConfig {
+        data_path: data_path.display().to_string(),
+        data_encryption: Some(DataEncryptionConfig {
+            key: key_source(&old_key_path),
+            previous_keys: vec![],
+        }),
+        ..Default::default()
+    };
+    FileBackend::reseal(&old_key_config)
+        .await
+        .expect("seal plaintext data file");
+    assert_eq!(sealed_key_id(&data_path), Some(old_key_id.clone()));
+
+    let store = CredsManageStore::load_from_config(&old_key_config)
+        .await
+        .expect("load encrypted store");
+    store
+        .create_basic_entry(
+            &AuditActor::system(),
+            "sealed-entry".to_string(),
+            "sealed-user".to_string(),
+            "secret123".to_string(),
+            vec![],
+        )
+        .await
+        .expect("create entry");
+    drop(store);
+
+    let raw = std::fs::read_to_string(&data_path).expect("read data file");
+    assert!(!raw.contains("plain-user"));
+    assert!(!raw.contains("sealed-user"));
+    assert!(!raw.contains("argon2"));
+    assert_eq!(sealed_key_id(&data_path), Some(old_key_id));
+
+    let config = CredsManageConfig {
+        data_path: data_path.display().to_string(),
+        data_encryption: Some(DataEncryptionConfig {
+            key: key_source(&new_key_path),
+            previous_keys: vec![key_source(&old_key_path)],
+        }),
+        ..Default::default()
+    };
+    let key_id = FileBackend::reseal(&config)
+        .await
+        .expect("reseal data file");
+    assert_eq!(key_id, new_key.id());
+    assert_eq!(sealed_key_id(&data_path).as_deref(), Some(new_key.id()));
+
+    let config = CredsManageConfig {
+        data_encryption: Some(DataEncryptionConfig {
+            key: key_source(&new_key_path),
+            previous_keys: vec![],
+        }),
+        ..config
+    };
+    let store = CredsManageStore::load_from_config(&config)
+        .await
+        .expect("load with the new key only");
+    let entries = store.list_entries().await.expect("list entries");
+    let mut names: Vec<_> = entries.iter().map(|e| e.meta.name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["plain-entry", "sealed-entry"]);
+}
@@ -1 +1,5 @@
+#[cfg(feature = "encryption")]
+mod encrypted_store;
+#[cfg(feature = "migration")]
+mod migrations;
 mod store_sync;
@@ -0,0 +1,153 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use securitydept_creds_manage::{
+    CredsManageConfig,
+    migrations::{
+        Migrator,
+        m2026022900050001_split_data_file_entries::{AuthEntryV1, DataFileV1},
+        models::{MigrationDirection, MigrationState, MigratorTrait},
+    },
+    models::{AuthEntryKind, AuthEntryMeta, DataFile, TokenEntryConstraints},
+};
+
+const SPLIT_ENTRIES: &str = "m2026022900050001_split_data_file_entries";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time before unix epoch")
+        .as_nanos();
+
+    let dir = std::env::temp_dir().join(format!("securitydept-{name}-{nanos}"));
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    dir
+}
+
+fn legacy_data_file() -> DataFileV1 {
+    DataFileV1 {
+        entries: vec![
+            AuthEntryV1 {
+                kind: AuthEntryKind::Basic,
+                username: Some("alice".to_string()),
+                password_hash: Some("$argon2id$v=19$m=19456,t=2,p=1$c2FsdA$aGFzaA".to_string()),
+                token_hash: None,
+                meta: AuthEntryMeta::new("alice".to_string(), vec![]),
+            },
+            AuthEntryV1 {
+                kind: AuthEntryKind::Token,
+                username: None,
+                password_hash: None,
+                token_hash: Some("ab".repeat(32)),
+                meta: AuthEntryMeta::new("deploy".to_string(), vec![]),
+            },
+        ],
+        groups: vec![],
+    }
+}
+
+fn migration_state(migrator: &Migrator, config: &CredsManageConfig) -> MigrationState {
+    migrator
+        .status(config)
+        .expect("migration status")
+        .into_iter()
+        .find(|status| status.name == SPLIT_ENTRIES)
+        .expect("split entries migration is listed")
+        .state
+}
+
+#[test]
+fn data_file_migrations_plan_apply_and_revert() {
+    let dir = temp_dir("migrations");
+    let data_path = dir.join("data.json");
+    let legacy = serde_json::to_vec_pretty(&legacy_data_file()).expect("serialize legacy file");
+    std::fs::write(&data_path, &legacy).expect("write legacy file");
+    let config = CredsManageConfig {
+        data_path: data_path.display().to_string(),
+        ..Default::default()
+    };
+    let migrator = Migrator::default();
+    assert_eq!(migration_state(&migrator, &config), MigrationState::Pending);
+
+    let plan = migrator
+        .plan(&config, MigrationDirection::Up, None)
+        .expect("plan migrations");
+    assert_eq!(plan.migrations, vec![SPLIT_ENTRIES]);
+    assert!(plan.data_file_diff.contains("-  \"entries\": ["));
+    assert!(plan.data_file_diff.contains("+  \"basic_creds\": ["));
+    assert_eq!(std::fs::read(&data_path).expect("read data file"), legacy);
+
+    let applied = migrator.up(&config, None).expect("apply migrations");
+    let backup_path = applied.backup_path.expect("data file is backed up");
+    assert_eq!(std::fs::read(&backup_path).expect("read backup"), legacy);
+    let migrated: DataFile =
+        serde_json::from_slice(&std::fs::read(&data_path).expect("read data file"))
+            .expect("data file has the current layout");
+    assert_eq!(migrated.basic_creds.len(), 1);
+    assert_eq!(migrated.token_creds.len(), 1);
+    assert!(matches!(
+        migration_state(&migrator, &config),
+        MigrationState::Applied { .. }
+    ));
+    assert!(
+        migrator
+            .up(&config, None)
+            .expect("nothing left to apply")
+            .migrations
+            .is_empty()
+    );
+
+    let reverted = migrator.down(&config, None).expect("revert migration");
+    assert_eq!(reverted.migrations, vec![SPLIT_ENTRIES]);
+    let restored: DataFileV1 =
+        serde_json::from_slice(&std::fs::read(&data_path).expect("read data file"))
+            .expect("data file has the legacy layout");
+    assert_eq!(restored.entries.len(), 2);
+    assert_eq!(restored.entries[0].username.as_deref(), Some("alice"));
+    assert_eq!(migration_state(&migrator, &config), MigrationState::Pending);
+
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn down_is_refused_while_token_entries_have_constraints() {
+    let dir = temp_dir("migrations-constraints");
+    let data_path = dir.join("data.json");
+    let legacy = serde_json::to_vec_pretty(&legacy_data_file()).expect("serialize legacy file");
+    std::fs::write(&data_path, &legacy).expect("write legacy file");
+    let config = CredsManageConfig {
+        data_path: data_path.display().to_string(),
+        ..Default::default()
+    };
+    let migrator = Migrator::default();
+    migrator.up(&config, None).expect("apply migrations");
+
+    let mut data: DataFile =
+        serde_json::from_slice(&std::fs::read(&data_path).expect("read data file"))
+            .expect("data file has the current layout");
+    data.token_creds[0].constraints = TokenEntryConstraints {
+        not_before: None,
+        scopes: vec!["deploy".to_string()],
+    };
+    let constrained = serde_json::to_vec_pretty(&data).expect("serialize data file");
+    std::fs::write(&data_path, &constrained).expect("write data file");
+
+    assert!(
+        migrator
+            .plan(&config, MigrationDirection::Down, None)
+            .is_err()
+    );
+    assert!(migrator.down(&config, None).is_err());
+    assert_eq!(
+        std::fs::read(&data_path).expect("read data file"),
+        constrained
+    );
+    assert!(matches!(
+        migration_state(&migrator, &config),
+        MigrationState::Applied { .. }
+    ));
+
+    std::fs::remove_dir_all(dir).ok();
+}
@@ -4,7 +4,11 @@ use std::{
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use securitydept_creds_manage::{models::DataFile, store::CredsManageStore};
+use securitydept_creds_manage::{
+    AuditActor, CredsManageError,
+    models::{DataFile, UpdateEntryRequest},
+    store::CredsManageStore,
+};
 
 fn temp_data_file_path(name: &str) -> PathBuf {
     let millis = SystemTime::now()
@@ -40,7 +44,7 @@ async fn store_syncs_external_file_changes() {
     let store = CredsManageStore::load(&path).await.expect("load store");
 
     let (created, _) = store
-        .create_token_entry("entry-a".to_string(), vec![])
+        .create_token_entry(&AuditActor::system(), "entry-a".to_string(), vec![])
         .await
         .expect("create entry");
 
@@ -84,8 +88,9 @@ async fn concurrent_store_instances_do_not_lose_updates() {
     let store_a = CredsManageStore::load(&path).await.expect("load store a");
     let store_b = CredsManageStore::load(&path).await.expect("load store b");
 
-    let create_a = store_a.create_token_entry("entry-a".to_string(), vec![]);
-    let create_b = store_b.create_token_entry("entry-b".to_string(), vec![]);
+    let actor = AuditActor::system();
+    let create_a = store_a.create_token_entry(&actor, "entry-a".to_string(), vec![]);
+    let create_b = store_b.create_token_entry(&actor, "entry-b".to_string(), vec![]);
 
     let (result_a, result_b) = tokio::join!(create_a, create_b);
     result_a.expect("create entry a");
@@ -124,17 +129,18 @@ async fn delete_group_removes_group_membership_from_entries() {
     let store = CredsManageStore::load(&path).await.expect("load store");
 
     let (e1, _) = store
-        .create_token_entry("entry-a".to_string(), vec![])
+        .create_token_entry(&AuditActor::system(), "entry-a".to_string(), vec![])
         .await
         .expect("create entry a");
 
     let (e2, _) = store
-        .create_token_entry("entry-b".to_string(), vec![])
+        .create_token_entry(&AuditActor::system(), "entry-b".to_string(), vec![])
         .await
         .expect("create entry b");
 
     let created_group = store
         .create_group(
+            &AuditActor::system(),
             securitydept_creds_manage::models::Group::new("g-delete".to_string()),
             Some(vec![e1.meta.id.clone(), e2.meta.id.clone()]),
         )
@@ -142,7 +148,7 @@ async fn delete_group_removes_group_membership_from_entries() {
         .expect("create group");
 
     store
-        .delete_group(&created_group.id)
+        .delete_group(&AuditActor::system(), &created_group.id)
         .await
         .expect("delete group");
 
@@ -172,17 +178,18 @@ async fn update_group_updates_entry_memberships_and_renames_group() {
     let store = CredsManageStore::load(&path).await.expect("load store");
 
     let (e1, _) = store
-        .create_token_entry("entry-a".to_string(), vec![])
+        .create_token_entry(&AuditActor::system(), "entry-a".to_string(), vec![])
         .await
         .expect("create entry a");
 
     let (e2, _) = store
-        .create_token_entry("entry-b".to_string(), vec![])
+        .create_token_entry(&AuditActor::system(), "entry-b".to_string(), vec![])
         .await
         .expect("create entry b");
 
     let created_group = store
         .create_group(
+            &AuditActor::system(),
             securitydept_creds_manage::models::Group::new("old-group".to_string()),
             Some(vec![e1.meta.id.clone()]),
         )
@@ -191,9 +198,11 @@ async fn update_group_updates_entry_memberships_and_renames_group() {
 
     store
         .update_group(
+            &AuditActor::system(),
             &created_group.id,
             "new-group".to_string(),
             Some(vec![e2.meta.id.clone()]),
+            None,
         )
         .await
         .expect("update group");
@@ -217,3 +226,91 @@ async fn update_group_updates_entry_memberships_and_renames_group() {
 
     let _ = tokio::fs::remove_file(&path).await;
 }
+
+#[tokio::test]
+async fn stale_revisions_are_rejected_across_instances_and_hand_edits() {
+    let path = temp_data_file_path("store-revisions");
+    let store_a = CredsManageStore::load(&path).await.expect("load store a");
+    let store_b = CredsManageStore::load(&path).await.expect("load store b");
+    let actor = AuditActor::system();
+
+    let (created, _) = store_a
+        .create_token_entry(&actor, "entry-a".to_string(), vec![])
+        .await
+        .expect("create entry");
+    let rename = |name: &str| UpdateEntryRequest {
+        name: Some(name.to_string()),
+        username: None,
+        password: None,
+        group_ids: None,
+    };
+
+    let updated = store_b
+        .update_entry_at_revision(
+            &actor,
+            &created.meta.id,
+            Some(created.meta.revision),
+            rename("entry-b"),
+        )
+        .await
+        .expect("update at the current revision");
+    assert_eq!(updated.meta.revision, created.meta.revision + 1);
+
+    let stale = store_a
+        .update_entry_at_revision(
+            &actor,
+            &created.meta.id,
+            Some(created.meta.revision),
+            rename("entry-c"),
+        )
+        .await
+        .expect_err("update at a stale revision must fail");
+    assert!(
+        matches!(stale, CredsManageError::RevisionMismatch { current, .. } if current == updated.meta.revision),
+        "unexpected error: {stale}"
+    );
+
+    let synced = wait_until(
+        Duration::from_secs(4),
+        Duration::from_millis(100),
+        || async {
+            match store_a.get_entry(&created.meta.id).await {
+                Ok(entry) => entry.meta.revision == updated.meta.revision,
+                Err(_) => false,
+            }
+        },
+    )
+    .await;
+    assert!(synced, "store a did not sync the update within timeout");
+
+    // A hand edit keeps the revision it found in the file.
+    let mut disk_data: DataFile = serde_json::from_str(
+        &tokio::fs::read_to_string(&path)
+            .await
+            .expect("read data file"),
+    )
+    .expect("parse data file");
+    disk_data.token_creds[0].meta.name = "entry-from-hand-edit".to_string();
+    tokio::fs::write(
+        &path,
+        serde_json::to_string_pretty(&disk_data).expect("serialize data file"),
+    )
+    .await
+    .expect("write changed data file");
+
+    let stale = store_a
+        .update_entry_at_revision(
+            &actor,
+            &created.meta.id,
+            Some(updated.meta.revision),
+            rename("entry-d"),
+        )
+        .await
+        .expect_err("a hand edit must invalidate the revision");
+    assert!(
+        matches!(stale, CredsManageError::RevisionMismatch { current, .. } if current == updated.meta.revision + 1),
+        "unexpected error: {stale}"
+    );
+
+    let _ = tokio::fs::remove_file(&path).await;
+}
@@ -1,7 +1,7 @@
 use std::fmt::{Debug, Formatter};
 
 use argon2::{
-    Argon2,
+    Algorithm, Argon2, Params,
     password_hash::{
         PasswordHasher, PasswordVerifier,
         phc::{PasswordHash, SaltString},
@@ -10,7 +10,7 @@ use argon2::{
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use serde::{Deserialize, Serialize};
 
-use crate::{CredsError, CredsResult};
+use crate::{Argon2HashConfig, CredsError, CredsResult, PasswordHashFormat, verify_password_hash};
 
 pub fn is_basic_auth_header(header_value: &str) -> bool {
     header_value.len() >= 6 && header_value[..6].eq_ignore_ascii_case("Basic ")
@@ -64,8 +64,16 @@ pub fn parse_basic_auth_header(header_value: &str) -> Result<(String, String), C
 
 /// Hash a plaintext password with argon2.
 pub fn hash_password_argon2(password: &str) -> CredsResult<String> {
+    hash_password_argon2_with_config(password, &Argon2HashConfig::default())
+}
+
+/// Hash a plaintext password with argon2id using the given cost parameters.
+pub fn hash_password_argon2_with_config(
+    password: &str,
+    config: &Argon2HashConfig,
+) -> CredsResult<String> {
     let salt = SaltString::generate();
-    let argon2 = Argon2::default();
+    let argon2 = config.argon2()?;
     let hash = argon2
         .hash_password_with_salt(password.as_bytes(), salt.as_bytes())
         .map_err(|e| CredsError::PasswordHash {
@@ -84,12 +92,36 @@ pub fn verify_password_argon2(password: &str, password_hash: &str) -> CredsResul
         .is_ok())
 }
 
+/// Whether an argon2 PHC string was produced with weaker parameters than
+/// `policy`, or with a variant other than argon2id.
+pub fn argon2_hash_below_policy(
+    password_hash: &str,
+    policy: &Argon2HashConfig,
+) -> CredsResult<bool> {
+    let parsed = PasswordHash::new(password_hash).map_err(|e| CredsError::PasswordHash {
+        message: e.to_string(),
+    })?;
+    let params = Params::try_from(&parsed).map_err(|e| CredsError::PasswordHash {
+        message: e.to_string(),
+    })?;
+    Ok(parsed.algorithm != Algorithm::Argon2id.ident()
+        || params.m_cost() < policy.memory_kib
+        || params.t_cost() < policy.iterations
+        || params.p_cost() < policy.parallelism)
+}
+
 pub trait BasicAuthCred: Clone {
     fn username(&self) -> &str;
     fn display_name(&self) -> &str {
         self.username()
     }
     fn verify_password(&self, password: &str) -> CredsResult<bool>;
+    /// Whether the stored hash should be replaced with a fresh argon2 hash
+    /// after the next successful [`verify_password`](Self::verify_password),
+    /// either because it uses a legacy format or falls below `policy`.
+    fn needs_rehash(&self, _policy: &Argon2HashConfig) -> bool {
+        false
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -108,7 +140,15 @@ impl Argon2BasicAuthCred {
     }
 
     pub fn update_password(&mut self, password: String) -> CredsResult<()> {
-        self.password_hash = hash_password_argon2(&password)?;
+        self.update_password_with_config(password, &Argon2HashConfig::default())
+    }
+
+    pub fn update_password_with_config(
+        &mut self,
+        password: String,
+        config: &Argon2HashConfig,
+    ) -> CredsResult<()> {
+        self.password_hash = hash_password_argon2_with_config(&password, config)?;
         Ok(())
     }
 }
@@ -129,6 +169,108 @@ impl BasicAuthCred for Argon2BasicAuthCred {
     fn verify_password(&self, password: &str) -> CredsResult<bool> {
         verify_password_argon2(password, &self.password_hash)
     }
+
+    fn needs_rehash(&self, policy: &Argon2HashConfig) -> bool {
+        argon2_hash_below_policy(&self.password_hash, policy).unwrap_or(false)
+    }
+}
+
+/// A basic auth credential whose stored hash may be argon2 or any of the
+/// legacy formats understood by [`verify_password_hash`].
+///
+/// Serialized identically to [`Argon2BasicAuthCred`], so existing data keeps
+/// loading. New and updated passwords are always hashed with argon2.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MultiFormatBasicAuthCred {
+    pub username: String,
+    pub password_hash: String,
+}
+
+impl MultiFormatBasicAuthCred {
+    pub fn new(username: String, password: String) -> CredsResult<Self> {
+        Self::new_with_config(username, password, &Argon2HashConfig::default())
+    }
+
+    pub fn new_with_config(
+        username: String,
+        password: String,
+        config: &Argon2HashConfig,
+    ) -> CredsResult<Self> {
+        let password_hash = hash_password_argon2_with_config(&password, config)?;
+        Ok(Self {
+            username,
+            password_hash,
+        })
+    }
+
+    /// Build a credential from an already hashed password, e.g. an imported
+    /// htpasswd line.
+    pub fn from_password_hash(username: String, password_hash: String) -> CredsResult<Self> {
+        if PasswordHashFormat::detect(&password_hash).is_none() {
+            return Err(CredsError::PasswordHash {
+                message: "Unsupported password hash format".to_string(),
+            });
+        }
+        Ok(Self {
+            username,
+            password_hash,
+        })
+    }
+
+    pub fn update_password(&mut self, password: String) -> CredsResult<()> {
+        self.update_password_with_config(password, &Argon2HashConfig::default())
+    }
+
+    pub fn update_password_with_config(
+        &mut self,
+        password: String,
+        config: &Argon2HashConfig,
+    ) -> CredsResult<()> {
+        self.password_hash = hash_password_argon2_with_config(&password, config)?;
+        Ok(())
+    }
+
+    pub fn format(&self) -> Option<PasswordHashFormat> {
+        PasswordHashFormat::detect(&self.password_hash)
+    }
+}
+
+impl From<Argon2BasicAuthCred> for MultiFormatBasicAuthCred {
+    fn from(value: Argon2BasicAuthCred) -> Self {
+        Self {
+            username: value.username,
+            password_hash: value.password_hash,
+        }
+    }
+}
+
+impl Debug for MultiFormatBasicAuthCred {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiFormatBasicAuthCred")
+            .field("username", &self.username)
+            .field("format", &self.format().map(PasswordHashFormat::as_str))
+            .finish()
+    }
+}
+
+impl BasicAuthCred for MultiFormatBasicAuthCred {
+    fn username(&self) -> &str {
+        &self.username
+    }
+
+    fn verify_password(&self, password: &str) -> CredsResult<bool> {
+        verify_password_hash(password, &self.password_hash)
+    }
+
+    fn needs_rehash(&self, policy: &Argon2HashConfig) -> bool {
+        match self.format() {
+            Some(PasswordHashFormat::Argon2) => {
+                argon2_hash_below_policy(&self.password_hash, policy).unwrap_or(false)
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -143,6 +285,50 @@ mod tests {
         assert!(!verify_password_argon2("wrong_password", &hash).unwrap());
     }
 
+    #[test]
+    fn test_multi_format_cred_needs_rehash() {
+        let legacy = MultiFormatBasicAuthCred::from_password_hash(
+            "admin".to_string(),
+            "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=".to_string(),
+        )
+        .unwrap();
+        let policy = Argon2HashConfig::default();
+        assert!(legacy.verify_password("password").unwrap());
+        assert!(legacy.needs_rehash(&policy));
+
+        let mut upgraded = legacy.clone();
+        upgraded.update_password("password".to_string()).unwrap();
+        assert!(upgraded.verify_password("password").unwrap());
+        assert!(!upgraded.needs_rehash(&policy));
+
+        assert!(
+            MultiFormatBasicAuthCred::from_password_hash("admin".to_string(), "x".to_string())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_argon2_hash_below_policy() {
+        let weak = Argon2HashConfig {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let hash = hash_password_argon2_with_config("password", &weak).unwrap();
+        assert!(hash.contains("m=8,t=1,p=1"));
+        assert!(verify_password_argon2("password", &hash).unwrap());
+
+        assert!(!argon2_hash_below_policy(&hash, &weak).unwrap());
+        assert!(argon2_hash_below_policy(&hash, &Argon2HashConfig::default()).unwrap());
+
+        let cred = MultiFormatBasicAuthCred {
+            username: "admin".to_string(),
+            password_hash: hash,
+        };
+        assert!(cred.needs_rehash(&Argon2HashConfig::default()));
+        assert!(!cred.needs_rehash(&weak));
+    }
+
     #[test]
     fn test_parse_basic_auth_header() {
         let credentials = BASE64.encode("username:password");
@@ -1,6 +1,58 @@
+use argon2::{Algorithm, Argon2, Params, Version};
 use serde::{Deserialize, Serialize};
 
-use crate::{BasicAuthCred, StaticTokenAuthCred};
+use crate::{BasicAuthCred, CredsError, StaticTokenAuthCred};
+
+/// Argon2id cost parameters used when hashing passwords.
+///
+/// Defaults match the argon2 crate defaults (19 MiB, 2 iterations, 1 lane).
+/// Stored hashes with weaker parameters are reported by
+/// [`BasicAuthCred::needs_rehash`] so hosts can re-hash them on login, or
+/// warn about them where the hash lives in configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Argon2HashConfig {
+    /// Memory size in KiB.
+    pub memory_kib: u32,
+    /// Number of iterations.
+    pub iterations: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for Argon2HashConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Argon2HashConfig {
+    pub fn params(&self) -> Result<Params, CredsError> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None).map_err(|e| {
+            CredsError::ConfigError {
+                message: format!("Invalid argon2 parameters: {e}"),
+            }
+        })
+    }
+
+    /// Build an argon2id hasher for these parameters.
+    pub fn argon2(&self) -> Result<Argon2<'static>, CredsError> {
+        Ok(Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            self.params()?,
+        ))
+    }
+
+    /// Validate the configuration.
+    pub fn validate(&self) -> Result<(), CredsError> {
+        self.params().map(|_| ())
+    }
+}
 
 /// Configuration for Basic Authentication.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -11,6 +63,9 @@ where
     /// List of allowed credentials.
     #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
     pub users: Vec<Creds>,
+    /// Argon2 policy for hashing and for detecting outdated stored hashes.
+    #[serde(default)]
+    pub argon2: Argon2HashConfig,
 }
 
 impl<Creds> Default for BasicAuthCredsConfig<Creds>
@@ -18,7 +73,10 @@ where
     Creds: BasicAuthCred,
 {
     fn default() -> Self {
-        Self { users: Vec::new() }
+        Self {
+            users: Vec::new(),
+            argon2: Argon2HashConfig::default(),
+        }
     }
 }
 
@@ -28,7 +86,12 @@ where
 {
     /// Validate the configuration.
     pub fn validate(&self) -> Result<(), crate::error::CredsError> {
-        Ok(())
+        self.argon2.validate()
+    }
+
+    /// Whether a credential's stored hash falls below the configured policy.
+    pub fn needs_rehash(&self, cred: &Creds) -> bool {
+        cred.needs_rehash(&self.argon2)
     }
 }
 
@@ -37,6 +37,21 @@ pub enum CredsError {
     #[cfg(feature = "jwe")]
     #[snafu(display("JWE error: {source}"))]
     JoseKit { source: josekit::JoseError },
+
+    #[cfg(feature = "paseto")]
+    #[snafu(display("PASETO error: {message}"))]
+    Paseto { message: String },
+
+    #[cfg(feature = "x5c")]
+    #[snafu(display("Untrusted JWK: {message}"))]
+    UntrustedJwk { message: String },
+
+    #[cfg(feature = "jwt-sign")]
+    #[snafu(display("Signing keyring I/O error at {}: {source}", path.display()))]
+    SigningKeyringIo {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
 }
 
 impl ToHttpStatus for CredsError {
@@ -49,9 +64,15 @@ impl ToHttpStatus for CredsError {
             CredsError::JSONWebToken { .. } => StatusCode::UNAUTHORIZED,
             #[cfg(feature = "jwe")]
             CredsError::JoseKit { .. } => StatusCode::UNAUTHORIZED,
+            #[cfg(feature = "paseto")]
+            CredsError::Paseto { .. } => StatusCode::UNAUTHORIZED,
+            #[cfg(feature = "x5c")]
+            CredsError::UntrustedJwk { .. } => StatusCode::UNAUTHORIZED,
             CredsError::PasswordHash { .. }
             | CredsError::ConfigError { .. }
             | CredsError::RandomBytes { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            #[cfg(feature = "jwt-sign")]
+            CredsError::SigningKeyringIo { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
@@ -86,6 +107,18 @@ impl ToErrorPresentation for CredsError {
                 "The access token is invalid or expired.",
                 UserRecovery::Reauthenticate,
             ),
+            #[cfg(feature = "paseto")]
+            CredsError::Paseto { .. } => ErrorPresentation::new(
+                "auth_invalid_token",
+                "The access token is invalid or expired.",
+                UserRecovery::Reauthenticate,
+            ),
+            #[cfg(feature = "x5c")]
+            CredsError::UntrustedJwk { .. } => ErrorPresentation::new(
+                "auth_invalid_token",
+                "The access token is invalid or expired.",
+                UserRecovery::Reauthenticate,
+            ),
             CredsError::PasswordHash { .. }
             | CredsError::ConfigError { .. }
             | CredsError::RandomBytes { .. } => ErrorPresentation::new(
@@ -93,6 +126,12 @@ impl ToErrorPresentation for CredsError {
                 "Authentication is temporarily unavailable.",
                 UserRecovery::ContactSupport,
             ),
+            #[cfg(feature = "jwt-sign")]
+            CredsError::SigningKeyringIo { .. } => ErrorPresentation::new(
+                "auth_temporarily_unavailable",
+                "Authentication is temporarily unavailable.",
+                UserRecovery::ContactSupport,
+            ),
         }
     }
 }
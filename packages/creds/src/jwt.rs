@@ -1,8 +1,13 @@
 use std::{collections::HashMap, ops::Deref};
 
-use jsonwebtoken::TokenData as JwtTokenDataOrigin;
 pub use jsonwebtoken::{
-    DecodingKey as JwtDecodingKey, Header as JwtHeader, Validation as JwtValidation,
+    Algorithm as JwtAlgorithm, DecodingKey as JwtDecodingKey, Header as JwtHeader,
+    Validation as JwtValidation,
+};
+use jsonwebtoken::{
+    TokenData as JwtTokenDataOrigin,
+    errors::ErrorKind as JwtErrorKind,
+    jwk::{Jwk, PublicKeyUse},
 };
 use securitydept_utils::ser::SpaceSeparated;
 use serde::{Deserialize, Serialize, Serializer, de::DeserializeOwned};
@@ -132,6 +137,8 @@ where
 
     let jwk = jwk.to_jwt_jwk()?;
 
+    check_jwk_matches_jwt_header(&jwk, &header)?;
+
     let key = JwtDecodingKey::from_jwk(&jwk).context(JSONWebTokenSnafu)?;
 
     let validation = validation_fn(JwtValidation::new(header.alg))?;
@@ -142,7 +149,54 @@ where
     Ok(token_data)
 }
 
-fn find_jwk_for_jwt<'a, JWK, JWKS>(kid: Option<&str>, jwks: &'a JWKS) -> CredsResult<&'a JWK>
+/// Reject a token whose `alg` differs from the `alg` the JWK is published
+/// for, or whose JWK is not published for signatures (`use` other than
+/// `sig`). Either field may be absent from the JWK.
+pub fn check_jwk_matches_jwt_header(jwk: &Jwk, header: &JwtHeader) -> CredsResult<()> {
+    let algorithm_matches = jwk.common.key_algorithm.is_none_or(|key_algorithm| {
+        key_algorithm
+            .to_string()
+            .parse::<JwtAlgorithm>()
+            .is_ok_and(|algorithm| algorithm == header.alg)
+    });
+    let use_matches = jwk
+        .common
+        .public_key_use
+        .as_ref()
+        .is_none_or(|key_use| *key_use == PublicKeyUse::Signature);
+
+    if algorithm_matches && use_matches {
+        Ok(())
+    } else {
+        Err(jsonwebtoken::errors::Error::from(
+            JwtErrorKind::InvalidAlgorithm,
+        ))
+        .context(JSONWebTokenSnafu)
+    }
+}
+
+/// Whether a JWT `typ` header matches `expected`.
+///
+/// Media type names are compared case-insensitively and the `application/`
+/// prefix is optional, as described in
+/// [RFC 7515 §4.1.9](https://www.rfc-editor.org/rfc/rfc7515#section-4.1.9).
+pub fn jwt_typ_matches(typ: Option<&str>, expected: &str) -> bool {
+    fn strip_application_prefix(value: &str) -> &str {
+        match value.get(..12) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("application/") => &value[12..],
+            _ => value,
+        }
+    }
+
+    typ.is_some_and(|typ| {
+        strip_application_prefix(typ).eq_ignore_ascii_case(strip_application_prefix(expected))
+    })
+}
+
+pub(crate) fn find_jwk_for_jwt<'a, JWK, JWKS>(
+    kid: Option<&str>,
+    jwks: &'a JWKS,
+) -> CredsResult<&'a JWK>
 where
     JWK: JwtJwkTrait,
     JWKS: JwtJwksTrait<JWK>,
@@ -164,3 +218,51 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::jwk::KeyAlgorithm;
+
+    use super::*;
+
+    fn sample_jwk(key_algorithm: Option<KeyAlgorithm>, key_use: Option<PublicKeyUse>) -> Jwk {
+        let mut jwk: Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo",
+        }))
+        .expect("sample JWK should deserialize");
+        jwk.common.key_algorithm = key_algorithm;
+        jwk.common.public_key_use = key_use;
+        jwk
+    }
+
+    #[test]
+    fn jwk_without_alg_or_use_accepts_any_header_alg() {
+        let jwk = sample_jwk(None, None);
+        assert!(check_jwk_matches_jwt_header(&jwk, &JwtHeader::new(JwtAlgorithm::EdDSA)).is_ok());
+    }
+
+    #[test]
+    fn jwk_alg_must_match_header_alg() {
+        let jwk = sample_jwk(Some(KeyAlgorithm::EdDSA), Some(PublicKeyUse::Signature));
+        assert!(check_jwk_matches_jwt_header(&jwk, &JwtHeader::new(JwtAlgorithm::EdDSA)).is_ok());
+
+        let jwk = sample_jwk(Some(KeyAlgorithm::RS256), None);
+        assert!(check_jwk_matches_jwt_header(&jwk, &JwtHeader::new(JwtAlgorithm::EdDSA)).is_err());
+    }
+
+    #[test]
+    fn encryption_jwk_is_rejected_for_signatures() {
+        let jwk = sample_jwk(None, Some(PublicKeyUse::Encryption));
+        assert!(check_jwk_matches_jwt_header(&jwk, &JwtHeader::new(JwtAlgorithm::EdDSA)).is_err());
+    }
+
+    #[test]
+    fn typ_comparison_ignores_case_and_application_prefix() {
+        assert!(jwt_typ_matches(Some("at+jwt"), "at+jwt"));
+        assert!(jwt_typ_matches(Some("application/AT+JWT"), "at+jwt"));
+        assert!(!jwt_typ_matches(Some("JWT"), "at+jwt"));
+        assert!(!jwt_typ_matches(None, "at+jwt"));
+    }
+}
@@ -7,17 +7,27 @@ pub mod jwe;
 pub mod jwk;
 #[cfg(feature = "jwt")]
 pub mod jwt;
+#[cfg(feature = "paseto")]
+pub mod paseto;
+pub mod password_hash;
 #[cfg(feature = "rfc9068")]
 pub mod rfc9068;
+#[cfg(feature = "jwt-sign")]
+pub mod signing;
+#[cfg(feature = "jwt-sign")]
+pub mod signing_keyring;
 pub mod static_token;
 pub mod token;
 pub mod validator;
+#[cfg(feature = "x5c")]
+pub mod x5c;
 
 pub use basic::{
-    Argon2BasicAuthCred, BasicAuthCred, hash_password_argon2, is_basic_auth_header,
+    Argon2BasicAuthCred, BasicAuthCred, MultiFormatBasicAuthCred, argon2_hash_below_policy,
+    hash_password_argon2, hash_password_argon2_with_config, is_basic_auth_header,
     parse_basic_auth_header_opt, verify_password_argon2,
 };
-pub use config::{BasicAuthCredsConfig, StaticTokenAuthCredsConfig};
+pub use config::{Argon2HashConfig, BasicAuthCredsConfig, StaticTokenAuthCredsConfig};
 pub use error::{CredsError, CredsResult};
 #[cfg(feature = "jwe")]
 pub use jwe::{
@@ -30,18 +40,37 @@ pub use jwk::{JweJwkTrait, JweJwksTrait, LocalJweDecryptionKeySet};
 pub use jwk::{JwtJwkTrait, JwtJwksTrait};
 #[cfg(feature = "jwt")]
 pub use jwt::{
-    Audience, CoreJwtClaims, JwtClaimsTrait, JwtDecodingKey, JwtHeader, JwtTokenData,
-    JwtValidation, Scope, verify_token_jwt, verify_token_jwt_with_jwks,
+    Audience, CoreJwtClaims, JwtAlgorithm, JwtClaimsTrait, JwtDecodingKey, JwtHeader, JwtTokenData,
+    JwtValidation, Scope, check_jwk_matches_jwt_header, jwt_typ_matches, verify_token_jwt,
+    verify_token_jwt_with_jwks,
 };
+#[cfg(feature = "paseto")]
+pub use paseto::{
+    PASETO_V4_LOCAL_HEADER, PASETO_V4_PUBLIC_HEADER, PasetoPurpose, PasetoTokenData,
+    verify_token_paseto_with_jwks,
+};
+pub use password_hash::{PasswordHashFormat, verify_password_hash};
 #[cfg(all(feature = "rfc9068", feature = "jwe"))]
 pub use rfc9068::verify_token_rfc9068_with_jwks;
 #[cfg(feature = "rfc9068")]
 pub use rfc9068::{
-    TokenData, TokenFormat, TokenJwtClaims, verify_token_rfc9068_with_jwks_without_jwe,
+    ACCESS_TOKEN_JWT_TYPE, TokenData, TokenFormat, TokenJwtClaims, is_access_token_jwt_typ,
+    verify_token_rfc9068_with_jwks_without_jwe,
+};
+#[cfg(all(feature = "jwt-sign", feature = "rfc9068"))]
+pub use signing::sign_token_rfc9068;
+#[cfg(feature = "jwt-sign")]
+pub use signing::{
+    JwtEncodingKey, JwtSigningKeyTrait, LocalJwtSigningKey, LocalJwtSigningKeySet, sign_token_jwt,
+};
+#[cfg(feature = "jwt-sign")]
+pub use signing_keyring::{
+    LocalJwtSigningKeyring, PendingKeyringRotation, SigningKeyringConfig, generate_private_key_pem,
 };
 pub use static_token::{
-    Sha256TokenAuthCred, StaticTokenAuthCred, generate_static_token, hash_token_sha256,
-    verify_token_sha256,
+    STRUCTURED_STATIC_TOKEN_PREFIX, Sha256TokenAuthCred, StaticTokenAuthCred,
+    StructuredStaticToken, generate_static_token, generate_structured_static_token,
+    hash_token_sha256, is_structured_static_token, verify_token_sha256,
 };
 pub use token::{
     TokenAuthCred, is_bearer_auth_header, parse_bearer_auth_header, parse_bearer_auth_header_opt,
@@ -50,3 +79,5 @@ pub use validator::{
     BasicAuthCredsValidator, MapBasicAuthCredsValidator, MapStaticTokenAuthCredsValidator,
     StaticTokenAuthCredsValidator,
 };
+#[cfg(feature = "x5c")]
+pub use x5c::{JwkTrustAnchors, X5cTrustedJwk, X5cTrustedJwks};
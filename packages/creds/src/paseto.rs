@@ -0,0 +1,416 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+    errors::ErrorKind as JwtErrorKind,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, KeyAlgorithm, PublicKeyUse},
+};
+use pasetors::{
+    Local, Public,
+    keys::{AsymmetricPublicKey, SymmetricKey},
+    token::UntrustedToken,
+    version4::{LocalToken, PublicToken, V4},
+};
+use serde_json::{Map, Value};
+use snafu::ResultExt;
+
+use crate::{
+    CredsError, CredsResult, JwtClaimsTrait, JwtJwkTrait, JwtJwksTrait, JwtValidation,
+    error::JSONWebTokenSnafu, jwt::find_jwk_for_jwt,
+};
+
+/// Header of [PASETO](https://github.com/paseto-standard/paseto-spec) v4
+/// public (Ed25519-signed) tokens.
+pub const PASETO_V4_PUBLIC_HEADER: &str = "v4.public.";
+/// Header of PASETO v4 local (symmetrically encrypted) tokens.
+pub const PASETO_V4_LOCAL_HEADER: &str = "v4.local.";
+
+/// PASETO claims that carry RFC 3339 timestamps, converted to JWT NumericDate
+/// seconds so the claims fit [`JwtClaimsTrait`].
+const PASETO_TIME_CLAIMS: [&str; 3] = ["exp", "nbf", "iat"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasetoPurpose {
+    Public,
+    Local,
+}
+
+impl PasetoPurpose {
+    /// The purpose of a v4 token, or `None` for anything else.
+    pub fn from_token(token: &str) -> Option<Self> {
+        if token.starts_with(PASETO_V4_PUBLIC_HEADER) {
+            Some(Self::Public)
+        } else if token.starts_with(PASETO_V4_LOCAL_HEADER) {
+            Some(Self::Local)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PasetoTokenData<CLAIMS> {
+    pub purpose: PasetoPurpose,
+    /// `kid` from a JSON footer, used to pick the verification key.
+    pub kid: Option<String>,
+    /// Raw (authenticated) footer bytes.
+    pub footer: Vec<u8>,
+    pub claims: CLAIMS,
+}
+
+fn paseto_error(message: impl Into<String>) -> CredsError {
+    CredsError::Paseto {
+        message: message.into(),
+    }
+}
+
+/// Verify a PASETO v4 token against a JWKS.
+///
+/// `v4.public` tokens need an Ed25519 `OKP` key; `v4.local` tokens need a
+/// 256-bit `oct` key without an `alg`, so a JWT HMAC secret is never reused
+/// as a PASETO key. The key is picked by the `kid` of a JSON footer, or is
+/// the only key in the set. Registered claims are checked against
+/// `validation` (issuer, audience, subject, `exp`/`nbf` with leeway and
+/// required claims); signature algorithms are fixed by the PASETO version.
+pub fn verify_token_paseto_with_jwks<CLAIMS, JWK, JWKS, F>(
+    token: &str,
+    jwks: &JWKS,
+    validation_fn: F,
+) -> CredsResult<PasetoTokenData<CLAIMS>>
+where
+    CLAIMS: JwtClaimsTrait,
+    JWK: JwtJwkTrait,
+    JWKS: JwtJwksTrait<JWK>,
+    F: FnOnce(JwtValidation) -> CredsResult<JwtValidation>,
+{
+    let purpose = PasetoPurpose::from_token(token)
+        .ok_or_else(|| paseto_error("Token is not a PASETO v4 public or local token"))?;
+
+    let (payload, footer) = match purpose {
+        PasetoPurpose::Public => {
+            let untrusted = UntrustedToken::<Public, V4>::try_from(token)
+                .map_err(|e| paseto_error(format!("Malformed v4.public token: {e:?}")))?;
+            let jwk = find_jwk_for_jwt(footer_kid(untrusted.untrusted_footer()).as_deref(), jwks)?
+                .to_jwt_jwk()?;
+            let key = public_key_from_jwk(&jwk)?;
+            let trusted = PublicToken::verify(&key, &untrusted, None, None)
+                .map_err(|_| paseto_error("v4.public token signature is invalid"))?;
+            (trusted.payload().to_string(), trusted.footer().to_vec())
+        }
+        PasetoPurpose::Local => {
+            let untrusted = UntrustedToken::<Local, V4>::try_from(token)
+                .map_err(|e| paseto_error(format!("Malformed v4.local token: {e:?}")))?;
+            let jwk = find_jwk_for_jwt(footer_kid(untrusted.untrusted_footer()).as_deref(), jwks)?
+                .to_jwt_jwk()?;
+            let key = local_key_from_jwk(&jwk)?;
+            let trusted = LocalToken::decrypt(&key, &untrusted, None, None)
+                .map_err(|_| paseto_error("v4.local token could not be decrypted"))?;
+            (trusted.payload().to_string(), trusted.footer().to_vec())
+        }
+    };
+
+    let mut claims: Map<String, Value> = serde_json::from_str(&payload)
+        .map_err(|e| paseto_error(format!("PASETO payload is not a JSON object: {e}")))?;
+    convert_time_claims(&mut claims)?;
+
+    let validation = validation_fn(JwtValidation::default())?;
+    validate_claims(&claims, &validation)?;
+
+    let claims = serde_json::from_value(Value::Object(claims)).map_err(|e| {
+        paseto_error(format!(
+            "PASETO claims do not match the expected shape: {e}"
+        ))
+    })?;
+
+    Ok(PasetoTokenData {
+        purpose,
+        kid: footer_kid(&footer),
+        footer,
+        claims,
+    })
+}
+
+fn footer_kid(footer: &[u8]) -> Option<String> {
+    serde_json::from_slice::<Map<String, Value>>(footer)
+        .ok()?
+        .get("kid")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn check_signature_use(jwk: &Jwk) -> CredsResult<()> {
+    if jwk
+        .common
+        .public_key_use
+        .as_ref()
+        .is_none_or(|key_use| *key_use == PublicKeyUse::Signature)
+    {
+        Ok(())
+    } else {
+        Err(paseto_error("JWK is not published for signatures"))
+    }
+}
+
+fn public_key_from_jwk(jwk: &Jwk) -> CredsResult<AsymmetricPublicKey<V4>> {
+    check_signature_use(jwk)?;
+    let AlgorithmParameters::OctetKeyPair(params) = &jwk.algorithm else {
+        return Err(paseto_error("v4.public tokens require an OKP JWK"));
+    };
+    if params.curve != EllipticCurve::Ed25519
+        || jwk
+            .common
+            .key_algorithm
+            .is_some_and(|algorithm| algorithm != KeyAlgorithm::EdDSA)
+    {
+        return Err(paseto_error(
+            "v4.public tokens require an Ed25519 (EdDSA) JWK",
+        ));
+    }
+
+    let bytes = URL_SAFE_NO_PAD
+        .decode(&params.x)
+        .map_err(|e| paseto_error(format!("Invalid Ed25519 JWK: {e}")))?;
+    AsymmetricPublicKey::<V4>::from(&bytes)
+        .map_err(|e| paseto_error(format!("Invalid Ed25519 JWK: {e:?}")))
+}
+
+fn local_key_from_jwk(jwk: &Jwk) -> CredsResult<SymmetricKey<V4>> {
+    check_signature_use(jwk)?;
+    let AlgorithmParameters::OctetKey(params) = &jwk.algorithm else {
+        return Err(paseto_error("v4.local tokens require an oct JWK"));
+    };
+    if jwk.common.key_algorithm.is_some() {
+        return Err(paseto_error(
+            "v4.local tokens require an oct JWK without an alg",
+        ));
+    }
+
+    let bytes = URL_SAFE_NO_PAD
+        .decode(&params.value)
+        .map_err(|e| paseto_error(format!("Invalid oct JWK: {e}")))?;
+    SymmetricKey::<V4>::from(&bytes)
+        .map_err(|_| paseto_error("v4.local keys must be exactly 32 bytes"))
+}
+
+fn convert_time_claims(claims: &mut Map<String, Value>) -> CredsResult<()> {
+    for name in PASETO_TIME_CLAIMS {
+        let Some(Value::String(value)) = claims.get(name) else {
+            continue;
+        };
+        let timestamp = chrono::DateTime::parse_from_rfc3339(value)
+            .map_err(|e| paseto_error(format!("PASETO claim {name} is not RFC 3339: {e}")))?
+            .timestamp();
+        claims.insert(name.to_string(), Value::from(timestamp));
+    }
+    Ok(())
+}
+
+fn claim_error(kind: JwtErrorKind) -> CredsResult<()> {
+    Err(jsonwebtoken::errors::Error::from(kind)).context(JSONWebTokenSnafu)
+}
+
+/// Apply the registered-claim checks `jsonwebtoken` performs for JWTs.
+fn validate_claims(claims: &Map<String, Value>, validation: &JwtValidation) -> CredsResult<()> {
+    for required in &validation.required_spec_claims {
+        if !claims.contains_key(required) {
+            return claim_error(JwtErrorKind::MissingRequiredClaim(required.clone()));
+        }
+    }
+
+    let now = jsonwebtoken::get_current_timestamp();
+    if validation.validate_exp
+        && let Some(exp) = claims.get("exp").and_then(Value::as_u64)
+        && exp.saturating_sub(validation.reject_tokens_expiring_in_less_than)
+            < now.saturating_sub(validation.leeway)
+    {
+        return claim_error(JwtErrorKind::ExpiredSignature);
+    }
+    if validation.validate_nbf
+        && let Some(nbf) = claims.get("nbf").and_then(Value::as_u64)
+        && nbf > now.saturating_add(validation.leeway)
+    {
+        return claim_error(JwtErrorKind::ImmatureSignature);
+    }
+
+    if let Some(issuers) = validation.iss.as_ref() {
+        match claims.get("iss").and_then(Value::as_str) {
+            Some(issuer) if issuers.contains(issuer) => {}
+            Some(_) => return claim_error(JwtErrorKind::InvalidIssuer),
+            None => return claim_error(JwtErrorKind::MissingRequiredClaim("iss".to_string())),
+        }
+    }
+
+    if let Some(subject) = validation.sub.as_deref()
+        && claims.get("sub").and_then(Value::as_str) != Some(subject)
+    {
+        return claim_error(JwtErrorKind::InvalidSubject);
+    }
+
+    if validation.validate_aud {
+        let audiences = match claims.get("aud") {
+            Some(Value::String(audience)) => vec![audience.as_str()],
+            Some(Value::Array(audiences)) => audiences.iter().filter_map(Value::as_str).collect(),
+            Some(_) => return claim_error(JwtErrorKind::InvalidAudience),
+            None => Vec::new(),
+        };
+        match validation.aud.as_ref() {
+            Some(_) if audiences.is_empty() => {
+                return claim_error(JwtErrorKind::MissingRequiredClaim("aud".to_string()));
+            }
+            Some(allowed) if !audiences.iter().any(|audience| allowed.contains(*audience)) => {
+                return claim_error(JwtErrorKind::InvalidAudience);
+            }
+            None if !audiences.is_empty() => return claim_error(JwtErrorKind::InvalidAudience),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::jwk::JwkSet;
+    use pasetors::keys::{AsymmetricKeyPair, Generate};
+
+    use super::*;
+    use crate::{CoreJwtClaims, TokenFormat};
+
+    const ISSUER: &str = "https://paseto.internal";
+
+    fn claims(exp: &str) -> String {
+        serde_json::json!({
+            "sub": "svc-a",
+            "iss": ISSUER,
+            "aud": "mesh-api",
+            "exp": exp,
+            "iat": "2024-01-01T00:00:00+00:00",
+        })
+        .to_string()
+    }
+
+    fn kid_footer(kid: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({ "kid": kid })).expect("footer should serialize")
+    }
+
+    fn jwk_set(jwk: serde_json::Value) -> JwkSet {
+        serde_json::from_value(serde_json::json!({ "keys": [jwk] })).expect("jwks should parse")
+    }
+
+    fn validation(mut validation: JwtValidation) -> CredsResult<JwtValidation> {
+        validation.set_required_spec_claims(&["exp", "iss"]);
+        validation.set_issuer(&[ISSUER]);
+        validation.set_audience(&["mesh-api"]);
+        Ok(validation)
+    }
+
+    fn public_fixture() -> (AsymmetricKeyPair<V4>, JwkSet) {
+        let key_pair = AsymmetricKeyPair::<V4>::generate().expect("key pair should generate");
+        let jwks = jwk_set(serde_json::json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "kid": "paseto-sig-1",
+            "x": URL_SAFE_NO_PAD.encode(key_pair.public.as_bytes()),
+        }));
+        (key_pair, jwks)
+    }
+
+    #[test]
+    fn token_format_detects_paseto_v4() {
+        assert_eq!(
+            TokenFormat::from_token("v4.public.abc"),
+            TokenFormat::PASETO
+        );
+        assert_eq!(TokenFormat::from_token("v4.local.abc"), TokenFormat::PASETO);
+        assert_eq!(
+            TokenFormat::from_token("v3.public.abc"),
+            TokenFormat::Opaque
+        );
+    }
+
+    #[test]
+    fn v4_public_token_verifies_and_maps_claims() {
+        let (key_pair, jwks) = public_fixture();
+        let token = PublicToken::sign(
+            &key_pair.secret,
+            claims("2099-01-01T00:00:00Z").as_bytes(),
+            Some(&kid_footer("paseto-sig-1")),
+            None,
+        )
+        .expect("token should sign");
+
+        let data =
+            verify_token_paseto_with_jwks::<CoreJwtClaims, _, _, _>(&token, &jwks, validation)
+                .expect("token should verify");
+
+        assert_eq!(data.purpose, PasetoPurpose::Public);
+        assert_eq!(data.kid.as_deref(), Some("paseto-sig-1"));
+        assert_eq!(data.claims.subject.as_deref(), Some("svc-a"));
+        assert_eq!(data.claims.expiration_time, Some(4_070_908_800));
+    }
+
+    #[test]
+    fn v4_public_token_rejects_expired_and_foreign_issuer() {
+        let (key_pair, jwks) = public_fixture();
+        let expired = PublicToken::sign(
+            &key_pair.secret,
+            claims("2020-01-01T00:00:00Z").as_bytes(),
+            None,
+            None,
+        )
+        .expect("token should sign");
+        let error =
+            verify_token_paseto_with_jwks::<CoreJwtClaims, _, _, _>(&expired, &jwks, validation)
+                .expect_err("expired token should fail");
+        assert!(matches!(error, CredsError::JSONWebToken { .. }));
+
+        let foreign = PublicToken::sign(
+            &key_pair.secret,
+            claims("2099-01-01T00:00:00Z")
+                .replace(ISSUER, "https://other.example.com")
+                .as_bytes(),
+            None,
+            None,
+        )
+        .expect("token should sign");
+        assert!(
+            verify_token_paseto_with_jwks::<CoreJwtClaims, _, _, _>(&foreign, &jwks, validation)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn v4_local_token_decrypts_with_oct_key() {
+        let key = SymmetricKey::<V4>::from(&[7u8; 32]).expect("key should build");
+        let jwks = jwk_set(serde_json::json!({
+            "kty": "oct",
+            "k": URL_SAFE_NO_PAD.encode([7u8; 32]),
+        }));
+        let token =
+            LocalToken::encrypt(&key, claims("2099-01-01T00:00:00Z").as_bytes(), None, None)
+                .expect("token should encrypt");
+
+        let data =
+            verify_token_paseto_with_jwks::<CoreJwtClaims, _, _, _>(&token, &jwks, validation)
+                .expect("token should decrypt");
+
+        assert_eq!(data.purpose, PasetoPurpose::Local);
+        assert_eq!(data.claims.issuer.as_deref(), Some(ISSUER));
+    }
+
+    #[test]
+    fn v4_local_token_rejects_hmac_bound_oct_key() {
+        let key = SymmetricKey::<V4>::from(&[7u8; 32]).expect("key should build");
+        let jwks = jwk_set(serde_json::json!({
+            "kty": "oct",
+            "alg": "HS256",
+            "k": URL_SAFE_NO_PAD.encode([7u8; 32]),
+        }));
+        let token =
+            LocalToken::encrypt(&key, claims("2099-01-01T00:00:00Z").as_bytes(), None, None)
+                .expect("token should encrypt");
+
+        let error =
+            verify_token_paseto_with_jwks::<CoreJwtClaims, _, _, _>(&token, &jwks, validation)
+                .expect_err("HMAC-bound keys must not decrypt PASETO tokens");
+        assert!(matches!(error, CredsError::Paseto { .. }));
+    }
+}
@@ -0,0 +1,287 @@
+//! Password hash format detection and verification.
+//!
+//! New hashes are always produced with argon2 (see
+//! [`hash_password_argon2`](crate::hash_password_argon2)). The legacy formats
+//! below are accepted for verification only, so that existing htpasswd-style
+//! users can be migrated without a password reset and re-hashed on their next
+//! successful login.
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use md5::Md5;
+use pbkdf2::{Pbkdf2, phc::PasswordHash as Pbkdf2PasswordHash};
+use sha1::{Digest, Sha1};
+
+use crate::{CredsError, CredsResult, verify_password_argon2};
+
+const MD5_CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// A password hash format understood by [`verify_password_hash`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordHashFormat {
+    /// Argon2 PHC string (`$argon2id$...`, `$argon2i$...`, `$argon2d$...`).
+    Argon2,
+    /// Bcrypt (`$2a$`, `$2b$`, `$2x$`, `$2y$`).
+    Bcrypt,
+    /// PBKDF2 PHC string (`$pbkdf2-sha256$...`, `$pbkdf2-sha512$...`).
+    Pbkdf2,
+    /// Apache htpasswd MD5 (`$apr1$...`).
+    Apr1Md5,
+    /// Unix MD5-crypt (`$1$...`).
+    Md5Crypt,
+    /// Unsalted htpasswd SHA-1 (`{SHA}base64`).
+    Sha1,
+}
+
+impl PasswordHashFormat {
+    /// Detect the format of a stored password hash from its prefix.
+    pub fn detect(password_hash: &str) -> Option<Self> {
+        if password_hash.starts_with("$argon2") {
+            Some(Self::Argon2)
+        } else if ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| password_hash.starts_with(prefix))
+        {
+            Some(Self::Bcrypt)
+        } else if password_hash.starts_with("$pbkdf2-") {
+            Some(Self::Pbkdf2)
+        } else if password_hash.starts_with("$apr1$") {
+            Some(Self::Apr1Md5)
+        } else if password_hash.starts_with("$1$") {
+            Some(Self::Md5Crypt)
+        } else if password_hash.starts_with("{SHA}") {
+            Some(Self::Sha1)
+        } else {
+            None
+        }
+    }
+
+    /// Whether hashes of this format should be replaced by argon2 once the
+    /// plaintext password is known.
+    pub fn is_legacy(self) -> bool {
+        !matches!(self, Self::Argon2)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Argon2 => "argon2",
+            Self::Bcrypt => "bcrypt",
+            Self::Pbkdf2 => "pbkdf2",
+            Self::Apr1Md5 => "apr1",
+            Self::Md5Crypt => "md5_crypt",
+            Self::Sha1 => "sha1",
+        }
+    }
+}
+
+fn unsupported_password_hash_error() -> CredsError {
+    CredsError::PasswordHash {
+        message: "Unsupported password hash format".to_string(),
+    }
+}
+
+/// Verify a plaintext password against a stored hash of any supported
+/// format.
+pub fn verify_password_hash(password: &str, password_hash: &str) -> CredsResult<bool> {
+    let format =
+        PasswordHashFormat::detect(password_hash).ok_or_else(unsupported_password_hash_error)?;
+
+    match format {
+        PasswordHashFormat::Argon2 => verify_password_argon2(password, password_hash),
+        PasswordHashFormat::Bcrypt => {
+            bcrypt::verify(password, password_hash).map_err(|e| CredsError::PasswordHash {
+                message: e.to_string(),
+            })
+        }
+        PasswordHashFormat::Pbkdf2 => verify_password_pbkdf2(password, password_hash),
+        PasswordHashFormat::Apr1Md5 => verify_password_md5_crypt(password, password_hash, "$apr1$"),
+        PasswordHashFormat::Md5Crypt => verify_password_md5_crypt(password, password_hash, "$1$"),
+        PasswordHashFormat::Sha1 => Ok(verify_password_sha1(password, password_hash)),
+    }
+}
+
+fn verify_password_pbkdf2(password: &str, password_hash: &str) -> CredsResult<bool> {
+    use pbkdf2::password_hash::PasswordVerifier;
+
+    let parsed = Pbkdf2PasswordHash::new(password_hash).map_err(|e| CredsError::PasswordHash {
+        message: e.to_string(),
+    })?;
+    Ok(Pbkdf2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok())
+}
+
+fn verify_password_sha1(password: &str, password_hash: &str) -> bool {
+    let expected = &password_hash["{SHA}".len()..];
+    let computed = BASE64.encode(Sha1::digest(password.as_bytes()));
+    constant_time_eq(computed.as_bytes(), expected.as_bytes())
+}
+
+fn verify_password_md5_crypt(
+    password: &str,
+    password_hash: &str,
+    magic: &str,
+) -> CredsResult<bool> {
+    let rest = &password_hash[magic.len()..];
+    let (salt, _) = rest
+        .split_once('$')
+        .ok_or_else(|| CredsError::PasswordHash {
+            message: "MD5-crypt hash is missing its salt separator".to_string(),
+        })?;
+    let computed = md5_crypt(password.as_bytes(), salt.as_bytes(), magic.as_bytes());
+    Ok(constant_time_eq(
+        computed.as_bytes(),
+        password_hash.as_bytes(),
+    ))
+}
+
+/// MD5-crypt as implemented by FreeBSD `crypt(3)` and Apache `htpasswd`; the
+/// two only differ in their magic prefix.
+fn md5_crypt(password: &[u8], salt: &[u8], magic: &[u8]) -> String {
+    let salt = &salt[..salt.len().min(8)];
+
+    let alternate = Md5::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut hasher = Md5::new();
+    hasher.update(password);
+    hasher.update(magic);
+    hasher.update(salt);
+    for chunk_start in (0..password.len()).step_by(16) {
+        let chunk_len = (password.len() - chunk_start).min(16);
+        hasher.update(&alternate[..chunk_len]);
+    }
+    let mut remaining = password.len();
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            hasher.update([0u8]);
+        } else {
+            hasher.update(&password[..1]);
+        }
+        remaining >>= 1;
+    }
+    let mut digest = hasher.finalize();
+
+    for round in 0..1000 {
+        let mut round_hasher = Md5::new();
+        if round & 1 == 1 {
+            round_hasher.update(password);
+        } else {
+            round_hasher.update(digest);
+        }
+        if round % 3 != 0 {
+            round_hasher.update(salt);
+        }
+        if round % 7 != 0 {
+            round_hasher.update(password);
+        }
+        if round & 1 == 1 {
+            round_hasher.update(digest);
+        } else {
+            round_hasher.update(password);
+        }
+        digest = round_hasher.finalize();
+    }
+
+    let mut encoded = String::with_capacity(22);
+    for (a, b, c) in [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)] {
+        let value =
+            (u32::from(digest[a]) << 16) | (u32::from(digest[b]) << 8) | u32::from(digest[c]);
+        push_md5_crypt_base64(&mut encoded, value, 4);
+    }
+    push_md5_crypt_base64(&mut encoded, u32::from(digest[11]), 2);
+
+    format!(
+        "{}{}${}",
+        String::from_utf8_lossy(magic),
+        String::from_utf8_lossy(salt),
+        encoded
+    )
+}
+
+fn push_md5_crypt_base64(out: &mut String, mut value: u32, chars: usize) {
+    for _ in 0..chars {
+        out.push(MD5_CRYPT_ALPHABET[(value & 0x3f) as usize] as char);
+        value >>= 6;
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_password_argon2;
+
+    #[test]
+    fn test_detect_password_hash_format() {
+        assert_eq!(
+            PasswordHashFormat::detect("$argon2id$v=19$m=19456,t=2,p=1$abc$def"),
+            Some(PasswordHashFormat::Argon2)
+        );
+        assert_eq!(
+            PasswordHashFormat::detect("$2y$05$abcdefghijklmnopqrstuu"),
+            Some(PasswordHashFormat::Bcrypt)
+        );
+        assert_eq!(
+            PasswordHashFormat::detect("{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g="),
+            Some(PasswordHashFormat::Sha1)
+        );
+        assert_eq!(PasswordHashFormat::detect("plaintext"), None);
+        assert!(!PasswordHashFormat::Argon2.is_legacy());
+        assert!(PasswordHashFormat::Apr1Md5.is_legacy());
+    }
+
+    #[test]
+    fn test_verify_argon2_hash() {
+        let hash = hash_password_argon2("password").unwrap();
+        assert!(verify_password_hash("password", &hash).unwrap());
+        assert!(!verify_password_hash("wrong", &hash).unwrap());
+    }
+
+    #[test]
+    fn test_verify_bcrypt_hash() {
+        let hash = bcrypt::hash("password", 4)
+            .unwrap()
+            .replacen("$2b$", "$2y$", 1);
+        assert!(verify_password_hash("password", &hash).unwrap());
+        assert!(!verify_password_hash("wrong", &hash).unwrap());
+    }
+
+    #[test]
+    fn test_verify_sha1_hash() {
+        let hash = "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=";
+        assert!(verify_password_hash("password", hash).unwrap());
+        assert!(!verify_password_hash("wrong", hash).unwrap());
+    }
+
+    #[test]
+    fn test_verify_md5_crypt_hashes() {
+        let apr1 = "$apr1$r31abcde$kl9eNjSys8oZ/nHjspdaj0";
+        assert!(verify_password_hash("myPassword", apr1).unwrap());
+        assert!(!verify_password_hash("wrong", apr1).unwrap());
+
+        let md5_crypt = "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/";
+        assert!(verify_password_hash("password", md5_crypt).unwrap());
+    }
+
+    #[test]
+    fn test_verify_pbkdf2_hash() {
+        let hash = "$pbkdf2-sha256$i=4096,l=40$c2FsdFNBTFRzYWx0U0FMVHNhbHRTQUxUc2FsdFNBTFRzYWx0$\
+                    NIyJ28vTKy8y2BS4EW6EzysXNH68GAAYHE4qH7jdU+HGNVGMfaxH6Q";
+        assert!(verify_password_hash("passwordPASSWORDpassword", hash).unwrap());
+        assert!(!verify_password_hash("wrong", hash).unwrap());
+    }
+
+    #[test]
+    fn test_verify_unknown_hash_format_is_error() {
+        assert!(verify_password_hash("password", "plaintext").is_err());
+    }
+}
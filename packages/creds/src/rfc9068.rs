@@ -5,9 +5,19 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     Audience, CredsResult, JwtClaimsTrait, JwtJwkTrait, JwtJwksTrait, JwtTokenData, JwtValidation,
-    Scope, verify_token_jwt_with_jwks,
+    Scope, jwt_typ_matches, verify_token_jwt_with_jwks,
 };
 
+/// `typ` header value of [RFC 9068](https://www.rfc-editor.org/rfc/rfc9068)
+/// access tokens.
+pub const ACCESS_TOKEN_JWT_TYPE: &str = "at+jwt";
+
+/// Whether a JWT `typ` header marks an RFC 9068 access token (`at+jwt` or
+/// `application/at+jwt`).
+pub fn is_access_token_jwt_typ(typ: Option<&str>) -> bool {
+    jwt_typ_matches(typ, ACCESS_TOKEN_JWT_TYPE)
+}
+
 /// [RFC 9068](https://www.rfc-editor.org/rfc/rfc9068) claims.
 /// This is the claims that are required by the RFC.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -77,11 +87,20 @@ struct JWTHeaderMinimalValidation {
 pub enum TokenFormat {
     JWT,
     JWE,
+    /// PASETO v4 (`v4.public.` / `v4.local.`). Only detected with the
+    /// `paseto` feature; without it such tokens are [`Self::Opaque`].
+    PASETO,
     Opaque,
 }
 
 impl TokenFormat {
     pub fn from_token(token: &str) -> Self {
+        #[cfg(feature = "paseto")]
+        if token.starts_with(crate::PASETO_V4_PUBLIC_HEADER)
+            || token.starts_with(crate::PASETO_V4_LOCAL_HEADER)
+        {
+            return TokenFormat::PASETO;
+        }
         // JWT tokens start with "eyJ" => {
         if !token.starts_with("eyJ") {
             return TokenFormat::Opaque;
@@ -107,9 +126,46 @@ pub enum TokenData<CLAIMS: JwtClaimsTrait> {
     JWE(Box<crate::JweTokenData<CLAIMS>>),
     #[cfg(not(feature = "jwe"))]
     JWE,
+    #[cfg(feature = "paseto")]
+    PASETO(Box<crate::PasetoTokenData<CLAIMS>>),
+    #[cfg(not(feature = "paseto"))]
+    PASETO,
     Opaque,
 }
 
+#[cfg(feature = "paseto")]
+fn verify_token_paseto_data<CLAIMS, JWTJWK, JWTJWKS, VF>(
+    token: &str,
+    jwt_jwks: &JWTJWKS,
+    validation_fn: VF,
+) -> CredsResult<TokenData<CLAIMS>>
+where
+    CLAIMS: JwtClaimsTrait,
+    JWTJWK: JwtJwkTrait,
+    JWTJWKS: JwtJwksTrait<JWTJWK>,
+    VF: FnOnce(JwtValidation) -> CredsResult<JwtValidation>,
+{
+    let data = crate::verify_token_paseto_with_jwks(token, jwt_jwks, validation_fn)?;
+    Ok(TokenData::PASETO(Box::new(data)))
+}
+
+#[cfg(not(feature = "paseto"))]
+fn verify_token_paseto_data<CLAIMS, JWTJWK, JWTJWKS, VF>(
+    _token: &str,
+    _jwt_jwks: &JWTJWKS,
+    _validation_fn: VF,
+) -> CredsResult<TokenData<CLAIMS>>
+where
+    CLAIMS: JwtClaimsTrait,
+    JWTJWK: JwtJwkTrait,
+    JWTJWKS: JwtJwksTrait<JWTJWK>,
+    VF: FnOnce(JwtValidation) -> CredsResult<JwtValidation>,
+{
+    Err(crate::CredsError::InvalidCredentialsFormat {
+        message: "PASETO token format is not supported when paseto feature is disabled".to_string(),
+    })
+}
+
 #[cfg(feature = "jwe")]
 pub fn verify_token_rfc9068_with_jwks<CLAIMS, JWTJWK, JWTJWKS, JWEJWK, JWEJWKS, VF>(
     token: &str,
@@ -140,6 +196,7 @@ where
             )?;
             Ok(TokenData::JWE(Box::new(data)))
         }
+        TokenFormat::PASETO => verify_token_paseto_data(token, jwt_jwks, validation_fn),
         TokenFormat::Opaque => Ok(TokenData::Opaque),
     }
 }
@@ -164,6 +221,21 @@ where
         TokenFormat::JWE => Err(crate::CredsError::InvalidCredentialsFormat {
             message: "JWE token format is not supported when JWE feature is disabled".to_string(),
         }),
+        TokenFormat::PASETO => verify_token_paseto_data(token, jwt_jwks, validation_fn),
         TokenFormat::Opaque => Ok(TokenData::Opaque),
     }
 }
+
+#[cfg(all(test, not(feature = "paseto")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paseto_like_tokens_stay_opaque_without_the_paseto_feature() {
+        assert_eq!(
+            TokenFormat::from_token("v4.public.abc"),
+            TokenFormat::Opaque
+        );
+        assert_eq!(TokenFormat::from_token("v4.local.abc"), TokenFormat::Opaque);
+    }
+}
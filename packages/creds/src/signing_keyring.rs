@@ -0,0 +1,538 @@
+//! Managed local signing keyring with rotation.
+//!
+//! The keyring owns a small JSON file holding PKCS#8 private keys. The newest
+//! unretired key signs; when it is older than
+//! [`SigningKeyringConfig::rotation_interval`] a fresh key is generated and
+//! the previous one is retired. Retired keys stay in the published JWKS for
+//! [`SigningKeyringConfig::overlap`] so that tokens signed just before a
+//! rotation keep verifying, and are dropped afterwards.
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use atomic_write_file::AtomicWriteFile;
+use ed25519_dalek::SigningKey as Ed25519SigningKey;
+use jsonwebtoken::jwk::JwkSet;
+use p256::pkcs8::{EncodePrivateKey, LineEnding};
+use rsa::rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use crate::{
+    CredsError, CredsResult, JwtAlgorithm, JwtJwksTrait, LocalJwtSigningKey, LocalJwtSigningKeySet,
+    error::SigningKeyringIoSnafu,
+};
+
+/// Configuration for [`LocalJwtSigningKeyring`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SigningKeyringConfig {
+    /// Path of the keyring file. Created on first use.
+    pub path: PathBuf,
+    /// Algorithm of newly generated keys: `RS256`, `ES256` or `EdDSA`.
+    #[serde(default = "default_signing_keyring_algorithm")]
+    pub algorithm: JwtAlgorithm,
+    /// How long a key signs before it is rotated out.
+    #[serde(
+        default = "default_signing_keyring_rotation_interval",
+        with = "humantime_serde"
+    )]
+    pub rotation_interval: Duration,
+    /// How long a rotated-out key stays published for verification.
+    #[serde(default = "default_signing_keyring_overlap", with = "humantime_serde")]
+    pub overlap: Duration,
+    /// Modulus size of generated RSA keys.
+    #[serde(default = "default_signing_keyring_rsa_bits")]
+    pub rsa_bits: usize,
+}
+
+fn default_signing_keyring_algorithm() -> JwtAlgorithm {
+    JwtAlgorithm::ES256
+}
+
+fn default_signing_keyring_rotation_interval() -> Duration {
+    Duration::from_secs(30 * 24 * 60 * 60)
+}
+
+fn default_signing_keyring_overlap() -> Duration {
+    Duration::from_secs(7 * 24 * 60 * 60)
+}
+
+fn default_signing_keyring_rsa_bits() -> usize {
+    2048
+}
+
+impl SigningKeyringConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            algorithm: default_signing_keyring_algorithm(),
+            rotation_interval: default_signing_keyring_rotation_interval(),
+            overlap: default_signing_keyring_overlap(),
+            rsa_bits: default_signing_keyring_rsa_bits(),
+        }
+    }
+
+    /// Validate the configuration.
+    pub fn validate(&self) -> CredsResult<()> {
+        if !matches!(
+            self.algorithm,
+            JwtAlgorithm::RS256 | JwtAlgorithm::ES256 | JwtAlgorithm::EdDSA
+        ) {
+            return Err(CredsError::ConfigError {
+                message: format!(
+                    "Unsupported signing keyring algorithm: {:?}",
+                    self.algorithm
+                ),
+            });
+        }
+        if self.rotation_interval.is_zero() {
+            return Err(CredsError::ConfigError {
+                message: "Signing keyring rotation_interval must be greater than zero".to_string(),
+            });
+        }
+        if self.algorithm == JwtAlgorithm::RS256 && self.rsa_bits < 2048 {
+            return Err(CredsError::ConfigError {
+                message: "Signing keyring rsa_bits must be at least 2048".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A key as persisted in the keyring file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct PersistedSigningKey {
+    kid: String,
+    algorithm: JwtAlgorithm,
+    /// Unix seconds.
+    created_at: u64,
+    /// Unix seconds; `None` while the key is active.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retired_at: Option<u64>,
+    private_key_pem: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct PersistedSigningKeyring {
+    keys: Vec<PersistedSigningKey>,
+}
+
+/// A rotating set of locally generated signing keys persisted to disk.
+#[derive(Debug)]
+pub struct LocalJwtSigningKeyring {
+    config: SigningKeyringConfig,
+    records: Vec<PersistedSigningKey>,
+    key_set: LocalJwtSigningKeySet,
+}
+
+impl LocalJwtSigningKeyring {
+    /// Load the keyring file, generating and persisting a first key when it
+    /// does not exist yet, and rotate if the active key is already due.
+    pub fn open(config: SigningKeyringConfig) -> CredsResult<Self> {
+        Self::open_at(config, SystemTime::now())
+    }
+
+    pub fn open_at(config: SigningKeyringConfig, now: SystemTime) -> CredsResult<Self> {
+        config.validate()?;
+        let records = match std::fs::read(&config.path) {
+            Ok(bytes) => {
+                serde_json::from_slice::<PersistedSigningKeyring>(&bytes)
+                    .map_err(|e| CredsError::ConfigError {
+                        message: format!(
+                            "Invalid signing keyring file {}: {e}",
+                            config.path.display()
+                        ),
+                    })?
+                    .keys
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(source) => {
+                return Err(source).context(SigningKeyringIoSnafu {
+                    path: config.path.clone(),
+                });
+            }
+        };
+
+        let mut keyring = Self {
+            key_set: build_key_set(&records)?,
+            config,
+            records,
+        };
+        keyring.rotate_if_due_at(now)?;
+        Ok(keyring)
+    }
+
+    pub fn config(&self) -> &SigningKeyringConfig {
+        &self.config
+    }
+
+    /// The key new tokens should be signed with.
+    pub fn active_key(&self) -> &LocalJwtSigningKey {
+        let index = self
+            .records
+            .iter()
+            .rposition(|record| record.retired_at.is_none())
+            .expect("an opened keyring always has an active key");
+        &self.key_set.keys()[index]
+    }
+
+    /// Every key still valid for verification, active key included.
+    pub fn key_set(&self) -> &LocalJwtSigningKeySet {
+        &self.key_set
+    }
+
+    /// The public JWKS to publish.
+    pub fn to_public_jwk_set(&self) -> JwkSet {
+        self.key_set.to_public_jwk_set()
+    }
+
+    /// When the active key is due for rotation, or when the oldest retired
+    /// key leaves the overlap window, whichever comes first.
+    pub fn next_transition_at(&self) -> SystemTime {
+        self.records
+            .iter()
+            .map(|record| match record.retired_at {
+                None => record.created_at + self.config.rotation_interval.as_secs(),
+                Some(retired_at) => retired_at + self.config.overlap.as_secs(),
+            })
+            .min()
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap_or(UNIX_EPOCH)
+    }
+
+    /// Rotate when the active key has reached its rotation interval and drop
+    /// retired keys past the overlap window. Returns whether anything
+    /// changed; changes are persisted before returning.
+    pub fn rotate_if_due(&mut self) -> CredsResult<bool> {
+        self.rotate_if_due_at(SystemTime::now())
+    }
+
+    pub fn rotate_if_due_at(&mut self, now: SystemTime) -> CredsResult<bool> {
+        match self.prepare_rotation_at(now)? {
+            Some(rotation) => self.apply_rotation(rotation),
+            None => Ok(false),
+        }
+    }
+
+    /// Compute what [`rotate_if_due`](Self::rotate_if_due) would change,
+    /// generating any new key, without modifying the keyring. Lets callers
+    /// sharing the keyring behind a lock do the expensive key generation
+    /// under a read guard and take the write guard only for
+    /// [`apply_rotation`](Self::apply_rotation).
+    pub fn prepare_rotation(&self) -> CredsResult<Option<PendingKeyringRotation>> {
+        self.prepare_rotation_at(SystemTime::now())
+    }
+
+    pub fn prepare_rotation_at(
+        &self,
+        now: SystemTime,
+    ) -> CredsResult<Option<PendingKeyringRotation>> {
+        let now = unix_seconds(now);
+        let rotation_due = match self
+            .records
+            .iter()
+            .rfind(|record| record.retired_at.is_none())
+        {
+            Some(active) => {
+                now >= active.created_at + self.config.rotation_interval.as_secs()
+                    || active.algorithm != self.config.algorithm
+            }
+            None => true,
+        };
+        let mut records = self.records.clone();
+        if rotation_due {
+            rotate_records(&mut records, &self.config, now)?;
+        }
+        let before_prune = records.len();
+        prune_records(&mut records, &self.config, now);
+
+        if !rotation_due && records.len() == before_prune {
+            return Ok(None);
+        }
+        Ok(Some(PendingKeyringRotation {
+            base_kids: record_kids(&self.records),
+            key_set: build_key_set(&records)?,
+            records,
+        }))
+    }
+
+    /// Persist and swap in a prepared rotation. Returns `false` without
+    /// changing anything when the keyring changed since the rotation was
+    /// prepared.
+    pub fn apply_rotation(&mut self, rotation: PendingKeyringRotation) -> CredsResult<bool> {
+        if rotation.base_kids != record_kids(&self.records) {
+            return Ok(false);
+        }
+        self.commit(rotation.records, rotation.key_set)?;
+        Ok(true)
+    }
+
+    /// Rotate immediately regardless of the schedule.
+    pub fn rotate(&mut self) -> CredsResult<()> {
+        self.rotate_at(SystemTime::now())
+    }
+
+    pub fn rotate_at(&mut self, now: SystemTime) -> CredsResult<()> {
+        let now = unix_seconds(now);
+        let mut records = self.records.clone();
+        rotate_records(&mut records, &self.config, now)?;
+        prune_records(&mut records, &self.config, now);
+        let key_set = build_key_set(&records)?;
+        self.commit(records, key_set)
+    }
+
+    fn commit(
+        &mut self,
+        records: Vec<PersistedSigningKey>,
+        key_set: LocalJwtSigningKeySet,
+    ) -> CredsResult<()> {
+        write_keyring_file(
+            &self.config.path,
+            &PersistedSigningKeyring {
+                keys: records.clone(),
+            },
+        )?;
+        self.records = records;
+        self.key_set = key_set;
+        Ok(())
+    }
+}
+
+/// Keyring changes computed ahead of the swap, see
+/// [`LocalJwtSigningKeyring::prepare_rotation`].
+#[derive(Debug)]
+pub struct PendingKeyringRotation {
+    /// Kids of the keyring the rotation was computed from.
+    base_kids: Vec<String>,
+    records: Vec<PersistedSigningKey>,
+    key_set: LocalJwtSigningKeySet,
+}
+
+fn record_kids(records: &[PersistedSigningKey]) -> Vec<String> {
+    records.iter().map(|record| record.kid.clone()).collect()
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn rotate_records(
+    records: &mut Vec<PersistedSigningKey>,
+    config: &SigningKeyringConfig,
+    now: u64,
+) -> CredsResult<()> {
+    for record in records.iter_mut() {
+        record.retired_at.get_or_insert(now);
+    }
+    records.push(PersistedSigningKey {
+        kid: generate_kid(),
+        algorithm: config.algorithm,
+        created_at: now,
+        retired_at: None,
+        private_key_pem: generate_private_key_pem(config.algorithm, config.rsa_bits)?,
+    });
+    Ok(())
+}
+
+fn prune_records(records: &mut Vec<PersistedSigningKey>, config: &SigningKeyringConfig, now: u64) {
+    records.retain(|record| {
+        record
+            .retired_at
+            .is_none_or(|retired_at| now < retired_at + config.overlap.as_secs())
+    });
+}
+
+fn build_key_set(records: &[PersistedSigningKey]) -> CredsResult<LocalJwtSigningKeySet> {
+    let keys = records
+        .iter()
+        .map(|record| {
+            LocalJwtSigningKey::from_pem(
+                record.kid.clone(),
+                record.algorithm,
+                record.private_key_pem.as_bytes(),
+            )
+        })
+        .collect::<CredsResult<Vec<_>>>()?;
+    Ok(LocalJwtSigningKeySet::new(keys))
+}
+
+fn generate_kid() -> String {
+    let mut bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Generate a fresh PKCS#8 PEM private key for `algorithm`.
+pub fn generate_private_key_pem(algorithm: JwtAlgorithm, rsa_bits: usize) -> CredsResult<String> {
+    let key_generation_error = |e: &dyn std::fmt::Display| CredsError::ConfigError {
+        message: format!("Failed to generate {algorithm:?} signing key: {e}"),
+    };
+    let pem = match algorithm {
+        JwtAlgorithm::RS256 => rsa::RsaPrivateKey::new(&mut OsRng, rsa_bits)
+            .map_err(|e| key_generation_error(&e))?
+            .to_pkcs8_pem(LineEnding::LF),
+        JwtAlgorithm::ES256 => p256::SecretKey::random(&mut OsRng).to_pkcs8_pem(LineEnding::LF),
+        JwtAlgorithm::EdDSA => {
+            let mut secret = [0u8; 32];
+            OsRng.fill_bytes(&mut secret);
+            Ed25519SigningKey::from_bytes(&secret).to_pkcs8_pem(LineEnding::LF)
+        }
+        other => {
+            return Err(CredsError::ConfigError {
+                message: format!("Unsupported signing keyring algorithm: {other:?}"),
+            });
+        }
+    }
+    .map_err(|e| key_generation_error(&e))?;
+    Ok(pem.to_string())
+}
+
+fn write_keyring_file(path: &Path, keyring: &PersistedSigningKeyring) -> CredsResult<()> {
+    let bytes = serde_json::to_vec_pretty(keyring).map_err(|e| CredsError::ConfigError {
+        message: format!("Failed to serialize signing keyring: {e}"),
+    })?;
+    let io_context = || SigningKeyringIoSnafu {
+        path: path.to_path_buf(),
+    };
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent).with_context(|_| io_context())?;
+    }
+
+    let mut options = AtomicWriteFile::options();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+
+        use atomic_write_file::unix::OpenOptionsExt as _;
+
+        options.preserve_mode(false).mode(0o600);
+    }
+    let mut file = options.open(path).with_context(|_| io_context())?;
+    file.write_all(&bytes).with_context(|_| io_context())?;
+    file.flush().with_context(|_| io_context())?;
+    file.commit().with_context(|_| io_context())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JwtSigningKeyTrait;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn test_config(label: &str, algorithm: JwtAlgorithm) -> SigningKeyringConfig {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after unix epoch")
+            .as_nanos();
+        let mut config = SigningKeyringConfig::new(
+            std::env::temp_dir().join(format!("securitydept-signing-keyring-{label}-{nanos}.json")),
+        );
+        config.algorithm = algorithm;
+        config.rotation_interval = 30 * DAY;
+        config.overlap = 7 * DAY;
+        config
+    }
+
+    #[test]
+    fn opening_creates_and_persists_a_first_key() {
+        let config = test_config("create", JwtAlgorithm::EdDSA);
+        let start = UNIX_EPOCH + 1000 * DAY;
+
+        let keyring = LocalJwtSigningKeyring::open_at(config.clone(), start).unwrap();
+        let kid = keyring.active_key().kid().to_string();
+        assert_eq!(keyring.key_set().keys().len(), 1);
+        assert_eq!(keyring.active_key().algorithm(), JwtAlgorithm::EdDSA);
+
+        let reopened = LocalJwtSigningKeyring::open_at(config.clone(), start + DAY).unwrap();
+        assert_eq!(reopened.active_key().kid(), kid);
+        assert_eq!(reopened.next_transition_at(), start + 30 * DAY);
+
+        std::fs::remove_file(&config.path).ok();
+    }
+
+    #[test]
+    fn rotation_keeps_previous_key_for_the_overlap_window() {
+        let config = test_config("rotate", JwtAlgorithm::ES256);
+        let start = UNIX_EPOCH + 1000 * DAY;
+
+        let mut keyring = LocalJwtSigningKeyring::open_at(config.clone(), start).unwrap();
+        let first_kid = keyring.active_key().kid().to_string();
+        assert!(!keyring.rotate_if_due_at(start + 29 * DAY).unwrap());
+
+        assert!(keyring.rotate_if_due_at(start + 30 * DAY).unwrap());
+        let second_kid = keyring.active_key().kid().to_string();
+        assert_ne!(first_kid, second_kid);
+        let jwks = keyring.to_public_jwk_set();
+        assert_eq!(jwks.keys.len(), 2);
+        assert!(keyring.key_set().find(&first_kid).is_some());
+
+        assert!(keyring.rotate_if_due_at(start + 37 * DAY).unwrap());
+        assert!(keyring.key_set().find(&first_kid).is_none());
+        assert_eq!(keyring.active_key().kid(), second_kid);
+
+        let reopened = LocalJwtSigningKeyring::open_at(config.clone(), start + 38 * DAY).unwrap();
+        assert_eq!(reopened.active_key().kid(), second_kid);
+        assert_eq!(reopened.key_set().keys().len(), 1);
+
+        std::fs::remove_file(&config.path).ok();
+    }
+
+    #[test]
+    fn prepared_rotation_is_dropped_when_the_keyring_changed() {
+        let config = test_config("prepare", JwtAlgorithm::EdDSA);
+        let start = UNIX_EPOCH + 1000 * DAY;
+
+        let mut keyring = LocalJwtSigningKeyring::open_at(config.clone(), start).unwrap();
+        let first_kid = keyring.active_key().kid().to_string();
+        assert!(keyring.prepare_rotation_at(start + DAY).unwrap().is_none());
+
+        let stale = keyring
+            .prepare_rotation_at(start + 30 * DAY)
+            .unwrap()
+            .unwrap();
+        let fresh = keyring
+            .prepare_rotation_at(start + 30 * DAY)
+            .unwrap()
+            .unwrap();
+        assert_eq!(keyring.active_key().kid(), first_kid);
+
+        assert!(keyring.apply_rotation(fresh).unwrap());
+        let second_kid = keyring.active_key().kid().to_string();
+        assert_ne!(second_kid, first_kid);
+        assert!(!keyring.apply_rotation(stale).unwrap());
+        assert_eq!(keyring.active_key().kid(), second_kid);
+
+        std::fs::remove_file(&config.path).ok();
+    }
+
+    #[test]
+    fn changing_the_algorithm_rotates_on_open() {
+        let config = test_config("algorithm", JwtAlgorithm::EdDSA);
+        let start = UNIX_EPOCH + 1000 * DAY;
+        LocalJwtSigningKeyring::open_at(config.clone(), start).unwrap();
+
+        let mut changed = config.clone();
+        changed.algorithm = JwtAlgorithm::ES256;
+        let keyring = LocalJwtSigningKeyring::open_at(changed, start + DAY).unwrap();
+        assert_eq!(keyring.active_key().algorithm(), JwtAlgorithm::ES256);
+        assert_eq!(keyring.key_set().keys().len(), 2);
+
+        std::fs::remove_file(&config.path).ok();
+    }
+
+    #[test]
+    fn generated_rsa_key_loads() {
+        let pem = generate_private_key_pem(JwtAlgorithm::RS256, 2048).unwrap();
+        let key = LocalJwtSigningKey::from_pem("rsa", JwtAlgorithm::RS256, pem.as_bytes()).unwrap();
+        assert_eq!(key.public_jwk().common.key_id.as_deref(), Some("rsa"));
+    }
+}
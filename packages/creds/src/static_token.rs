@@ -10,17 +10,87 @@ use crate::{
     error::{CredsError, CredsResult},
 };
 
-pub fn generate_static_token() -> CredsResult<String> {
+/// Prefix of structured static tokens, so secret scanners can match them.
+pub const STRUCTURED_STATIC_TOKEN_PREFIX: &str = "sdt_";
+
+fn random_bytes() -> CredsResult<[u8; 32]> {
     let mut bytes = [0u8; 32];
     rand::rng()
         .try_fill_bytes(&mut bytes)
         .map_err(|e| CredsError::RandomBytes {
             message: e.to_string(),
         })?;
-    let token: String = BASE64.encode(bytes).to_string();
+    Ok(bytes)
+}
+
+/// Generate a bare static token (base64 of 32 random bytes).
+///
+/// Prefer [`generate_structured_static_token`] for new tokens; bare tokens
+/// remain supported for verification.
+pub fn generate_static_token() -> CredsResult<String> {
+    let token: String = BASE64.encode(random_bytes()?).to_string();
     Ok(token)
 }
 
+/// Generate a structured static token `sdt_<id>_<secret>_<crc>`.
+///
+/// `id` is a public, non-secret identifier (e.g. the owning entry id) and
+/// must not contain `_`. The trailing CRC32 covers everything before it.
+pub fn generate_structured_static_token(id: &str) -> CredsResult<String> {
+    if id.is_empty() || id.contains('_') {
+        return Err(CredsError::InvalidCredentialsFormat {
+            message: "Structured static token id must be non-empty and must not contain '_'"
+                .to_string(),
+        });
+    }
+    let body = format!(
+        "{STRUCTURED_STATIC_TOKEN_PREFIX}{id}_{}",
+        hex::encode(random_bytes()?)
+    );
+    let checksum = static_token_checksum(&body);
+    Ok(format!("{body}_{checksum}"))
+}
+
+fn static_token_checksum(body: &str) -> String {
+    format!("{:08x}", crc32fast::hash(body.as_bytes()))
+}
+
+/// The parts of a structured static token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructuredStaticToken<'a> {
+    pub id: &'a str,
+    pub secret: &'a str,
+}
+
+impl<'a> StructuredStaticToken<'a> {
+    /// Parse a token that carries the structured prefix.
+    ///
+    /// Returns `Ok(None)` for bare tokens. Tokens with the prefix but a
+    /// malformed layout or a bad checksum are rejected without hashing.
+    pub fn parse(token: &'a str) -> CredsResult<Option<Self>> {
+        let Some(rest) = token.strip_prefix(STRUCTURED_STATIC_TOKEN_PREFIX) else {
+            return Ok(None);
+        };
+        let (id_and_secret, checksum) = rest
+            .rsplit_once('_')
+            .ok_or(CredsError::InvalidStaticTokenCredentials)?;
+        let (id, secret) = id_and_secret
+            .split_once('_')
+            .ok_or(CredsError::InvalidStaticTokenCredentials)?;
+        let body = &token[..token.len() - checksum.len() - 1];
+        if id.is_empty() || secret.is_empty() || static_token_checksum(body) != checksum {
+            return Err(CredsError::InvalidStaticTokenCredentials);
+        }
+        Ok(Some(Self { id, secret }))
+    }
+}
+
+/// Whether `token` looks like a structured static token with a valid
+/// checksum. Intended for offline leak detection.
+pub fn is_structured_static_token(token: &str) -> bool {
+    matches!(StructuredStaticToken::parse(token), Ok(Some(_)))
+}
+
 /// Hash a token with SHA-256 and return hex.
 pub fn hash_token_sha256(token: &str) -> String {
     let mut hasher = Sha256::new();
@@ -82,6 +152,35 @@ mod tests {
         assert!(!verify_token_sha256("wrong_token", &hash));
     }
 
+    #[test]
+    fn test_structured_static_token_roundtrip() {
+        let token = generate_structured_static_token("entry-1").unwrap();
+        assert!(token.starts_with("sdt_entry-1_"));
+        assert!(is_structured_static_token(&token));
+
+        let parsed = StructuredStaticToken::parse(&token).unwrap().unwrap();
+        assert_eq!(parsed.id, "entry-1");
+        assert_eq!(parsed.secret.len(), 64);
+    }
+
+    #[test]
+    fn test_structured_static_token_rejects_bad_checksum() {
+        let token = generate_structured_static_token("entry-1").unwrap();
+        let mut tampered = token.clone();
+        tampered.replace_range(13..14, if &token[13..14] == "a" { "b" } else { "a" });
+
+        assert!(StructuredStaticToken::parse(&tampered).is_err());
+        assert!(StructuredStaticToken::parse("sdt_missing").is_err());
+        assert!(!is_structured_static_token(&tampered));
+    }
+
+    #[test]
+    fn test_bare_token_is_not_structured() {
+        let token = generate_static_token().unwrap();
+        assert_eq!(StructuredStaticToken::parse(&token).unwrap(), None);
+        assert!(generate_structured_static_token("bad_id").is_err());
+    }
+
     #[test]
     fn test_parse_bearer_auth_header() {
         let header = "Bearer my_token_123";
@@ -108,6 +108,7 @@ mod tests {
                 Argon2BasicAuthCred::new("admin".to_string(), "secret123".to_string()).unwrap(),
                 Argon2BasicAuthCred::new("user".to_string(), "password".to_string()).unwrap(),
             ],
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,407 @@
+//! X.509 certificate chain (`x5c`) validation for JWKs.
+//!
+//! A JWKS fetched from a discovery document is otherwise trusted only because
+//! of the URL it came from. [`JwkTrustAnchors`] additionally requires each key
+//! to carry an `x5c` chain that builds to a locally configured CA bundle and
+//! whose leaf certificate holds the same public key as the JWK.
+
+use std::borrow::Cow;
+
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk};
+use rsa::{
+    BigUint, RsaPublicKey,
+    pkcs8::{DecodePublicKey, ObjectIdentifier, SubjectPublicKeyInfoRef},
+    traits::PublicKeyParts,
+};
+use rustls_pki_types::{CertificateDer, TrustAnchor, UnixTime, pem::PemObject};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use webpki::{EndEntityCert, ExtendedKeyUsageValidator, KeyPurposeIdIter};
+
+use crate::{CredsError, CredsResult, JwtJwkTrait, JwtJwksTrait};
+
+const RSA_ENCRYPTION_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC_PUBLIC_KEY_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const SECP256R1_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const SECP384R1_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const SECP521R1_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.35");
+
+fn untrusted(message: impl Into<String>) -> CredsError {
+    CredsError::UntrustedJwk {
+        message: message.into(),
+    }
+}
+
+/// There is no registered extended key usage for JWS signing keys, so any
+/// well-formed EKU extension on the leaf certificate is accepted.
+struct AnyExtendedKeyUsage;
+
+impl ExtendedKeyUsageValidator for AnyExtendedKeyUsage {
+    fn validate(&self, mut iter: KeyPurposeIdIter<'_, '_>) -> Result<(), webpki::Error> {
+        iter.try_for_each(|purpose| purpose.map(|_| ()))
+    }
+}
+
+/// CA certificates that JWK `x5c` chains must build to.
+#[derive(Debug, Clone)]
+pub struct JwkTrustAnchors {
+    anchors: Vec<TrustAnchor<'static>>,
+}
+
+impl JwkTrustAnchors {
+    /// Load trust anchors from a PEM bundle of one or more CA certificates.
+    pub fn from_pem(pem: &[u8]) -> CredsResult<Self> {
+        let mut anchors = Vec::new();
+        for certificate in CertificateDer::pem_slice_iter(pem) {
+            let certificate = certificate.map_err(|e| CredsError::ConfigError {
+                message: format!("Invalid certificate in trust anchor bundle: {e}"),
+            })?;
+            let anchor = webpki::anchor_from_trusted_cert(&certificate).map_err(|e| {
+                CredsError::ConfigError {
+                    message: format!("Unusable certificate in trust anchor bundle: {e}"),
+                }
+            })?;
+            anchors.push(anchor.to_owned());
+        }
+
+        if anchors.is_empty() {
+            return Err(CredsError::ConfigError {
+                message: "Trust anchor bundle contains no certificates".to_string(),
+            });
+        }
+
+        Ok(Self { anchors })
+    }
+
+    /// Wrap `jwks` so that every key is checked with [`Self::verify_jwk`]
+    /// before it is used to verify a token.
+    pub fn trusted_jwks<'a, JWK, JWKS>(&'a self, jwks: &'a JWKS) -> X5cTrustedJwks<'a, JWK, JWKS>
+    where
+        JWK: JwtJwkTrait,
+        JWKS: JwtJwksTrait<JWK>,
+    {
+        X5cTrustedJwks {
+            jwks,
+            keys: jwks
+                .keys()
+                .iter()
+                .map(|jwk| X5cTrustedJwk {
+                    jwk,
+                    trust_anchors: self,
+                })
+                .collect(),
+        }
+    }
+
+    /// Check that `jwk` carries an `x5c` chain that builds to one of the trust
+    /// anchors, that the leaf certificate holds the JWK's public key, and that
+    /// `x5t` / `x5t#S256` match the leaf certificate when present.
+    pub fn verify_jwk(&self, jwk: &Jwk) -> CredsResult<()> {
+        let chain = jwk
+            .common
+            .x509_chain
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|certificate| {
+                STANDARD
+                    .decode(certificate)
+                    .map(CertificateDer::from)
+                    .map_err(|e| untrusted(format!("x5c entry is not valid base64: {e}")))
+            })
+            .collect::<CredsResult<Vec<_>>>()?;
+        let Some((leaf, intermediates)) = chain.split_first() else {
+            return Err(untrusted("JWK has no x5c certificate chain"));
+        };
+
+        let end_entity = EndEntityCert::try_from(leaf)
+            .map_err(|e| untrusted(format!("x5c leaf certificate is malformed: {e}")))?;
+        end_entity
+            .verify_for_usage(
+                webpki::ALL_VERIFICATION_ALGS,
+                &self.anchors,
+                intermediates,
+                UnixTime::now(),
+                AnyExtendedKeyUsage,
+                None,
+                None,
+            )
+            .map_err(|e| untrusted(format!("x5c chain does not build to a trust anchor: {e}")))?;
+
+        check_thumbprints(jwk, leaf)?;
+        check_leaf_public_key(jwk, &end_entity.subject_public_key_info())
+    }
+}
+
+fn check_thumbprints(jwk: &Jwk, leaf: &[u8]) -> CredsResult<()> {
+    if let Some(thumbprint) = jwk.common.x509_sha256_fingerprint.as_deref()
+        && thumbprint != URL_SAFE_NO_PAD.encode(Sha256::digest(leaf))
+    {
+        return Err(untrusted(
+            "x5t#S256 does not match the x5c leaf certificate",
+        ));
+    }
+    if let Some(thumbprint) = jwk.common.x509_sha1_fingerprint.as_deref()
+        && thumbprint != URL_SAFE_NO_PAD.encode(Sha1::digest(leaf))
+    {
+        return Err(untrusted("x5t does not match the x5c leaf certificate"));
+    }
+    Ok(())
+}
+
+fn check_leaf_public_key(jwk: &Jwk, spki_der: &[u8]) -> CredsResult<()> {
+    let spki = SubjectPublicKeyInfoRef::try_from(spki_der)
+        .map_err(|e| untrusted(format!("x5c leaf public key is malformed: {e}")))?;
+    let public_key = spki.subject_public_key.raw_bytes();
+
+    let matches = match &jwk.algorithm {
+        AlgorithmParameters::RSA(params) if spki.algorithm.oid == RSA_ENCRYPTION_OID => {
+            let key = RsaPublicKey::from_public_key_der(spki_der)
+                .map_err(|e| untrusted(format!("x5c leaf RSA key is malformed: {e}")))?;
+            decode_biguint(&params.n)? == *key.n() && decode_biguint(&params.e)? == *key.e()
+        }
+        AlgorithmParameters::EllipticCurve(params) if spki.algorithm.oid == EC_PUBLIC_KEY_OID => {
+            let curve_oid = match params.curve {
+                EllipticCurve::P256 => SECP256R1_OID,
+                EllipticCurve::P384 => SECP384R1_OID,
+                EllipticCurve::P521 => SECP521R1_OID,
+                EllipticCurve::Ed25519 => return Err(untrusted("EC JWK uses an OKP curve")),
+            };
+            let mut point = vec![0x04];
+            point.extend(decode_base64url(&params.x)?);
+            point.extend(decode_base64url(&params.y)?);
+            spki.algorithm
+                .parameters_oid()
+                .is_ok_and(|oid| oid == curve_oid)
+                && public_key == point
+        }
+        AlgorithmParameters::OctetKeyPair(params)
+            if params.curve == EllipticCurve::Ed25519 && spki.algorithm.oid == ED25519_OID =>
+        {
+            public_key == decode_base64url(&params.x)?
+        }
+        _ => false,
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        Err(untrusted(
+            "x5c leaf certificate does not hold the JWK public key",
+        ))
+    }
+}
+
+fn decode_base64url(value: &str) -> CredsResult<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .map_err(|e| untrusted(format!("JWK key parameter is not valid base64url: {e}")))
+}
+
+fn decode_biguint(value: &str) -> CredsResult<BigUint> {
+    Ok(BigUint::from_bytes_be(&decode_base64url(value)?))
+}
+
+/// A JWK that is checked against [`JwkTrustAnchors`] each time it is
+/// converted for verification.
+#[derive(Debug)]
+pub struct X5cTrustedJwk<'a, JWK> {
+    jwk: &'a JWK,
+    trust_anchors: &'a JwkTrustAnchors,
+}
+
+impl<JWK: JwtJwkTrait> JwtJwkTrait for X5cTrustedJwk<'_, JWK> {
+    fn to_jwt_jwk<'a>(&'a self) -> CredsResult<Cow<'a, Jwk>> {
+        let jwk = self.jwk.to_jwt_jwk()?;
+        self.trust_anchors.verify_jwk(&jwk)?;
+        Ok(jwk)
+    }
+}
+
+/// A key set whose keys must pass `x5c` validation before use, built with
+/// [`JwkTrustAnchors::trusted_jwks`].
+#[derive(Debug)]
+pub struct X5cTrustedJwks<'a, JWK, JWKS> {
+    jwks: &'a JWKS,
+    keys: Vec<X5cTrustedJwk<'a, JWK>>,
+}
+
+impl<'a, JWK, JWKS> JwtJwksTrait<X5cTrustedJwk<'a, JWK>> for X5cTrustedJwks<'a, JWK, JWKS>
+where
+    JWK: JwtJwkTrait,
+    JWKS: JwtJwksTrait<JWK>,
+{
+    fn find(&self, kid: &str) -> Option<&X5cTrustedJwk<'a, JWK>> {
+        let jwk = self.jwks.find(kid)?;
+        self.keys
+            .iter()
+            .find(|trusted| std::ptr::eq(trusted.jwk, jwk))
+    }
+
+    fn keys(&self) -> &[X5cTrustedJwk<'a, JWK>] {
+        &self.keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{EncodingKey, Header, jwk::JwkSet};
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, Issuer, KeyPair};
+
+    use super::*;
+    use crate::{CoreJwtClaims, JwtAlgorithm, verify_token_jwt_with_jwks};
+
+    fn ca_params() -> CertificateParams {
+        let mut params =
+            CertificateParams::new(Vec::<String>::new()).expect("CA params should build");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+    }
+
+    /// A self-signed root CA as `(issuer, PEM bundle)`.
+    fn root_ca() -> (Issuer<'static, KeyPair>, String) {
+        let key = KeyPair::generate().expect("CA key should generate");
+        let params = ca_params();
+        let pem = params.self_signed(&key).expect("CA should self-sign").pem();
+        (Issuer::new(params, key), pem)
+    }
+
+    fn leaf_der(public_key: &KeyPair, issuer: &Issuer<'_, KeyPair>) -> Vec<u8> {
+        CertificateParams::new(Vec::<String>::new())
+            .expect("leaf params should build")
+            .signed_by(public_key, issuer)
+            .expect("leaf should be signed")
+            .der()
+            .to_vec()
+    }
+
+    fn ec_jwk(key: &KeyPair, x5c: &[&[u8]]) -> Jwk {
+        let point = key.public_key_raw();
+        serde_json::from_value(serde_json::json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "kid": "sig-1",
+            "alg": "ES256",
+            "use": "sig",
+            "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+            "y": URL_SAFE_NO_PAD.encode(&point[33..65]),
+            "x5c": x5c.iter().map(|der| STANDARD.encode(der)).collect::<Vec<_>>(),
+        }))
+        .expect("JWK should parse")
+    }
+
+    fn anchors(pem: &str) -> JwkTrustAnchors {
+        JwkTrustAnchors::from_pem(pem.as_bytes()).expect("trust anchors should load")
+    }
+
+    fn assert_untrusted(result: CredsResult<()>) {
+        assert!(
+            matches!(result, Err(CredsError::UntrustedJwk { .. })),
+            "expected UntrustedJwk, got {result:?}"
+        );
+    }
+
+    #[test]
+    fn accepts_jwk_whose_chain_builds_to_a_trust_anchor() {
+        let (root, root_pem) = root_ca();
+        let key = KeyPair::generate().expect("leaf key should generate");
+        let leaf = leaf_der(&key, &root);
+        let mut jwk = ec_jwk(&key, &[&leaf]);
+        jwk.common.x509_sha256_fingerprint = Some(URL_SAFE_NO_PAD.encode(Sha256::digest(&leaf)));
+
+        let jwks = JwkSet { keys: vec![jwk] };
+        let token = jsonwebtoken::encode(
+            &Header {
+                kid: Some("sig-1".to_string()),
+                ..Header::new(JwtAlgorithm::ES256)
+            },
+            &serde_json::json!({ "sub": "svc-a", "exp": 4_102_444_800u64 }),
+            &EncodingKey::from_ec_pem(key.serialize_pem().as_bytes())
+                .expect("signing key should load"),
+        )
+        .expect("token should sign");
+
+        let trust_anchors = anchors(&root_pem);
+        let token_data = verify_token_jwt_with_jwks::<CoreJwtClaims, _, _, _>(
+            &token,
+            &trust_anchors.trusted_jwks(&jwks),
+            |mut validation| {
+                validation.validate_aud = false;
+                Ok(validation)
+            },
+        )
+        .expect("token signed by a trusted key should verify");
+        assert_eq!(token_data.claims.subject.as_deref(), Some("svc-a"));
+    }
+
+    #[test]
+    fn accepts_chain_through_an_intermediate() {
+        let (root, root_pem) = root_ca();
+        let intermediate_key = KeyPair::generate().expect("intermediate key should generate");
+        let intermediate_params = ca_params();
+        let intermediate = intermediate_params
+            .signed_by(&intermediate_key, &root)
+            .expect("intermediate should be signed")
+            .der()
+            .to_vec();
+        let intermediate_issuer = Issuer::new(intermediate_params, intermediate_key);
+        let key = KeyPair::generate().expect("leaf key should generate");
+        let leaf = leaf_der(&key, &intermediate_issuer);
+
+        anchors(&root_pem)
+            .verify_jwk(&ec_jwk(&key, &[&leaf, &intermediate]))
+            .expect("chain through an intermediate should be trusted");
+    }
+
+    #[test]
+    fn rejects_jwk_without_x5c() {
+        let (_, root_pem) = root_ca();
+        let key = KeyPair::generate().expect("leaf key should generate");
+
+        assert_untrusted(anchors(&root_pem).verify_jwk(&ec_jwk(&key, &[])));
+    }
+
+    #[test]
+    fn rejects_chain_from_another_ca() {
+        let (_, root_pem) = root_ca();
+        let (other_root, _) = root_ca();
+        let key = KeyPair::generate().expect("leaf key should generate");
+        let leaf = leaf_der(&key, &other_root);
+
+        assert_untrusted(anchors(&root_pem).verify_jwk(&ec_jwk(&key, &[&leaf])));
+    }
+
+    #[test]
+    fn rejects_leaf_certificate_for_a_different_key() {
+        let (root, root_pem) = root_ca();
+        let key = KeyPair::generate().expect("leaf key should generate");
+        let other_key = KeyPair::generate().expect("other key should generate");
+        let leaf = leaf_der(&other_key, &root);
+
+        assert_untrusted(anchors(&root_pem).verify_jwk(&ec_jwk(&key, &[&leaf])));
+    }
+
+    #[test]
+    fn rejects_mismatched_x5t_s256() {
+        let (root, root_pem) = root_ca();
+        let key = KeyPair::generate().expect("leaf key should generate");
+        let leaf = leaf_der(&key, &root);
+        let mut jwk = ec_jwk(&key, &[&leaf]);
+        jwk.common.x509_sha256_fingerprint = Some(URL_SAFE_NO_PAD.encode(Sha256::digest(b"x")));
+
+        assert_untrusted(anchors(&root_pem).verify_jwk(&jwk));
+    }
+
+    #[test]
+    fn trust_anchor_bundle_must_contain_certificates() {
+        assert!(matches!(
+            JwkTrustAnchors::from_pem(b""),
+            Err(CredsError::ConfigError { .. })
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use securitydept_creds::JwtAlgorithm;
+use securitydept_utils::secret::SecretString;
+use serde::Deserialize;
+
+use crate::{OAuthResourceServerError, OAuthResourceServerResult};
+
+/// Local verification keys for offline deployments without a reachable
+/// discovery document or JWKS endpoint.
+#[cfg_attr(feature = "config-schema", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct OAuthResourceServerLocalKeysConfig {
+    /// Expected `iss` claim of accepted tokens.
+    pub issuer: String,
+    /// Path to a local JWKS file containing one or more public keys.
+    #[serde(default)]
+    pub jwks_path: Option<String>,
+    /// Path to a local single JWK file containing one public key.
+    #[serde(default)]
+    pub jwk_path: Option<String>,
+    /// Path to a local PEM key file.
+    ///
+    /// Accepts SPKI public keys (`BEGIN PUBLIC KEY`), PKCS#1 RSA public keys
+    /// (`BEGIN RSA PUBLIC KEY`), PKCS#8 private keys (`BEGIN PRIVATE KEY`)
+    /// and SEC1 EC private keys (`BEGIN EC PRIVATE KEY`), from which only the
+    /// public half is kept. RSA, P-256, P-384 and Ed25519 keys are supported;
+    /// P-521 keys are rejected because `ES512` tokens cannot be verified.
+    #[serde(default)]
+    pub pem_path: Option<String>,
+    /// Optional `kid` applied to the JWK derived from `pem_path`.
+    #[serde(default)]
+    pub pem_key_id: Option<String>,
+    /// Optional `alg` the key from `pem_path` is bound to. Defaults to `ES256`
+    /// for P-256, `ES384` for P-384 and `EdDSA` for Ed25519 keys; RSA keys
+    /// accept any RSA algorithm unless set.
+    #[serde(default)]
+    #[cfg_attr(feature = "config-schema", schemars(with = "Option<String>"))]
+    pub pem_algorithm: Option<JwtAlgorithm>,
+    /// Shared secret for HMAC-signed tokens.
+    #[serde(default)]
+    pub hmac_secret: Option<SecretString>,
+    /// Path to a file holding the HMAC shared secret. Trailing whitespace is
+    /// ignored.
+    #[serde(default)]
+    pub hmac_secret_path: Option<String>,
+    /// Optional `kid` applied to the HMAC key.
+    #[serde(default)]
+    pub hmac_key_id: Option<String>,
+    /// HMAC algorithm the shared secret is bound to. Defaults to `HS256`.
+    #[serde(default)]
+    #[cfg_attr(feature = "config-schema", schemars(with = "Option<String>"))]
+    pub hmac_algorithm: Option<JwtAlgorithm>,
+    /// Poll interval for watching local key files for key rotation.
+    /// Set to `"0s"` to disable background watching.
+    #[serde(default, with = "humantime_serde")]
+    #[cfg_attr(feature = "config-schema", schemars(with = "String"))]
+    pub watch_interval: Duration,
+}
+
+impl OAuthResourceServerLocalKeysConfig {
+    pub fn validate(&self) -> OAuthResourceServerResult<()> {
+        if self.issuer.trim().is_empty() {
+            return Err(OAuthResourceServerError::InvalidConfig {
+                message: "local_keys.issuer must be set".to_string(),
+            });
+        }
+        openidconnect::IssuerUrl::new(self.issuer.clone()).map_err(|e| {
+            OAuthResourceServerError::InvalidConfig {
+                message: format!("local_keys.issuer is not a valid URL: {e}"),
+            }
+        })?;
+
+        if self.jwks_path.is_none()
+            && self.jwk_path.is_none()
+            && self.pem_path.is_none()
+            && !self.has_hmac_secret()
+        {
+            return Err(OAuthResourceServerError::InvalidConfig {
+                message: "local_keys requires at least one of jwks_path, jwk_path, pem_path, \
+                          hmac_secret or hmac_secret_path"
+                    .to_string(),
+            });
+        }
+
+        if self.hmac_secret.is_some() && self.hmac_secret_path.is_some() {
+            return Err(OAuthResourceServerError::InvalidConfig {
+                message: "local_keys.hmac_secret and local_keys.hmac_secret_path are mutually \
+                          exclusive"
+                    .to_string(),
+            });
+        }
+
+        if let Some(algorithm) = self.pem_algorithm
+            && !matches!(
+                algorithm,
+                JwtAlgorithm::RS256
+                    | JwtAlgorithm::RS384
+                    | JwtAlgorithm::RS512
+                    | JwtAlgorithm::PS256
+                    | JwtAlgorithm::PS384
+                    | JwtAlgorithm::PS512
+                    | JwtAlgorithm::ES256
+                    | JwtAlgorithm::ES384
+                    | JwtAlgorithm::EdDSA
+            )
+        {
+            return Err(OAuthResourceServerError::InvalidConfig {
+                message: format!("local_keys.pem_algorithm {algorithm:?} is not supported"),
+            });
+        }
+
+        if self
+            .hmac_algorithm
+            .is_some_and(|algorithm| !is_hmac_algorithm(algorithm))
+        {
+            return Err(OAuthResourceServerError::InvalidConfig {
+                message: "local_keys.hmac_algorithm must be one of HS256, HS384 or HS512"
+                    .to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether an HMAC shared secret is configured, inline or as a file.
+    pub fn has_hmac_secret(&self) -> bool {
+        self.hmac_secret.is_some() || self.hmac_secret_path.is_some()
+    }
+}
+
+pub(crate) fn is_hmac_algorithm(algorithm: JwtAlgorithm) -> bool {
+    matches!(
+        algorithm,
+        JwtAlgorithm::HS256 | JwtAlgorithm::HS384 | JwtAlgorithm::HS512
+    )
+}
@@ -1,12 +1,15 @@
 pub mod introspection;
 #[cfg(feature = "jwe")]
 pub mod jwe;
+pub mod local_keys;
 
 use std::time::Duration;
 
 pub use introspection::OAuthResourceServerIntrospectionConfig;
 #[cfg(feature = "jwe")]
 pub use jwe::OAuthResourceServerJweConfig;
+pub use local_keys::OAuthResourceServerLocalKeysConfig;
+use securitydept_creds::JwtAlgorithm;
 use securitydept_oauth_provider::{
     OAuthProviderConfig, OAuthProviderOidcConfig, OAuthProviderRemoteConfig, OidcSharedConfig,
 };
@@ -14,6 +17,7 @@ use securitydept_utils::ser::CommaOrSpaceSeparated;
 use serde::Deserialize;
 use serde_with::{PickFirst, serde_as};
 
+use self::local_keys::is_hmac_algorithm;
 use crate::{OAuthResourceServerError, OAuthResourceServerResult};
 
 #[serde_as]
@@ -43,6 +47,23 @@ pub struct OAuthResourceServerConfig {
     #[serde(default = "default_clock_skew", with = "humantime_serde")]
     #[cfg_attr(feature = "config-schema", schemars(with = "String"))]
     pub clock_skew: Duration,
+    /// Accepted JWT `alg` values, e.g. `["RS256", "ES256"]`. Empty accepts
+    /// any algorithm the matching JWK supports. When set, PASETO `v4.public`
+    /// tokens are only accepted with `EdDSA` listed, and `v4.local` tokens
+    /// are rejected.
+    #[serde_as(as = "PickFirst<(CommaOrSpaceSeparated<JwtAlgorithm>, _)>")]
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "config-schema",
+        schemars(with = "securitydept_utils::schema::StringOrVecString")
+    )]
+    pub allowed_algorithms: Vec<JwtAlgorithm>,
+    /// Required JWT `typ` header. Set to `"at+jwt"` to only accept
+    /// [RFC 9068](https://www.rfc-editor.org/rfc/rfc9068) access tokens and
+    /// reject ID tokens signed by the same issuer. PASETO tokens carry no
+    /// `typ` header and are not affected.
+    #[serde(default)]
+    pub required_typ: Option<String>,
     /// Optional opaque-token introspection configuration.
     ///
     /// Example TOML:
@@ -77,11 +98,53 @@ pub struct OAuthResourceServerConfig {
     /// ```
     #[serde(default)]
     pub jwe: Option<OAuthResourceServerJweConfig>,
+    /// Optional local verification keys. When set, JWTs are verified offline
+    /// against these keys and the remote discovery settings are not used.
+    ///
+    /// Example TOML:
+    /// ```toml
+    /// [oauth_resource_server.local_keys]
+    /// issuer = "https://issuer.internal"
+    /// pem_path = "config/issuer-public.pem"
+    /// # or jwks_path = "config/issuer.jwks"
+    /// # or jwk_path = "config/issuer.jwk"
+    /// # or hmac_secret_path = "config/issuer.secret"
+    /// pem_key_id = "sig-1"
+    /// pem_algorithm = "RS256"
+    /// watch_interval = "30s"
+    /// ```
+    #[serde(default)]
+    pub local_keys: Option<OAuthResourceServerLocalKeysConfig>,
+    #[cfg(feature = "x5c")]
+    /// Optional PEM bundle of CA certificates. When set, every verification
+    /// key must carry an `x5c` certificate chain that builds to one of these
+    /// CAs and whose leaf certificate holds the key; other keys are rejected.
+    ///
+    /// Example TOML:
+    /// ```toml
+    /// [oauth_resource_server]
+    /// well_known_url = "https://issuer.example.com/.well-known/openid-configuration"
+    /// x5c_trust_anchors_path = "config/jwks-ca.pem"
+    /// ```
+    #[serde(default)]
+    pub x5c_trust_anchors_path: Option<String>,
 }
 
 impl OAuthResourceServerConfig {
     pub fn validate(&self) -> OAuthResourceServerResult<()> {
-        self.remote.validate()?;
+        match self.local_keys.as_ref() {
+            Some(local_keys) => {
+                local_keys.validate()?;
+                if self.introspection.is_some() {
+                    return Err(OAuthResourceServerError::InvalidConfig {
+                        message: "introspection requires a remote provider and cannot be combined \
+                                  with local_keys"
+                            .to_string(),
+                    });
+                }
+            }
+            None => self.remote.validate()?,
+        }
 
         if let Some(introspection) = self.introspection.as_ref()
             && introspection
@@ -109,6 +172,35 @@ impl OAuthResourceServerConfig {
             });
         }
 
+        if self
+            .allowed_algorithms
+            .iter()
+            .any(|algorithm| is_hmac_algorithm(*algorithm))
+            && !self
+                .local_keys
+                .as_ref()
+                .is_some_and(OAuthResourceServerLocalKeysConfig::has_hmac_secret)
+        {
+            return Err(OAuthResourceServerError::InvalidConfig {
+                message: "allowed_algorithms may only contain HMAC algorithms when a local_keys \
+                          HMAC secret is configured"
+                    .to_string(),
+            });
+        }
+
+        #[cfg(feature = "x5c")]
+        if self.x5c_trust_anchors_path.is_some()
+            && self.local_keys.as_ref().is_some_and(|local_keys| {
+                local_keys.pem_path.is_some() || local_keys.has_hmac_secret()
+            })
+        {
+            return Err(OAuthResourceServerError::InvalidConfig {
+                message: "x5c_trust_anchors_path requires keys with an x5c chain and cannot be \
+                          combined with local_keys pem_path or HMAC secrets"
+                    .to_string(),
+            });
+        }
+
         Ok(())
     }
 
@@ -159,6 +251,12 @@ impl OAuthResourceServerConfig {
         self.validate()
     }
 
+    /// Returns `true` when a verifier can be built from this config, either
+    /// from a remote discovery source or from `local_keys`.
+    pub fn is_verifier_configured(&self) -> bool {
+        self.local_keys.is_some() || self.remote.is_discovery_configured()
+    }
+
     pub fn provider_config(&self) -> OAuthProviderConfig {
         OAuthProviderConfig {
             remote: self.remote.clone(),
@@ -180,9 +278,14 @@ impl Default for OAuthResourceServerConfig {
             audiences: Vec::new(),
             required_scopes: Vec::new(),
             clock_skew: default_clock_skew(),
+            allowed_algorithms: Vec::new(),
+            required_typ: None,
             introspection: None,
             #[cfg(feature = "jwe")]
             jwe: None,
+            local_keys: None,
+            #[cfg(feature = "x5c")]
+            x5c_trust_anchors_path: None,
         }
     }
 }
@@ -193,12 +296,16 @@ fn default_clock_skew() -> Duration {
 
 #[cfg(test)]
 mod tests {
+    use securitydept_creds::JwtAlgorithm;
     use securitydept_oauth_provider::{OAuthProviderRemoteConfig, OidcSharedConfig};
     use securitydept_utils::secret::SecretString;
 
     #[cfg(feature = "jwe")]
     use super::OAuthResourceServerJweConfig;
-    use super::{OAuthResourceServerConfig, OAuthResourceServerIntrospectionConfig};
+    use super::{
+        OAuthResourceServerConfig, OAuthResourceServerIntrospectionConfig,
+        OAuthResourceServerLocalKeysConfig,
+    };
 
     #[test]
     fn validate_accepts_well_known_only() {
@@ -215,6 +322,106 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn validate_rejects_hmac_allowed_algorithms() {
+        let config = OAuthResourceServerConfig {
+            remote: OAuthProviderRemoteConfig {
+                well_known_url: Some(
+                    "https://issuer.example.com/.well-known/openid-configuration".to_string(),
+                ),
+                ..Default::default()
+            },
+            allowed_algorithms: vec![JwtAlgorithm::RS256, JwtAlgorithm::HS256],
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_accepts_local_keys_without_discovery() {
+        let config = OAuthResourceServerConfig {
+            local_keys: Some(OAuthResourceServerLocalKeysConfig {
+                issuer: "https://issuer.internal".to_string(),
+                pem_path: Some("config/issuer-public.pem".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_ok());
+        assert!(config.is_verifier_configured());
+    }
+
+    #[test]
+    fn validate_rejects_local_keys_without_key_source() {
+        let config = OAuthResourceServerConfig {
+            local_keys: Some(OAuthResourceServerLocalKeysConfig {
+                issuer: "https://issuer.internal".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_local_keys_with_introspection() {
+        let config = OAuthResourceServerConfig {
+            local_keys: Some(OAuthResourceServerLocalKeysConfig {
+                issuer: "https://issuer.internal".to_string(),
+                jwks_path: Some("config/issuer.jwks".to_string()),
+                ..Default::default()
+            }),
+            introspection: Some(OAuthResourceServerIntrospectionConfig {
+                client_id: Some("resource-server".to_string()),
+                introspection_url: Some("https://issuer.internal/introspect".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_allows_hmac_algorithms_only_with_local_hmac_secret() {
+        let mut config = OAuthResourceServerConfig {
+            local_keys: Some(OAuthResourceServerLocalKeysConfig {
+                issuer: "https://issuer.internal".to_string(),
+                hmac_secret: Some(SecretString::from("shared-secret")),
+                ..Default::default()
+            }),
+            allowed_algorithms: vec![JwtAlgorithm::HS256],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.local_keys = Some(OAuthResourceServerLocalKeysConfig {
+            issuer: "https://issuer.internal".to_string(),
+            pem_path: Some("config/issuer-public.pem".to_string()),
+            ..Default::default()
+        });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn allowed_algorithms_accept_comma_separated_string() {
+        let config: OAuthResourceServerConfig = serde_json::from_value(serde_json::json!({
+            "well_known_url": "https://issuer.example.com/.well-known/openid-configuration",
+            "allowed_algorithms": "RS256, ES256",
+            "required_typ": "at+jwt",
+        }))
+        .expect("config should deserialize");
+
+        assert_eq!(
+            config.allowed_algorithms,
+            vec![JwtAlgorithm::RS256, JwtAlgorithm::ES256]
+        );
+        assert_eq!(config.required_typ.as_deref(), Some("at+jwt"));
+    }
+
     #[test]
     fn validate_rejects_missing_manual_fields() {
         let config = OAuthResourceServerConfig::default();
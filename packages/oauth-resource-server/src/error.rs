@@ -34,6 +34,9 @@ pub enum OAuthResourceServerError {
     #[snafu(display("OAuth resource server JWE key error: {message}"))]
     JweKey { message: String },
 
+    #[snafu(display("OAuth resource server local key error: {message}"))]
+    LocalKey { message: String },
+
     #[snafu(display(
         "OAuth resource server does not support {token_format:?} access tokens in this verifier"
     ))]
@@ -60,7 +63,8 @@ impl ToHttpStatus for OAuthResourceServerError {
             | OAuthResourceServerError::Introspection { .. } => StatusCode::UNAUTHORIZED,
             OAuthResourceServerError::InvalidConfig { .. }
             | OAuthResourceServerError::Metadata { .. }
-            | OAuthResourceServerError::HttpClient { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            | OAuthResourceServerError::HttpClient { .. }
+            | OAuthResourceServerError::LocalKey { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             #[cfg(feature = "jwe")]
             OAuthResourceServerError::JweKey { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -84,7 +88,8 @@ impl ToErrorPresentation for OAuthResourceServerError {
             ),
             OAuthResourceServerError::InvalidConfig { .. }
             | OAuthResourceServerError::Metadata { .. }
-            | OAuthResourceServerError::HttpClient { .. } => ErrorPresentation::new(
+            | OAuthResourceServerError::HttpClient { .. }
+            | OAuthResourceServerError::LocalKey { .. } => ErrorPresentation::new(
                 "auth_temporarily_unavailable",
                 "Authentication is temporarily unavailable.",
                 UserRecovery::Retry,
@@ -5,7 +5,10 @@ pub mod verifier;
 
 #[cfg(feature = "jwe")]
 pub use config::OAuthResourceServerJweConfig;
-pub use config::{OAuthResourceServerConfig, OAuthResourceServerIntrospectionConfig};
+pub use config::{
+    OAuthResourceServerConfig, OAuthResourceServerIntrospectionConfig,
+    OAuthResourceServerLocalKeysConfig,
+};
 pub use error::{OAuthResourceServerError, OAuthResourceServerResult};
 #[cfg(feature = "jwe")]
 pub use models::LocalJweDecryptionKeySet;
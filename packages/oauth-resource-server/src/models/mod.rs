@@ -1,7 +1,11 @@
+#[cfg(feature = "x5c")]
+use std::sync::Arc;
 use std::{collections::HashMap, time::Duration};
 
 use openidconnect::{IntrospectionUrl, IssuerUrl, JsonWebKeySetUrl, core::CoreJsonWebKeySet};
-use securitydept_creds::{JwtClaimsTrait, Scope, TokenData};
+#[cfg(feature = "x5c")]
+use securitydept_creds::JwkTrustAnchors;
+use securitydept_creds::{JwtAlgorithm, JwtClaimsTrait, Scope, TokenData};
 use serde_json::Value;
 
 pub mod introspection;
@@ -15,7 +19,8 @@ pub use jwe::LocalJweDecryptionKeySet;
 #[derive(Debug, Clone)]
 pub struct OAuthResourceServerMetadata {
     pub issuer: IssuerUrl,
-    pub jwks_uri: JsonWebKeySetUrl,
+    /// Remote JWKS location; `None` when verifying against local keys.
+    pub jwks_uri: Option<JsonWebKeySetUrl>,
     pub introspection_url: Option<IntrospectionUrl>,
 }
 
@@ -24,6 +29,10 @@ pub struct VerificationPolicy {
     allowed_audiences: Vec<String>,
     required_scopes: Vec<String>,
     clock_skew: Duration,
+    allowed_algorithms: Vec<JwtAlgorithm>,
+    required_typ: Option<String>,
+    #[cfg(feature = "x5c")]
+    x5c_trust_anchors: Option<Arc<JwkTrustAnchors>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,9 +55,38 @@ impl VerificationPolicy {
             allowed_audiences,
             required_scopes,
             clock_skew,
+            allowed_algorithms: Vec::new(),
+            required_typ: None,
+            #[cfg(feature = "x5c")]
+            x5c_trust_anchors: None,
         }
     }
 
+    /// Restrict accepted JWT `alg` values. Empty accepts any algorithm the
+    /// matching JWK supports. PASETO `v4.public` tokens count as `EdDSA`;
+    /// `v4.local` tokens are rejected under a non-empty allowlist.
+    pub fn with_allowed_algorithms(mut self, allowed_algorithms: Vec<JwtAlgorithm>) -> Self {
+        self.allowed_algorithms = allowed_algorithms;
+        self
+    }
+
+    /// Require a JWT `typ` header, e.g. `at+jwt` for RFC 9068 access tokens.
+    pub fn with_required_typ(mut self, required_typ: Option<String>) -> Self {
+        self.required_typ = required_typ;
+        self
+    }
+
+    /// Require every verification key to carry an `x5c` chain that builds to
+    /// one of `x5c_trust_anchors`.
+    #[cfg(feature = "x5c")]
+    pub fn with_x5c_trust_anchors(
+        mut self,
+        x5c_trust_anchors: Option<Arc<JwkTrustAnchors>>,
+    ) -> Self {
+        self.x5c_trust_anchors = x5c_trust_anchors;
+        self
+    }
+
     pub fn allowed_audiences(&self) -> &[String] {
         &self.allowed_audiences
     }
@@ -60,6 +98,19 @@ impl VerificationPolicy {
     pub fn clock_skew(&self) -> Duration {
         self.clock_skew
     }
+
+    pub fn allowed_algorithms(&self) -> &[JwtAlgorithm] {
+        &self.allowed_algorithms
+    }
+
+    pub fn required_typ(&self) -> Option<&str> {
+        self.required_typ.as_deref()
+    }
+
+    #[cfg(feature = "x5c")]
+    pub fn x5c_trust_anchors(&self) -> Option<&JwkTrustAnchors> {
+        self.x5c_trust_anchors.as_deref()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -127,6 +178,10 @@ where
 {
     let claims = match token_data {
         TokenData::JWT(token) => &token.claims,
+        #[cfg(feature = "jwe")]
+        TokenData::JWE(token) => token.claims(),
+        #[cfg(feature = "paseto")]
+        TokenData::PASETO(token) => &token.claims,
         TokenData::Opaque => unreachable!("structured token data must not be opaque"),
         #[allow(unreachable_patterns)]
         _ => unreachable!("unexpected structured token variant"),
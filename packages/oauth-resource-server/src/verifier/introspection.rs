@@ -38,7 +38,7 @@ impl OAuthResourceServerVerifierIntrospection {
 
         let resource_metadata = OAuthResourceServerMetadata {
             issuer: metadata.issuer,
-            jwks_uri: metadata.jwks_uri,
+            jwks_uri: Some(metadata.jwks_uri),
             introspection_url: metadata.introspection_endpoint,
         };
 
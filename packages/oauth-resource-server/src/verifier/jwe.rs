@@ -5,7 +5,8 @@ use josekit::jwk::{
     alg::{ec::EcKeyPair, ecx::EcxKeyPair, ed::EdKeyPair, rsa::RsaKeyPair},
 };
 use securitydept_creds::{
-    JwtClaimsTrait, JwtValidation, TokenData, verify_token_rfc9068_with_jwks,
+    JwtClaimsTrait, JwtJwkTrait, JwtJwksTrait, JwtValidation, TokenData,
+    verify_token_rfc9068_with_jwks,
 };
 use tokio::{sync::RwLock, task::JoinHandle};
 
@@ -32,15 +33,17 @@ impl OAuthResourceServerVerifierJwe {
         })
     }
 
-    pub async fn verify_token_data<CLAIMS>(
+    pub async fn verify_token_data<CLAIMS, JWK, JWKS>(
         &self,
         token: &str,
-        jwt_jwks: &openidconnect::core::CoreJsonWebKeySet,
+        jwt_jwks: &JWKS,
         metadata: &OAuthResourceServerMetadata,
         policy: &VerificationPolicy,
     ) -> OAuthResourceServerResult<TokenData<CLAIMS>>
     where
         CLAIMS: JwtClaimsTrait,
+        JWK: JwtJwkTrait,
+        JWKS: JwtJwksTrait<JWK>,
     {
         let jwe_guard = self.decryption_keys.read().await;
         let Some(jwe_jwks) = jwe_guard.as_ref() else {
@@ -49,19 +52,44 @@ impl OAuthResourceServerVerifierJwe {
             });
         };
 
-        verify_token_rfc9068_with_jwks(
-            token,
-            jwt_jwks,
-            jwe_jwks,
-            |mut validation: JwtValidation| {
-                apply_validation_policy(&mut validation, metadata, policy);
-                Ok(validation)
-            },
-        )
-        .map_err(|source| OAuthResourceServerError::TokenValidation { source })
+        #[cfg(feature = "x5c")]
+        if let Some(trust_anchors) = policy.x5c_trust_anchors() {
+            return verify_jwe_with_policy::<CLAIMS, securitydept_creds::X5cTrustedJwk<'_, JWK>, _>(
+                token,
+                &trust_anchors.trusted_jwks(jwt_jwks),
+                jwe_jwks,
+                metadata,
+                policy,
+            );
+        }
+        verify_jwe_with_policy::<CLAIMS, JWK, JWKS>(token, jwt_jwks, jwe_jwks, metadata, policy)
     }
 }
 
+fn verify_jwe_with_policy<CLAIMS, JWK, JWKS>(
+    token: &str,
+    jwt_jwks: &JWKS,
+    jwe_jwks: &LocalJweDecryptionKeySet,
+    metadata: &OAuthResourceServerMetadata,
+    policy: &VerificationPolicy,
+) -> OAuthResourceServerResult<TokenData<CLAIMS>>
+where
+    CLAIMS: JwtClaimsTrait,
+    JWK: JwtJwkTrait,
+    JWKS: JwtJwksTrait<JWK>,
+{
+    verify_token_rfc9068_with_jwks(
+        token,
+        jwt_jwks,
+        jwe_jwks,
+        |mut validation: JwtValidation| {
+            apply_validation_policy(&mut validation, metadata, policy);
+            Ok(validation)
+        },
+    )
+    .map_err(|source| OAuthResourceServerError::TokenValidation { source })
+}
+
 impl Drop for OAuthResourceServerVerifierJwe {
     fn drop(&mut self) {
         if let Some(handle) = &self.watcher_handle {
@@ -1,25 +1,40 @@
 mod introspection;
 #[cfg(feature = "jwe")]
 mod jwe;
-#[cfg(feature = "jwe")]
+mod local_keys;
 mod watcher;
+#[cfg(feature = "x5c")]
+mod x5c;
 
 use std::sync::Arc;
 
-use securitydept_creds::{JwtClaimsTrait, JwtValidation, TokenData, TokenFormat, TokenJwtClaims};
+use securitydept_creds::{
+    JwtClaimsTrait, JwtJwkTrait, JwtJwksTrait, JwtValidation, TokenData, TokenFormat,
+    TokenJwtClaims, jwt_typ_matches,
+};
+use securitydept_oauth_provider::OAuthProviderRuntime;
 use tracing::debug;
 
-use self::introspection::OAuthResourceServerVerifierIntrospection;
 #[cfg(feature = "jwe")]
 use self::jwe::OAuthResourceServerVerifierJwe;
+use self::{
+    introspection::OAuthResourceServerVerifierIntrospection,
+    local_keys::OAuthResourceServerVerifierLocalKeys,
+};
 use crate::{
     OAuthResourceServerConfig, OAuthResourceServerError, OAuthResourceServerMetadata,
     OAuthResourceServerResult, VerificationPolicy, VerifiedAccessToken, VerifiedOpaqueToken,
     VerifiedToken, models::scope_contains_all,
 };
 
+/// Where the verifier gets its issuer metadata and JWT verification keys.
+enum OAuthResourceServerKeySource {
+    Provider(Arc<OAuthProviderRuntime>),
+    Local(Box<OAuthResourceServerVerifierLocalKeys>),
+}
+
 pub struct OAuthResourceServerVerifier {
-    provider: Arc<securitydept_oauth_provider::OAuthProviderRuntime>,
+    key_source: OAuthResourceServerKeySource,
     policy: VerificationPolicy,
     introspection: Option<OAuthResourceServerVerifierIntrospection>,
     #[cfg(feature = "jwe")]
@@ -27,30 +42,50 @@ pub struct OAuthResourceServerVerifier {
 }
 
 impl OAuthResourceServerVerifier {
+    /// Build a verifier from config. When `local_keys` is set, tokens are
+    /// verified offline and no provider runtime is created.
     pub async fn from_config(config: OAuthResourceServerConfig) -> OAuthResourceServerResult<Self> {
         config.validate()?;
-        let provider = Arc::new(
-            securitydept_oauth_provider::OAuthProviderRuntime::from_config(
-                config.provider_config(),
-            )
-            .await?,
-        );
+        if let Some(local_keys) = config.local_keys.as_ref() {
+            let local_keys =
+                Box::new(OAuthResourceServerVerifierLocalKeys::from_config(local_keys).await?);
+            return Self::from_key_source(OAuthResourceServerKeySource::Local(local_keys), config)
+                .await;
+        }
+
+        let provider = Arc::new(OAuthProviderRuntime::from_config(config.provider_config()).await?);
         Self::from_provider(provider, config).await
     }
 
     pub async fn from_provider(
-        provider: Arc<securitydept_oauth_provider::OAuthProviderRuntime>,
+        provider: Arc<OAuthProviderRuntime>,
         config: OAuthResourceServerConfig,
     ) -> OAuthResourceServerResult<Self> {
         config.validate()?;
+        Self::from_key_source(OAuthResourceServerKeySource::Provider(provider), config).await
+    }
+
+    async fn from_key_source(
+        key_source: OAuthResourceServerKeySource,
+        config: OAuthResourceServerConfig,
+    ) -> OAuthResourceServerResult<Self> {
+        let policy = VerificationPolicy::new(
+            config.audiences.clone(),
+            config.required_scopes.clone(),
+            config.clock_skew,
+        )
+        .with_allowed_algorithms(config.allowed_algorithms.clone())
+        .with_required_typ(config.required_typ.clone());
+        #[cfg(feature = "x5c")]
+        let policy =
+            policy.with_x5c_trust_anchors(match config.x5c_trust_anchors_path.as_deref() {
+                Some(path) => Some(Arc::new(x5c::load_x5c_trust_anchors(path).await?)),
+                None => None,
+            });
 
         Ok(Self {
-            provider,
-            policy: VerificationPolicy::new(
-                config.audiences.clone(),
-                config.required_scopes.clone(),
-                config.clock_skew,
-            ),
+            key_source,
+            policy,
             introspection: config
                 .introspection
                 .as_ref()
@@ -66,10 +101,16 @@ impl OAuthResourceServerVerifier {
     }
 
     pub async fn metadata(&self) -> OAuthResourceServerResult<OAuthResourceServerMetadata> {
-        let metadata = self.provider.metadata().await?;
+        let provider = match &self.key_source {
+            OAuthResourceServerKeySource::Provider(provider) => provider,
+            OAuthResourceServerKeySource::Local(local_keys) => {
+                return Ok(local_keys.metadata().clone());
+            }
+        };
+        let metadata = provider.metadata().await?;
         Ok(OAuthResourceServerMetadata {
             issuer: metadata.issuer,
-            jwks_uri: metadata.jwks_uri,
+            jwks_uri: Some(metadata.jwks_uri),
             introspection_url: metadata.introspection_endpoint,
         })
     }
@@ -78,8 +119,13 @@ impl OAuthResourceServerVerifier {
         &self.policy
     }
 
-    pub fn provider(&self) -> &Arc<securitydept_oauth_provider::OAuthProviderRuntime> {
-        &self.provider
+    /// The remote provider runtime, or `None` when verifying against local
+    /// keys.
+    pub fn provider(&self) -> Option<&Arc<OAuthProviderRuntime>> {
+        match &self.key_source {
+            OAuthResourceServerKeySource::Provider(provider) => Some(provider),
+            OAuthResourceServerKeySource::Local(_) => None,
+        }
     }
 
     pub async fn verify_token<CLAIMS>(
@@ -101,12 +147,14 @@ impl OAuthResourceServerVerifier {
         &self,
         token: &str,
     ) -> OAuthResourceServerResult<VerifiedOpaqueToken> {
-        self.introspection
-            .as_ref()
-            .ok_or(OAuthResourceServerError::UnsupportedTokenFormat {
+        let (Some(introspection), Some(provider)) = (self.introspection.as_ref(), self.provider())
+        else {
+            return Err(OAuthResourceServerError::UnsupportedTokenFormat {
                 token_format: TokenFormat::Opaque,
-            })?
-            .introspect(token, &self.provider, &self.policy)
+            });
+        };
+        introspection
+            .introspect(token, provider, &self.policy)
             .await
     }
 
@@ -147,13 +195,29 @@ impl OAuthResourceServerVerifier {
     where
         CLAIMS: JwtClaimsTrait,
     {
-        match TokenFormat::from_token(token) {
-            TokenFormat::JWT => self.verify_jwt_token_data(token).await,
-            TokenFormat::Opaque => Err(OAuthResourceServerError::UnsupportedTokenFormat {
-                token_format: TokenFormat::Opaque,
-            }),
-            TokenFormat::JWE => self.verify_jwe_token_data(token).await,
-        }
+        let token_data = match TokenFormat::from_token(token) {
+            TokenFormat::JWT => self.verify_jwt_token_data(token).await?,
+            // PASETO keys are published in the same JWKS as JWT keys.
+            #[cfg(feature = "paseto")]
+            TokenFormat::PASETO => {
+                validate_paseto_algorithm_policy(token, &self.policy)?;
+                self.verify_jwt_token_data(token).await?
+            }
+            #[cfg(not(feature = "paseto"))]
+            TokenFormat::PASETO => {
+                return Err(OAuthResourceServerError::UnsupportedTokenFormat {
+                    token_format: TokenFormat::PASETO,
+                });
+            }
+            TokenFormat::Opaque => {
+                return Err(OAuthResourceServerError::UnsupportedTokenFormat {
+                    token_format: TokenFormat::Opaque,
+                });
+            }
+            TokenFormat::JWE => self.verify_jwe_token_data(token).await?,
+        };
+        validate_typ_policy(&token_data, &self.policy)?;
+        Ok(token_data)
     }
 
     async fn verify_jwt_token_data<CLAIMS>(
@@ -163,17 +227,30 @@ impl OAuthResourceServerVerifier {
     where
         CLAIMS: JwtClaimsTrait,
     {
-        match self.verify_jwt_with_current_jwks(token).await {
+        let provider = match &self.key_source {
+            OAuthResourceServerKeySource::Provider(provider) => provider,
+            OAuthResourceServerKeySource::Local(local_keys) => {
+                let jwks = local_keys.verification_keys.read().await;
+                return verify_jwt_with_policy::<CLAIMS, _, _>(
+                    token,
+                    &*jwks,
+                    local_keys.metadata(),
+                    &self.policy,
+                );
+            }
+        };
+
+        match self.verify_jwt_with_current_jwks(token, provider).await {
             Ok(token_data) => Ok(token_data),
             Err(error) if should_retry_with_refreshed_jwks(&error) => {
                 debug!("Retrying access token verification after JWKS refresh");
-                let metadata = self.provider.refresh_jwks().await?;
-                verify_jwt_with_policy::<CLAIMS>(
+                let metadata = provider.refresh_jwks().await?;
+                verify_jwt_with_policy::<CLAIMS, _, _>(
                     token,
                     &metadata.jwks,
                     &OAuthResourceServerMetadata {
                         issuer: metadata.issuer,
-                        jwks_uri: metadata.jwks_uri,
+                        jwks_uri: Some(metadata.jwks_uri),
                         introspection_url: metadata.introspection_endpoint,
                     },
                     &self.policy,
@@ -186,17 +263,23 @@ impl OAuthResourceServerVerifier {
     async fn verify_jwt_with_current_jwks<CLAIMS>(
         &self,
         token: &str,
+        provider: &OAuthProviderRuntime,
     ) -> OAuthResourceServerResult<TokenData<CLAIMS>>
     where
         CLAIMS: JwtClaimsTrait,
     {
-        let metadata = self.provider.metadata().await?;
+        let metadata = provider.metadata().await?;
         let resource_metadata = OAuthResourceServerMetadata {
             issuer: metadata.issuer.clone(),
-            jwks_uri: metadata.jwks_uri.clone(),
+            jwks_uri: Some(metadata.jwks_uri.clone()),
             introspection_url: metadata.introspection_endpoint.clone(),
         };
-        verify_jwt_with_policy::<CLAIMS>(token, &metadata.jwks, &resource_metadata, &self.policy)
+        verify_jwt_with_policy::<CLAIMS, _, _>(
+            token,
+            &metadata.jwks,
+            &resource_metadata,
+            &self.policy,
+        )
     }
 
     async fn verify_jwe_token_data<CLAIMS>(
@@ -208,22 +291,42 @@ impl OAuthResourceServerVerifier {
     {
         #[cfg(feature = "jwe")]
         {
-            return match self.verify_jwe_with_current_jwks(token).await {
+            let provider = match &self.key_source {
+                OAuthResourceServerKeySource::Provider(provider) => provider,
+                OAuthResourceServerKeySource::Local(local_keys) => {
+                    let jwks = local_keys.verification_keys.read().await;
+                    return self
+                        .jwe
+                        .as_ref()
+                        .ok_or(OAuthResourceServerError::UnsupportedTokenFormat {
+                            token_format: TokenFormat::JWE,
+                        })?
+                        .verify_token_data::<CLAIMS, _, _>(
+                            token,
+                            &*jwks,
+                            local_keys.metadata(),
+                            &self.policy,
+                        )
+                        .await;
+                }
+            };
+
+            return match self.verify_jwe_with_current_jwks(token, provider).await {
                 Ok(token_data) => Ok(token_data),
                 Err(error) if should_retry_with_refreshed_jwks(&error) => {
                     debug!("Retrying JWE access token verification after JWKS refresh");
-                    let metadata = self.provider.refresh_jwks().await?;
+                    let metadata = provider.refresh_jwks().await?;
                     self.jwe
                         .as_ref()
                         .ok_or(OAuthResourceServerError::UnsupportedTokenFormat {
                             token_format: TokenFormat::JWE,
                         })?
-                        .verify_token_data::<CLAIMS>(
+                        .verify_token_data::<CLAIMS, _, _>(
                             token,
                             &metadata.jwks,
                             &OAuthResourceServerMetadata {
                                 issuer: metadata.issuer,
-                                jwks_uri: metadata.jwks_uri,
+                                jwks_uri: Some(metadata.jwks_uri),
                                 introspection_url: metadata.introspection_endpoint,
                             },
                             &self.policy,
@@ -247,22 +350,23 @@ impl OAuthResourceServerVerifier {
     async fn verify_jwe_with_current_jwks<CLAIMS>(
         &self,
         token: &str,
+        provider: &OAuthProviderRuntime,
     ) -> OAuthResourceServerResult<TokenData<CLAIMS>>
     where
         CLAIMS: JwtClaimsTrait,
     {
-        let metadata = self.provider.metadata().await?;
+        let metadata = provider.metadata().await?;
         self.jwe
             .as_ref()
             .ok_or(OAuthResourceServerError::UnsupportedTokenFormat {
                 token_format: TokenFormat::JWE,
             })?
-            .verify_token_data::<CLAIMS>(
+            .verify_token_data::<CLAIMS, _, _>(
                 token,
                 &metadata.jwks,
                 &OAuthResourceServerMetadata {
                     issuer: metadata.issuer,
-                    jwks_uri: metadata.jwks_uri,
+                    jwks_uri: Some(metadata.jwks_uri),
                     introspection_url: metadata.introspection_endpoint,
                 },
                 &self.policy,
@@ -271,15 +375,40 @@ impl OAuthResourceServerVerifier {
     }
 }
 
+fn verify_jwt_with_policy<CLAIMS, JWK, JWKS>(
+    token: &str,
+    jwks: &JWKS,
+    metadata: &OAuthResourceServerMetadata,
+    policy: &VerificationPolicy,
+) -> OAuthResourceServerResult<TokenData<CLAIMS>>
+where
+    CLAIMS: JwtClaimsTrait,
+    JWK: JwtJwkTrait,
+    JWKS: JwtJwksTrait<JWK>,
+{
+    #[cfg(feature = "x5c")]
+    if let Some(trust_anchors) = policy.x5c_trust_anchors() {
+        return verify_rfc9068_with_policy::<CLAIMS, securitydept_creds::X5cTrustedJwk<'_, JWK>, _>(
+            token,
+            &trust_anchors.trusted_jwks(jwks),
+            metadata,
+            policy,
+        );
+    }
+    verify_rfc9068_with_policy::<CLAIMS, JWK, JWKS>(token, jwks, metadata, policy)
+}
+
 #[cfg(not(feature = "jwe"))]
-fn verify_jwt_with_policy<CLAIMS>(
+fn verify_rfc9068_with_policy<CLAIMS, JWK, JWKS>(
     token: &str,
-    jwks: &openidconnect::core::CoreJsonWebKeySet,
+    jwks: &JWKS,
     metadata: &OAuthResourceServerMetadata,
     policy: &VerificationPolicy,
 ) -> OAuthResourceServerResult<TokenData<CLAIMS>>
 where
     CLAIMS: JwtClaimsTrait,
+    JWK: JwtJwkTrait,
+    JWKS: JwtJwksTrait<JWK>,
 {
     securitydept_creds::verify_token_rfc9068_with_jwks_without_jwe(
         token,
@@ -293,14 +422,16 @@ where
 }
 
 #[cfg(feature = "jwe")]
-fn verify_jwt_with_policy<CLAIMS>(
+fn verify_rfc9068_with_policy<CLAIMS, JWK, JWKS>(
     token: &str,
-    jwks: &openidconnect::core::CoreJsonWebKeySet,
+    jwks: &JWKS,
     metadata: &OAuthResourceServerMetadata,
     policy: &VerificationPolicy,
 ) -> OAuthResourceServerResult<TokenData<CLAIMS>>
 where
     CLAIMS: JwtClaimsTrait,
+    JWK: JwtJwkTrait,
+    JWKS: JwtJwksTrait<JWK>,
 {
     use crate::LocalJweDecryptionKeySet;
 
@@ -325,6 +456,9 @@ pub(super) fn apply_validation_policy(
     validation.validate_nbf = true;
     validation.set_required_spec_claims(&["exp", "iss"]);
     validation.set_issuer(&[metadata.issuer.as_str()]);
+    if !policy.allowed_algorithms().is_empty() {
+        validation.algorithms = policy.allowed_algorithms().to_vec();
+    }
     if !policy.allowed_audiences().is_empty() {
         validation.set_audience(policy.allowed_audiences());
     } else {
@@ -332,6 +466,67 @@ pub(super) fn apply_validation_policy(
     }
 }
 
+/// PASETO tokens carry no `alg` header; the version and purpose fix the
+/// algorithm. Under an algorithm allowlist, `v4.public` (Ed25519) tokens need
+/// `EdDSA` in it, and `v4.local` tokens, whose symmetric encryption no JWT
+/// algorithm names, are refused.
+#[cfg(feature = "paseto")]
+fn validate_paseto_algorithm_policy(
+    token: &str,
+    policy: &VerificationPolicy,
+) -> OAuthResourceServerResult<()> {
+    use securitydept_creds::{JwtAlgorithm, PasetoPurpose};
+
+    let allowed_algorithms = policy.allowed_algorithms();
+    if allowed_algorithms.is_empty() {
+        return Ok(());
+    }
+    match PasetoPurpose::from_token(token) {
+        Some(PasetoPurpose::Public) if allowed_algorithms.contains(&JwtAlgorithm::EdDSA) => Ok(()),
+        Some(PasetoPurpose::Public) => Err(OAuthResourceServerError::PolicyViolation {
+            message: "PASETO v4.public tokens require EdDSA in allowed_algorithms".to_string(),
+        }),
+        _ => Err(OAuthResourceServerError::PolicyViolation {
+            message: "PASETO v4.local tokens are not accepted when allowed_algorithms is set"
+                .to_string(),
+        }),
+    }
+}
+
+/// Check the `typ` header after the signature has been verified, so an ID
+/// token from the same issuer cannot stand in for an access token.
+fn validate_typ_policy<CLAIMS>(
+    token_data: &TokenData<CLAIMS>,
+    policy: &VerificationPolicy,
+) -> OAuthResourceServerResult<()>
+where
+    CLAIMS: JwtClaimsTrait,
+{
+    let Some(required_typ) = policy.required_typ() else {
+        return Ok(());
+    };
+    let typ = match token_data {
+        TokenData::JWT(data) => data.header.typ.as_deref(),
+        #[cfg(feature = "jwe")]
+        TokenData::JWE(data) => data.jwt_header().typ.as_deref(),
+        _ => return Ok(()),
+    };
+    if jwt_typ_matches(typ, required_typ) {
+        return Ok(());
+    }
+
+    Err(OAuthResourceServerError::PolicyViolation {
+        message: format!(
+            "Access token typ header {} does not match required {required_typ}",
+            typ.unwrap_or("<missing>")
+        ),
+    })
+}
+
+// The `{ .. }` patterns below also match the data-carrying variants that
+// `securitydept-creds` has when another crate in the build enables its `jwe`
+// or `paseto` feature.
+#[allow(clippy::unneeded_struct_pattern)]
 fn validate_rfc9068_scope_policy(
     token_data: &TokenData<TokenJwtClaims>,
     policy: &VerificationPolicy,
@@ -340,13 +535,19 @@ fn validate_rfc9068_scope_policy(
         TokenData::JWT(data) => validate_scope_policy(data.claims.scope.as_ref(), policy),
         #[cfg(feature = "jwe")]
         TokenData::JWE(data) => validate_scope_policy(data.claims().scope.as_ref(), policy),
+        #[cfg(feature = "paseto")]
+        TokenData::PASETO(data) => validate_scope_policy(data.claims.scope.as_ref(), policy),
         TokenData::Opaque => Err(OAuthResourceServerError::UnsupportedTokenFormat {
             token_format: TokenFormat::Opaque,
         }),
         #[cfg(not(feature = "jwe"))]
-        _ => Err(OAuthResourceServerError::UnsupportedTokenFormat {
+        TokenData::JWE { .. } => Err(OAuthResourceServerError::UnsupportedTokenFormat {
             token_format: TokenFormat::JWE,
         }),
+        #[cfg(not(feature = "paseto"))]
+        TokenData::PASETO { .. } => Err(OAuthResourceServerError::UnsupportedTokenFormat {
+            token_format: TokenFormat::PASETO,
+        }),
     }
 }
 
@@ -378,3 +579,59 @@ fn should_retry_with_refreshed_jwks(error: &OAuthResourceServerError) -> bool {
         }
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use securitydept_creds::{CoreJwtClaims, JwtAlgorithm, JwtHeader, JwtTokenData};
+
+    use super::*;
+
+    fn jwt_token_data(typ: Option<&str>) -> TokenData<CoreJwtClaims> {
+        let mut header = JwtHeader::new(JwtAlgorithm::RS256);
+        header.typ = typ.map(str::to_string);
+        let claims = serde_json::from_value(serde_json::json!({ "sub": "svc-a" }))
+            .expect("sample claims should deserialize");
+        TokenData::JWT(Box::new(JwtTokenData { header, claims }))
+    }
+
+    fn policy(required_typ: Option<&str>) -> VerificationPolicy {
+        VerificationPolicy::new(Vec::new(), Vec::new(), Duration::from_secs(60))
+            .with_required_typ(required_typ.map(str::to_string))
+    }
+
+    #[test]
+    fn typ_policy_is_skipped_when_not_configured() {
+        assert!(validate_typ_policy(&jwt_token_data(Some("JWT")), &policy(None)).is_ok());
+    }
+
+    #[test]
+    fn typ_policy_rejects_id_token_typ_when_at_jwt_is_required() {
+        let policy = policy(Some("at+jwt"));
+
+        assert!(validate_typ_policy(&jwt_token_data(Some("at+jwt")), &policy).is_ok());
+        assert!(validate_typ_policy(&jwt_token_data(Some("application/at+jwt")), &policy).is_ok());
+        assert!(matches!(
+            validate_typ_policy(&jwt_token_data(Some("JWT")), &policy),
+            Err(OAuthResourceServerError::PolicyViolation { .. })
+        ));
+        assert!(validate_typ_policy(&jwt_token_data(None), &policy).is_err());
+    }
+
+    #[test]
+    fn allowed_algorithms_replace_the_header_derived_validation_algorithm() {
+        let metadata = OAuthResourceServerMetadata {
+            issuer: openidconnect::IssuerUrl::new("https://issuer.example.com".to_string())
+                .expect("issuer should parse"),
+            jwks_uri: None,
+            introspection_url: None,
+        };
+        let policy = policy(None).with_allowed_algorithms(vec![JwtAlgorithm::ES256]);
+        let mut validation = JwtValidation::new(JwtAlgorithm::RS256);
+
+        apply_validation_policy(&mut validation, &metadata, &policy);
+
+        assert_eq!(validation.algorithms, vec![JwtAlgorithm::ES256]);
+    }
+}
@@ -1,12 +1,22 @@
-use std::{path::PathBuf, sync::Arc, time::SystemTime};
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
+use jsonwebtoken::jwk::JwkSet;
 use tokio::{sync::RwLock, task::JoinHandle, time::sleep};
 use tracing::{debug, warn};
 
+#[cfg(feature = "jwe")]
 use crate::{
     OAuthResourceServerJweConfig, models::LocalJweDecryptionKeySet,
     verifier::jwe::load_jwe_decryption_keys,
 };
+use crate::{
+    OAuthResourceServerLocalKeysConfig, OAuthResourceServerResult,
+    verifier::local_keys::load_local_verification_keys,
+};
 
 #[derive(Debug, Clone)]
 struct WatchedFileState {
@@ -20,22 +30,63 @@ struct FileFingerprint {
     len: u64,
 }
 
+#[cfg(feature = "jwe")]
 pub(super) fn spawn_jwe_key_watcher(
     config: OAuthResourceServerJweConfig,
     decryption_keys: Arc<RwLock<Option<LocalJweDecryptionKeySet>>>,
 ) -> Option<JoinHandle<()>> {
-    if config.watch_interval.is_zero() {
-        return None;
-    }
-
     let watched_files = watched_files_from_config(&config);
-    if watched_files.is_empty() {
+    let interval = config.watch_interval;
+    spawn_key_file_watcher(
+        watched_files,
+        interval,
+        decryption_keys,
+        "JWE decryption keys",
+        move || {
+            let config = config.clone();
+            async move { load_jwe_decryption_keys(&config).await }
+        },
+    )
+}
+
+pub(super) fn spawn_local_verification_key_watcher(
+    config: OAuthResourceServerLocalKeysConfig,
+    verification_keys: Arc<RwLock<JwkSet>>,
+) -> Option<JoinHandle<()>> {
+    let watched_files = watched_files_from_local_keys_config(&config);
+    let interval = config.watch_interval;
+    spawn_key_file_watcher(
+        watched_files,
+        interval,
+        verification_keys,
+        "local verification keys",
+        move || {
+            let config = config.clone();
+            async move { load_local_verification_keys(&config).await }
+        },
+    )
+}
+
+/// Poll `watched_files` every `interval` and replace `keys` with a fresh
+/// `load()` whenever any of them changes. A zero interval disables watching.
+fn spawn_key_file_watcher<T, F, Fut>(
+    watched_files: Vec<PathBuf>,
+    interval: Duration,
+    keys: Arc<RwLock<T>>,
+    label: &'static str,
+    load: F,
+) -> Option<JoinHandle<()>>
+where
+    T: Send + Sync + 'static,
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = OAuthResourceServerResult<T>> + Send,
+{
+    if interval.is_zero() || watched_files.is_empty() {
         return None;
     }
 
     Some(tokio::spawn(async move {
         let mut watched_files = initialize_watch_state(watched_files).await;
-        let interval = config.watch_interval;
 
         loop {
             sleep(interval).await;
@@ -43,13 +94,13 @@ pub(super) fn spawn_jwe_key_watcher(
                 continue;
             }
 
-            match load_jwe_decryption_keys(&config).await {
+            match load().await {
                 Ok(updated) => {
-                    *decryption_keys.write().await = updated;
-                    debug!("Reloaded JWE decryption keys after file rotation");
+                    *keys.write().await = updated;
+                    debug!("Reloaded {label} after file rotation");
                 }
                 Err(error) => {
-                    warn!(error = %error, "Failed to reload rotated JWE decryption keys");
+                    warn!(error = %error, "Failed to reload rotated {label}");
                 }
             }
         }
@@ -69,6 +120,21 @@ fn watched_files_from_config(config: &OAuthResourceServerJweConfig) -> Vec<PathB
     .collect()
 }
 
+fn watched_files_from_local_keys_config(
+    config: &OAuthResourceServerLocalKeysConfig,
+) -> Vec<PathBuf> {
+    [
+        config.jwks_path.as_deref(),
+        config.jwk_path.as_deref(),
+        config.pem_path.as_deref(),
+        config.hmac_secret_path.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(PathBuf::from)
+    .collect()
+}
+
 async fn initialize_watch_state(paths: Vec<PathBuf>) -> Vec<WatchedFileState> {
     let mut result = Vec::with_capacity(paths.len());
     for path in paths {
@@ -0,0 +1,16 @@
+use securitydept_creds::JwkTrustAnchors;
+
+use crate::{OAuthResourceServerError, OAuthResourceServerResult};
+
+pub(super) async fn load_x5c_trust_anchors(
+    path: &str,
+) -> OAuthResourceServerResult<JwkTrustAnchors> {
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|e| OAuthResourceServerError::LocalKey {
+            message: format!("Failed to read x5c trust anchor file '{path}': {e}"),
+        })?;
+    JwkTrustAnchors::from_pem(&data).map_err(|e| OAuthResourceServerError::LocalKey {
+        message: format!("Failed to load x5c trust anchors from '{path}': {e}"),
+    })
+}
@@ -99,6 +99,15 @@ impl RealIpResolver {
         }
     }
 
+    /// Whether `peer_ip` belongs to the peers of any configured source, i.e.
+    /// whether it is a proxy that may report the client address on our behalf.
+    pub async fn is_trusted_peer(&self, peer_ip: IpAddr) -> bool {
+        self.compile_sources()
+            .await
+            .iter()
+            .any(|source| source.matches_peer(peer_ip))
+    }
+
     async fn compile_sources(&self) -> Vec<CompiledSource> {
         let mut compiled = Vec::with_capacity(self.config.sources.len());
         for source in &self.config.sources {
@@ -399,6 +408,31 @@ mod tests {
         assert_eq!(resolved.header_name.as_deref(), Some("x-forwarded-for"));
     }
 
+    #[tokio::test]
+    async fn only_source_peers_are_trusted() {
+        let config = RealIpResolveConfig {
+            providers: vec![ProviderConfig::Core(CoreProviderConfig::Inline(
+                InlineProviderConfig {
+                    name: "envoy".to_string(),
+                    cidrs: vec!["10.0.0.0/8".parse().unwrap()],
+                    extra: Default::default(),
+                },
+            ))],
+            sources: vec![SourceConfig {
+                name: "envoy".to_string(),
+                priority: 100,
+                peers_from: vec!["envoy".to_string()],
+                accept_transport: vec![],
+                accept_headers: vec![],
+            }],
+            fallback: Default::default(),
+        };
+        let resolver = RealIpResolver::from_config(config).await.unwrap();
+
+        assert!(resolver.is_trusted_peer("10.1.2.3".parse().unwrap()).await);
+        assert!(!resolver.is_trusted_peer("192.0.2.1".parse().unwrap()).await);
+    }
+
     #[tokio::test]
     async fn loads_local_file_provider() {
         let path = temp_file("local-provider", "127.0.0.1/32\n::1/128\n");
@@ -1,9 +1,13 @@
 use std::time::Duration as StdDuration;
 
-use securitydept_utils::redirect::{RedirectTargetConfig, UriRelativeRedirectTargetResolver};
+use securitydept_utils::redirect::{
+    RedirectTargetConfig, RedirectTargetRule, UriRedirectTargetResolver,
+    UriRelativeRedirectTargetResolver,
+};
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use typed_builder::TypedBuilder;
+use url::Url;
 
 use crate::{SessionContextError, SessionContextResult, SessionCookieSameSite};
 
@@ -25,6 +29,11 @@ pub struct SessionContextConfig {
     #[builder(default = "/".to_string())]
     #[serde(default = "default_cookie_path")]
     pub cookie_path: String,
+    /// Domain of the session cookie, e.g. `example.com` to share the session
+    /// with apps on its subdomains. Host-only when unset.
+    #[builder(default, setter(strip_option, into))]
+    #[serde(default)]
+    pub cookie_domain: Option<String>,
     #[builder(default = true)]
     #[serde(default = "default_true")]
     pub http_only: bool,
@@ -47,11 +56,16 @@ pub struct ResolvedSessionContextConfig {
     pub cookie_name: String,
     pub session_context_key: String,
     pub cookie_path: String,
+    pub cookie_domain: Option<String>,
     pub http_only: bool,
     pub secure: bool,
     pub same_site: SessionCookieSameSite,
     pub ttl: Option<StdDuration>,
     pub post_auth_redirect: RedirectTargetConfig,
+    /// Absolute URLs, e.g. of apps behind forward auth, that may be requested
+    /// as post-auth redirects besides the relative `post_auth_redirect`
+    /// targets. Empty unless set by the embedding application.
+    pub absolute_post_auth_redirect_targets: Vec<RedirectTargetRule>,
 }
 
 #[derive(Debug, Snafu)]
@@ -68,6 +82,9 @@ pub trait SessionContextConfigSource {
     fn cookie_name_config(&self) -> &str;
     fn session_context_key_config(&self) -> &str;
     fn cookie_path_config(&self) -> &str;
+    fn cookie_domain_config(&self) -> Option<&str> {
+        None
+    }
     fn http_only_config(&self) -> bool;
     fn secure_config(&self) -> bool;
     fn same_site_config(&self) -> SessionCookieSameSite;
@@ -86,6 +103,10 @@ pub trait SessionContextConfigSource {
         self.cookie_path_config().to_string()
     }
 
+    fn resolve_cookie_domain(&self) -> Option<String> {
+        self.cookie_domain_config().map(str::to_string)
+    }
+
     fn resolve_http_only(&self) -> bool {
         self.http_only_config()
     }
@@ -130,6 +151,7 @@ pub trait SessionContextConfigSource {
             cookie_name: self.resolve_cookie_name(),
             session_context_key: self.resolve_session_context_key(),
             cookie_path: self.resolve_cookie_path(),
+            cookie_domain: self.resolve_cookie_domain(),
             http_only: self.resolve_http_only(),
             secure: self.resolve_secure(),
             same_site: self.resolve_same_site(),
@@ -137,6 +159,7 @@ pub trait SessionContextConfigSource {
             post_auth_redirect: self
                 .resolve_post_auth_redirect_config()
                 .map_err(|source| SessionContextConfigValidationFailure::Config { source })?,
+            absolute_post_auth_redirect_targets: Vec::new(),
         })
     }
 }
@@ -154,6 +177,10 @@ impl SessionContextConfigSource for SessionContextConfig {
         &self.cookie_path
     }
 
+    fn cookie_domain_config(&self) -> Option<&str> {
+        self.cookie_domain.as_deref()
+    }
+
     fn http_only_config(&self) -> bool {
         self.http_only
     }
@@ -181,6 +208,7 @@ impl Default for SessionContextConfig {
             cookie_name: default_cookie_name(),
             session_context_key: default_session_context_key(),
             cookie_path: default_cookie_path(),
+            cookie_domain: None,
             http_only: default_true(),
             secure: false,
             same_site: SessionCookieSameSite::default(),
@@ -196,11 +224,13 @@ impl Default for ResolvedSessionContextConfig {
             cookie_name: default_cookie_name(),
             session_context_key: default_session_context_key(),
             cookie_path: default_cookie_path(),
+            cookie_domain: None,
             http_only: default_true(),
             secure: false,
             same_site: SessionCookieSameSite::default(),
             ttl: default_ttl(),
             post_auth_redirect: default_post_auth_redirect(),
+            absolute_post_auth_redirect_targets: Vec::new(),
         }
     }
 }
@@ -210,6 +240,18 @@ impl ResolvedSessionContextConfig {
         &self,
         requested_post_auth_redirect: Option<&str>,
     ) -> SessionContextResult<String> {
+        if let Some(requested) = requested_post_auth_redirect
+            && !self.absolute_post_auth_redirect_targets.is_empty()
+            && Url::parse(requested).is_ok()
+        {
+            return UriRedirectTargetResolver::from_config(RedirectTargetConfig::dynamic_targets(
+                self.absolute_post_auth_redirect_targets.iter().cloned(),
+            ))
+            .map_err(|source| SessionContextError::RedirectTarget { source })?
+            .resolve_redirect_target(Some(requested))
+            .map(|value| value.to_string())
+            .map_err(|source| SessionContextError::RedirectTarget { source });
+        }
         resolve_session_post_auth_redirect(&self.post_auth_redirect, requested_post_auth_redirect)
     }
 }
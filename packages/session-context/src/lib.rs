@@ -83,6 +83,9 @@ where
         .with_http_only(config.http_only)
         .with_secure(config.secure);
 
+    if let Some(domain) = &config.cookie_domain {
+        layer = layer.with_domain(domain.clone());
+    }
     if let Some(ttl) = config.ttl {
         layer = layer.with_expiry(Expiry::OnInactivity(
             Duration::seconds(ttl.as_secs() as i64),
@@ -234,6 +237,7 @@ impl SessionContextSession {
 
 #[cfg(test)]
 mod tests {
+    use regex::Regex;
     use securitydept_utils::redirect::RedirectTargetRule;
 
     use super::*;
@@ -316,6 +320,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn absolute_post_auth_redirect_needs_an_explicit_target() {
+        let wiki = RedirectTargetRule::Regex {
+            value: Regex::new(r"^https://[a-z]+\.example\.com/").unwrap(),
+        };
+        let mut config = SessionContextConfigSource::resolve_all(
+            &SessionContextConfig::builder()
+                .cookie_domain("example.com")
+                .post_auth_redirect(RedirectTargetConfig::dynamic_default_and_dynamic_targets(
+                    "/",
+                    [wiki.clone()],
+                ))
+                .build(),
+        )
+        .expect("session context config should resolve");
+
+        assert_eq!(config.cookie_domain.as_deref(), Some("example.com"));
+        assert!(
+            config
+                .resolve_post_auth_redirect(Some("https://wiki.example.com/page?id=1"))
+                .is_err(),
+            "post_auth_redirect only accepts relative targets"
+        );
+
+        config.absolute_post_auth_redirect_targets = vec![wiki];
+        assert_eq!(
+            config
+                .resolve_post_auth_redirect(Some("https://wiki.example.com/page?id=1"))
+                .expect("matching absolute redirect should resolve"),
+            "https://wiki.example.com/page?id=1"
+        );
+        assert!(
+            config
+                .resolve_post_auth_redirect(Some("https://evil.example.net/"))
+                .is_err()
+        );
+        assert!(
+            config
+                .resolve_post_auth_redirect(Some("//evil.example.net/"))
+                .is_err()
+        );
+    }
+
     #[test]
     fn fixed_post_auth_redirect_validator_rejects_override() {
         let config = SessionContextConfig::builder()
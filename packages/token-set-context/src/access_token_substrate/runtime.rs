@@ -71,7 +71,7 @@ impl AccessTokenSubstrateRuntime {
         config: &super::config::ResolvedAccessTokenSubstrateConfig,
     ) -> Result<(Self, Option<Arc<OAuthResourceServerVerifier>>), AccessTokenSubstrateRuntimeError>
     {
-        let resource_verifier = if config.resource_server.remote.is_discovery_configured() {
+        let resource_verifier = if config.resource_server.is_verifier_configured() {
             let verifier = OAuthResourceServerVerifier::from_config(config.resource_server.clone())
                 .await
                 .map_err(AccessTokenSubstrateRuntimeError::ResourceServer)?;
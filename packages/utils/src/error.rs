@@ -36,7 +36,7 @@ impl ServerErrorKind {
             400 | 404 | 422 => Self::InvalidRequest,
             401 => Self::Unauthenticated,
             403 => Self::Unauthorized,
-            409 => Self::Conflict,
+            409 | 412 => Self::Conflict,
             503 => Self::Unavailable,
             _ if status >= 500 => Self::Internal,
             _ => Self::InvalidRequest,
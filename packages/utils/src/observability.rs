@@ -24,27 +24,44 @@ impl AuthFlowOperation {
     pub const CREDS_MANAGE_GROUP_CREATE: &'static str = "creds_manage.group.create";
     pub const CREDS_MANAGE_GROUP_UPDATE: &'static str = "creds_manage.group.update";
     pub const CREDS_MANAGE_GROUP_DELETE: &'static str = "creds_manage.group.delete";
+    pub const CREDS_MANAGE_GROUP_AVAILABILITY: &'static str = "creds_manage.group.availability";
+    pub const CREDS_MANAGE_GROUP_IDENTITY_ACCESS: &'static str =
+        "creds_manage.group.identity_access";
+    pub const CREDS_MANAGE_GROUP_ACCESS_RULES: &'static str = "creds_manage.group.access_rules";
     pub const CREDS_MANAGE_ENTRY_LIST: &'static str = "creds_manage.entry.list";
     pub const CREDS_MANAGE_ENTRY_GET: &'static str = "creds_manage.entry.get";
     pub const CREDS_MANAGE_ENTRY_CREATE_BASIC: &'static str = "creds_manage.entry.create_basic";
     pub const CREDS_MANAGE_ENTRY_CREATE_TOKEN: &'static str = "creds_manage.entry.create_token";
     pub const CREDS_MANAGE_ENTRY_UPDATE: &'static str = "creds_manage.entry.update";
     pub const CREDS_MANAGE_ENTRY_DELETE: &'static str = "creds_manage.entry.delete";
+    pub const CREDS_MANAGE_ENTRY_AVAILABILITY: &'static str = "creds_manage.entry.availability";
+    pub const CREDS_MANAGE_AUDIT_LIST: &'static str = "creds_manage.audit.list";
+    pub const CREDS_MANAGE_AUDIT_VERIFY: &'static str = "creds_manage.audit.verify";
+    pub const CREDS_MANAGE_STORE_EXPORT: &'static str = "creds_manage.store.export";
+    pub const CREDS_MANAGE_STORE_IMPORT: &'static str = "creds_manage.store.import";
+    pub const CREDS_MANAGE_STORE_EVENTS: &'static str = "creds_manage.store.events";
 }
 
 pub struct AuthFlowDiagnosisField;
 
 impl AuthFlowDiagnosisField {
     pub const ADAPTER: &'static str = "adapter";
+    pub const ACCEPTS_BEARER: &'static str = "accepts_bearer";
+    pub const ACCEPTS_SESSION: &'static str = "accepts_session";
+    pub const ACCESS_RULE_ID: &'static str = "access_rule_id";
+    pub const ACCESS_RULES_COUNT: &'static str = "access_rules_count";
     pub const ACCESS_TOKEN_PRESENT: &'static str = "access_token_present";
     pub const AUTH_FAMILY: &'static str = "auth_family";
     pub const AUTH_SCHEME: &'static str = "auth_scheme";
     pub const CALLBACK_PATH: &'static str = "callback_path";
     pub const CREDENTIAL_SOURCE: &'static str = "credential_source";
     pub const DIRECTIVE_HEADER: &'static str = "directive_header";
+    pub const DISABLED: &'static str = "disabled";
+    pub const DRY_RUN: &'static str = "dry_run";
     pub const ENTRY_IDS_COUNT: &'static str = "entry_ids_count";
     pub const ENTRY_NAME: &'static str = "entry_name";
     pub const ENTITY_KIND: &'static str = "entity_kind";
+    pub const EXPECTED_REVISION: &'static str = "expected_revision";
     pub const EXTERNAL_BASE_URL: &'static str = "external_base_url";
     pub const FAILURE_STAGE: &'static str = "failure_stage";
     pub const GROUP: &'static str = "group";
@@ -53,6 +70,7 @@ impl AuthFlowDiagnosisField {
     pub const HAS_AUTHORIZATION_HEADER: &'static str = "has_authorization_header";
     pub const HAS_CODE: &'static str = "has_code";
     pub const HAS_COOKIE_HEADER: &'static str = "has_cookie_header";
+    pub const HAS_EXPIRY: &'static str = "has_expiry";
     pub const HAS_ID_TOKEN: &'static str = "has_id_token";
     pub const HAS_METADATA: &'static str = "has_metadata";
     pub const HAS_POST_AUTH_REDIRECT_URI: &'static str = "has_post_auth_redirect_uri";
@@ -62,15 +80,21 @@ impl AuthFlowDiagnosisField {
     pub const HAS_STATE: &'static str = "has_state";
     pub const HAS_TARGET_ID: &'static str = "has_target_id";
     pub const HTTP_STATUS: &'static str = "http_status";
+    pub const LOGIN_REDIRECT: &'static str = "login_redirect";
     pub const METADATA_ID_PRESENT: &'static str = "metadata_id_present";
     pub const METADATA_REDEEMED: &'static str = "metadata_redeemed";
     pub const METHOD: &'static str = "method";
     pub const MODE: &'static str = "mode";
     pub const OPERATION_KIND: &'static str = "operation_kind";
+    pub const ORIGINAL_HOST: &'static str = "original_host";
+    pub const ORIGINAL_METHOD: &'static str = "original_method";
+    pub const ORIGINAL_PATH: &'static str = "original_path";
+    pub const PASSWORD_REHASHED: &'static str = "password_rehashed";
     pub const POST_AUTH_REDIRECT_PRESENT: &'static str = "post_auth_redirect_present";
     pub const PROPAGATION_ENABLED: &'static str = "propagation_enabled";
     pub const REASON: &'static str = "reason";
     pub const REQUEST_PATH: &'static str = "request_path";
+    pub const REQUIRED_SCOPES_COUNT: &'static str = "required_scopes_count";
     pub const RESPONSE_TRANSPORT: &'static str = "response_transport";
     pub const RESOLVED_CLIENT_IP_PRESENT: &'static str = "resolved_client_ip_present";
     pub const RESULT_COUNT: &'static str = "result_count";
@@ -274,6 +298,30 @@ mod tests {
             AuthFlowOperation::CREDS_MANAGE_ENTRY_DELETE,
             "creds_manage.entry.delete"
         );
+        assert_eq!(
+            AuthFlowOperation::CREDS_MANAGE_ENTRY_AVAILABILITY,
+            "creds_manage.entry.availability"
+        );
+        assert_eq!(
+            AuthFlowOperation::CREDS_MANAGE_GROUP_AVAILABILITY,
+            "creds_manage.group.availability"
+        );
+        assert_eq!(
+            AuthFlowOperation::CREDS_MANAGE_GROUP_IDENTITY_ACCESS,
+            "creds_manage.group.identity_access"
+        );
+        assert_eq!(
+            AuthFlowOperation::CREDS_MANAGE_GROUP_ACCESS_RULES,
+            "creds_manage.group.access_rules"
+        );
+        assert_eq!(
+            AuthFlowOperation::CREDS_MANAGE_AUDIT_LIST,
+            "creds_manage.audit.list"
+        );
+        assert_eq!(
+            AuthFlowOperation::CREDS_MANAGE_AUDIT_VERIFY,
+            "creds_manage.audit.verify"
+        );
     }
 
     #[test]